@@ -1,7 +1,11 @@
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{future::Future, pin::Pin, sync::Arc};
 
-use crate::AgentState;
+use crate::{
+    cache,
+    output::{self, ActionOutput},
+    AgentState,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EmptyParams {}
@@ -22,6 +26,9 @@ pub struct ActionDefinition {
     pub parameters: Vec<ActionParameter>,
 }
 
+/// The boxed future returned by action and confirm handlers.
+pub type ActionFuture = Pin<Box<dyn Future<Output = Result<String, String>> + Send + Sync>>;
+
 pub type Handler<S> = Box<
     dyn Fn(
             serde_json::Value,
@@ -58,16 +65,27 @@ impl<S: Send + Sync + Clone + 'static> FunctionAction<S> {
         params: serde_json::Value,
         send_state: serde_json::Value,
         state: AgentState<S>,
-    ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + Sync>> {
+    ) -> ActionFuture {
         (self.handler)(params, send_state, state)
     }
 
+    /// Like [`execute`](Self::execute), but also returns the artifacts and UI
+    /// hints the action produced for the user.
+    pub async fn execute_rich(
+        &self,
+        params: serde_json::Value,
+        send_state: serde_json::Value,
+        state: AgentState<S>,
+    ) -> Result<ActionOutput, String> {
+        output::collect(self.execute(params, send_state, state)).await
+    }
+
     pub fn confirm(
         &self,
         params: serde_json::Value,
         send_state: serde_json::Value,
         state: AgentState<S>,
-    ) -> Option<Pin<Box<dyn Future<Output = Result<String, String>> + Send + Sync>>> {
+    ) -> Option<ActionFuture> {
         self.confirm_handler
             .as_ref()
             .map(|handler| handler(params, send_state, state))
@@ -144,6 +162,7 @@ where
 
     pub fn build(self) -> FunctionAction<S> {
         let handler = self.handler;
+        let is_preview = self.confirm_handler.is_some();
         FunctionAction {
             definition: ActionDefinition {
                 name: self.name,
@@ -160,10 +179,20 @@ where
                             .map_err(|e| format!("Invalid parameters: {}", e))?;
                         let send_state = serde_json::from_value(send_state)
                             .map_err(|e| format!("Invalid send_state: {}", e))?;
-                        let result = handler(params, send_state, state).await?;
-                        // If there's a confirm handler, mark this as a preview
-                        serde_json::to_string(&result)
-                            .map_err(|e| format!("Failed to serialize result: {}", e))
+                        let (result, cache_hits) =
+                            cache::collect_hits(handler(params, send_state, state)).await;
+                        let result = result?;
+                        // Previews are handed back to the confirm handler as-is,
+                        // so only plain results are annotated with cache hits.
+                        if is_preview || cache_hits.is_empty() {
+                            serde_json::to_string(&result)
+                        } else {
+                            serde_json::to_string(&CachedResult {
+                                result: &result,
+                                cached: &cache_hits,
+                            })
+                        }
+                        .map_err(|e| format!("Failed to serialize result: {}", e))
                     })
                 },
             ),
@@ -190,6 +219,27 @@ where
     }
 }
 
+/// Adapts a handler that takes a client as its first argument into an action
+/// handler. Each invocation gets its own clone of `client`.
+pub(crate) fn with_client<C, H, P, V, S, Fut>(
+    client: &C,
+    handler: H,
+) -> impl Fn(P, V, AgentState<S>) -> Fut + Send + Sync + Clone + 'static
+where
+    C: Clone + Send + Sync + 'static,
+    H: Fn(C, P, V, AgentState<S>) -> Fut + Send + Sync + Clone + 'static,
+{
+    let client = client.clone();
+    move |params, send_state, state| handler(client.clone(), params, send_state, state)
+}
+
+/// Action output annotated with the provider responses served from cache.
+#[derive(Serialize)]
+struct CachedResult<'a, Q> {
+    result: &'a Q,
+    cached: &'a [cache::CacheHit],
+}
+
 /// Represents a group of related actions
 pub trait ActionGroup<S: Send + Sync + Clone + 'static> {
     fn actions(&self) -> &[Arc<FunctionAction<S>>];
@@ -273,4 +323,63 @@ mod tests {
         let result = action.execute(params, send_state, state.clone()).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_cache_hits_are_noted_in_result() {
+        use crate::cache::{CachePolicy, MemoryBackend, ResponseCache};
+        use std::time::Duration;
+
+        let cache = ResponseCache::new(
+            Arc::new(MemoryBackend::default()),
+            CachePolicy::new().rule("birdeye", "/defi/price", Duration::from_secs(60)),
+        );
+        let url = reqwest::Url::parse("https://example.com/defi/price?address=abc").unwrap();
+        cache.put("birdeye", &url, "42");
+
+        let handler = move |_params: EmptyParams, _send_state: serde_json::Value, _state| {
+            let (cache, url) = (cache.clone(), url.clone());
+            async move { Ok(cache.get("birdeye", &url).unwrap_or_default()) }
+        };
+        let action = ActionBuilder::<_, _, _, ()>::new("get_price", handler, None).build();
+
+        let state = Arc::new(Mutex::new(()));
+        let result = action
+            .execute(serde_json::json!({}), serde_json::json!({}), state)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result["result"], "42");
+        assert_eq!(result["cached"][0]["provider"], "birdeye");
+        assert_eq!(result["cached"][0]["endpoint"], "/defi/price");
+    }
+
+    #[tokio::test]
+    async fn test_rich_output_keeps_string_result() {
+        use crate::output::{Attachment, UiHint};
+
+        let handler = |_params: EmptyParams, _send_state: serde_json::Value, _state| async {
+            let delivered = output::attach(Attachment::png("chart.png", vec![1, 2, 3]));
+            output::hint(UiHint::button("Line chart", "Show it as a line chart"));
+            Ok(delivered.to_string())
+        };
+        let action = ActionBuilder::<_, _, _, ()>::new("render_chart", handler, None).build();
+
+        let state = Arc::new(Mutex::new(()));
+        let output = action
+            .execute_rich(serde_json::json!({}), serde_json::json!({}), state.clone())
+            .await
+            .unwrap();
+        assert_eq!(output.content, "\"true\"");
+        assert_eq!(
+            output.attachments().collect::<Vec<_>>(),
+            vec![&Attachment::png("chart.png", vec![1, 2, 3])]
+        );
+        assert_eq!(output.hints.len(), 1);
+
+        // Plain execution has nowhere to deliver the file
+        let result = action
+            .execute(serde_json::json!({}), serde_json::json!({}), state)
+            .await;
+        assert_eq!(result.unwrap(), "\"false\"");
+    }
 }
@@ -1,7 +1,7 @@
 pub mod client;
 
 use crate::{
-    action::{ActionBuilder, ActionGroup, FunctionAction},
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
     AgentState,
 };
 use client::BirdeyeClient;
@@ -147,27 +147,43 @@ impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for BirdeyeActionGroup<S>
     }
 }
 
+impl<S: Send + Sync + Clone + 'static> Default for BirdeyeActionGroup<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
+    /// Builds the group with the API key from `BIRDEYE_API_KEY`, read once here.
+    /// See [`BirdeyeClient::from_env`].
     pub fn new() -> Self {
+        Self::with_client(BirdeyeClient::from_env())
+    }
+
+    /// Builds the group around a configured client, e.g. one with its own key
+    /// or a base URL pointing at a mock server.
+    pub fn with_client(client: BirdeyeClient) -> Self {
         let mut actions = Vec::new();
 
         // Add token price action
         {
             async fn get_token_price<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TokenPriceParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client.get_token_price(params.address).await
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_token_price", get_token_price, None)
-                .description("Get real-time price data for a token")
-                .parameter("address", "Token address", "string", true)
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_token_price",
+                with_client(&client, get_token_price),
+                None,
+            )
+            .description("Get real-time price data for a token")
+            .parameter("address", "Token address", "string", true)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -175,14 +191,11 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add token price history action
         {
             async fn get_token_price_history<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TokenPriceHistoryParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
-
                 let time_to = chrono::Utc::now().timestamp();
                 let time_from = calculate_time_from(time_to, &params.resolution)?;
 
@@ -199,7 +212,7 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_token_price_history",
-                get_token_price_history,
+                with_client(&client, get_token_price_history),
                 None,
             )
             .description("Get historical price data for a token")
@@ -220,19 +233,17 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add multi token price action
         {
             async fn get_multi_token_price<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: MultiTokenPriceParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client.get_multi_token_price(params.addresses).await
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_multi_token_price",
-                get_multi_token_price,
+                with_client(&client, get_multi_token_price),
                 None,
             )
             .description("Get price data for multiple tokens")
@@ -250,21 +261,22 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add token trending action
         {
             async fn get_token_trending<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TokenTrendingParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client.get_token_trending(params.limit).await
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_token_trending", get_token_trending, None)
-                    .description("Get trending tokens")
-                    .parameter("limit", "Number of tokens to return", "integer", false)
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_token_trending",
+                with_client(&client, get_token_trending),
+                None,
+            )
+            .description("Get trending tokens")
+            .parameter("limit", "Number of tokens to return", "integer", false)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -272,14 +284,11 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add token OHLCV action
         {
             async fn get_token_ohlcv<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TokenOhlcvParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
-
                 let time_to = chrono::Utc::now().timestamp();
                 let time_from = calculate_time_from(time_to, &params.resolution)?;
 
@@ -290,7 +299,7 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_token_ohlcv",
-                get_token_ohlcv,
+                with_client(&client, get_token_ohlcv),
                 None,
             )
             .description("Get OHLCV data for a token (only solana tokens). Do not use if it is an ethereum token")
@@ -309,14 +318,11 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add pair OHLCV action
         {
             async fn get_pair_ohlcv<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: PairOhlcvParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
-
                 let time_to = chrono::Utc::now().timestamp();
                 let time_from = calculate_time_from(time_to, &params.resolution)?;
 
@@ -327,7 +333,7 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_pair_ohlcv",
-                get_pair_ohlcv,
+                with_client(&client, get_pair_ohlcv),
                 None,
             )
             .description("Get OHLCV data for a trading pair")
@@ -346,25 +352,26 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add token trades action
         {
             async fn get_token_trades<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TokenTradesParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client
                     .get_token_trades(params.address, params.limit, params.offset)
                     .await
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_token_trades", get_token_trades, None)
-                    .description("Get recent trades for a token")
-                    .parameter("address", "Token address", "string", true)
-                    .parameter("limit", "Number of trades to return", "integer", false)
-                    .parameter("offset", "Number of trades to skip", "integer", false)
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_token_trades",
+                with_client(&client, get_token_trades),
+                None,
+            )
+            .description("Get recent trades for a token")
+            .parameter("address", "Token address", "string", true)
+            .parameter("limit", "Number of trades to return", "integer", false)
+            .parameter("offset", "Number of trades to skip", "integer", false)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -372,24 +379,26 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add pair trades action
         {
             async fn get_pair_trades<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: PairTradesParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client
                     .get_pair_trades(params.pair_address, params.limit, params.offset)
                     .await
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_pair_trades", get_pair_trades, None)
-                .description("Get recent trades for a trading pair")
-                .parameter("pair_address", "Pair address", "string", true)
-                .parameter("limit", "Number of trades to return", "integer", false)
-                .parameter("offset", "Number of trades to skip", "integer", false)
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_pair_trades",
+                with_client(&client, get_pair_trades),
+                None,
+            )
+            .description("Get recent trades for a trading pair")
+            .parameter("pair_address", "Pair address", "string", true)
+            .parameter("limit", "Number of trades to return", "integer", false)
+            .parameter("offset", "Number of trades to skip", "integer", false)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -397,21 +406,22 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add token overview action
         {
             async fn get_token_overview<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TokenOverviewParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client.get_token_overview(params.address).await
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_token_overview", get_token_overview, None)
-                    .description("Get comprehensive overview data for a token")
-                    .parameter("address", "Token address", "string", true)
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_token_overview",
+                with_client(&client, get_token_overview),
+                None,
+            )
+            .description("Get comprehensive overview data for a token")
+            .parameter("address", "Token address", "string", true)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -419,21 +429,23 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add token list action
         {
             async fn get_token_list<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TokenListParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client.get_token_list(params.limit, params.offset).await
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_token_list", get_token_list, None)
-                .description("Get list of tokens with market data")
-                .parameter("limit", "Number of tokens to return", "integer", false)
-                .parameter("offset", "Number of tokens to skip", "integer", false)
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_token_list",
+                with_client(&client, get_token_list),
+                None,
+            )
+            .description("Get list of tokens with market data")
+            .parameter("limit", "Number of tokens to return", "integer", false)
+            .parameter("offset", "Number of tokens to skip", "integer", false)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -441,21 +453,22 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add token security action
         {
             async fn get_token_security<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TokenSecurityParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client.get_token_security(params.address).await
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_token_security", get_token_security, None)
-                    .description("Get security information for a token")
-                    .parameter("address", "Token address", "string", true)
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_token_security",
+                with_client(&client, get_token_security),
+                None,
+            )
+            .description("Get security information for a token")
+            .parameter("address", "Token address", "string", true)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -463,19 +476,17 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add token market list action
         {
             async fn get_token_market_list<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TokenMarketListParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client.get_token_market_list(params.address).await
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_token_market_list",
-                get_token_market_list,
+                with_client(&client, get_token_market_list),
                 None,
             )
             .description("Get list of markets where a token is traded")
@@ -488,13 +499,11 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add token new listing action
         {
             async fn get_token_new_listing<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TokenNewListingParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client
                     .get_token_new_listing(params.limit, params.offset)
                     .await
@@ -502,7 +511,7 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_token_new_listing",
-                get_token_new_listing,
+                with_client(&client, get_token_new_listing),
                 None,
             )
             .description("Get list of newly listed tokens")
@@ -516,13 +525,11 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add token top traders action
         {
             async fn get_token_top_traders<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TokenTopTradersParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client
                     .get_token_top_traders(params.address, params.limit)
                     .await
@@ -530,7 +537,7 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_token_top_traders",
-                get_token_top_traders,
+                with_client(&client, get_token_top_traders),
                 None,
             )
             .description("Get top traders for a token")
@@ -544,20 +551,21 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add gainers/losers action
         {
             async fn get_gainers_losers<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 _params: GainersLosersParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client.get_gainers_losers().await
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_gainers_losers", get_gainers_losers, None)
-                    .description("Get gainers and losers data")
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_gainers_losers",
+                with_client(&client, get_gainers_losers),
+                None,
+            )
+            .description("Get gainers and losers data")
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -565,13 +573,11 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add trader transactions by time action
         {
             async fn get_trader_txs_by_time<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: TraderTxsByTimeParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client
                     .get_trader_txs_by_time(
                         params.address,
@@ -584,7 +590,7 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_trader_txs_by_time",
-                get_trader_txs_by_time,
+                with_client(&client, get_trader_txs_by_time),
                 None,
             )
             .description("Get trader transactions within a time range")
@@ -605,19 +611,17 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add supported chains action
         {
             async fn list_supported_chains<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 _params: SupportedChainsParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client.list_supported_chains().await
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "list_supported_chains",
-                list_supported_chains,
+                with_client(&client, list_supported_chains),
                 None,
             )
             .description("List supported blockchain networks")
@@ -629,13 +633,11 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add wallet portfolio action
         {
             async fn get_wallet_portfolio<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: WalletPortfolioParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client
                     .get_wallet_portfolio(params.wallet_address, params.chain_id)
                     .await
@@ -643,7 +645,7 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_wallet_portfolio",
-                get_wallet_portfolio,
+                with_client(&client, get_wallet_portfolio),
                 None,
             )
             .description("Get wallet portfolio for a specific chain")
@@ -657,13 +659,11 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add multichain wallet portfolio action
         {
             async fn get_wallet_portfolio_multichain<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: WalletPortfolioMultichainParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client
                     .get_wallet_portfolio_multichain(params.wallet_address)
                     .await
@@ -671,7 +671,7 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_wallet_portfolio_multichain",
-                get_wallet_portfolio_multichain,
+                with_client(&client, get_wallet_portfolio_multichain),
                 None,
             )
             .description("Get wallet portfolio across all chains")
@@ -684,13 +684,11 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add wallet transaction history action
         {
             async fn get_wallet_transaction_history<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: WalletTransactionHistoryParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client
                     .get_wallet_transaction_history(
                         params.wallet_address,
@@ -703,7 +701,7 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_wallet_transaction_history",
-                get_wallet_transaction_history,
+                with_client(&client, get_wallet_transaction_history),
                 None,
             )
             .description("Get wallet transaction history for a specific chain")
@@ -724,13 +722,11 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add multichain wallet transaction history action
         {
             async fn get_wallet_transaction_history_multichain<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: WalletTransactionHistoryMultichainParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client
                     .get_wallet_transaction_history_multichain(
                         params.wallet_address,
@@ -742,7 +738,7 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_wallet_transaction_history_multichain",
-                get_wallet_transaction_history_multichain,
+                with_client(&client, get_wallet_transaction_history_multichain),
                 None,
             )
             .description("Get wallet transaction history across all chains")
@@ -762,13 +758,11 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
         // Add transaction simulation action
         {
             async fn simulate_transaction<S: Send + Sync + Clone + 'static>(
+                client: BirdeyeClient,
                 params: SimulateTransactionParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("BIRDEYE_API_KEY")
-                    .map_err(|_| "BIRDEYE_API_KEY environment variable not set".to_string())?;
-                let client = BirdeyeClient::new(api_key);
                 client
                     .simulate_transaction(params.chain_id, params.tx_data)
                     .await
@@ -776,7 +770,7 @@ impl<S: Send + Sync + Clone + 'static> BirdeyeActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "simulate_transaction",
-                simulate_transaction,
+                with_client(&client, simulate_transaction),
                 None,
             )
             .description("Simulate a transaction")
@@ -815,3 +809,72 @@ fn calculate_time_from(time_to: i64, resolution: &str) -> Result<i64, String> {
 
     Ok(time_to - duration.num_seconds())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::Mutex;
+
+    const SOL_ADDRESS: &str = "So11111111111111111111111111111111111111112";
+
+    fn find_action(group: &BirdeyeActionGroup<()>, name: &str) -> Arc<FunctionAction<()>> {
+        group
+            .actions()
+            .iter()
+            .find(|action| action.definition().name == name)
+            .cloned()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_group_uses_injected_client() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/defi/price")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "address".into(),
+                SOL_ADDRESS.into(),
+            ))
+            .match_header("x-api-key", "test-key")
+            .with_status(200)
+            .with_body(r#"{"success":true,"data":{"value":150.0}}"#)
+            .create_async()
+            .await;
+
+        let client = BirdeyeClient::new("test-key".to_string()).with_base_url(server.url());
+        let group = BirdeyeActionGroup::with_client(client);
+        let result = find_action(&group, "get_token_price")
+            .execute(
+                serde_json::json!({ "address": SOL_ADDRESS }),
+                serde_json::json!({}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        let body: String = serde_json::from_str(&result).unwrap();
+        assert!(body.contains("150.0"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_api_key_is_reported() {
+        // Only meaningful when no real key is configured
+        if std::env::var("BIRDEYE_API_KEY").is_ok() {
+            return;
+        }
+        let client = BirdeyeClient::from_env().with_base_url("http://127.0.0.1:9".to_string());
+        let group = BirdeyeActionGroup::with_client(client);
+        let result = find_action(&group, "get_token_price")
+            .execute(
+                serde_json::json!({ "address": SOL_ADDRESS }),
+                serde_json::json!({}),
+                Arc::new(Mutex::new(())),
+            )
+            .await;
+        assert_eq!(
+            result.unwrap_err(),
+            "BIRDEYE_API_KEY environment variable not set"
+        );
+    }
+}
@@ -1,49 +1,75 @@
-use reqwest::{
-    header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE},
-    Client,
-};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE};
 use solana_sdk::pubkey::Pubkey;
 
+use std::sync::Arc;
+
+use crate::http::{HttpConfig, ProviderHttp, RateLimiter, BIRDEYE_QUOTA};
+
 const BASE_URL: &str = "https://public-api.birdeye.so";
 
 #[derive(Debug, Clone)]
 pub struct BirdeyeClient {
-    api_key: String,
-    client: Client,
+    api_key: Option<String>,
+    http: ProviderHttp,
+    base_url: String,
 }
 
 impl BirdeyeClient {
     pub fn new(api_key: String) -> Self {
+        Self::with_api_key(Some(api_key))
+    }
+
+    /// Reads the API key from `BIRDEYE_API_KEY`. Without one the client is still
+    /// built, but every request fails with an error naming the variable.
+    pub fn from_env() -> Self {
+        Self::with_api_key(std::env::var("BIRDEYE_API_KEY").ok())
+    }
+
+    fn with_api_key(api_key: Option<String>) -> Self {
         Self {
             api_key,
-            client: Client::new(),
+            http: ProviderHttp::new("birdeye", BIRDEYE_QUOTA),
+            base_url: BASE_URL.to_string(),
         }
     }
 
-    fn get_headers(&self) -> HeaderMap {
+    /// Overrides the API host, e.g. to point the client at a mock server.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn with_http_config(mut self, config: HttpConfig) -> Self {
+        self.http = self.http.with_config(config);
+        self
+    }
+
+    /// Replaces the shared rate limiter, e.g. for a plan with a higher quota.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.http = self.http.with_limiter(limiter);
+        self
+    }
+
+    fn get_headers(&self, api_key: &str) -> Result<HeaderMap, String> {
         let mut headers = HeaderMap::new();
-        headers.insert("X-API-KEY", HeaderValue::from_str(&self.api_key).unwrap());
+        let api_key = HeaderValue::from_str(api_key)
+            .map_err(|e| format!("Invalid Birdeye API key: {}", e))?;
+        headers.insert("X-API-KEY", api_key);
         headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-        headers
+        Ok(headers)
     }
 
     async fn make_request(&self, endpoint: &str) -> Result<String, String> {
-        let url = format!("{}{}", BASE_URL, endpoint);
-        println!("Making request to {}", url);
-        let response = self
-            .client
-            .get(&url)
-            .headers(self.get_headers())
-            .send()
+        let api_key = self
+            .api_key
+            .as_deref()
+            .ok_or_else(|| "BIRDEYE_API_KEY environment variable not set".to_string())?;
+        let url = format!("{}{}", self.base_url, endpoint);
+        self.http
+            .get(&url, &self.get_headers(api_key)?)
             .await
-            .map_err(|e| e.to_string())?;
-
-        if response.status().is_success() {
-            response.text().await.map_err(|e| e.to_string())
-        } else {
-            Err(format!("Request failed with status: {}", response.status()))
-        }
+            .map_err(|e| e.to_string())
     }
 
     fn format_resolution(resolution: String) -> String {
@@ -63,7 +89,7 @@ impl BirdeyeClient {
 
     pub async fn get_token_price(&self, address: String) -> Result<String, String> {
         let pubkey = Self::validate_solana_address(&address)?;
-        self.make_request(&format!("/defi/price?address={}", pubkey.to_string()))
+        self.make_request(&format!("/defi/price?address={}", pubkey))
             .await
     }
 
@@ -79,8 +105,7 @@ impl BirdeyeClient {
         let formatted_resolution = Self::format_resolution(resolution);
         let mut endpoint = format!(
             "/defi/history_price?address={}&address_type=token&type={}",
-            pubkey.to_string(),
-            formatted_resolution
+            pubkey, formatted_resolution
         );
 
         if let Some(from) = time_from {
@@ -134,10 +159,7 @@ impl BirdeyeClient {
         let formatted_resolution = Self::format_resolution(resolution);
         self.make_request(&format!(
             "/defi/ohlcv?address={}&type={}&time_from={}&time_to={}",
-            pubkey.to_string(),
-            formatted_resolution,
-            time_from,
-            time_to
+            pubkey, formatted_resolution, time_from, time_to
         ))
         .await
     }
@@ -153,10 +175,7 @@ impl BirdeyeClient {
         let formatted_resolution = Self::format_resolution(resolution);
         self.make_request(&format!(
             "/defi/ohlcv/pair?address={}&type={}&time_from={}&time_to={}",
-            pubkey.to_string(),
-            formatted_resolution,
-            time_from,
-            time_to
+            pubkey, formatted_resolution, time_from, time_to
         ))
         .await
     }
@@ -168,11 +187,7 @@ impl BirdeyeClient {
         offset: Option<i32>,
     ) -> Result<String, String> {
         let pubkey = Self::validate_solana_address(&address)?;
-        println!("Pubkey: {:?}", pubkey);
-        let mut endpoint = format!(
-            "/defi/txs/token?address={}&sort_type=desc",
-            pubkey.to_string()
-        );
+        let mut endpoint = format!("/defi/txs/token?address={}&sort_type=desc", pubkey);
         if let Some(limit) = limit {
             endpoint.push_str(&format!("&limit={}", limit));
         }
@@ -189,10 +204,9 @@ impl BirdeyeClient {
         offset: Option<i32>,
     ) -> Result<String, String> {
         let pubkey = Self::validate_solana_address(&pair_address)?;
-        println!("Pubkey: {:?}", pubkey);
         let mut endpoint = format!(
             "/defi/txs/pair?address={}&tx_type=swap&sort_type=desc",
-            pubkey.to_string()
+            pubkey
         );
         if let Some(limit) = limit {
             if limit >= 50 {
@@ -209,11 +223,8 @@ impl BirdeyeClient {
 
     pub async fn get_token_overview(&self, address: String) -> Result<String, String> {
         let pubkey = Self::validate_solana_address(&address)?;
-        self.make_request(&format!(
-            "/defi/token_overview?address={}",
-            pubkey.to_string()
-        ))
-        .await
+        self.make_request(&format!("/defi/token_overview?address={}", pubkey))
+            .await
     }
 
     pub async fn get_token_list(
@@ -239,16 +250,13 @@ impl BirdeyeClient {
 
     pub async fn get_token_security(&self, address: String) -> Result<String, String> {
         let pubkey = Self::validate_solana_address(&address)?;
-        self.make_request(&format!(
-            "/defi/token_security?address={}",
-            pubkey.to_string()
-        ))
-        .await
+        self.make_request(&format!("/defi/token_security?address={}", pubkey))
+            .await
     }
 
     pub async fn get_token_market_list(&self, address: String) -> Result<String, String> {
         let pubkey = Self::validate_solana_address(&address)?;
-        self.make_request(&format!("/defi/v2/markets?address={}", pubkey.to_string()))
+        self.make_request(&format!("/defi/v2/markets?address={}", pubkey))
             .await
     }
 
@@ -279,7 +287,7 @@ impl BirdeyeClient {
         limit: Option<i32>,
     ) -> Result<String, String> {
         let pubkey = Self::validate_solana_address(&address)?;
-        let mut endpoint = format!("/defi/v2/tokens/top_traders?address={}", pubkey.to_string());
+        let mut endpoint = format!("/defi/v2/tokens/top_traders?address={}", pubkey);
         if let Some(limit) = limit {
             endpoint.push_str(&format!("&limit={}", limit));
         }
@@ -301,9 +309,7 @@ impl BirdeyeClient {
         let pubkey = Self::validate_solana_address(&address)?;
         let mut endpoint = format!(
             "/trader/txs/seek_by_time?address={}&from={}&to={}",
-            pubkey.to_string(),
-            time_from,
-            time_to
+            pubkey, time_from, time_to
         );
         if let Some(limit) = limit {
             endpoint.push_str(&format!("&limit={}", limit));
@@ -417,6 +423,7 @@ mod tests {
     const TEST_CHAIN_ID: &str = "solana";
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_token_price() {
         let client = setup_client();
         let result = client.get_token_price(SOL_ADDRESS.to_string()).await;
@@ -425,6 +432,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_token_price_history() {
         let client = setup_client();
         let result = client
@@ -441,6 +449,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_multi_token_price() {
         let client = setup_client();
         let addresses = format!("{},{}", SOL_ADDRESS, USDC_ADDRESS);
@@ -450,6 +459,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_token_ohlcv() {
         let client = setup_client();
         let result = client
@@ -465,6 +475,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_pair_ohlcv() {
         let client = setup_client();
         let result = client
@@ -480,6 +491,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_token_trades() {
         let client = setup_client();
         let result = client
@@ -490,6 +502,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_pair_trades() {
         let client = setup_client();
         let result = client
@@ -504,6 +517,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_token_overview() {
         let client = setup_client();
         let result = client.get_token_overview(SOL_ADDRESS.to_string()).await;
@@ -512,6 +526,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_token_list() {
         let client = setup_client();
         let result = client.get_token_list(Some(10), Some(0)).await;
@@ -520,6 +535,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_token_security() {
         let client = setup_client();
         let result = client.get_token_security(SOL_ADDRESS.to_string()).await;
@@ -528,6 +544,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_token_market_list() {
         let client = setup_client();
         let result = client.get_token_market_list(SOL_ADDRESS.to_string()).await;
@@ -536,6 +553,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_token_new_listing() {
         let client = setup_client();
         let result = client.get_token_new_listing(Some(10), Some(0)).await;
@@ -544,6 +562,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_token_top_traders() {
         let client = setup_client();
         let result = client
@@ -554,6 +573,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_token_trending() {
         let client = setup_client();
         let result = client.get_token_trending(Some(10)).await;
@@ -562,6 +582,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_gainers_losers() {
         let client = setup_client();
         let result = client.get_gainers_losers().await;
@@ -570,6 +591,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_trader_txs_by_time() {
         let client = setup_client();
         let result = client
@@ -580,6 +602,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_list_supported_chains() {
         let client = setup_client();
         let result = client.list_supported_chains().await;
@@ -588,6 +611,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_wallet_portfolio() {
         let client = setup_client();
         let result = client
@@ -598,6 +622,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_wallet_portfolio_multichain() {
         let client = setup_client();
         let result = client
@@ -622,6 +647,7 @@ mod tests {
     // }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_wallet_transaction_history() {
         let client = setup_client();
         let result = client
@@ -637,6 +663,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_get_wallet_transaction_history_multichain() {
         let client = setup_client();
         let result = client
@@ -647,6 +674,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_simulate_transaction() {
         let client = setup_client();
         let result = client
@@ -660,6 +688,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_error_handling() {
         let client = BirdeyeClient::new("invalid-api-key".to_string());
         let result = client.get_token_price(SOL_ADDRESS.to_string()).await;
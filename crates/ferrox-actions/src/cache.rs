@@ -0,0 +1,506 @@
+//! Opt-in TTL cache for provider responses.
+//!
+//! The cache sits inside [`ProviderHttp`](crate::http::ProviderHttp): a fresh
+//! entry is returned without touching the network or the rate limiter. Entries
+//! are keyed by provider plus the full request URL (endpoint and query), and the
+//! [`CachePolicy`] decides per endpoint how long a response stays fresh.
+//!
+//! Nothing is cached unless a cache is installed, either process-wide with
+//! [`install`] or per client with `with_cache`.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    path::PathBuf,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+/// A stored response body with its timestamps in unix milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub body: String,
+    pub stored_at: u64,
+    pub expires_at: u64,
+}
+
+impl CachedResponse {
+    fn is_fresh(&self, now: u64) -> bool {
+        now < self.expires_at
+    }
+}
+
+/// Storage for cached responses. Implementations must tolerate concurrent use.
+pub trait CacheBackend: Send + Sync + fmt::Debug {
+    fn get(&self, key: &str) -> Option<CachedResponse>;
+    fn put(&self, key: &str, response: CachedResponse);
+}
+
+/// In-process backend, bounded to `max_entries`.
+#[derive(Debug)]
+pub struct MemoryBackend {
+    entries: Mutex<HashMap<String, CachedResponse>>,
+    max_entries: usize,
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ENTRIES)
+    }
+}
+
+impl MemoryBackend {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_entries: max_entries.max(1),
+        }
+    }
+}
+
+impl CacheBackend for MemoryBackend {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        self.entries.lock().unwrap().get(key).cloned()
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(key) {
+            let now = now_millis();
+            entries.retain(|_, entry| entry.is_fresh(now));
+            if entries.len() >= self.max_entries {
+                // Still full: drop whatever expires soonest.
+                if let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.expires_at)
+                    .map(|(key, _)| key.clone())
+                {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(key.to_string(), response);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    key: String,
+    #[serde(flatten)]
+    response: CachedResponse,
+}
+
+/// On-disk backend: one JSON file per entry, named by the SHA-256 of the key.
+/// Survives restarts, so repeated runs don't spend quota on the same data.
+#[derive(Debug)]
+pub struct DiskBackend {
+    dir: PathBuf,
+}
+
+impl DiskBackend {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, String> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let digest = Sha256::digest(key.as_bytes());
+        let name: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.dir.join(format!("{}.json", name))
+    }
+}
+
+impl CacheBackend for DiskBackend {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        let contents = std::fs::read_to_string(self.path(key)).ok()?;
+        let entry: DiskEntry = serde_json::from_str(&contents).ok()?;
+        (entry.key == key).then_some(entry.response)
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) {
+        let path = self.path(key);
+        let entry = DiskEntry {
+            key: key.to_string(),
+            response,
+        };
+        let result = serde_json::to_string(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                // Write then rename so readers never see a partial file.
+                let tmp = path.with_extension("tmp");
+                std::fs::write(&tmp, contents).map_err(|e| e.to_string())?;
+                std::fs::rename(&tmp, &path).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            log::warn!("Failed to write cache entry {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// A TTL for requests to `provider` whose path ends with `pattern`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheRule {
+    /// Matched as a prefix of the provider name, so `coingecko` covers every tier.
+    pub provider: String,
+    /// Path segments matched against the end of the request path; `*` matches
+    /// any single segment. `/coins/*/ohlc` matches `/api/v3/coins/bitcoin/ohlc`.
+    pub pattern: String,
+    pub ttl: Duration,
+}
+
+impl CacheRule {
+    pub fn new(provider: impl Into<String>, pattern: impl Into<String>, ttl: Duration) -> Self {
+        Self {
+            provider: provider.into(),
+            pattern: pattern.into(),
+            ttl,
+        }
+    }
+
+    fn matches(&self, provider: &str, path: &str) -> bool {
+        if !provider.starts_with(&self.provider) {
+            return false;
+        }
+        let pattern: Vec<&str> = segments(&self.pattern).collect();
+        let path: Vec<&str> = segments(path).collect();
+        path.len() >= pattern.len()
+            && path[path.len() - pattern.len()..]
+                .iter()
+                .zip(&pattern)
+                .all(|(segment, expected)| *expected == "*" || segment == expected)
+    }
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+/// Per-endpoint TTLs. The first matching rule wins; requests matching no rule
+/// are not cached.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CachePolicy {
+    rules: Vec<CacheRule>,
+}
+
+impl CachePolicy {
+    /// An empty policy that caches nothing until rules are added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule. Rules are checked in insertion order.
+    pub fn rule(mut self, provider: &str, pattern: &str, ttl: Duration) -> Self {
+        self.rules.push(CacheRule::new(provider, pattern, ttl));
+        self
+    }
+
+    pub fn ttl_for(&self, provider: &str, path: &str) -> Option<Duration> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(provider, path))
+            .map(|rule| rule.ttl)
+            .filter(|ttl| !ttl.is_zero())
+    }
+
+    /// TTLs for the market-data endpoints of every built-in provider. Trades,
+    /// wallet data and simulations are deliberately left out.
+    pub fn market_data() -> Self {
+        let secs = Duration::from_secs;
+        Self::new()
+            // Birdeye
+            .rule("birdeye", "/defi/price", secs(15))
+            .rule("birdeye", "/defi/multi_price", secs(15))
+            .rule("birdeye", "/defi/history_price", secs(60))
+            .rule("birdeye", "/defi/ohlcv", secs(60))
+            .rule("birdeye", "/defi/ohlcv/pair", secs(60))
+            .rule("birdeye", "/defi/token_overview", secs(60))
+            .rule("birdeye", "/defi/token_trending", secs(120))
+            .rule("birdeye", "/defi/tokenList", secs(300))
+            .rule("birdeye", "/defi/v2/markets", secs(300))
+            .rule("birdeye", "/defi/token_security", secs(3600))
+            // CoinGecko
+            .rule("coingecko", "/simple/price", secs(30))
+            .rule("coingecko", "/coins/*/market_chart", secs(60))
+            .rule("coingecko", "/coins/*/market_chart/range", secs(300))
+            .rule("coingecko", "/coins/*/contract/*/market_chart", secs(60))
+            .rule(
+                "coingecko",
+                "/coins/*/contract/*/market_chart/range",
+                secs(300),
+            )
+            .rule("coingecko", "/coins/*/ohlc", secs(60))
+            .rule("coingecko", "/coins/*/tickers", secs(120))
+            .rule("coingecko", "/coins/*/history", secs(3600))
+            .rule("coingecko", "/coins/*/contract/*", secs(300))
+            .rule("coingecko", "/coins/list", secs(3600))
+            .rule("coingecko", "/coins/categories/list", secs(3600))
+            .rule("coingecko", "/asset_platforms", secs(3600))
+            .rule("coingecko", "/search/trending", secs(300))
+            .rule("coingecko", "/global", secs(300))
+            .rule("coingecko", "/global/decentralized_finance_defi", secs(300))
+            // DexScreener
+            .rule("dexscreener", "/latest/dex/pairs/*/*", secs(15))
+            .rule("dexscreener", "/latest/dex/search", secs(30))
+            .rule("dexscreener", "/tokens/v1/*/*", secs(15))
+            .rule("dexscreener", "/token-pairs/v1/*/*", secs(30))
+            .rule("dexscreener", "/token-profiles/latest/v1", secs(60))
+            .rule("dexscreener", "/token-boosts/latest/v1", secs(60))
+            .rule("dexscreener", "/token-boosts/top/v1", secs(60))
+            // GMGN
+            .rule("gmgn", "/tokens/kline/*/*", secs(60))
+            .rule("gmgn", "/tokens/top_holders/*/*", secs(300))
+            .rule("gmgn", "/quotation/v1/tokens/*/*", secs(60))
+            .rule("gmgn", "/pairs/*/new_pairs", secs(15))
+    }
+}
+
+/// A response cache: a backend plus the policy deciding what goes in it.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    backend: Arc<dyn CacheBackend>,
+    policy: CachePolicy,
+}
+
+impl ResponseCache {
+    pub fn new(backend: Arc<dyn CacheBackend>, policy: CachePolicy) -> Self {
+        Self { backend, policy }
+    }
+
+    /// In-memory cache with the [`CachePolicy::market_data`] TTLs.
+    pub fn memory() -> Self {
+        Self::new(
+            Arc::new(MemoryBackend::default()),
+            CachePolicy::market_data(),
+        )
+    }
+
+    /// On-disk cache under `dir` with the [`CachePolicy::market_data`] TTLs.
+    pub fn disk(dir: impl Into<PathBuf>) -> Result<Self, String> {
+        Ok(Self::new(
+            Arc::new(DiskBackend::new(dir)?),
+            CachePolicy::market_data(),
+        ))
+    }
+
+    pub fn with_policy(mut self, policy: CachePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> &CachePolicy {
+        &self.policy
+    }
+
+    /// Returns a fresh entry for `url`, recording the hit for the running action.
+    pub fn get(&self, provider: &str, url: &reqwest::Url) -> Option<String> {
+        self.policy.ttl_for(provider, url.path())?;
+        let now = now_millis();
+        let cached = self
+            .backend
+            .get(&cache_key(provider, url))
+            .filter(|cached| cached.is_fresh(now))?;
+        record_hit(CacheHit {
+            provider: provider.to_string(),
+            endpoint: url.path().to_string(),
+            age_secs: now.saturating_sub(cached.stored_at) / 1000,
+        });
+        Some(cached.body)
+    }
+
+    /// Stores `body` if the policy has a TTL for `url`.
+    pub fn put(&self, provider: &str, url: &reqwest::Url, body: &str) {
+        let Some(ttl) = self.policy.ttl_for(provider, url.path()) else {
+            return;
+        };
+        let now = now_millis();
+        self.backend.put(
+            &cache_key(provider, url),
+            CachedResponse {
+                body: body.to_string(),
+                stored_at: now,
+                expires_at: now + ttl.as_millis() as u64,
+            },
+        );
+    }
+}
+
+/// The key of `url` for `provider`. Query pairs are sorted, so the same
+/// request hits the cache whatever order its parameters were added in.
+fn cache_key(provider: &str, url: &reqwest::Url) -> String {
+    let mut pairs: Vec<_> = url.query_pairs().collect();
+    pairs.sort();
+    let mut url = url.clone();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    format!("{} {}", provider, url)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+static GLOBAL_CACHE: OnceLock<ResponseCache> = OnceLock::new();
+
+/// Installs `cache` for every provider client created afterwards. Returns the
+/// cache back if one was already installed.
+pub fn install(cache: ResponseCache) -> Result<(), ResponseCache> {
+    GLOBAL_CACHE.set(cache)
+}
+
+/// The process-wide cache, if one was installed.
+pub fn global() -> Option<&'static ResponseCache> {
+    GLOBAL_CACHE.get()
+}
+
+/// A response served from the cache while an action was running.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheHit {
+    pub provider: String,
+    pub endpoint: String,
+    pub age_secs: u64,
+}
+
+tokio::task_local! {
+    static CACHE_HITS: Mutex<Vec<CacheHit>>;
+}
+
+fn record_hit(hit: CacheHit) {
+    let _ = CACHE_HITS.try_with(|hits| hits.lock().unwrap().push(hit));
+}
+
+/// Runs `future`, returning its output along with the cache hits it produced.
+pub(crate) async fn collect_hits<F: Future>(future: F) -> (F::Output, Vec<CacheHit>) {
+    CACHE_HITS
+        .scope(Mutex::new(Vec::new()), async move {
+            let output = future.await;
+            let hits = CACHE_HITS.with(|hits| std::mem::take(&mut *hits.lock().unwrap()));
+            (output, hits)
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(path: &str) -> reqwest::Url {
+        reqwest::Url::parse(&format!("https://example.com{}", path)).unwrap()
+    }
+
+    #[test]
+    fn test_policy_matching() {
+        let policy = CachePolicy::market_data();
+        assert_eq!(
+            policy.ttl_for("coingecko-pro", "/api/v3/coins/bitcoin/market_chart"),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            policy.ttl_for("coingecko-demo", "/api/v3/coins/bitcoin/market_chart/range"),
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(
+            policy.ttl_for("birdeye", "/defi/price"),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(policy.ttl_for("birdeye", "/defi/txs/token"), None);
+        assert_eq!(policy.ttl_for("dexscreener", "/defi/price"), None);
+    }
+
+    #[test]
+    fn test_query_order_does_not_matter() {
+        let cache = ResponseCache::new(
+            Arc::new(MemoryBackend::default()),
+            CachePolicy::market_data(),
+        );
+        let chart =
+            url("/api/v3/coins/bitcoin/market_chart?vs_currency=usd&days=30&interval=daily");
+        cache.put("coingecko-pro", &chart, "{\"prices\":[]}");
+        for query in [
+            "days=30&interval=daily&vs_currency=usd",
+            "interval=daily&vs_currency=usd&days=30",
+        ] {
+            let reordered = url(&format!("/api/v3/coins/bitcoin/market_chart?{}", query));
+            assert_eq!(
+                cache.get("coingecko-pro", &reordered).as_deref(),
+                Some("{\"prices\":[]}")
+            );
+        }
+        let other = url("/api/v3/coins/bitcoin/market_chart?vs_currency=usd&days=7&interval=daily");
+        assert_eq!(cache.get("coingecko-pro", &other), None);
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_expires_and_records_hits() {
+        let cache = ResponseCache::new(
+            Arc::new(MemoryBackend::default()),
+            CachePolicy::new().rule("birdeye", "/defi/price", Duration::from_millis(50)),
+        );
+        let price = url("/defi/price?address=So11111111111111111111111111111111111111112");
+        let other = url("/defi/price?address=EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+        cache.put("birdeye", &price, "{\"value\":1}");
+        let (body, hits) = collect_hits(async { cache.get("birdeye", &price) }).await;
+        assert_eq!(body.as_deref(), Some("{\"value\":1}"));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].endpoint, "/defi/price");
+        assert_eq!(cache.get("birdeye", &other), None);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(cache.get("birdeye", &price), None);
+    }
+
+    #[test]
+    fn test_uncached_endpoints_are_not_stored() {
+        let backend = Arc::new(MemoryBackend::default());
+        let cache = ResponseCache::new(backend.clone(), CachePolicy::market_data());
+        let trades = url("/defi/txs/token?address=abc");
+        cache.put("birdeye", &trades, "[]");
+        assert_eq!(backend.get(&cache_key("birdeye", &trades)), None);
+    }
+
+    #[test]
+    fn test_memory_backend_evicts_when_full() {
+        let backend = MemoryBackend::new(2);
+        let entry = |expires_at| CachedResponse {
+            body: String::new(),
+            stored_at: 0,
+            expires_at,
+        };
+        let far = now_millis() + 60_000;
+        backend.put("a", entry(far + 1));
+        backend.put("b", entry(far));
+        backend.put("c", entry(far + 2));
+        assert!(backend.get("a").is_some());
+        assert!(backend.get("b").is_none());
+        assert!(backend.get("c").is_some());
+    }
+
+    #[test]
+    fn test_disk_backend_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("ferrox-cache-test-{}", std::process::id()));
+        let cache = ResponseCache::disk(&dir).unwrap();
+        let chart = url("/api/v3/coins/bitcoin/market_chart?days=1&vs_currency=usd");
+        cache.put("coingecko-pro", &chart, "{\"prices\":[]}");
+
+        let reopened = ResponseCache::disk(&dir).unwrap();
+        assert_eq!(
+            reopened.get("coingecko-pro", &chart).as_deref(),
+            Some("{\"prices\":[]}")
+        );
+        assert_eq!(reopened.get("coingecko-public", &chart), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+pub mod fetcher;
+pub mod models;
+
+use crate::{
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
+    AgentState,
+};
+use fetcher::{CandleFetcher, CandleRequest};
+pub use models::{Candle, CandleInterval, CandleSeries, CandleSource, Gap};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct CandlesParams {
+    address: String,
+    time_from: i64,
+    time_to: i64,
+    interval: Option<String>,
+    chain: Option<String>,
+    coingecko_id: Option<String>,
+    fill_gaps: Option<bool>,
+}
+
+impl CandlesParams {
+    pub(crate) fn into_request(self) -> Result<CandleRequest, String> {
+        Ok(CandleRequest {
+            address: self.address,
+            chain: self
+                .chain
+                .map_or(Ok(Default::default()), |chain| chain.parse())?,
+            interval: self
+                .interval
+                .map_or(Ok(CandleInterval::default()), |interval| interval.parse())?,
+            time_from: self.time_from,
+            time_to: self.time_to,
+            coingecko_id: self.coingecko_id,
+            fill_gaps: self.fill_gaps.unwrap_or(false),
+        })
+    }
+}
+
+pub struct CandleActionGroup<S: Send + Sync + Clone + 'static> {
+    actions: Vec<Arc<FunctionAction<S>>>,
+}
+
+impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for CandleActionGroup<S> {
+    fn actions(&self) -> &[Arc<FunctionAction<S>>] {
+        &self.actions
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> Default for CandleActionGroup<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> CandleActionGroup<S> {
+    pub fn new() -> Self {
+        Self::with_fetcher(CandleFetcher::new())
+    }
+
+    /// Builds the group around a configured fetcher, e.g. one with a different
+    /// provider order.
+    pub fn with_fetcher(fetcher: CandleFetcher) -> Self {
+        let mut actions = Vec::new();
+        // Add candles action
+        {
+            async fn get_candles<S: Send + Sync + Clone + 'static>(
+                fetcher: CandleFetcher,
+                params: CandlesParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let series = fetcher.fetch(&params.into_request()?).await?;
+
+                serde_json::to_string(&series)
+                    .map_err(|e| format!("Failed to serialize candles: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_candles",
+                with_client(&fetcher, get_candles),
+                None,
+            )
+            .description(
+                "Get OHLCV candles for a token. Tries Birdeye, then GMGN, then CoinGecko, and returns the first that has data in one common format. Missing candles are listed under `gaps`",
+            )
+            .parameter("address", "Token address", "string", true)
+            .parameter("time_from", "Start timestamp (Unix seconds)", "integer", true)
+            .parameter("time_to", "End timestamp (Unix seconds)", "integer", true)
+            .parameter(
+                "interval",
+                "Candle width: 1m, 5m, 15m, 30m, 1h, 4h or 1d (default 1h)",
+                "string",
+                false,
+            )
+            .parameter(
+                "chain",
+                "Chain: sol, eth, base, bsc or tron (default sol)",
+                "string",
+                false,
+            )
+            .parameter(
+                "coingecko_id",
+                "CoinGecko coin id (e.g. solana), enables CoinGecko as a last resort",
+                "string",
+                false,
+            )
+            .parameter(
+                "fill_gaps",
+                "Fill missing candles with flat candles at the previous close (default false)",
+                "boolean",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        Self { actions }
+    }
+}
@@ -0,0 +1,284 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::models::{
+    candles_from_birdeye, resample, Candle, CandleInterval, CandleSeries, CandleSource,
+};
+use crate::{
+    birdeye::client::BirdeyeClient, coingecko::client::CoinGeckoClient, gmgn::client::GmgnClient,
+    gmgn::GmgnChain,
+};
+
+/// What to fetch. Times are Unix seconds.
+#[derive(Debug, Clone)]
+pub struct CandleRequest {
+    pub address: String,
+    pub chain: GmgnChain,
+    pub interval: CandleInterval,
+    pub time_from: i64,
+    pub time_to: i64,
+    /// CoinGecko coin id (e.g. `solana`). CoinGecko is only tried when set,
+    /// as it does not look candles up by contract address.
+    pub coingecko_id: Option<String>,
+    pub fill_gaps: bool,
+}
+
+/// Fetches candles from the first provider that has them, in the order
+/// given by [`CandleFetcher::with_sources`] (Birdeye, GMGN, then CoinGecko by
+/// default).
+#[derive(Debug, Clone)]
+pub struct CandleFetcher {
+    birdeye: BirdeyeClient,
+    gmgn: GmgnClient,
+    coingecko: CoinGeckoClient,
+    sources: Vec<CandleSource>,
+}
+
+impl Default for CandleFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CandleFetcher {
+    /// Uses clients configured from the environment.
+    pub fn new() -> Self {
+        Self {
+            birdeye: BirdeyeClient::from_env(),
+            gmgn: GmgnClient::new(),
+            coingecko: CoinGeckoClient::from_env(),
+            sources: vec![
+                CandleSource::Birdeye,
+                CandleSource::Gmgn,
+                CandleSource::CoinGecko,
+            ],
+        }
+    }
+
+    pub fn with_birdeye(mut self, client: BirdeyeClient) -> Self {
+        self.birdeye = client;
+        self
+    }
+
+    pub fn with_gmgn(mut self, client: GmgnClient) -> Self {
+        self.gmgn = client;
+        self
+    }
+
+    pub fn with_coingecko(mut self, client: CoinGeckoClient) -> Self {
+        self.coingecko = client;
+        self
+    }
+
+    /// Sets which providers are tried, and in what order.
+    pub fn with_sources(mut self, sources: Vec<CandleSource>) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Returns the first non-empty series. Providers that fail or come back
+    /// empty are listed in [`CandleSeries::skipped`]; if none has data the
+    /// error lists them all.
+    pub async fn fetch(&self, request: &CandleRequest) -> Result<CandleSeries, String> {
+        if request.time_from >= request.time_to {
+            return Err(format!(
+                "time_from ({}) must be before time_to ({})",
+                request.time_from, request.time_to
+            ));
+        }
+
+        let mut skipped = Vec::new();
+        for source in &self.sources {
+            let candles = match source {
+                CandleSource::Birdeye => self.fetch_birdeye(request).await,
+                CandleSource::Gmgn => self.fetch_gmgn(request).await,
+                CandleSource::CoinGecko => self.fetch_coingecko(request).await,
+            };
+            match candles {
+                Ok(candles) => {
+                    let mut series = CandleSeries::new(*source, request.interval, candles);
+                    series.retain_range(request.time_from, request.time_to);
+                    if series.is_empty() {
+                        skipped.push(format!("{}: no candles in range", source));
+                        continue;
+                    }
+                    if request.fill_gaps {
+                        series.fill_gaps();
+                    }
+                    series.skipped = skipped;
+                    return Ok(series);
+                }
+                Err(e) => skipped.push(format!("{}: {}", source, e)),
+            }
+        }
+
+        Err(format!("No candles available ({})", skipped.join("; ")))
+    }
+
+    async fn fetch_birdeye(&self, request: &CandleRequest) -> Result<Vec<Candle>, String> {
+        if request.chain != GmgnChain::Sol {
+            return Err("only Solana tokens are supported".to_string());
+        }
+        let body = self
+            .birdeye
+            .get_token_ohlcv(
+                request.address.clone(),
+                request.interval.birdeye_type().to_string(),
+                request.time_from,
+                request.time_to,
+            )
+            .await?;
+        candles_from_birdeye(&body)
+    }
+
+    async fn fetch_gmgn(&self, request: &CandleRequest) -> Result<Vec<Candle>, String> {
+        let resolution = request
+            .interval
+            .gmgn_resolution()
+            .ok_or_else(|| format!("no {} candles", request.interval))?;
+        let response = self
+            .gmgn
+            .get_kline(
+                request.chain,
+                request.address.clone(),
+                resolution,
+                request.time_from,
+                request.time_to,
+            )
+            .await?;
+        Ok(response.data.into_iter().map(Candle::from).collect())
+    }
+
+    /// CoinGecko picks the candle width from `days`: 30 minutes up to two
+    /// days, 4 hours up to 30 days and 4 days beyond. Finer requests can't be
+    /// served; coarser ones are resampled.
+    async fn fetch_coingecko(&self, request: &CandleRequest) -> Result<Vec<Candle>, String> {
+        let id = request
+            .coingecko_id
+            .clone()
+            .ok_or_else(|| "no coingecko_id given".to_string())?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs() as i64;
+        let needed = (now - request.time_from).max(0) / 86_400 + 1;
+        let days = [1, 7, 14, 30, 90, 180, 365]
+            .into_iter()
+            .find(|days| *days >= needed)
+            .ok_or_else(|| "range starts more than a year ago".to_string())?;
+        let native = match days {
+            1 => CandleInterval::ThirtyMinutes,
+            7..=30 => CandleInterval::FourHours,
+            _ => return Err(format!("only 4 day candles for {} days", days)),
+        };
+
+        let ohlc = self
+            .coingecko
+            .get_coin_ohlc(id, "usd".to_string(), days.to_string())
+            .await
+            .map_err(|e| e.to_string())?;
+        // CoinGecko stamps each candle with its close time
+        let candles: Vec<Candle> = ohlc
+            .into_iter()
+            .map(|ohlc| {
+                let candle = Candle::from(ohlc);
+                Candle {
+                    time: candle.time - native.seconds(),
+                    ..candle
+                }
+            })
+            .collect();
+        resample(&candles, native, request.interval)
+            .ok_or_else(|| format!("only {} candles for this range", native))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{Quota, RateLimiter};
+    use std::sync::Arc;
+
+    const SOL: &str = "So11111111111111111111111111111111111111112";
+
+    #[tokio::test]
+    async fn test_falls_back_when_provider_is_empty() {
+        let mut server = mockito::Server::new_async().await;
+        let birdeye = server
+            .mock("GET", "/defi/ohlcv")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"success":true,"data":{"items":[]}}"#)
+            .create_async()
+            .await;
+        let gmgn = server
+            .mock(
+                "GET",
+                format!("/defi/quotation/v1/tokens/kline/sol/{}", SOL).as_str(),
+            )
+            .match_query(mockito::Matcher::Any)
+            .with_body(
+                r#"{"code":0,"msg":"success","data":[
+                    {"open":"1","high":"2","low":"0.5","close":"1.5","volume":"10","time":"1700002800000"},
+                    {"open":"1.5","high":"2","low":"1","close":"1.8","volume":"12","time":"1700010000000"}
+                ]}"#,
+            )
+            .create_async()
+            .await;
+
+        let limiter = Arc::new(RateLimiter::new(Quota::per_second(100)));
+        let fetcher = CandleFetcher::new()
+            .with_birdeye(
+                BirdeyeClient::new("test-key".to_string())
+                    .with_base_url(server.url())
+                    .with_rate_limiter(limiter.clone()),
+            )
+            .with_gmgn(
+                GmgnClient::new()
+                    .with_base_url(server.url())
+                    .with_rate_limiter(limiter),
+            );
+        let series = fetcher
+            .fetch(&CandleRequest {
+                address: SOL.to_string(),
+                chain: GmgnChain::Sol,
+                interval: CandleInterval::OneHour,
+                time_from: 1_700_000_000,
+                time_to: 1_700_086_400,
+                coingecko_id: None,
+                fill_gaps: true,
+            })
+            .await
+            .unwrap();
+
+        birdeye.assert_async().await;
+        gmgn.assert_async().await;
+        assert_eq!(series.source, CandleSource::Gmgn);
+        assert_eq!(series.skipped, vec!["birdeye: no candles in range"]);
+        assert_eq!(series.gaps.len(), 1);
+        assert_eq!(series.candles.len(), 3);
+        assert!(series.candles[1].filled);
+    }
+
+    #[tokio::test]
+    async fn test_reports_every_failed_source() {
+        let fetcher = CandleFetcher::new()
+            .with_birdeye(BirdeyeClient::new("test-key".to_string()))
+            .with_sources(vec![CandleSource::Birdeye, CandleSource::CoinGecko]);
+        let error = fetcher
+            .fetch(&CandleRequest {
+                address: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".to_string(),
+                chain: GmgnChain::Eth,
+                interval: CandleInterval::OneHour,
+                time_from: 1_700_000_000,
+                time_to: 1_700_086_400,
+                coingecko_id: None,
+                fill_gaps: false,
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            "No candles available (birdeye: only Solana tokens are supported; coingecko: no coingecko_id given)"
+        );
+    }
+}
@@ -0,0 +1,425 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+use crate::{
+    coingecko::models::Ohlc,
+    gmgn::{GmgnResolution, KlineData},
+};
+
+/// Candle width, shared by every provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum CandleInterval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "30m")]
+    ThirtyMinutes,
+    #[serde(rename = "1h")]
+    #[default]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl CandleInterval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandleInterval::OneMinute => "1m",
+            CandleInterval::FiveMinutes => "5m",
+            CandleInterval::FifteenMinutes => "15m",
+            CandleInterval::ThirtyMinutes => "30m",
+            CandleInterval::OneHour => "1h",
+            CandleInterval::FourHours => "4h",
+            CandleInterval::OneDay => "1d",
+        }
+    }
+
+    /// Width of one candle in seconds.
+    pub fn seconds(&self) -> i64 {
+        match self {
+            CandleInterval::OneMinute => 60,
+            CandleInterval::FiveMinutes => 5 * 60,
+            CandleInterval::FifteenMinutes => 15 * 60,
+            CandleInterval::ThirtyMinutes => 30 * 60,
+            CandleInterval::OneHour => 60 * 60,
+            CandleInterval::FourHours => 4 * 60 * 60,
+            CandleInterval::OneDay => 24 * 60 * 60,
+        }
+    }
+
+    /// The `type` value Birdeye's OHLCV endpoints expect.
+    pub fn birdeye_type(&self) -> &'static str {
+        match self {
+            CandleInterval::OneMinute => "1m",
+            CandleInterval::FiveMinutes => "5m",
+            CandleInterval::FifteenMinutes => "15m",
+            CandleInterval::ThirtyMinutes => "30m",
+            CandleInterval::OneHour => "1H",
+            CandleInterval::FourHours => "4H",
+            CandleInterval::OneDay => "1D",
+        }
+    }
+
+    /// The matching GMGN resolution. GMGN has no 30 minute candles.
+    pub fn gmgn_resolution(&self) -> Option<GmgnResolution> {
+        match self {
+            CandleInterval::OneMinute => Some(GmgnResolution::OneMinute),
+            CandleInterval::FiveMinutes => Some(GmgnResolution::FiveMinutes),
+            CandleInterval::FifteenMinutes => Some(GmgnResolution::FifteenMinutes),
+            CandleInterval::ThirtyMinutes => None,
+            CandleInterval::OneHour => Some(GmgnResolution::OneHour),
+            CandleInterval::FourHours => Some(GmgnResolution::FourHours),
+            CandleInterval::OneDay => Some(GmgnResolution::OneDay),
+        }
+    }
+}
+
+impl fmt::Display for CandleInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CandleInterval {
+    type Err = String;
+
+    /// Accepts the provider spellings too, e.g. Birdeye's `1H` or `15`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "1m" | "1" => Ok(CandleInterval::OneMinute),
+            "5m" | "5" => Ok(CandleInterval::FiveMinutes),
+            "15m" | "15" => Ok(CandleInterval::FifteenMinutes),
+            "30m" | "30" => Ok(CandleInterval::ThirtyMinutes),
+            "1h" | "60" => Ok(CandleInterval::OneHour),
+            "4h" | "240" => Ok(CandleInterval::FourHours),
+            "1d" | "d" => Ok(CandleInterval::OneDay),
+            other => Err(format!(
+                "Unsupported interval '{}', expected one of 1m, 5m, 15m, 30m, 1h, 4h, 1d",
+                other
+            )),
+        }
+    }
+}
+
+/// Where a series came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CandleSource {
+    Birdeye,
+    Gmgn,
+    CoinGecko,
+}
+
+impl fmt::Display for CandleSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CandleSource::Birdeye => "birdeye",
+            CandleSource::Gmgn => "gmgn",
+            CandleSource::CoinGecko => "coingecko",
+        })
+    }
+}
+
+/// One OHLCV candle. `time` is the open time in Unix seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    pub time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Traded volume, when the provider reports it (CoinGecko does not).
+    pub volume: Option<f64>,
+    /// Set on candles synthesised by [`CandleSeries::fill_gaps`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub filled: bool,
+}
+
+impl From<KlineData> for Candle {
+    /// GMGN times are in milliseconds.
+    fn from(kline: KlineData) -> Self {
+        Self {
+            time: kline.time / 1000,
+            open: kline.open,
+            high: kline.high,
+            low: kline.low,
+            close: kline.close,
+            volume: Some(kline.volume),
+            filled: false,
+        }
+    }
+}
+
+impl From<Ohlc> for Candle {
+    /// CoinGecko stamps candles in milliseconds. Callers that know the
+    /// candle width should shift `time` back by it, as CoinGecko uses the
+    /// close time.
+    fn from(ohlc: Ohlc) -> Self {
+        Self {
+            time: ohlc.timestamp / 1000,
+            open: ohlc.open,
+            high: ohlc.high,
+            low: ohlc.low,
+            close: ohlc.close,
+            volume: None,
+            filled: false,
+        }
+    }
+}
+
+/// An item of Birdeye's `/defi/ohlcv` and `/defi/ohlcv/pair` responses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BirdeyeOhlcvItem {
+    pub o: f64,
+    pub h: f64,
+    pub l: f64,
+    pub c: f64,
+    pub v: f64,
+    #[serde(rename = "unixTime")]
+    pub unix_time: i64,
+}
+
+impl From<BirdeyeOhlcvItem> for Candle {
+    fn from(item: BirdeyeOhlcvItem) -> Self {
+        Self {
+            time: item.unix_time,
+            open: item.o,
+            high: item.h,
+            low: item.l,
+            close: item.c,
+            volume: Some(item.v),
+            filled: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BirdeyeOhlcvResponse {
+    data: BirdeyeOhlcvData,
+}
+
+#[derive(Debug, Deserialize)]
+struct BirdeyeOhlcvData {
+    #[serde(default)]
+    items: Vec<BirdeyeOhlcvItem>,
+}
+
+/// Parses the raw body of a Birdeye OHLCV response into candles.
+pub fn candles_from_birdeye(body: &str) -> Result<Vec<Candle>, String> {
+    let response: BirdeyeOhlcvResponse = serde_json::from_str(body)
+        .map_err(|e| format!("Error parsing Birdeye OHLCV data: {}", e))?;
+    Ok(response.data.items.into_iter().map(Candle::from).collect())
+}
+
+/// A run of missing candles: `missing` intervals starting at `from`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Gap {
+    pub from: i64,
+    pub to: i64,
+    pub missing: i64,
+}
+
+/// Candles of one interval, sorted by time, with any holes flagged in `gaps`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandleSeries {
+    pub source: CandleSource,
+    pub interval: CandleInterval,
+    pub candles: Vec<Candle>,
+    #[serde(default)]
+    pub gaps: Vec<Gap>,
+    /// Providers tried before `source`, and why they were passed over.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+impl CandleSeries {
+    /// Sorts and de-duplicates `candles`, then records the gaps between them.
+    pub fn new(source: CandleSource, interval: CandleInterval, mut candles: Vec<Candle>) -> Self {
+        candles.sort_by_key(|candle| candle.time);
+        candles.dedup_by_key(|candle| candle.time);
+        let mut series = Self {
+            source,
+            interval,
+            candles,
+            gaps: Vec::new(),
+            skipped: Vec::new(),
+        };
+        series.gaps = series.find_gaps();
+        series
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candles.is_empty()
+    }
+
+    fn find_gaps(&self) -> Vec<Gap> {
+        let step = self.interval.seconds();
+        self.candles
+            .windows(2)
+            .filter_map(|pair| {
+                let missing = (pair[1].time - pair[0].time) / step - 1;
+                (missing > 0).then(|| Gap {
+                    from: pair[0].time + step,
+                    to: pair[1].time - step,
+                    missing,
+                })
+            })
+            .collect()
+    }
+
+    /// Fills every gap with flat candles at the previous close and zero
+    /// volume, marked `filled`. The gaps stay listed so callers can still
+    /// tell real candles from filler.
+    pub fn fill_gaps(&mut self) {
+        if self.gaps.is_empty() {
+            return;
+        }
+        let step = self.interval.seconds();
+        let mut filled = Vec::with_capacity(self.candles.len());
+        for candle in self.candles.drain(..) {
+            if let Some(previous) = filled.last().copied() {
+                let Candle { time, close, .. } = previous;
+                let mut time = time + step;
+                while time < candle.time {
+                    filled.push(Candle {
+                        time,
+                        open: close,
+                        high: close,
+                        low: close,
+                        close,
+                        volume: Some(0.0),
+                        filled: true,
+                    });
+                    time += step;
+                }
+            }
+            filled.push(candle);
+        }
+        self.candles = filled;
+    }
+
+    /// Drops candles outside `[time_from, time_to]`.
+    pub fn retain_range(&mut self, time_from: i64, time_to: i64) {
+        self.candles
+            .retain(|candle| candle.time >= time_from && candle.time <= time_to);
+        self.gaps = self.find_gaps();
+    }
+}
+
+/// Merges `candles` of width `from` into candles of width `to`. Returns
+/// `None` when `to` is not a whole multiple of `from`.
+pub fn resample(
+    candles: &[Candle],
+    from: CandleInterval,
+    to: CandleInterval,
+) -> Option<Vec<Candle>> {
+    let (from, to) = (from.seconds(), to.seconds());
+    if to < from || to % from != 0 {
+        return None;
+    }
+    let mut merged: Vec<Candle> = Vec::new();
+    for candle in candles {
+        let bucket = candle.time - candle.time.rem_euclid(to);
+        match merged.last_mut() {
+            Some(last) if last.time == bucket => {
+                last.high = last.high.max(candle.high);
+                last.low = last.low.min(candle.low);
+                last.close = candle.close;
+                last.volume = match (last.volume, candle.volume) {
+                    (Some(a), Some(b)) => Some(a + b),
+                    _ => None,
+                };
+            }
+            _ => merged.push(Candle {
+                time: bucket,
+                ..*candle
+            }),
+        }
+    }
+    Some(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(time: i64, close: f64) -> Candle {
+        Candle {
+            time,
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume: Some(10.0),
+            filled: false,
+        }
+    }
+
+    #[test]
+    fn test_adapters() {
+        let kline = KlineData {
+            open: 1.0,
+            high: 2.0,
+            low: 0.5,
+            close: 1.5,
+            volume: 100.0,
+            time: 1_700_002_800_000,
+        };
+        assert_eq!(Candle::from(kline).time, 1_700_002_800);
+
+        let body = r#"{"success":true,"data":{"items":[
+            {"o":1.0,"h":2.0,"l":0.5,"c":1.5,"v":100.0,"unixTime":1700002800,"address":"x","type":"1H"}
+        ]}}"#;
+        let candles = candles_from_birdeye(body).unwrap();
+        assert_eq!(candles[0].close, 1.5);
+        assert_eq!(candles[0].volume, Some(100.0));
+    }
+
+    #[test]
+    fn test_gaps_are_flagged_and_filled() {
+        let hour = 3600;
+        let mut series = CandleSeries::new(
+            CandleSource::Gmgn,
+            CandleInterval::OneHour,
+            vec![candle(3 * hour, 12.0), candle(0, 10.0), candle(hour, 11.0)],
+        );
+        assert_eq!(
+            series.gaps,
+            vec![Gap {
+                from: 2 * hour,
+                to: 2 * hour,
+                missing: 1
+            }]
+        );
+
+        series.fill_gaps();
+        let times: Vec<i64> = series.candles.iter().map(|c| c.time).collect();
+        assert_eq!(times, vec![0, hour, 2 * hour, 3 * hour]);
+        assert!(series.candles[2].filled);
+        assert_eq!(series.candles[2].close, 11.0);
+        assert_eq!(series.gaps.len(), 1);
+    }
+
+    #[test]
+    fn test_resample() {
+        let candles: Vec<Candle> = (0..8).map(|i| candle(i * 30 * 60, i as f64)).collect();
+        let hourly = resample(
+            &candles,
+            CandleInterval::ThirtyMinutes,
+            CandleInterval::OneHour,
+        )
+        .unwrap();
+        assert_eq!(hourly.len(), 4);
+        assert_eq!(hourly[1].open, 2.0);
+        assert_eq!(hourly[1].close, 3.0);
+        assert_eq!(hourly[1].high, 4.0);
+        assert_eq!(hourly[1].volume, Some(20.0));
+
+        assert!(resample(&candles, CandleInterval::FourHours, CandleInterval::OneHour).is_none());
+    }
+}
@@ -0,0 +1,190 @@
+pub mod render;
+
+use crate::{
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
+    candles::{fetcher::CandleFetcher, CandleInterval, CandleSource, CandlesParams},
+    output::{self, Attachment, UiHint},
+    AgentState,
+};
+pub use render::{ChartKind, ChartOptions, Overlay};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct PriceChartParams {
+    #[serde(flatten)]
+    candles: CandlesParams,
+    kind: Option<String>,
+    overlays: Option<String>,
+    title: Option<String>,
+}
+
+/// What the model is told about a chart it sent; the image itself goes to
+/// the user.
+#[derive(Debug, Serialize)]
+struct ChartSummary {
+    attached: String,
+    source: CandleSource,
+    interval: CandleInterval,
+    candles: usize,
+    open: f64,
+    close: f64,
+    change_percent: f64,
+    high: f64,
+    low: f64,
+}
+
+pub struct ChartActionGroup<S: Send + Sync + Clone + 'static> {
+    actions: Vec<Arc<FunctionAction<S>>>,
+}
+
+impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for ChartActionGroup<S> {
+    fn actions(&self) -> &[Arc<FunctionAction<S>>] {
+        &self.actions
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> Default for ChartActionGroup<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> ChartActionGroup<S> {
+    pub fn new() -> Self {
+        Self::with_fetcher(CandleFetcher::new())
+    }
+
+    /// Builds the group around the fetcher that supplies its candles.
+    pub fn with_fetcher(fetcher: CandleFetcher) -> Self {
+        let mut actions = Vec::new();
+        // Add price chart action
+        {
+            async fn render_price_chart<S: Send + Sync + Clone + 'static>(
+                fetcher: CandleFetcher,
+                params: PriceChartParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let kind = params
+                    .kind
+                    .as_deref()
+                    .map(str::parse::<ChartKind>)
+                    .transpose()?
+                    .unwrap_or_default();
+                let overlays = params
+                    .overlays
+                    .as_deref()
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|overlay| !overlay.trim().is_empty())
+                    .map(str::parse::<Overlay>)
+                    .collect::<Result<Vec<_>, _>>()?;
+                let request = params.candles.into_request()?;
+                let series = fetcher.fetch(&request).await?;
+                let (first, last) = match (series.candles.first(), series.candles.last()) {
+                    (Some(first), Some(last)) => (first, last),
+                    _ => return Err("No candles to chart".to_string()),
+                };
+                let title = params
+                    .title
+                    .unwrap_or_else(|| format!("{} {}", request.address, series.interval));
+                let png = render::render_png(
+                    &series,
+                    &ChartOptions {
+                        kind,
+                        overlays,
+                        title,
+                        ..Default::default()
+                    },
+                )?;
+
+                let file_name = format!("{}_{}.png", request.address, series.interval);
+                if !output::attach(Attachment::png(file_name.clone(), png)) {
+                    return Err("This agent cannot send images to the user".to_string());
+                }
+                output::hint(UiHint::link(
+                    "Open on GMGN",
+                    format!(
+                        "https://gmgn.ai/{}/token/{}",
+                        request.chain, request.address
+                    ),
+                ));
+                let summary = ChartSummary {
+                    attached: file_name,
+                    source: series.source,
+                    interval: series.interval,
+                    candles: series.candles.len(),
+                    open: first.open,
+                    close: last.close,
+                    change_percent: (last.close - first.open) / first.open * 100.0,
+                    high: series
+                        .candles
+                        .iter()
+                        .map(|c| c.high)
+                        .fold(f64::MIN, f64::max),
+                    low: series
+                        .candles
+                        .iter()
+                        .map(|c| c.low)
+                        .fold(f64::MAX, f64::min),
+                };
+                serde_json::to_string(&summary)
+                    .map_err(|e| format!("Failed to serialize chart summary: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "render_price_chart",
+                with_client(&fetcher, render_price_chart),
+                None,
+            )
+            .description(
+                "Render a candlestick or line chart of a token's price, optionally with indicator overlays, and send it to the user as an image. Returns a short summary of the charted candles; your reply is shown as the image caption, so keep it brief",
+            )
+            .parameter("address", "Token address", "string", true)
+            .parameter("time_from", "Start timestamp (Unix seconds)", "integer", true)
+            .parameter("time_to", "End timestamp (Unix seconds)", "integer", true)
+            .parameter(
+                "interval",
+                "Candle width: 1m, 5m, 15m, 30m, 1h, 4h or 1d (default 1h)",
+                "string",
+                false,
+            )
+            .parameter(
+                "chain",
+                "Chain: sol, eth, base, bsc or tron (default sol)",
+                "string",
+                false,
+            )
+            .parameter(
+                "coingecko_id",
+                "CoinGecko coin id (e.g. solana), enables CoinGecko as a last resort",
+                "string",
+                false,
+            )
+            .parameter(
+                "kind",
+                "Chart kind: candlestick or line (default candlestick)",
+                "string",
+                false,
+            )
+            .parameter(
+                "overlays",
+                "Comma-separated overlays: smaN, emaN, bbN (Bollinger Bands) or vwap, e.g. sma20,bb20",
+                "string",
+                false,
+            )
+            .parameter(
+                "title",
+                "Chart title (default the address and interval)",
+                "string",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        Self { actions }
+    }
+}
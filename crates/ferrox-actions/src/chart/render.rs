@@ -0,0 +1,428 @@
+//! PNG rendering of candle series on the CPU, via plotters' bitmap backend.
+
+use std::{str::FromStr, sync::OnceLock};
+
+use plotters::{
+    prelude::*,
+    style::{register_font, FontStyle},
+};
+
+use crate::{
+    candles::{CandleInterval, CandleSeries},
+    ta::indicators,
+};
+
+/// Fonts tried, in order, when `FERROX_CHART_FONT` is not set.
+const FONT_PATHS: [&str; 6] = [
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+
+const FONT: &str = "sans-serif";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartKind {
+    #[default]
+    Candlestick,
+    Line,
+}
+
+impl FromStr for ChartKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "candlestick" | "candles" | "candle" => Ok(ChartKind::Candlestick),
+            "line" => Ok(ChartKind::Line),
+            other => Err(format!(
+                "Unsupported chart kind '{}', expected candlestick or line",
+                other
+            )),
+        }
+    }
+}
+
+/// An indicator drawn over the price.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overlay {
+    Sma(usize),
+    Ema(usize),
+    Bollinger(usize),
+    Vwap,
+}
+
+impl FromStr for Overlay {
+    type Err = String;
+
+    /// Parses `sma20`, `ema50`, `bb20` or `vwap`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let period = |prefix: &str| {
+            s[prefix.len()..]
+                .parse::<usize>()
+                .ok()
+                .filter(|period| *period > 0)
+                .ok_or_else(|| format!("Overlay '{}' needs a period, e.g. {}20", s, prefix))
+        };
+        if s == "vwap" {
+            Ok(Overlay::Vwap)
+        } else if s.starts_with("sma") {
+            Ok(Overlay::Sma(period("sma")?))
+        } else if s.starts_with("ema") {
+            Ok(Overlay::Ema(period("ema")?))
+        } else if s.starts_with("bb") {
+            Ok(Overlay::Bollinger(period("bb")?))
+        } else {
+            Err(format!(
+                "Unsupported overlay '{}', expected smaN, emaN, bbN or vwap",
+                s
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChartOptions {
+    pub kind: ChartKind,
+    pub overlays: Vec<Overlay>,
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for ChartOptions {
+    fn default() -> Self {
+        Self {
+            kind: ChartKind::default(),
+            overlays: Vec::new(),
+            title: String::new(),
+            width: 1024,
+            height: 640,
+        }
+    }
+}
+
+/// Registers a TTF font for labels, once. Returns whether one was found.
+fn font_available() -> bool {
+    static FONT_LOADED: OnceLock<bool> = OnceLock::new();
+    *FONT_LOADED.get_or_init(|| {
+        let configured = std::env::var("FERROX_CHART_FONT").ok();
+        let font = configured
+            .iter()
+            .map(String::as_str)
+            .chain(FONT_PATHS)
+            .find_map(|path| std::fs::read(path).ok())
+            .is_some_and(|bytes| {
+                // plotters keeps a reference for the life of the process
+                let bytes: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+                register_font(FONT, FontStyle::Normal, bytes).is_ok()
+            });
+        font
+    })
+}
+
+const OVERLAY_COLORS: [RGBColor; 4] = [
+    RGBColor(33, 150, 243),
+    RGBColor(255, 152, 0),
+    RGBColor(156, 39, 176),
+    RGBColor(0, 150, 136),
+];
+
+/// Renders `series` as a PNG. Volume, when the provider reports it, is drawn
+/// in a panel under the price.
+pub fn render_png(series: &CandleSeries, options: &ChartOptions) -> Result<Vec<u8>, String> {
+    let candles = &series.candles;
+    if candles.is_empty() {
+        return Err("No candles to chart".to_string());
+    }
+    let (width, height) = (options.width, options.height);
+    let labels = font_available();
+    let mut pixels = vec![0u8; width as usize * height as usize * 3];
+    draw(series, options, labels, &mut pixels)
+        .map_err(|e| format!("Failed to draw chart: {}", e))?;
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|e| format!("Failed to encode chart: {}", e))?;
+    Ok(png)
+}
+
+fn draw(
+    series: &CandleSeries,
+    options: &ChartOptions,
+    labels: bool,
+    pixels: &mut [u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let candles = &series.candles;
+    let step = series.interval.seconds();
+    let x_range = candles[0].time - step..candles[candles.len() - 1].time + step;
+    let closes: Vec<f64> = candles.iter().map(|c| c.close).collect();
+
+    let overlays: Vec<(String, Vec<(i64, f64)>)> = options
+        .overlays
+        .iter()
+        .flat_map(|overlay| overlay_lines(overlay, series, &closes))
+        .collect();
+    let prices = candles.iter().flat_map(|c| [c.low, c.high]).chain(
+        overlays
+            .iter()
+            .flat_map(|(_, line)| line.iter().map(|p| p.1)),
+    );
+    let (low, high) = prices.fold((f64::MAX, f64::MIN), |(lo, hi), p| (lo.min(p), hi.max(p)));
+    let padding = ((high - low) * 0.05).max(high.abs() * 1e-6);
+    let y_range = low - padding..high + padding;
+
+    let root =
+        BitMapBackend::with_buffer(pixels, (options.width, options.height)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let has_volume = candles.iter().any(|c| c.volume.is_some_and(|v| v > 0.0));
+    let (upper, lower) = if has_volume {
+        root.split_vertically(options.height * 3 / 4)
+    } else {
+        (root.clone(), root.clone())
+    };
+
+    let label_size = if labels { 80 } else { 0 };
+    let mut builder = ChartBuilder::on(&upper);
+    builder
+        .margin(12)
+        .y_label_area_size(label_size)
+        .x_label_area_size(if has_volume { 0 } else { label_size / 2 });
+    if labels && !options.title.is_empty() {
+        builder.caption(&options.title, (FONT, 22));
+    }
+    let mut chart = builder.build_cartesian_2d(x_range.clone(), y_range)?;
+    let time_format = time_format(series.interval);
+    let format_x = |time: &i64| format_time(*time, time_format);
+    let format_y = |value: &f64| format_price(*value);
+    let mut mesh = chart.configure_mesh();
+    // Without a font the label areas are empty, so no text is drawn
+    mesh.light_line_style(WHITE.mix(0.0)).x_labels(8);
+    if labels {
+        mesh.label_style((FONT, 13))
+            .y_label_formatter(&format_y)
+            .x_label_formatter(&format_x);
+    }
+    mesh.draw()?;
+
+    let body_width = ((options.width as f64 * 0.85 / candles.len() as f64) * 0.7).max(1.0) as u32;
+    match options.kind {
+        ChartKind::Candlestick => {
+            chart.draw_series(candles.iter().map(|c| {
+                CandleStick::new(
+                    c.time,
+                    c.open,
+                    c.high,
+                    c.low,
+                    c.close,
+                    RGBColor(38, 166, 154).filled(),
+                    RGBColor(239, 83, 80).filled(),
+                    body_width,
+                )
+            }))?;
+        }
+        ChartKind::Line => {
+            chart.draw_series(LineSeries::new(
+                candles.iter().map(|c| (c.time, c.close)),
+                BLACK.stroke_width(2),
+            ))?;
+        }
+    }
+
+    for (index, (name, line)) in overlays.iter().enumerate() {
+        let color = OVERLAY_COLORS[index % OVERLAY_COLORS.len()];
+        let drawn =
+            chart.draw_series(LineSeries::new(line.iter().copied(), color.stroke_width(2)))?;
+        if labels {
+            drawn
+                .label(name.as_str())
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+        }
+    }
+    if labels && !overlays.is_empty() {
+        chart
+            .configure_series_labels()
+            .label_font((FONT, 13))
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK.mix(0.3))
+            .position(SeriesLabelPosition::UpperLeft)
+            .draw()?;
+    }
+
+    if has_volume {
+        let max_volume = candles.iter().filter_map(|c| c.volume).fold(0.0, f64::max);
+        let mut volume = ChartBuilder::on(&lower)
+            .margin(12)
+            .y_label_area_size(label_size)
+            .x_label_area_size(label_size / 2)
+            .build_cartesian_2d(x_range, 0.0..max_volume * 1.1)?;
+        let mut mesh = volume.configure_mesh();
+        mesh.light_line_style(WHITE.mix(0.0))
+            .x_labels(8)
+            .y_labels(3);
+        if labels {
+            mesh.label_style((FONT, 13))
+                .y_label_formatter(&format_y)
+                .x_label_formatter(&format_x);
+        }
+        mesh.draw()?;
+        let half = step * 7 / 20;
+        volume.draw_series(candles.iter().filter(|c| !c.filled).map(|c| {
+            let color = if c.close >= c.open {
+                RGBColor(38, 166, 154)
+            } else {
+                RGBColor(239, 83, 80)
+            };
+            Rectangle::new(
+                [
+                    (c.time - half, 0.0),
+                    (c.time + half, c.volume.unwrap_or(0.0)),
+                ],
+                color.mix(0.6).filled(),
+            )
+        }))?;
+    }
+
+    root.present()?;
+    Ok(())
+}
+
+/// The line(s) an overlay draws, with their legend names.
+fn overlay_lines(
+    overlay: &Overlay,
+    series: &CandleSeries,
+    closes: &[f64],
+) -> Vec<(String, Vec<(i64, f64)>)> {
+    let points = |values: Vec<Option<f64>>| -> Vec<(i64, f64)> {
+        series
+            .candles
+            .iter()
+            .zip(values)
+            .filter_map(|(candle, value)| Some((candle.time, value?)))
+            .collect()
+    };
+    match *overlay {
+        Overlay::Sma(period) => vec![(
+            format!("SMA {}", period),
+            points(indicators::sma(closes, period)),
+        )],
+        Overlay::Ema(period) => vec![(
+            format!("EMA {}", period),
+            points(indicators::ema(closes, period)),
+        )],
+        Overlay::Bollinger(period) => {
+            let bands = indicators::bollinger(closes, period, 2.0);
+            vec![
+                (
+                    format!("BB {} upper", period),
+                    points(bands.iter().map(|b| b.map(|b| b.upper)).collect()),
+                ),
+                (
+                    format!("BB {} lower", period),
+                    points(bands.iter().map(|b| b.map(|b| b.lower)).collect()),
+                ),
+            ]
+        }
+        Overlay::Vwap => vec![(
+            "VWAP".to_string(),
+            points(indicators::vwap(&series.candles)),
+        )],
+    }
+}
+
+fn time_format(interval: CandleInterval) -> &'static str {
+    if interval >= CandleInterval::OneDay {
+        "%Y-%m-%d"
+    } else {
+        "%m-%d %H:%M"
+    }
+}
+
+fn format_time(time: i64, format: &str) -> String {
+    chrono::DateTime::from_timestamp(time, 0)
+        .map(|time| time.format(format).to_string())
+        .unwrap_or_default()
+}
+
+/// Short price labels that stay readable from memecoin to BTC prices.
+fn format_price(price: f64) -> String {
+    let magnitude = price.abs();
+    if magnitude >= 1_000_000.0 {
+        format!("{:.2}M", price / 1_000_000.0)
+    } else if magnitude >= 1_000.0 {
+        format!("{:.1}K", price / 1_000.0)
+    } else if magnitude >= 1.0 || magnitude == 0.0 {
+        format!("{:.2}", price)
+    } else {
+        format!("{:.*}", (-magnitude.log10()).ceil() as usize + 3, price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candles::{Candle, CandleSource};
+
+    fn series(volume: Option<f64>) -> CandleSeries {
+        let candles = (0..48)
+            .map(|i| {
+                let close = 100.0 + (i as f64 / 4.0).sin() * 5.0;
+                Candle {
+                    time: 1_700_000_000 + i * 3600,
+                    open: close - 1.0,
+                    high: close + 2.0,
+                    low: close - 2.0,
+                    close,
+                    volume,
+                    filled: false,
+                }
+            })
+            .collect();
+        CandleSeries::new(CandleSource::Birdeye, CandleInterval::OneHour, candles)
+    }
+
+    #[test]
+    fn test_renders_png() {
+        let options = ChartOptions {
+            overlays: vec![Overlay::Sma(10), Overlay::Bollinger(20), Overlay::Vwap],
+            title: "SOL/USD 1h".to_string(),
+            ..Default::default()
+        };
+        let png = render_png(&series(Some(1000.0)), &options).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let line = ChartOptions {
+            kind: ChartKind::Line,
+            width: 400,
+            height: 300,
+            ..Default::default()
+        };
+        let png = render_png(&series(None), &line).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn test_parse_overlays() {
+        assert_eq!("SMA20".parse::<Overlay>(), Ok(Overlay::Sma(20)));
+        assert_eq!("bb20".parse::<Overlay>(), Ok(Overlay::Bollinger(20)));
+        assert_eq!("vwap".parse::<Overlay>(), Ok(Overlay::Vwap));
+        assert!("sma".parse::<Overlay>().is_err());
+        assert!("rsi14".parse::<Overlay>().is_err());
+    }
+
+    #[test]
+    fn test_format_price() {
+        assert_eq!(format_price(64_012.5), "64.0K");
+        assert_eq!(format_price(147.853), "147.85");
+        assert_eq!(format_price(0.0000241), "0.00002410");
+    }
+}
@@ -1,11 +1,16 @@
-pub mod pro;
+pub mod client;
+pub mod error;
+pub mod models;
+
+/// The client module under its old name.
+pub use client as pro;
 
 use crate::{
-    action::{ActionBuilder, ActionGroup, FunctionAction},
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
     AgentState,
 };
-use pro::CoinGeckoProClient;
-use serde::Deserialize;
+use client::{CoinGeckoClient, CoinGeckoTier};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 // Parameter structs for each action
@@ -155,6 +160,16 @@ pub struct CompaniesPublicTreasuryParams {
     coin_id: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct TopGainersLosersParams {
+    vs_currency: String,
+    duration: Option<String>,
+    top_coins: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewCoinsParams {}
+
 // Action group that contains all CoinGecko actions
 pub struct CoinGeckoActionGroup<S: Send + Sync + Clone + 'static> {
     actions: Vec<Arc<FunctionAction<S>>>,
@@ -166,27 +181,37 @@ impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for CoinGeckoActionGroup<S
     }
 }
 
+impl<S: Send + Sync + Clone + 'static> Default for CoinGeckoActionGroup<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
+    /// Builds the group for the tier configured in the environment. See
+    /// [`CoinGeckoClient::from_env`].
     pub fn new() -> Self {
+        Self::with_client(CoinGeckoClient::from_env())
+    }
+
+    /// Builds the group around a configured client. Pro-only actions are
+    /// registered only when the client is on the Pro tier.
+    pub fn with_client(client: CoinGeckoClient) -> Self {
+        let tier = client.tier();
         let mut actions = Vec::new();
 
         // Update coin contract market chart range action
         {
             async fn get_coin_contract_market_chart_range<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: CoinContractMarketChartRangeParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-
                 let to = chrono::Utc::now().timestamp() as u64;
                 let from = calculate_from_timestamp(to, &params.days)?;
 
-                client
+                let result = client
                     .get_coin_contract_market_chart_range(
                         params.id,
                         params.contract_address,
@@ -194,12 +219,13 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
                         from,
                         to,
                     )
-                    .await
+                    .await?;
+                to_json(&result)
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_coin_contract_market_chart_range",
-                get_coin_contract_market_chart_range,
+                with_client(&client, get_coin_contract_market_chart_range),
                 None,
             )
             .description("Get historical market data for a token contract address")
@@ -230,28 +256,25 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add coin market chart action
         {
             async fn get_coin_market_chart<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: CoinMarketChartParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client
+                let result = client
                     .get_coin_market_chart(
                         params.id,
                         params.vs_currency,
                         params.days,
                         params.interval,
                     )
-                    .await
+                    .await?;
+                to_json(&result)
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_coin_market_chart",
-                get_coin_market_chart,
+                with_client(&client, get_coin_market_chart),
                 None,
             )
             .description("Get historical market data include price, market cap, and 24h volume")
@@ -272,22 +295,21 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add network status action
         {
             async fn get_network_status<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 _params: NetworkStatusParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_network_status().await
+                client.get_network_status().await.map_err(|e| e.to_string())
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_network_status", get_network_status, None)
-                    .description("Check API server status")
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_network_status",
+                with_client(&client, get_network_status),
+                None,
+            )
+            .description("Check API server status")
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -295,21 +317,22 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add global data action
         {
             async fn get_global_data<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 _params: GlobalDataParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_global_data().await
+                let result = client.get_global_data().await?;
+                to_json(&result)
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_global_data", get_global_data, None)
-                .description("Get cryptocurrency global data")
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_global_data",
+                with_client(&client, get_global_data),
+                None,
+            )
+            .description("Get cryptocurrency global data")
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -317,21 +340,18 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add global defi data action
         {
             async fn get_global_defi_data<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 _params: GlobalDefiDataParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_global_defi_data().await
+                let result = client.get_global_defi_data().await?;
+                to_json(&result)
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_global_defi_data",
-                get_global_defi_data,
+                with_client(&client, get_global_defi_data),
                 None,
             )
             .description("Get cryptocurrency global decentralized finance (defi) data")
@@ -343,23 +363,26 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add exchanges action
         {
             async fn get_exchanges<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: ExchangesParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_exchanges(params.per_page, params.page).await
+                client
+                    .get_exchanges(params.per_page, params.page)
+                    .await
+                    .map_err(|e| e.to_string())
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_exchanges", get_exchanges, None)
-                .description("List all exchanges")
-                .parameter("per_page", "Total results per page", "integer", false)
-                .parameter("page", "Page number", "integer", false)
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_exchanges",
+                with_client(&client, get_exchanges),
+                None,
+            )
+            .description("List all exchanges")
+            .parameter("per_page", "Total results per page", "integer", false)
+            .parameter("page", "Page number", "integer", false)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -367,22 +390,25 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add exchange action
         {
             async fn get_exchange<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: ExchangeParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_exchange(params.id).await
+                client
+                    .get_exchange(params.id)
+                    .await
+                    .map_err(|e| e.to_string())
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_exchange", get_exchange, None)
-                .description("Get exchange volume in BTC and top 100 tickers only")
-                .parameter("id", "Exchange id", "string", true)
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_exchange",
+                with_client(&client, get_exchange),
+                None,
+            )
+            .description("Get exchange volume in BTC and top 100 tickers only")
+            .parameter("id", "Exchange id", "string", true)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -390,16 +416,12 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add exchange tickers action
         {
             async fn get_exchange_tickers<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: ExchangeTickersParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client
+                let result = client
                     .get_exchange_tickers(
                         params.id,
                         params.coin_ids,
@@ -408,12 +430,13 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
                         params.depth,
                         params.order,
                     )
-                    .await
+                    .await?;
+                to_json(&result)
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_exchange_tickers",
-                get_exchange_tickers,
+                with_client(&client, get_exchange_tickers),
                 None,
             )
             .description("Get exchange tickers (paginated)")
@@ -436,23 +459,20 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add exchange volume chart action
         {
             async fn get_exchange_volume_chart<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: ExchangeVolumeChartParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
                 client
                     .get_exchange_volume_chart(params.id, params.days)
                     .await
+                    .map_err(|e| e.to_string())
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_exchange_volume_chart",
-                get_exchange_volume_chart,
+                with_client(&client, get_exchange_volume_chart),
                 None,
             )
             .description("Get volume chart data for a given exchange")
@@ -466,27 +486,30 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add coins list action
         {
             async fn get_coins_list<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: CoinsListParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_coins_list(params.include_platform).await
+                client
+                    .get_coins_list(params.include_platform)
+                    .await
+                    .map_err(|e| e.to_string())
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_coins_list", get_coins_list, None)
-                .description("List all supported coins with id and name")
-                .parameter(
-                    "include_platform",
-                    "Include platform contract addresses",
-                    "boolean",
-                    false,
-                )
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_coins_list",
+                with_client(&client, get_coins_list),
+                None,
+            )
+            .description("List all supported coins with id and name")
+            .parameter(
+                "include_platform",
+                "Include platform contract addresses",
+                "boolean",
+                false,
+            )
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -494,16 +517,12 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add coin tickers action
         {
             async fn get_coin_tickers<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: CoinTickersParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client
+                let result = client
                     .get_coin_tickers(
                         params.id,
                         params.exchange_ids,
@@ -512,29 +531,33 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
                         params.order,
                         params.depth,
                     )
-                    .await
+                    .await?;
+                to_json(&result)
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_coin_tickers", get_coin_tickers, None)
-                    .description("Get coin tickers (paginated to 100 items)")
-                    .parameter("id", "The coin id", "string", true)
-                    .parameter(
-                        "exchange_ids",
-                        "Filter results by exchange ids",
-                        "array",
-                        false,
-                    )
-                    .parameter(
-                        "include_exchange_logo",
-                        "Include exchange logo",
-                        "boolean",
-                        false,
-                    )
-                    .parameter("page", "Page through results", "integer", false)
-                    .parameter("order", "Sort results by order", "string", false)
-                    .parameter("depth", "Include 2% orderbook depth", "boolean", false)
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_coin_tickers",
+                with_client(&client, get_coin_tickers),
+                None,
+            )
+            .description("Get coin tickers (paginated to 100 items)")
+            .parameter("id", "The coin id", "string", true)
+            .parameter(
+                "exchange_ids",
+                "Filter results by exchange ids",
+                "array",
+                false,
+            )
+            .parameter(
+                "include_exchange_logo",
+                "Include exchange logo",
+                "boolean",
+                false,
+            )
+            .parameter("page", "Page through results", "integer", false)
+            .parameter("order", "Sort results by order", "string", false)
+            .parameter("depth", "Include 2% orderbook depth", "boolean", false)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -542,23 +565,20 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add coin history action
         {
             async fn get_coin_history<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: CoinHistoryParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
                 client
                     .get_coin_history(params.id, params.date, params.localization)
                     .await
+                    .map_err(|e| e.to_string())
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_coin_history",
-                get_coin_history,
+                with_client(&client, get_coin_history),
                 None,
             )
             .description(
@@ -585,31 +605,32 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add coin OHLC action
         {
             async fn get_coin_ohlc<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: CoinOhlcParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client
+                let result = client
                     .get_coin_ohlc(params.id, params.vs_currency, params.days)
-                    .await
+                    .await?;
+                to_json(&result)
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_coin_ohlc", get_coin_ohlc, None)
-                .description("Get coin's OHLC (Open, High, Low, Close) data")
-                .parameter("id", "The coin id", "string", true)
-                .parameter(
-                    "vs_currency",
-                    "The target currency of market data (usd, eur, jpy, etc.)",
-                    "string",
-                    true,
-                )
-                .parameter("days", "Data up to number of days ago", "string", true)
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_coin_ohlc",
+                with_client(&client, get_coin_ohlc),
+                None,
+            )
+            .description("Get coin's OHLC (Open, High, Low, Close) data")
+            .parameter("id", "The coin id", "string", true)
+            .parameter(
+                "vs_currency",
+                "The target currency of market data (usd, eur, jpy, etc.)",
+                "string",
+                true,
+            )
+            .parameter("days", "Data up to number of days ago", "string", true)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -617,31 +638,31 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add coin contract action
         {
             async fn get_coin_contract<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: CoinContractParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client
+                let result = client
                     .get_coin_contract(params.id, params.contract_address)
-                    .await
+                    .await?;
+                to_json(&result)
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_coin_contract", get_coin_contract, None)
-                    .description("Get coin info from contract address")
-                    .parameter("id", "Asset platform (e.g. ethereum)", "string", true)
-                    .parameter(
-                        "contract_address",
-                        "Token's contract address",
-                        "string",
-                        true,
-                    )
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_coin_contract",
+                with_client(&client, get_coin_contract),
+                None,
+            )
+            .description("Get coin info from contract address")
+            .parameter("id", "Asset platform (e.g. ethereum)", "string", true)
+            .parameter(
+                "contract_address",
+                "Token's contract address",
+                "string",
+                true,
+            )
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -649,28 +670,25 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add coin contract market chart action
         {
             async fn get_coin_contract_market_chart<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: CoinContractMarketChartParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client
+                let result = client
                     .get_coin_contract_market_chart(
                         params.id,
                         params.contract_address,
                         params.vs_currency,
                         params.days,
                     )
-                    .await
+                    .await?;
+                to_json(&result)
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_coin_contract_market_chart",
-                get_coin_contract_market_chart,
+                with_client(&client, get_coin_contract_market_chart),
                 None,
             )
             .description("Get historical market data for a contract address")
@@ -696,22 +714,24 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add asset platforms action
         {
             async fn get_asset_platforms<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 _params: AssetPlatformsParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_asset_platforms().await
+                client
+                    .get_asset_platforms()
+                    .await
+                    .map_err(|e| e.to_string())
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_asset_platforms", get_asset_platforms, None)
-                    .description("List all asset platforms (blockchain networks)")
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_asset_platforms",
+                with_client(&client, get_asset_platforms),
+                None,
+            )
+            .description("List all asset platforms (blockchain networks)")
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -719,21 +739,20 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add coins categories list action
         {
             async fn get_coins_categories_list<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 _params: CoinsCategoriesListParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_coins_categories_list().await
+                client
+                    .get_coins_categories_list()
+                    .await
+                    .map_err(|e| e.to_string())
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_coins_categories_list",
-                get_coins_categories_list,
+                with_client(&client, get_coins_categories_list),
                 None,
             )
             .description("List all categories")
@@ -745,21 +764,20 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add coins categories action
         {
             async fn get_coins_categories<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: CoinsCategoriesParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_coins_categories(params.order).await
+                client
+                    .get_coins_categories(params.order)
+                    .await
+                    .map_err(|e| e.to_string())
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_coins_categories",
-                get_coins_categories,
+                with_client(&client, get_coins_categories),
                 None,
             )
             .description("List all categories with market data")
@@ -772,21 +790,21 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add indexes action
         {
             async fn get_indexes<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 _params: IndexesParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_indexes().await
+                client.get_indexes().await.map_err(|e| e.to_string())
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_indexes", get_indexes, None)
-                .description("List all market indexes")
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_indexes",
+                with_client(&client, get_indexes),
+                None,
+            )
+            .description("List all market indexes")
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -794,22 +812,21 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add indexes list action
         {
             async fn get_indexes_list<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 _params: IndexesListParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_indexes_list().await
+                client.get_indexes_list().await.map_err(|e| e.to_string())
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_indexes_list", get_indexes_list, None)
-                    .description("List market indexes id and name")
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_indexes_list",
+                with_client(&client, get_indexes_list),
+                None,
+            )
+            .description("List market indexes id and name")
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -817,21 +834,21 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add derivatives action
         {
             async fn get_derivatives<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 _params: DerivativesParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_derivatives().await
+                client.get_derivatives().await.map_err(|e| e.to_string())
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_derivatives", get_derivatives, None)
-                .description("List all derivative tickers")
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_derivatives",
+                with_client(&client, get_derivatives),
+                None,
+            )
+            .description("List all derivative tickers")
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -839,23 +856,20 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add derivatives exchanges action
         {
             async fn get_derivatives_exchanges<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: DerivativesExchangesParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
                 client
                     .get_derivatives_exchanges(params.order, params.per_page, params.page)
                     .await
+                    .map_err(|e| e.to_string())
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_derivatives_exchanges",
-                get_derivatives_exchanges,
+                with_client(&client, get_derivatives_exchanges),
                 None,
             )
             .description("List all derivative exchanges")
@@ -870,23 +884,20 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add derivatives exchange action
         {
             async fn get_derivatives_exchange<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: DerivativesExchangeParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
                 client
                     .get_derivatives_exchange(params.id, params.include_tickers)
                     .await
+                    .map_err(|e| e.to_string())
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_derivatives_exchange",
-                get_derivatives_exchange,
+                with_client(&client, get_derivatives_exchange),
                 None,
             )
             .description("Show derivative exchange data")
@@ -905,22 +916,21 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add exchange rates action
         {
             async fn get_exchange_rates<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 _params: ExchangeRatesParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_exchange_rates().await
+                client.get_exchange_rates().await.map_err(|e| e.to_string())
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_exchange_rates", get_exchange_rates, None)
-                    .description("Get BTC-to-Currency exchange rates")
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_exchange_rates",
+                with_client(&client, get_exchange_rates),
+                None,
+            )
+            .description("Get BTC-to-Currency exchange rates")
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -928,22 +938,20 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add search action
         {
             async fn search<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: SearchParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.search(params.query).await
+                let result = client.search(params.query).await?;
+                to_json(&result)
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("search", search, None)
-                .description("Search for coins, categories and markets")
-                .parameter("query", "Search string", "string", true)
-                .build();
+            let action =
+                ActionBuilder::<_, _, _, _>::new("search", with_client(&client, search), None)
+                    .description("Search for coins, categories and markets")
+                    .parameter("query", "Search string", "string", true)
+                    .build();
 
             actions.push(Arc::new(action));
         }
@@ -951,21 +959,22 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add trending action
         {
             async fn get_trending<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 _params: TrendingParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_trending().await
+                let result = client.get_trending().await?;
+                to_json(&result)
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_trending", get_trending, None)
-                .description("Get trending search coins (Top-7) on CoinGecko")
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_trending",
+                with_client(&client, get_trending),
+                None,
+            )
+            .description("Get trending search coins (Top-7) on CoinGecko")
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -973,21 +982,20 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
         // Add companies public treasury action
         {
             async fn get_companies_public_treasury<S: Send + Sync + Clone + 'static>(
+                client: CoinGeckoClient,
                 params: CompaniesPublicTreasuryParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let api_key = std::env::var("COINGECKO_PRO_API_KEY").map_err(|_| {
-                    "COINGECKO_PRO_API_KEY environment variable not set".to_string()
-                })?;
-
-                let client = CoinGeckoProClient::new(api_key);
-                client.get_companies_public_treasury(params.coin_id).await
+                client
+                    .get_companies_public_treasury(params.coin_id)
+                    .await
+                    .map_err(|e| e.to_string())
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_companies_public_treasury",
-                get_companies_public_treasury,
+                with_client(&client, get_companies_public_treasury),
                 None,
             )
             .description("Get public companies bitcoin or ethereum holdings")
@@ -1002,6 +1010,78 @@ impl<S: Send + Sync + Clone + 'static> CoinGeckoActionGroup<S> {
             actions.push(Arc::new(action));
         }
 
+        // Pro-only actions are hidden from the model on the public and Demo tiers
+        if tier == CoinGeckoTier::Pro {
+            // Add top gainers/losers action
+            {
+                async fn get_top_gainers_losers<S: Send + Sync + Clone + 'static>(
+                    client: CoinGeckoClient,
+                    params: TopGainersLosersParams,
+                    _send_state: serde_json::Value,
+                    _state: AgentState<S>,
+                ) -> Result<String, String> {
+                    client
+                        .get_top_gainers_losers(
+                            params.vs_currency,
+                            params.duration,
+                            params.top_coins,
+                        )
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+
+                let action = ActionBuilder::<_, _, _, _>::new(
+                    "get_top_gainers_losers",
+                    with_client(&client, get_top_gainers_losers),
+                    None,
+                )
+                .description("Get the top 30 coins with the largest price gain and loss")
+                .parameter(
+                    "vs_currency",
+                    "The target currency (e.g. usd)",
+                    "string",
+                    true,
+                )
+                .parameter(
+                    "duration",
+                    "Time range (1h, 24h, 7d, 14d, 30d, 60d, 1y)",
+                    "string",
+                    false,
+                )
+                .parameter(
+                    "top_coins",
+                    "Filter by market cap ranking (300, 500, 1000, all)",
+                    "string",
+                    false,
+                )
+                .build();
+
+                actions.push(Arc::new(action));
+            }
+
+            // Add new coins action
+            {
+                async fn get_new_coins<S: Send + Sync + Clone + 'static>(
+                    client: CoinGeckoClient,
+                    _params: NewCoinsParams,
+                    _send_state: serde_json::Value,
+                    _state: AgentState<S>,
+                ) -> Result<String, String> {
+                    client.get_new_coins().await.map_err(|e| e.to_string())
+                }
+
+                let action = ActionBuilder::<_, _, _, _>::new(
+                    "get_new_coins",
+                    with_client(&client, get_new_coins),
+                    None,
+                )
+                .description("Get the 200 coins most recently listed on CoinGecko")
+                .build();
+
+                actions.push(Arc::new(action));
+            }
+        }
+
         Self { actions }
     }
 
@@ -1034,3 +1114,71 @@ fn calculate_from_timestamp(to: u64, days: &str) -> Result<u64, String> {
 
     Ok(from)
 }
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value)
+        .map_err(|e| format!("Failed to serialize CoinGecko response: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action_names(group: &CoinGeckoActionGroup<()>) -> Vec<String> {
+        group
+            .actions()
+            .iter()
+            .map(|action| action.definition().name)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_group_uses_injected_client() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/ping")
+            .match_header("x-cg-demo-api-key", "demo-key")
+            .with_status(200)
+            .with_body(r#"{"gecko_says":"(V3) To the Moon!"}"#)
+            .create_async()
+            .await;
+
+        let client = CoinGeckoClient::demo("demo-key".to_string()).with_base_url(server.url());
+        let group = CoinGeckoActionGroup::<()>::with_client(client);
+        let action = group
+            .actions()
+            .iter()
+            .find(|action| action.definition().name == "get_network_status")
+            .unwrap();
+        let result = action
+            .execute(
+                serde_json::json!({}),
+                serde_json::json!({}),
+                Arc::new(tokio::sync::Mutex::new(())),
+            )
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert!(result.contains("To the Moon"));
+    }
+
+    #[test]
+    fn test_pro_only_actions_hidden_below_pro() {
+        for client in [
+            CoinGeckoClient::public(),
+            CoinGeckoClient::demo("demo-key".to_string()),
+        ] {
+            let names = action_names(&CoinGeckoActionGroup::with_client(client));
+            assert!(names.contains(&"get_coin_ohlc".to_string()));
+            assert!(!names.contains(&"get_top_gainers_losers".to_string()));
+            assert!(!names.contains(&"get_new_coins".to_string()));
+        }
+
+        let names = action_names(&CoinGeckoActionGroup::with_client(CoinGeckoClient::new(
+            "pro-key".to_string(),
+        )));
+        assert!(names.contains(&"get_top_gainers_losers".to_string()));
+        assert!(names.contains(&"get_new_coins".to_string()));
+    }
+}
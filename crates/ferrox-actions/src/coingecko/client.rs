@@ -1,15 +1,84 @@
 use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::http::{
+    HttpConfig, ProviderHttp, Quota, RateLimiter, COINGECKO_DEMO_QUOTA, COINGECKO_PRO_QUOTA,
+    COINGECKO_PUBLIC_QUOTA,
+};
+
+use super::{
+    error::CoinGeckoError,
+    models::{CoinData, Global, GlobalDefi, MarketChart, Ohlc, SearchResults, Tickers, Trending},
+};
+
+const PUBLIC_BASE_URL: &str = "https://api.coingecko.com/api/v3";
+const PRO_BASE_URL: &str = "https://pro-api.coingecko.com/api/v3";
+
+/// Endpoints that CoinGecko only serves to paid (Pro) plans.
+const PRO_ONLY_ENDPOINTS: &[&str] = &["/coins/top_gainers_losers", "/coins/list/new"];
+
+/// CoinGecko API plan. Public and Demo share the public host, Pro has its own
+/// host and header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoinGeckoTier {
+    Public,
+    Demo,
+    Pro,
+}
+
+impl CoinGeckoTier {
+    pub fn base_url(&self) -> &'static str {
+        match self {
+            CoinGeckoTier::Public | CoinGeckoTier::Demo => PUBLIC_BASE_URL,
+            CoinGeckoTier::Pro => PRO_BASE_URL,
+        }
+    }
+
+    fn api_key_header(&self) -> Option<&'static str> {
+        match self {
+            CoinGeckoTier::Public => None,
+            CoinGeckoTier::Demo => Some("x-cg-demo-api-key"),
+            CoinGeckoTier::Pro => Some("x-cg-pro-api-key"),
+        }
+    }
+
+    /// Documented rate limit for this tier.
+    pub fn quota(&self) -> Quota {
+        match self {
+            CoinGeckoTier::Public => COINGECKO_PUBLIC_QUOTA,
+            CoinGeckoTier::Demo => COINGECKO_DEMO_QUOTA,
+            CoinGeckoTier::Pro => COINGECKO_PRO_QUOTA,
+        }
+    }
+
+    fn provider(&self) -> &'static str {
+        match self {
+            CoinGeckoTier::Public => "coingecko-public",
+            CoinGeckoTier::Demo => "coingecko-demo",
+            CoinGeckoTier::Pro => "coingecko-pro",
+        }
+    }
 
-const BASE_URL: &str = "https://pro-api.coingecko.com/api/v3";
+    /// Whether `endpoint` is available on this tier.
+    pub fn supports(&self, endpoint: &str) -> bool {
+        *self == CoinGeckoTier::Pro || !PRO_ONLY_ENDPOINTS.contains(&endpoint)
+    }
+}
 
 #[derive(Debug, Clone)]
-pub struct CoinGeckoProClient {
-    api_key: String,
-    client: reqwest::Client,
+pub struct CoinGeckoClient {
+    api_key: Option<String>,
+    tier: CoinGeckoTier,
+    http: ProviderHttp,
+    base_url: Option<String>,
 }
 
+/// The client's name from when it only spoke to the Pro API.
+#[deprecated(note = "renamed to `CoinGeckoClient`")]
+pub type CoinGeckoProClient = CoinGeckoClient;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum OrderType {
     #[serde(rename = "market_cap_desc")]
@@ -44,59 +113,119 @@ pub enum PriceChangePercentage {
     OneYear,
 }
 
-impl CoinGeckoProClient {
+impl CoinGeckoClient {
+    /// Creates a client for the Pro API.
     pub fn new(api_key: String) -> Self {
-        let client = reqwest::Client::new();
-        Self { api_key, client }
+        Self::with_tier(CoinGeckoTier::Pro, Some(api_key))
+    }
+
+    /// Creates a client for the free Demo API.
+    pub fn demo(api_key: String) -> Self {
+        Self::with_tier(CoinGeckoTier::Demo, Some(api_key))
+    }
+
+    /// Creates a keyless client for the public API.
+    pub fn public() -> Self {
+        Self::with_tier(CoinGeckoTier::Public, None)
+    }
+
+    /// Picks the tier from the environment: `COINGECKO_PRO_API_KEY` first, then
+    /// `COINGECKO_DEMO_API_KEY`, falling back to the keyless public API.
+    pub fn from_env() -> Self {
+        if let Ok(api_key) = std::env::var("COINGECKO_PRO_API_KEY") {
+            Self::new(api_key)
+        } else if let Ok(api_key) = std::env::var("COINGECKO_DEMO_API_KEY") {
+            Self::demo(api_key)
+        } else {
+            Self::public()
+        }
+    }
+
+    fn with_tier(tier: CoinGeckoTier, api_key: Option<String>) -> Self {
+        Self {
+            api_key,
+            tier,
+            http: ProviderHttp::new(tier.provider(), tier.quota()),
+            base_url: None,
+        }
+    }
+
+    pub fn with_http_config(mut self, config: HttpConfig) -> Self {
+        self.http = self.http.with_config(config);
+        self
+    }
+
+    /// Replaces the shared rate limiter, e.g. for a plan with a higher quota.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.http = self.http.with_limiter(limiter);
+        self
+    }
+
+    pub fn tier(&self) -> CoinGeckoTier {
+        self.tier
     }
 
-    fn get_headers(&self) -> HeaderMap {
+    /// Overrides the tier's host, e.g. to point the client at a mock server.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    fn base_url(&self) -> &str {
+        self.base_url
+            .as_deref()
+            .unwrap_or_else(|| self.tier.base_url())
+    }
+
+    fn get_headers(&self) -> Result<HeaderMap, CoinGeckoError> {
         let mut headers = HeaderMap::new();
-        headers.insert(
-            "X-CG-Pro-API-Key",
-            HeaderValue::from_str(&self.api_key).unwrap(),
-        );
+        if let (Some(header), Some(api_key)) = (self.tier.api_key_header(), &self.api_key) {
+            let api_key = HeaderValue::from_str(api_key)
+                .map_err(|e| CoinGeckoError::InvalidApiKey(e.to_string()))?;
+            headers.insert(header, api_key);
+        }
         headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-        headers
+        Ok(headers)
     }
 
     async fn make_request(
         &self,
         endpoint: &str,
-        params: Option<HashMap<String, String>>,
-    ) -> Result<String, String> {
-        println!("Making coingecko request to {}", endpoint);
-        let url = format!("{}{}", BASE_URL, endpoint);
-        println!("URL: {} params {:?}", url, params);
-        let response = self
-            .client
-            .get(&url)
-            .headers(self.get_headers())
-            .query(&params.unwrap_or_default())
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
-        println!("Got response from {}", url);
-        if response.status().is_success() {
-            let text = response.text().await.map_err(|e| e.to_string())?;
-            Ok(text)
-        } else {
-            Err(format!("Request failed with status: {}", response.status()))
+        params: Option<BTreeMap<String, String>>,
+    ) -> Result<String, CoinGeckoError> {
+        if !self.tier.supports(endpoint) {
+            return Err(CoinGeckoError::TierNotSupported {
+                endpoint: endpoint.to_string(),
+                tier: self.tier,
+            });
         }
+        let url = format!("{}{}", self.base_url(), endpoint);
+        self.http
+            .get_with_query(&url, &self.get_headers()?, &params.unwrap_or_default())
+            .await
+            .map_err(|e| CoinGeckoError::from_http(e, endpoint))
     }
 
-    // Pro API specific endpoints
-    pub async fn get_network_status(&self) -> Result<String, String> {
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        params: Option<BTreeMap<String, String>>,
+    ) -> Result<T, CoinGeckoError> {
+        let body = self.make_request(endpoint, params).await?;
+        serde_json::from_str(&body).map_err(|e| CoinGeckoError::Decode(e.to_string()))
+    }
+
+    pub async fn get_network_status(&self) -> Result<String, CoinGeckoError> {
         self.make_request("/ping", None).await
     }
 
-    pub async fn get_global_data(&self) -> Result<String, String> {
-        self.make_request("/global", None).await
+    pub async fn get_global_data(&self) -> Result<Global, CoinGeckoError> {
+        self.get_json("/global", None).await
     }
 
-    pub async fn get_global_defi_data(&self) -> Result<String, String> {
-        self.make_request("/global/decentralized_finance_defi", None)
+    pub async fn get_global_defi_data(&self) -> Result<GlobalDefi, CoinGeckoError> {
+        self.get_json("/global/decentralized_finance_defi", None)
             .await
     }
 
@@ -104,8 +233,8 @@ impl CoinGeckoProClient {
         &self,
         per_page: Option<u32>,
         page: Option<u32>,
-    ) -> Result<String, String> {
-        let mut params = HashMap::new();
+    ) -> Result<String, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         if let Some(per_page) = per_page {
             params.insert("per_page".to_string(), per_page.to_string());
         }
@@ -115,7 +244,7 @@ impl CoinGeckoProClient {
         self.make_request("/exchanges", Some(params)).await
     }
 
-    pub async fn get_exchange(&self, id: String) -> Result<String, String> {
+    pub async fn get_exchange(&self, id: String) -> Result<String, CoinGeckoError> {
         self.make_request(&format!("/exchanges/{}", id), None).await
     }
 
@@ -127,8 +256,8 @@ impl CoinGeckoProClient {
         page: Option<u32>,
         depth: Option<bool>,
         order: Option<String>,
-    ) -> Result<String, String> {
-        let mut params = HashMap::new();
+    ) -> Result<Tickers, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         if let Some(coin_ids) = coin_ids {
             params.insert("coin_ids".to_string(), coin_ids.join(","));
         }
@@ -147,19 +276,26 @@ impl CoinGeckoProClient {
         if let Some(order) = order {
             params.insert("order".to_string(), order);
         }
-        self.make_request(&format!("/exchanges/{}/tickers", id), Some(params))
+        self.get_json(&format!("/exchanges/{}/tickers", id), Some(params))
             .await
     }
 
-    pub async fn get_exchange_volume_chart(&self, id: String, days: u32) -> Result<String, String> {
-        let mut params = HashMap::new();
+    pub async fn get_exchange_volume_chart(
+        &self,
+        id: String,
+        days: u32,
+    ) -> Result<String, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         params.insert("days".to_string(), days.to_string());
         self.make_request(&format!("/exchanges/{}/volume_chart", id), Some(params))
             .await
     }
 
-    pub async fn get_coins_list(&self, include_platform: Option<bool>) -> Result<String, String> {
-        let mut params = HashMap::new();
+    pub async fn get_coins_list(
+        &self,
+        include_platform: Option<bool>,
+    ) -> Result<String, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         if let Some(include_platform) = include_platform {
             params.insert("include_platform".to_string(), include_platform.to_string());
         }
@@ -174,8 +310,8 @@ impl CoinGeckoProClient {
         page: Option<u32>,
         order: Option<String>,
         depth: Option<bool>,
-    ) -> Result<String, String> {
-        let mut params = HashMap::new();
+    ) -> Result<Tickers, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         if let Some(exchange_ids) = exchange_ids {
             params.insert("exchange_ids".to_string(), exchange_ids.join(","));
         }
@@ -194,7 +330,7 @@ impl CoinGeckoProClient {
         if let Some(depth) = depth {
             params.insert("depth".to_string(), depth.to_string());
         }
-        self.make_request(&format!("/coins/{}/tickers", id), Some(params))
+        self.get_json(&format!("/coins/{}/tickers", id), Some(params))
             .await
     }
 
@@ -203,8 +339,8 @@ impl CoinGeckoProClient {
         id: String,
         date: String,
         localization: Option<bool>,
-    ) -> Result<String, String> {
-        let mut params = HashMap::new();
+    ) -> Result<String, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         params.insert("date".to_string(), date);
         if let Some(localization) = localization {
             params.insert("localization".to_string(), localization.to_string());
@@ -219,14 +355,14 @@ impl CoinGeckoProClient {
         vs_currency: String,
         days: String,
         interval: Option<String>,
-    ) -> Result<String, String> {
-        let mut params = HashMap::new();
+    ) -> Result<MarketChart, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         params.insert("vs_currency".to_string(), vs_currency);
         params.insert("days".to_string(), days);
         if let Some(interval) = interval {
             params.insert("interval".to_string(), interval);
         }
-        self.make_request(&format!("/coins/{}/market_chart", id), Some(params))
+        self.get_json(&format!("/coins/{}/market_chart", id), Some(params))
             .await
     }
 
@@ -236,12 +372,12 @@ impl CoinGeckoProClient {
         vs_currency: String,
         from: u64,
         to: u64,
-    ) -> Result<String, String> {
-        let mut params = HashMap::new();
+    ) -> Result<MarketChart, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         params.insert("vs_currency".to_string(), vs_currency);
         params.insert("from".to_string(), from.to_string());
         params.insert("to".to_string(), to.to_string());
-        self.make_request(&format!("/coins/{}/market_chart/range", id), Some(params))
+        self.get_json(&format!("/coins/{}/market_chart/range", id), Some(params))
             .await
     }
 
@@ -250,11 +386,11 @@ impl CoinGeckoProClient {
         id: String,
         vs_currency: String,
         days: String,
-    ) -> Result<String, String> {
-        let mut params = HashMap::new();
+    ) -> Result<Vec<Ohlc>, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         params.insert("vs_currency".to_string(), vs_currency);
         params.insert("days".to_string(), days);
-        self.make_request(&format!("/coins/{}/ohlc", id), Some(params))
+        self.get_json(&format!("/coins/{}/ohlc", id), Some(params))
             .await
     }
 
@@ -262,8 +398,8 @@ impl CoinGeckoProClient {
         &self,
         id: String,
         contract_address: String,
-    ) -> Result<String, String> {
-        self.make_request(
+    ) -> Result<CoinData, CoinGeckoError> {
+        self.get_json(
             &format!("/coins/{}/contract/{}", id, contract_address),
             None,
         )
@@ -276,11 +412,11 @@ impl CoinGeckoProClient {
         contract_address: String,
         vs_currency: String,
         days: String,
-    ) -> Result<String, String> {
-        let mut params = HashMap::new();
+    ) -> Result<MarketChart, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         params.insert("vs_currency".to_string(), vs_currency);
         params.insert("days".to_string(), days);
-        self.make_request(
+        self.get_json(
             &format!("/coins/{}/contract/{}/market_chart", id, contract_address),
             Some(params),
         )
@@ -294,12 +430,12 @@ impl CoinGeckoProClient {
         vs_currency: String,
         from: u64,
         to: u64,
-    ) -> Result<String, String> {
-        let mut params = HashMap::new();
+    ) -> Result<MarketChart, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         params.insert("vs_currency".to_string(), vs_currency);
         params.insert("from".to_string(), from.to_string());
         params.insert("to".to_string(), to.to_string());
-        self.make_request(
+        self.get_json(
             &format!(
                 "/coins/{}/contract/{}/market_chart/range",
                 id, contract_address
@@ -309,31 +445,34 @@ impl CoinGeckoProClient {
         .await
     }
 
-    pub async fn get_asset_platforms(&self) -> Result<String, String> {
+    pub async fn get_asset_platforms(&self) -> Result<String, CoinGeckoError> {
         self.make_request("/asset_platforms", None).await
     }
 
-    pub async fn get_coins_categories_list(&self) -> Result<String, String> {
+    pub async fn get_coins_categories_list(&self) -> Result<String, CoinGeckoError> {
         self.make_request("/coins/categories/list", None).await
     }
 
-    pub async fn get_coins_categories(&self, order: Option<String>) -> Result<String, String> {
-        let mut params = HashMap::new();
+    pub async fn get_coins_categories(
+        &self,
+        order: Option<String>,
+    ) -> Result<String, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         if let Some(order) = order {
             params.insert("order".to_string(), order);
         }
         self.make_request("/coins/categories", Some(params)).await
     }
 
-    pub async fn get_indexes(&self) -> Result<String, String> {
+    pub async fn get_indexes(&self) -> Result<String, CoinGeckoError> {
         self.make_request("/indexes", None).await
     }
 
-    pub async fn get_indexes_list(&self) -> Result<String, String> {
+    pub async fn get_indexes_list(&self) -> Result<String, CoinGeckoError> {
         self.make_request("/indexes/list", None).await
     }
 
-    pub async fn get_derivatives(&self) -> Result<String, String> {
+    pub async fn get_derivatives(&self) -> Result<String, CoinGeckoError> {
         self.make_request("/derivatives", None).await
     }
 
@@ -342,8 +481,8 @@ impl CoinGeckoProClient {
         order: Option<String>,
         per_page: Option<u32>,
         page: Option<u32>,
-    ) -> Result<String, String> {
-        let mut params = HashMap::new();
+    ) -> Result<String, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         if let Some(order) = order {
             params.insert("order".to_string(), order);
         }
@@ -361,8 +500,8 @@ impl CoinGeckoProClient {
         &self,
         id: String,
         include_tickers: Option<String>,
-    ) -> Result<String, String> {
-        let mut params = HashMap::new();
+    ) -> Result<String, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         if let Some(include_tickers) = include_tickers {
             params.insert("include_tickers".to_string(), include_tickers);
         }
@@ -370,37 +509,65 @@ impl CoinGeckoProClient {
             .await
     }
 
-    pub async fn get_exchange_rates(&self) -> Result<String, String> {
+    pub async fn get_exchange_rates(&self) -> Result<String, CoinGeckoError> {
         self.make_request("/exchange_rates", None).await
     }
 
-    pub async fn search(&self, query: String) -> Result<String, String> {
-        let mut params = HashMap::new();
+    pub async fn search(&self, query: String) -> Result<SearchResults, CoinGeckoError> {
+        let mut params = BTreeMap::new();
         params.insert("query".to_string(), query);
-        self.make_request("/search", Some(params)).await
+        self.get_json("/search", Some(params)).await
     }
 
-    pub async fn get_trending(&self) -> Result<String, String> {
-        self.make_request("/search/trending", None).await
+    pub async fn get_trending(&self) -> Result<Trending, CoinGeckoError> {
+        self.get_json("/search/trending", None).await
     }
 
-    pub async fn get_companies_public_treasury(&self, coin_id: String) -> Result<String, String> {
+    pub async fn get_companies_public_treasury(
+        &self,
+        coin_id: String,
+    ) -> Result<String, CoinGeckoError> {
         self.make_request(&format!("/companies/public_treasury/{}", coin_id), None)
             .await
     }
+
+    // Pro API specific endpoints
+    pub async fn get_top_gainers_losers(
+        &self,
+        vs_currency: String,
+        duration: Option<String>,
+        top_coins: Option<String>,
+    ) -> Result<String, CoinGeckoError> {
+        let mut params = BTreeMap::new();
+        params.insert("vs_currency".to_string(), vs_currency);
+        if let Some(duration) = duration {
+            params.insert("duration".to_string(), duration);
+        }
+        if let Some(top_coins) = top_coins {
+            params.insert("top_coins".to_string(), top_coins);
+        }
+        self.make_request("/coins/top_gainers_losers", Some(params))
+            .await
+    }
+
+    pub async fn get_new_coins(&self) -> Result<String, CoinGeckoError> {
+        self.make_request("/coins/list/new", None).await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
 
-    fn get_test_client() -> CoinGeckoProClient {
+    fn get_test_client() -> CoinGeckoClient {
         let api_key = std::env::var("COINGECKO_PRO_API_KEY")
             .expect("COINGECKO_PRO_API_KEY must be set for tests");
-        CoinGeckoProClient::new(api_key)
+        CoinGeckoClient::new(api_key)
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_network_status() {
         let client = get_test_client();
         let result = client.get_network_status().await;
@@ -408,6 +575,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_global_data() {
         let client = get_test_client();
         let result = client.get_global_data().await;
@@ -415,6 +583,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_global_defi_data() {
         let client = get_test_client();
         let result = client.get_global_defi_data().await;
@@ -422,6 +591,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_exchanges() {
         let client = get_test_client();
         let result = client.get_exchanges(Some(10), Some(1)).await;
@@ -429,6 +599,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_exchange() {
         let client = get_test_client();
         let result = client.get_exchange("binance".to_string()).await;
@@ -436,6 +607,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_exchange_tickers() {
         let client = get_test_client();
         let result = client
@@ -452,6 +624,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_exchange_volume_chart() {
         let client = get_test_client();
         let result = client
@@ -461,6 +634,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_coins_list() {
         let client = get_test_client();
         let result = client.get_coins_list(Some(true)).await;
@@ -468,6 +642,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_coin_tickers() {
         let client = get_test_client();
         let result = client
@@ -484,6 +659,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_coin_history() {
         let client = get_test_client();
         let result = client
@@ -493,6 +669,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_coin_market_chart() {
         let client = get_test_client();
         let result = client
@@ -507,6 +684,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_coin_market_chart_range() {
         let client = get_test_client();
         let now = std::time::SystemTime::now()
@@ -520,6 +698,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_coin_ohlc() {
         let client = get_test_client();
         let result = client
@@ -529,6 +708,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_coin_contract() {
         let client = get_test_client();
         let result = client
@@ -541,6 +721,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_coin_contract_market_chart() {
         let client = get_test_client();
         let result = client
@@ -555,6 +736,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_coin_contract_market_chart_range() {
         let client = get_test_client();
         let now = std::time::SystemTime::now()
@@ -574,6 +756,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_asset_platforms() {
         let client = get_test_client();
         let result = client.get_asset_platforms().await;
@@ -581,6 +764,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_coins_categories_list() {
         let client = get_test_client();
         let result = client.get_coins_categories_list().await;
@@ -588,6 +772,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_coins_categories() {
         let client = get_test_client();
         let result = client
@@ -597,6 +782,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_indexes() {
         let client = get_test_client();
         let result = client.get_indexes().await;
@@ -604,6 +790,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_indexes_list() {
         let client = get_test_client();
         let result = client.get_indexes_list().await;
@@ -611,6 +798,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_derivatives() {
         let client = get_test_client();
         let result = client.get_derivatives().await;
@@ -618,6 +806,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_derivatives_exchanges() {
         let client = get_test_client();
         let result = client
@@ -627,6 +816,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_derivatives_exchange() {
         let client = get_test_client();
         let result = client
@@ -636,6 +826,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_exchange_rates() {
         let client = get_test_client();
         let result = client.get_exchange_rates().await;
@@ -643,6 +834,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_search() {
         let client = get_test_client();
         let result = client.search("bitcoin".to_string()).await;
@@ -650,6 +842,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_trending() {
         let client = get_test_client();
         let result = client.get_trending().await;
@@ -657,6 +850,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "hits the live API; needs network and API keys"]
     async fn test_companies_public_treasury() {
         let client = get_test_client();
         let result = client
@@ -664,4 +858,140 @@ mod tests {
             .await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_rate_limited_error() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/search/trending")
+            .with_status(429)
+            .with_header("retry-after", "30")
+            .with_body(r#"{"status":{"error_code":429,"error_message":"Rate limited"}}"#)
+            .create_async()
+            .await;
+
+        let client = CoinGeckoClient::new("test-key".to_string()).with_base_url(server.url());
+        let result = client.get_trending().await;
+
+        mock.assert_async().await;
+        assert_eq!(
+            result.unwrap_err(),
+            CoinGeckoError::RateLimited {
+                retry_after: Some(Duration::from_secs(30))
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_and_not_found_errors() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/global")
+            .with_status(401)
+            .with_body("invalid api key")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/coins/ethereum/contract/0xdead")
+            .with_status(404)
+            .with_body(r#"{"error":"coin not found"}"#)
+            .create_async()
+            .await;
+
+        let client = CoinGeckoClient::new("bad-key".to_string()).with_base_url(server.url());
+        assert_eq!(
+            client.get_global_data().await.unwrap_err(),
+            CoinGeckoError::Unauthorized("invalid api key".to_string())
+        );
+        assert_eq!(
+            client
+                .get_coin_contract("ethereum".to_string(), "0xdead".to_string())
+                .await
+                .unwrap_err(),
+            CoinGeckoError::NotFound("/coins/ethereum/contract/0xdead".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unsendable_api_key_is_an_error() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("GET", "/global").expect(0).create_async().await;
+
+        // As read from a file or env var with a trailing newline
+        let client = CoinGeckoClient::new("test-key\n".to_string()).with_base_url(server.url());
+        assert!(matches!(
+            client.get_global_data().await.unwrap_err(),
+            CoinGeckoError::InvalidApiKey(_)
+        ));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_typed_ohlc_and_decode_error() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/coins/bitcoin/ohlc")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("vs_currency".into(), "usd".into()),
+                mockito::Matcher::UrlEncoded("days".into(), "1".into()),
+            ]))
+            .match_header("x-cg-pro-api-key", "test-key")
+            .with_status(200)
+            .with_body("[[1709395200000, 61942, 62211, 61721, 61845]]")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/search")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body("<html>maintenance</html>")
+            .create_async()
+            .await;
+
+        let client = CoinGeckoClient::new("test-key".to_string()).with_base_url(server.url());
+        let ohlc = client
+            .get_coin_ohlc("bitcoin".to_string(), "usd".to_string(), "1".to_string())
+            .await
+            .unwrap();
+        mock.assert_async().await;
+        assert_eq!(ohlc[0].high, 62211.0);
+
+        let result = client.search("bitcoin".to_string()).await;
+        assert!(matches!(result, Err(CoinGeckoError::Decode(_))));
+    }
+
+    #[tokio::test]
+    async fn test_demo_tier_uses_demo_header() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/ping")
+            .match_header("x-cg-demo-api-key", "demo-key")
+            .match_header("x-cg-pro-api-key", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_body(r#"{"gecko_says":"(V3) To the Moon!"}"#)
+            .create_async()
+            .await;
+
+        let client = CoinGeckoClient::demo("demo-key".to_string()).with_base_url(server.url());
+        assert_eq!(client.tier(), CoinGeckoTier::Demo);
+        assert!(client.get_network_status().await.is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_pro_only_endpoint_rejected_below_pro() {
+        let client = CoinGeckoClient::public();
+        assert_eq!(client.base_url(), "https://api.coingecko.com/api/v3");
+        assert_eq!(
+            client.get_new_coins().await.unwrap_err(),
+            CoinGeckoError::TierNotSupported {
+                endpoint: "/coins/list/new".to_string(),
+                tier: CoinGeckoTier::Public,
+            }
+        );
+
+        let client = CoinGeckoClient::new("pro-key".to_string());
+        assert_eq!(client.base_url(), "https://pro-api.coingecko.com/api/v3");
+        assert!(client.tier().supports("/coins/list/new"));
+    }
 }
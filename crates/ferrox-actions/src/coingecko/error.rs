@@ -0,0 +1,112 @@
+use reqwest::StatusCode;
+use std::{fmt, time::Duration};
+
+use super::client::CoinGeckoTier;
+use crate::http::HttpError;
+
+/// Errors returned by [`CoinGeckoClient`](super::client::CoinGeckoClient).
+///
+/// The variants are distinct so callers (and the agent, via the `Display` text)
+/// can tell "back off and retry" apart from "fix the key" or "fix the id".
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoinGeckoError {
+    /// HTTP 429. `retry_after` is taken from the `Retry-After` header when present.
+    RateLimited { retry_after: Option<Duration> },
+    /// HTTP 401/403, usually a missing or invalid API key.
+    Unauthorized(String),
+    /// The configured API key cannot be sent, such as one read with a
+    /// trailing newline.
+    InvalidApiKey(String),
+    /// HTTP 404, usually an unknown coin id, exchange id or contract address.
+    NotFound(String),
+    /// Any other non-2xx response.
+    Status { status: u16, body: String },
+    /// The request never produced a response (DNS, TLS, connection reset, ...).
+    Request(String),
+    /// The response body did not match the expected shape.
+    Decode(String),
+    /// The endpoint is not available on the client's plan.
+    TierNotSupported {
+        endpoint: String,
+        tier: CoinGeckoTier,
+    },
+}
+
+impl CoinGeckoError {
+    /// Classifies a failed request to `endpoint`.
+    pub(crate) fn from_http(error: HttpError, endpoint: &str) -> Self {
+        match error {
+            HttpError::Status {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                retry_after,
+                ..
+            } => CoinGeckoError::RateLimited { retry_after },
+            HttpError::Status {
+                status: StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN,
+                body,
+                ..
+            } => CoinGeckoError::Unauthorized(body),
+            HttpError::Status {
+                status: StatusCode::NOT_FOUND,
+                ..
+            } => CoinGeckoError::NotFound(endpoint.to_string()),
+            HttpError::Status { status, body, .. } => CoinGeckoError::Status {
+                status: status.as_u16(),
+                body,
+            },
+            HttpError::Timeout(message)
+            | HttpError::Connect(message)
+            | HttpError::Transport(message) => CoinGeckoError::Request(message),
+        }
+    }
+}
+
+impl fmt::Display for CoinGeckoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoinGeckoError::RateLimited {
+                retry_after: Some(retry_after),
+            } => write!(
+                f,
+                "CoinGecko rate limit exceeded, retry after {}s",
+                retry_after.as_secs()
+            ),
+            CoinGeckoError::RateLimited { retry_after: None } => {
+                write!(f, "CoinGecko rate limit exceeded, retry later")
+            }
+            CoinGeckoError::Unauthorized(message) => {
+                write!(f, "CoinGecko rejected the API key: {}", message)
+            }
+            CoinGeckoError::InvalidApiKey(message) => {
+                write!(f, "Invalid CoinGecko API key: {}", message)
+            }
+            CoinGeckoError::NotFound(endpoint) => {
+                write!(f, "CoinGecko resource not found: {}", endpoint)
+            }
+            CoinGeckoError::Status { status, body } => {
+                write!(
+                    f,
+                    "CoinGecko request failed with status {}: {}",
+                    status, body
+                )
+            }
+            CoinGeckoError::Request(message) => write!(f, "CoinGecko request failed: {}", message),
+            CoinGeckoError::Decode(message) => {
+                write!(f, "Failed to decode CoinGecko response: {}", message)
+            }
+            CoinGeckoError::TierNotSupported { endpoint, tier } => write!(
+                f,
+                "CoinGecko endpoint {} requires a Pro API key (current tier: {:?})",
+                endpoint, tier
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CoinGeckoError {}
+
+impl From<CoinGeckoError> for String {
+    fn from(error: CoinGeckoError) -> Self {
+        error.to_string()
+    }
+}
@@ -0,0 +1,378 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A single `[timestamp_ms, value]` point as returned by the market chart endpoints.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartPoint(pub i64, pub f64);
+
+impl ChartPoint {
+    pub fn timestamp(&self) -> i64 {
+        self.0
+    }
+
+    pub fn value(&self) -> f64 {
+        self.1
+    }
+}
+
+/// Response of `/coins/{id}/market_chart` and the contract/range variants.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarketChart {
+    #[serde(default)]
+    pub prices: Vec<ChartPoint>,
+    #[serde(default)]
+    pub market_caps: Vec<ChartPoint>,
+    #[serde(default)]
+    pub total_volumes: Vec<ChartPoint>,
+}
+
+/// One candle of `/coins/{id}/ohlc`. CoinGecko sends these as
+/// `[timestamp_ms, open, high, low, close]` arrays.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(from = "(i64, f64, f64, f64, f64)", into = "(i64, f64, f64, f64, f64)")]
+pub struct Ohlc {
+    pub timestamp: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+impl From<(i64, f64, f64, f64, f64)> for Ohlc {
+    fn from((timestamp, open, high, low, close): (i64, f64, f64, f64, f64)) -> Self {
+        Self {
+            timestamp,
+            open,
+            high,
+            low,
+            close,
+        }
+    }
+}
+
+impl From<Ohlc> for (i64, f64, f64, f64, f64) {
+    fn from(ohlc: Ohlc) -> Self {
+        (ohlc.timestamp, ohlc.open, ohlc.high, ohlc.low, ohlc.close)
+    }
+}
+
+/// Response of `/coins/{id}/contract/{contract_address}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoinData {
+    pub id: String,
+    pub symbol: String,
+    pub name: String,
+    pub asset_platform_id: Option<String>,
+    #[serde(default)]
+    pub platforms: HashMap<String, Option<String>>,
+    #[serde(default)]
+    pub detail_platforms: HashMap<String, DetailPlatform>,
+    #[serde(default)]
+    pub categories: Vec<Option<String>>,
+    #[serde(default)]
+    pub description: HashMap<String, String>,
+    pub market_cap_rank: Option<u32>,
+    pub market_data: Option<CoinMarketData>,
+    pub last_updated: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetailPlatform {
+    pub decimal_place: Option<u8>,
+    pub contract_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoinMarketData {
+    #[serde(default)]
+    pub current_price: HashMap<String, f64>,
+    #[serde(default)]
+    pub market_cap: HashMap<String, f64>,
+    #[serde(default)]
+    pub total_volume: HashMap<String, f64>,
+    #[serde(default)]
+    pub fully_diluted_valuation: HashMap<String, f64>,
+    #[serde(default)]
+    pub high_24h: HashMap<String, f64>,
+    #[serde(default)]
+    pub low_24h: HashMap<String, f64>,
+    pub price_change_percentage_24h: Option<f64>,
+    pub price_change_percentage_7d: Option<f64>,
+    pub price_change_percentage_30d: Option<f64>,
+    pub market_cap_change_percentage_24h: Option<f64>,
+    pub circulating_supply: Option<f64>,
+    pub total_supply: Option<f64>,
+    pub max_supply: Option<f64>,
+}
+
+/// Response of `/coins/{id}/tickers` and `/exchanges/{id}/tickers`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tickers {
+    pub name: String,
+    #[serde(default)]
+    pub tickers: Vec<Ticker>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ticker {
+    pub base: String,
+    pub target: String,
+    pub market: TickerMarket,
+    pub last: Option<f64>,
+    pub volume: Option<f64>,
+    #[serde(default)]
+    pub converted_last: HashMap<String, f64>,
+    #[serde(default)]
+    pub converted_volume: HashMap<String, f64>,
+    pub cost_to_move_up_usd: Option<f64>,
+    pub cost_to_move_down_usd: Option<f64>,
+    pub trust_score: Option<String>,
+    pub bid_ask_spread_percentage: Option<f64>,
+    pub timestamp: Option<String>,
+    pub last_traded_at: Option<String>,
+    pub last_fetch_at: Option<String>,
+    #[serde(default)]
+    pub is_anomaly: bool,
+    #[serde(default)]
+    pub is_stale: bool,
+    pub trade_url: Option<String>,
+    pub coin_id: Option<String>,
+    pub target_coin_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TickerMarket {
+    pub name: String,
+    pub identifier: String,
+    #[serde(default)]
+    pub has_trading_incentive: bool,
+    pub logo: Option<String>,
+}
+
+/// Response of `/search/trending`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Trending {
+    #[serde(default)]
+    pub coins: Vec<TrendingCoin>,
+    #[serde(default)]
+    pub nfts: Vec<TrendingNft>,
+    #[serde(default)]
+    pub categories: Vec<TrendingCategory>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendingCoin {
+    pub item: TrendingCoinItem,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendingCoinItem {
+    pub id: String,
+    pub coin_id: Option<u64>,
+    pub name: String,
+    pub symbol: String,
+    pub market_cap_rank: Option<u32>,
+    pub thumb: Option<String>,
+    pub slug: Option<String>,
+    pub price_btc: Option<f64>,
+    pub score: Option<u32>,
+    pub data: Option<TrendingCoinData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendingCoinData {
+    pub price: Option<f64>,
+    pub market_cap: Option<String>,
+    pub total_volume: Option<String>,
+    #[serde(default)]
+    pub price_change_percentage_24h: HashMap<String, f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendingNft {
+    pub id: String,
+    pub name: String,
+    pub symbol: String,
+    pub thumb: Option<String>,
+    pub native_currency_symbol: Option<String>,
+    pub floor_price_in_native_currency: Option<f64>,
+    pub floor_price_24h_percentage_change: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendingCategory {
+    pub id: u64,
+    pub name: String,
+    pub market_cap_1h_change: Option<f64>,
+    pub slug: Option<String>,
+    pub coins_count: Option<u64>,
+}
+
+/// Response of `/search`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchResults {
+    #[serde(default)]
+    pub coins: Vec<SearchCoin>,
+    #[serde(default)]
+    pub exchanges: Vec<SearchExchange>,
+    #[serde(default)]
+    pub categories: Vec<SearchCategory>,
+    #[serde(default)]
+    pub nfts: Vec<SearchNft>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchCoin {
+    pub id: String,
+    pub name: String,
+    pub api_symbol: Option<String>,
+    pub symbol: String,
+    pub market_cap_rank: Option<u32>,
+    pub thumb: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchExchange {
+    pub id: String,
+    pub name: String,
+    pub market_type: Option<String>,
+    pub thumb: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchCategory {
+    // Numeric on older responses, a slug on newer ones
+    pub id: serde_json::Value,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchNft {
+    pub id: String,
+    pub name: String,
+    pub symbol: Option<String>,
+    pub thumb: Option<String>,
+}
+
+/// Response of `/global`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Global {
+    pub data: GlobalData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalData {
+    pub active_cryptocurrencies: u64,
+    pub upcoming_icos: Option<u64>,
+    pub ongoing_icos: Option<u64>,
+    pub ended_icos: Option<u64>,
+    pub markets: u64,
+    #[serde(default)]
+    pub total_market_cap: HashMap<String, f64>,
+    #[serde(default)]
+    pub total_volume: HashMap<String, f64>,
+    #[serde(default)]
+    pub market_cap_percentage: HashMap<String, f64>,
+    pub market_cap_change_percentage_24h_usd: Option<f64>,
+    pub updated_at: Option<i64>,
+}
+
+/// Response of `/global/decentralized_finance_defi`. CoinGecko returns the
+/// figures as decimal strings to avoid precision loss.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalDefi {
+    pub data: GlobalDefiData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalDefiData {
+    pub defi_market_cap: Option<String>,
+    pub eth_market_cap: Option<String>,
+    pub defi_to_eth_ratio: Option<String>,
+    pub trading_volume_24h: Option<String>,
+    pub defi_dominance: Option<String>,
+    pub top_coin_name: Option<String>,
+    pub top_coin_defi_dominance: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ohlc_from_array() {
+        let ohlc: Vec<Ohlc> =
+            serde_json::from_str("[[1709395200000, 61942.0, 62211.0, 61721.0, 61845.0]]").unwrap();
+        assert_eq!(ohlc.len(), 1);
+        assert_eq!(ohlc[0].timestamp, 1709395200000);
+        assert_eq!(ohlc[0].open, 61942.0);
+        assert_eq!(ohlc[0].close, 61845.0);
+
+        // Serializes back into the compact array form
+        let json = serde_json::to_string(&ohlc[0]).unwrap();
+        assert_eq!(json, "[1709395200000,61942.0,62211.0,61721.0,61845.0]");
+    }
+
+    #[test]
+    fn test_market_chart() {
+        let chart: MarketChart = serde_json::from_str(
+            r#"{
+                "prices": [[1711843200000, 69702.3087473573], [1711929600000, 71246.9514406015]],
+                "market_caps": [[1711843200000, 1370247487960.09]],
+                "total_volumes": [[1711843200000, 16408802301.8374]]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(chart.prices.len(), 2);
+        assert_eq!(chart.prices[1].timestamp(), 1711929600000);
+        assert_eq!(chart.market_caps[0].value(), 1370247487960.09);
+    }
+
+    #[test]
+    fn test_global() {
+        let global: Global = serde_json::from_str(
+            r#"{
+                "data": {
+                    "active_cryptocurrencies": 13690,
+                    "upcoming_icos": 0,
+                    "ongoing_icos": 49,
+                    "ended_icos": 3376,
+                    "markets": 1046,
+                    "total_market_cap": {"btc": 39003738.0847159, "usd": 2001591486388.83},
+                    "total_volume": {"usd": 69143531490.3826},
+                    "market_cap_percentage": {"btc": 51.2, "eth": 16.8},
+                    "market_cap_change_percentage_24h_usd": 1.72,
+                    "updated_at": 1712512855
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(global.data.active_cryptocurrencies, 13690);
+        assert_eq!(global.data.market_cap_percentage["btc"], 51.2);
+    }
+
+    #[test]
+    fn test_trending() {
+        let trending: Trending = serde_json::from_str(
+            r#"{
+                "coins": [{"item": {
+                    "id": "moon-tropica", "coin_id": 28470, "name": "Moon Tropica",
+                    "symbol": "CAH", "market_cap_rank": null,
+                    "thumb": "https://assets.coingecko.com/thumb.png", "slug": "moon-tropica",
+                    "price_btc": 0.000530163474333298, "score": 0,
+                    "data": {"price": 36.97, "market_cap": "$99,703,583",
+                             "total_volume": "$282,142",
+                             "price_change_percentage_24h": {"usd": -4.61}}
+                }}],
+                "nfts": [],
+                "categories": [{"id": 251, "name": "Solana Meme Coins",
+                                "market_cap_1h_change": 1.44, "slug": "solana-meme-coins",
+                                "coins_count": 79}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(trending.coins[0].item.id, "moon-tropica");
+        assert_eq!(trending.coins[0].item.market_cap_rank, None);
+        assert_eq!(trending.categories[0].coins_count, Some(79));
+    }
+}
@@ -1,7 +1,7 @@
 pub mod client;
 
 use crate::{
-    action::{ActionBuilder, ActionGroup, FunctionAction},
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
     AgentState,
 };
 use client::DexScreenerClient;
@@ -58,25 +58,40 @@ impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for DexScreenerActionGroup
     }
 }
 
+impl<S: Send + Sync + Clone + 'static> Default for DexScreenerActionGroup<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<S: Send + Sync + Clone + 'static> DexScreenerActionGroup<S> {
     pub fn new() -> Self {
+        Self::with_client(DexScreenerClient::new())
+    }
+
+    /// Builds the group around a configured client, e.g. one with a base URL
+    /// pointing at a mock server.
+    pub fn with_client(client: DexScreenerClient) -> Self {
         let mut actions = Vec::new();
 
         // Add get latest token profiles action
         {
             async fn get_token_profiles<S: Send + Sync + Clone + 'static>(
+                client: DexScreenerClient,
                 _params: TokenProfilesParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let client = DexScreenerClient::new();
                 client.get_token_profiles().await
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_token_profiles", get_token_profiles, None)
-                    .description("Get the latest token profiles")
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_token_profiles",
+                with_client(&client, get_token_profiles),
+                None,
+            )
+            .description("Get the latest token profiles")
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -84,22 +99,25 @@ impl<S: Send + Sync + Clone + 'static> DexScreenerActionGroup<S> {
         // Add check token orders action
         {
             async fn get_token_orders<S: Send + Sync + Clone + 'static>(
+                client: DexScreenerClient,
                 params: TokenOrdersParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let client = DexScreenerClient::new();
                 client
                     .get_token_orders(params.chain_id, params.token_address)
                     .await
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_token_orders", get_token_orders, None)
-                    .description("Check orders paid for of token")
-                    .parameter("chain_id", "The chain ID (e.g. solana)", "string", true)
-                    .parameter("token_address", "Token's address", "string", true)
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_token_orders",
+                with_client(&client, get_token_orders),
+                None,
+            )
+            .description("Check orders paid for of token")
+            .parameter("chain_id", "The chain ID (e.g. solana)", "string", true)
+            .parameter("token_address", "Token's address", "string", true)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -107,18 +125,21 @@ impl<S: Send + Sync + Clone + 'static> DexScreenerActionGroup<S> {
         // Add get latest token boosts action
         {
             async fn get_token_boosts<S: Send + Sync + Clone + 'static>(
+                client: DexScreenerClient,
                 _params: TokenBoostsParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let client = DexScreenerClient::new();
                 client.get_token_boosts().await
             }
 
-            let action =
-                ActionBuilder::<_, _, _, _>::new("get_token_boosts", get_token_boosts, None)
-                    .description("Get the latest boosted tokens")
-                    .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_token_boosts",
+                with_client(&client, get_token_boosts),
+                None,
+            )
+            .description("Get the latest boosted tokens")
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -126,17 +147,17 @@ impl<S: Send + Sync + Clone + 'static> DexScreenerActionGroup<S> {
         // Add get top token boosts action
         {
             async fn get_token_boosts_top<S: Send + Sync + Clone + 'static>(
+                client: DexScreenerClient,
                 _params: TokenBoostsTopParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let client = DexScreenerClient::new();
                 client.get_token_boosts_top().await
             }
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_token_boosts_top",
-                get_token_boosts_top,
+                with_client(&client, get_token_boosts_top),
                 None,
             )
             .description("Get the tokens with most active boosts")
@@ -148,21 +169,25 @@ impl<S: Send + Sync + Clone + 'static> DexScreenerActionGroup<S> {
         // Add get token pairs action
         {
             async fn get_token_pairs<S: Send + Sync + Clone + 'static>(
+                client: DexScreenerClient,
                 params: TokenPairsParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let client = DexScreenerClient::new();
                 client
                     .get_token_pairs(params.chain_id, params.token_address)
                     .await
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_token_pairs", get_token_pairs, None)
-                .description("Get the pools of a given token address")
-                .parameter("chain_id", "The chain ID (e.g. solana)", "string", true)
-                .parameter("token_address", "Token's address", "string", true)
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_token_pairs",
+                with_client(&client, get_token_pairs),
+                None,
+            )
+            .description("Get the pools of a given token address")
+            .parameter("chain_id", "The chain ID (e.g. solana)", "string", true)
+            .parameter("token_address", "Token's address", "string", true)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -170,26 +195,30 @@ impl<S: Send + Sync + Clone + 'static> DexScreenerActionGroup<S> {
         // Add get tokens action
         {
             async fn get_tokens<S: Send + Sync + Clone + 'static>(
+                client: DexScreenerClient,
                 params: TokensParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let client = DexScreenerClient::new();
                 client
                     .get_tokens(params.chain_id, params.token_addresses)
                     .await
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_tokens", get_tokens, None)
-                .description("Get one or multiple pairs by token address")
-                .parameter("chain_id", "The chain ID (e.g. solana)", "string", true)
-                .parameter(
-                    "token_addresses",
-                    "Comma-separated list of token addresses (up to 30)",
-                    "string",
-                    true,
-                )
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_tokens",
+                with_client(&client, get_tokens),
+                None,
+            )
+            .description("Get one or multiple pairs by token address")
+            .parameter("chain_id", "The chain ID (e.g. solana)", "string", true)
+            .parameter(
+                "token_addresses",
+                "Comma-separated list of token addresses (up to 30)",
+                "string",
+                true,
+            )
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -197,18 +226,22 @@ impl<S: Send + Sync + Clone + 'static> DexScreenerActionGroup<S> {
         // Add search pairs action
         {
             async fn search_pairs<S: Send + Sync + Clone + 'static>(
+                client: DexScreenerClient,
                 params: SearchPairsParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let client = DexScreenerClient::new();
                 client.search_pairs(params.query).await
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("search_pairs", search_pairs, None)
-                .description("Search for pairs or tokens matching query")
-                .parameter("query", "Search query", "string", true)
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "search_pairs",
+                with_client(&client, search_pairs),
+                None,
+            )
+            .description("Search for pairs or tokens matching query")
+            .parameter("query", "Search query", "string", true)
+            .build();
 
             actions.push(Arc::new(action));
         }
@@ -216,19 +249,23 @@ impl<S: Send + Sync + Clone + 'static> DexScreenerActionGroup<S> {
         // Add get pairs action
         {
             async fn get_pairs<S: Send + Sync + Clone + 'static>(
+                client: DexScreenerClient,
                 params: PairsParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let client = DexScreenerClient::new();
                 client.get_pairs(params.chain_id, params.pair_id).await
             }
 
-            let action = ActionBuilder::<_, _, _, _>::new("get_pairs", get_pairs, None)
-                .description("Get one or multiple pairs by chain and pair address")
-                .parameter("chain_id", "The chain ID (e.g. solana)", "string", true)
-                .parameter("pair_id", "Pair ID", "string", true)
-                .build();
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_pairs",
+                with_client(&client, get_pairs),
+                None,
+            )
+            .description("Get one or multiple pairs by chain and pair address")
+            .parameter("chain_id", "The chain ID (e.g. solana)", "string", true)
+            .parameter("pair_id", "Pair ID", "string", true)
+            .build();
 
             actions.push(Arc::new(action));
         }
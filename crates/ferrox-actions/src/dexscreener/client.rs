@@ -1,33 +1,70 @@
-use reqwest::Client;
+use reqwest::header::HeaderMap;
+use std::sync::Arc;
+
+use crate::http::{
+    HttpConfig, ProviderHttp, RateLimiter, DEXSCREENER_PAIRS_QUOTA, DEXSCREENER_QUOTA,
+};
 
 const BASE_URL: &str = "https://api.dexscreener.com";
 
+/// Endpoint prefixes on DexScreener's 60 requests/minute budget. Everything else
+/// (pairs, search, tokens) is on the 300 requests/minute budget.
+const SLOW_ENDPOINTS: &[&str] = &["/token-profiles/", "/token-boosts/", "/orders/"];
+
 #[derive(Debug, Clone)]
 pub struct DexScreenerClient {
-    client: Client,
+    http: ProviderHttp,
+    pairs_http: ProviderHttp,
+    base_url: String,
+}
+
+impl Default for DexScreenerClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DexScreenerClient {
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            http: ProviderHttp::new("dexscreener", DEXSCREENER_QUOTA),
+            pairs_http: ProviderHttp::new("dexscreener-pairs", DEXSCREENER_PAIRS_QUOTA),
+            base_url: BASE_URL.to_string(),
         }
     }
 
-    async fn make_request(&self, endpoint: &str) -> Result<String, String> {
-        let url = format!("{}{}", BASE_URL, endpoint);
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+    /// Overrides the API host, e.g. to point the client at a mock server.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self
+    }
 
-        if response.status().is_success() {
-            response.text().await.map_err(|e| e.to_string())
+    pub fn with_http_config(mut self, config: HttpConfig) -> Self {
+        self.http = self.http.with_config(config.clone());
+        self.pairs_http = self.pairs_http.with_config(config);
+        self
+    }
+
+    /// Replaces both shared rate limiters with `limiter`.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.http = self.http.with_limiter(limiter.clone());
+        self.pairs_http = self.pairs_http.with_limiter(limiter);
+        self
+    }
+
+    async fn make_request(&self, endpoint: &str) -> Result<String, String> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let http = if SLOW_ENDPOINTS
+            .iter()
+            .any(|prefix| endpoint.starts_with(prefix))
+        {
+            &self.http
         } else {
-            Err(format!("Request failed with status: {}", response.status()))
-        }
+            &self.pairs_http
+        };
+        http.get(&url, &HeaderMap::new())
+            .await
+            .map_err(|e| e.to_string())
     }
 
     pub async fn get_token_profiles(&self) -> Result<String, String> {
@@ -1,53 +1,30 @@
+pub mod client;
+pub mod models;
+
 use crate::{
-    action::{ActionBuilder, ActionGroup, FunctionAction},
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
     AgentState,
 };
-use serde::{Deserialize, Serialize};
+use client::GmgnClient;
+pub use models::{GmgnChain, GmgnKlineResponse, GmgnResolution, KlineData};
+use serde::Deserialize;
 use std::sync::Arc;
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct GmgnKlineResponse {
-    pub code: i32,
-    pub msg: String,
-    pub data: Vec<KlineData>,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct KlineData {
-    pub open: String,
-    pub high: String,
-    pub low: String,
-    pub close: String,
-    pub volume: String,
-    pub time: String,
-}
-
 pub async fn fetch_k_line_data_from_gmgn(
+    chain: GmgnChain,
     token_address: String,
+    resolution: GmgnResolution,
     time_from: i64,
     time_to: i64,
 ) -> Result<GmgnKlineResponse, String> {
-    let client = reqwest::Client::new();
-
-    let url = format!(
-        "https://www.gmgn.cc/defi/quotation/v1/tokens/kline/sol/{}?resolution=1h&from={}&to={}",
-        token_address, time_from, time_to
-    );
-    println!("Fetching kline data from GMGN: {}", url);
-
-    match client.get(&url).send().await {
-        Ok(response) => match response.json::<GmgnKlineResponse>().await {
-            Ok(kline_data) => Ok(kline_data),
-            Err(e) => {
-                println!("Failed to parse GMGN response: {}", e);
-                Err("Error parsing kline data".to_string())
-            }
-        },
-        Err(e) => {
-            println!("Failed to fetch from GMGN: {}", e);
-            Err("Failed to fetch kline data".to_string())
-        }
-    }
+    GmgnClient::new()
+        .get_kline(chain, token_address, resolution, time_from, time_to)
+        .await
+}
+
+/// Parses the optional `chain` parameter, defaulting to Solana.
+fn parse_chain(chain: Option<String>) -> Result<GmgnChain, String> {
+    chain.map_or(Ok(GmgnChain::default()), |chain| chain.parse())
 }
 
 #[derive(Debug, Deserialize)]
@@ -55,6 +32,35 @@ pub struct KlineDataParams {
     token_address: String,
     time_from: i64,
     time_to: i64,
+    resolution: Option<String>,
+    chain: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenInfoParams {
+    token_address: String,
+    chain: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopHoldersParams {
+    token_address: String,
+    chain: Option<String>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WalletActivityParams {
+    wallet_address: String,
+    chain: Option<String>,
+    limit: Option<u32>,
+    cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewPairsParams {
+    chain: Option<String>,
+    limit: Option<u32>,
 }
 
 pub struct GmgnActionGroup<S: Send + Sync + Clone + 'static> {
@@ -67,22 +73,44 @@ impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for GmgnActionGroup<S> {
     }
 }
 
+impl<S: Send + Sync + Clone + 'static> Default for GmgnActionGroup<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<S: Send + Sync + Clone + 'static> GmgnActionGroup<S> {
     pub fn new() -> Self {
+        Self::with_client(GmgnClient::new())
+    }
+
+    /// Builds the group around a configured client, e.g. one with a base URL
+    /// pointing at a mock server.
+    pub fn with_client(client: GmgnClient) -> Self {
         let mut actions = Vec::new();
         // Add kline data action
         {
             async fn get_kline_data<S: Send + Sync + Clone + 'static>(
+                client: GmgnClient,
                 params: KlineDataParams,
                 _send_state: serde_json::Value,
                 _state: AgentState<S>,
             ) -> Result<String, String> {
-                let kline_data = fetch_k_line_data_from_gmgn(
-                    params.token_address,
-                    params.time_from,
-                    params.time_to,
-                )
-                .await?;
+                let chain = parse_chain(params.chain)?;
+                let resolution = params
+                    .resolution
+                    .map_or(Ok(GmgnResolution::default()), |resolution| {
+                        resolution.parse()
+                    })?;
+                let kline_data = client
+                    .get_kline(
+                        chain,
+                        params.token_address,
+                        resolution,
+                        params.time_from,
+                        params.time_to,
+                    )
+                    .await?;
 
                 serde_json::to_string(&kline_data)
                     .map_err(|e| format!("Failed to serialize GMGN response: {}", e))
@@ -90,15 +118,182 @@ impl<S: Send + Sync + Clone + 'static> GmgnActionGroup<S> {
 
             let action = ActionBuilder::<_, _, _, _>::new(
                 "get_gmgn_kline_data",
-                get_kline_data,
+                with_client(&client, get_kline_data),
                 None,
             )
             .description(
-                "Get OHLCV kline data for a Solana token from GMGN (alternative to Birdeye). Use this if the birdeye response is empty or errored",
+                "Get OHLCV kline data for a token from GMGN (alternative to Birdeye). Use this if the birdeye response is empty or errored",
+            )
+            .parameter("token_address", "Token address", "string", true)
+            .parameter("time_from", "Start timestamp (Unix seconds)", "integer", true)
+            .parameter("time_to", "End timestamp (Unix seconds)", "integer", true)
+            .parameter(
+                "resolution",
+                "Candle width: 1m, 5m, 15m, 1h, 4h or 1d (default 1h). At most 1000 candles per request",
+                "string",
+                false,
+            )
+            .parameter(
+                "chain",
+                "Chain: sol, eth, base, bsc or tron (default sol)",
+                "string",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add token info action
+        {
+            async fn get_token_info<S: Send + Sync + Clone + 'static>(
+                client: GmgnClient,
+                params: TokenInfoParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let chain = parse_chain(params.chain)?;
+                let info = client.get_token_info(chain, params.token_address).await?;
+
+                serde_json::to_string(&info)
+                    .map_err(|e| format!("Failed to serialize GMGN response: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_gmgn_token_info",
+                with_client(&client, get_token_info),
+                None,
+            )
+            .description(
+                "Get price, market cap, liquidity, holder count and creation details of a token from GMGN",
+            )
+            .parameter("token_address", "Token address", "string", true)
+            .parameter(
+                "chain",
+                "Chain: sol, eth, base, bsc or tron (default sol)",
+                "string",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add top holders action
+        {
+            async fn get_top_holders<S: Send + Sync + Clone + 'static>(
+                client: GmgnClient,
+                params: TopHoldersParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let chain = parse_chain(params.chain)?;
+                let holders = client
+                    .get_top_holders(chain, params.token_address, params.limit)
+                    .await?;
+
+                serde_json::to_string(&holders)
+                    .map_err(|e| format!("Failed to serialize GMGN response: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_gmgn_top_holders",
+                with_client(&client, get_top_holders),
+                None,
+            )
+            .description(
+                "Get the largest holders of a token from GMGN with their share of supply, PnL and wallet tags (smart money, snipers, ...)",
+            )
+            .parameter("token_address", "Token address", "string", true)
+            .parameter(
+                "chain",
+                "Chain: sol, eth, base, bsc or tron (default sol)",
+                "string",
+                false,
+            )
+            .parameter("limit", "Number of holders to return (default 20)", "integer", false)
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add wallet activity action
+        {
+            async fn get_wallet_activity<S: Send + Sync + Clone + 'static>(
+                client: GmgnClient,
+                params: WalletActivityParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let chain = parse_chain(params.chain)?;
+                let activity = client
+                    .get_wallet_activity(chain, params.wallet_address, params.limit, params.cursor)
+                    .await?;
+
+                serde_json::to_string(&activity)
+                    .map_err(|e| format!("Failed to serialize GMGN response: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_gmgn_wallet_activity",
+                with_client(&client, get_wallet_activity),
+                None,
+            )
+            .description(
+                "Get the recent buys, sells and transfers of a wallet from GMGN, e.g. to follow a smart-money wallet",
+            )
+            .parameter("wallet_address", "Wallet address", "string", true)
+            .parameter(
+                "chain",
+                "Chain: sol, eth, base, bsc or tron (default sol)",
+                "string",
+                false,
+            )
+            .parameter("limit", "Number of activities to return (default 20)", "integer", false)
+            .parameter(
+                "cursor",
+                "The `next` cursor of the previous page, to fetch older activity",
+                "string",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add new pairs action
+        {
+            async fn get_new_pairs<S: Send + Sync + Clone + 'static>(
+                client: GmgnClient,
+                params: NewPairsParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let chain = parse_chain(params.chain)?;
+                let pairs = client.get_new_pairs(chain, params.limit).await?;
+
+                serde_json::to_string(&pairs)
+                    .map_err(|e| format!("Failed to serialize GMGN response: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_gmgn_new_pairs",
+                with_client(&client, get_new_pairs),
+                None,
+            )
+            .description("Get the most recently opened trading pairs on a chain from GMGN")
+            .parameter(
+                "chain",
+                "Chain: sol, eth, base, bsc or tron (default sol)",
+                "string",
+                false,
+            )
+            .parameter(
+                "limit",
+                "Number of pairs to return (default 20)",
+                "integer",
+                false,
             )
-            .parameter("token_address", "Solana token address", "string", true)
-            .parameter("time_from", "Start timestamp (Unix)", "integer", true)
-            .parameter("time_to", "End timestamp (Unix)", "integer", true)
             .build();
 
             actions.push(Arc::new(action));
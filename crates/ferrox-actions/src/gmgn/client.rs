@@ -0,0 +1,275 @@
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+use super::models::{
+    GmgnChain, GmgnKlineResponse, GmgnResolution, GmgnResponse, NewPairsData, TokenInfoData,
+    TopHolder, WalletActivityData,
+};
+use crate::http::{HttpConfig, ProviderHttp, RateLimiter, GMGN_QUOTA};
+
+const BASE_URL: &str = "https://www.gmgn.cc";
+
+/// Most candles a single kline request may ask for. Wider ranges have to be
+/// split or fetched at a coarser resolution.
+pub const MAX_CANDLES: i64 = 1000;
+
+/// Timestamps above this are taken to be milliseconds (it is year 5138 in
+/// seconds).
+const MAX_UNIX_SECONDS: i64 = 100_000_000_000;
+
+#[derive(Debug, Clone)]
+pub struct GmgnClient {
+    http: ProviderHttp,
+    base_url: String,
+}
+
+impl Default for GmgnClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GmgnClient {
+    pub fn new() -> Self {
+        Self {
+            http: ProviderHttp::new("gmgn", GMGN_QUOTA),
+            base_url: BASE_URL.to_string(),
+        }
+    }
+
+    /// Overrides the API host, e.g. to point the client at a mock server.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn with_http_config(mut self, config: HttpConfig) -> Self {
+        self.http = self.http.with_config(config);
+        self
+    }
+
+    /// Replaces the shared rate limiter.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.http = self.http.with_limiter(limiter);
+        self
+    }
+
+    /// Fetches `endpoint` and decodes GMGN's `{code, msg, data}` envelope,
+    /// turning a non-zero `code` into an error. `what` names the data in error
+    /// messages.
+    async fn get_data<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        query: &[(&str, String)],
+        what: &str,
+    ) -> Result<GmgnResponse<T>, String> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let body = self
+            .http
+            .get_with_query(&url, &HeaderMap::new(), query)
+            .await
+            .map_err(|e| {
+                log::warn!("Failed to fetch from GMGN: {}", e);
+                format!("Failed to fetch {}: {}", what, e)
+            })?;
+        let parse_error = |e: serde_json::Error| {
+            log::warn!("Failed to parse GMGN response: {}", e);
+            format!("Error parsing {}: {}", what, e)
+        };
+        // Error responses usually carry `data: null`, so check the code before
+        // decoding the payload
+        let response: GmgnResponse<serde_json::Value> =
+            serde_json::from_str(&body).map_err(parse_error)?;
+        if response.code != 0 {
+            return Err(format!("GMGN error {}: {}", response.code, response.msg));
+        }
+        Ok(GmgnResponse {
+            code: response.code,
+            msg: response.msg,
+            data: serde_json::from_value(response.data).map_err(parse_error)?,
+        })
+    }
+
+    /// Candles for `token_address` between `time_from` and `time_to` (Unix
+    /// seconds).
+    pub async fn get_kline(
+        &self,
+        chain: GmgnChain,
+        token_address: String,
+        resolution: GmgnResolution,
+        time_from: i64,
+        time_to: i64,
+    ) -> Result<GmgnKlineResponse, String> {
+        validate_time_range(time_from, time_to, resolution)?;
+        self.get_data(
+            &format!(
+                "/defi/quotation/v1/tokens/kline/{}/{}",
+                chain, token_address
+            ),
+            &[
+                ("resolution", resolution.to_string()),
+                ("from", time_from.to_string()),
+                ("to", time_to.to_string()),
+            ],
+            "kline data",
+        )
+        .await
+    }
+
+    pub async fn get_token_info(
+        &self,
+        chain: GmgnChain,
+        token_address: String,
+    ) -> Result<GmgnResponse<TokenInfoData>, String> {
+        self.get_data(
+            &format!("/defi/quotation/v1/tokens/{}/{}", chain, token_address),
+            &[],
+            "token info",
+        )
+        .await
+    }
+
+    /// Largest holders of a token, biggest first.
+    pub async fn get_top_holders(
+        &self,
+        chain: GmgnChain,
+        token_address: String,
+        limit: Option<u32>,
+    ) -> Result<GmgnResponse<Vec<TopHolder>>, String> {
+        self.get_data(
+            &format!(
+                "/defi/quotation/v1/tokens/top_holders/{}/{}",
+                chain, token_address
+            ),
+            &[
+                ("limit", limit.unwrap_or(20).to_string()),
+                ("orderby", "amount_percentage".to_string()),
+                ("direction", "desc".to_string()),
+            ],
+            "top holders",
+        )
+        .await
+    }
+
+    /// Recent trades and transfers of a wallet, newest first. Pass the `next`
+    /// cursor of the previous page to continue.
+    pub async fn get_wallet_activity(
+        &self,
+        chain: GmgnChain,
+        wallet_address: String,
+        limit: Option<u32>,
+        cursor: Option<String>,
+    ) -> Result<GmgnResponse<WalletActivityData>, String> {
+        let mut query = vec![
+            ("wallet", wallet_address),
+            ("limit", limit.unwrap_or(20).to_string()),
+        ];
+        if let Some(cursor) = cursor {
+            query.push(("cursor", cursor));
+        }
+        self.get_data(
+            &format!("/defi/quotation/v1/wallet_activity/{}", chain),
+            &query,
+            "wallet activity",
+        )
+        .await
+    }
+
+    /// Newest pools, most recently opened first.
+    pub async fn get_new_pairs(
+        &self,
+        chain: GmgnChain,
+        limit: Option<u32>,
+    ) -> Result<GmgnResponse<NewPairsData>, String> {
+        self.get_data(
+            &format!("/defi/quotation/v1/pairs/{}/new_pairs", chain),
+            &[
+                ("limit", limit.unwrap_or(20).to_string()),
+                ("orderby", "open_timestamp".to_string()),
+                ("direction", "desc".to_string()),
+            ],
+            "new pairs",
+        )
+        .await
+    }
+}
+
+/// Rejects ranges GMGN would answer with an empty or truncated series, so the
+/// model gets told what to fix instead.
+pub fn validate_time_range(
+    time_from: i64,
+    time_to: i64,
+    resolution: GmgnResolution,
+) -> Result<(), String> {
+    if time_from < 0 || time_to < 0 {
+        return Err("time_from and time_to must be positive Unix timestamps".to_string());
+    }
+    if time_from >= MAX_UNIX_SECONDS || time_to >= MAX_UNIX_SECONDS {
+        return Err("time_from and time_to must be Unix seconds, not milliseconds".to_string());
+    }
+    if time_from >= time_to {
+        return Err(format!(
+            "time_from ({}) must be before time_to ({})",
+            time_from, time_to
+        ));
+    }
+    let candles = (time_to - time_from) / resolution.seconds();
+    if candles > MAX_CANDLES {
+        return Err(format!(
+            "Range covers {} {} candles, at most {} are allowed; narrow the range or use a coarser resolution",
+            candles, resolution, MAX_CANDLES
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_time_range() {
+        let hour = GmgnResolution::OneHour;
+        assert!(validate_time_range(1_700_000_000, 1_700_086_400, hour).is_ok());
+        assert!(validate_time_range(1_700_086_400, 1_700_000_000, hour).is_err());
+        assert!(validate_time_range(1_700_000_000, 1_700_000_000, hour).is_err());
+        assert!(
+            validate_time_range(1_700_000_000_000, 1_700_086_400_000, hour)
+                .unwrap_err()
+                .contains("milliseconds")
+        );
+        // A year of minute candles is far over the cap
+        assert!(validate_time_range(
+            1_700_000_000,
+            1_700_000_000 + 365 * 86_400,
+            GmgnResolution::OneMinute
+        )
+        .unwrap_err()
+        .contains("coarser resolution"));
+    }
+
+    #[tokio::test]
+    async fn test_api_error_code_is_reported() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/defi/quotation/v1/tokens/sol/unknown")
+            .with_status(200)
+            .with_body(r#"{"code":40000,"msg":"token not found","data":null}"#)
+            .create_async()
+            .await;
+
+        let client = GmgnClient::new()
+            .with_base_url(server.url())
+            .with_rate_limiter(Arc::new(RateLimiter::new(crate::http::Quota::per_second(
+                100,
+            ))));
+        let error = client
+            .get_token_info(GmgnChain::Sol, "unknown".to_string())
+            .await
+            .unwrap_err();
+
+        mock.assert_async().await;
+        assert_eq!(error, "GMGN error 40000: token not found");
+    }
+}
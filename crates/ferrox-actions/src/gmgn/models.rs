@@ -0,0 +1,352 @@
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Chains GMGN serves data for, in the path form its API expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GmgnChain {
+    #[default]
+    Sol,
+    Eth,
+    Base,
+    Bsc,
+    Tron,
+}
+
+impl GmgnChain {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GmgnChain::Sol => "sol",
+            GmgnChain::Eth => "eth",
+            GmgnChain::Base => "base",
+            GmgnChain::Bsc => "bsc",
+            GmgnChain::Tron => "tron",
+        }
+    }
+}
+
+impl fmt::Display for GmgnChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GmgnChain {
+    type Err = String;
+
+    /// Accepts GMGN's short names as well as the full chain names the model
+    /// tends to use, e.g. `solana` or `ethereum`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "sol" | "solana" => Ok(GmgnChain::Sol),
+            "eth" | "ethereum" => Ok(GmgnChain::Eth),
+            "base" => Ok(GmgnChain::Base),
+            "bsc" | "bnb" => Ok(GmgnChain::Bsc),
+            "tron" | "trx" => Ok(GmgnChain::Tron),
+            other => Err(format!(
+                "Unsupported GMGN chain '{}', expected one of sol, eth, base, bsc, tron",
+                other
+            )),
+        }
+    }
+}
+
+/// Candle widths supported by the kline endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GmgnResolution {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "1h")]
+    #[default]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl GmgnResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GmgnResolution::OneMinute => "1m",
+            GmgnResolution::FiveMinutes => "5m",
+            GmgnResolution::FifteenMinutes => "15m",
+            GmgnResolution::OneHour => "1h",
+            GmgnResolution::FourHours => "4h",
+            GmgnResolution::OneDay => "1d",
+        }
+    }
+
+    /// Width of one candle in seconds.
+    pub fn seconds(&self) -> i64 {
+        match self {
+            GmgnResolution::OneMinute => 60,
+            GmgnResolution::FiveMinutes => 5 * 60,
+            GmgnResolution::FifteenMinutes => 15 * 60,
+            GmgnResolution::OneHour => 60 * 60,
+            GmgnResolution::FourHours => 4 * 60 * 60,
+            GmgnResolution::OneDay => 24 * 60 * 60,
+        }
+    }
+}
+
+impl fmt::Display for GmgnResolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GmgnResolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "1m" | "1" => Ok(GmgnResolution::OneMinute),
+            "5m" | "5" => Ok(GmgnResolution::FiveMinutes),
+            "15m" | "15" => Ok(GmgnResolution::FifteenMinutes),
+            "1h" | "60" => Ok(GmgnResolution::OneHour),
+            "4h" | "240" => Ok(GmgnResolution::FourHours),
+            "1d" | "d" => Ok(GmgnResolution::OneDay),
+            other => Err(format!(
+                "Unsupported GMGN resolution '{}', expected one of 1m, 5m, 15m, 1h, 4h, 1d",
+                other
+            )),
+        }
+    }
+}
+
+/// Envelope GMGN wraps every payload in. A non-zero `code` is an API error
+/// described by `msg`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GmgnResponse<T> {
+    pub code: i32,
+    #[serde(default)]
+    pub msg: String,
+    pub data: T,
+}
+
+pub type GmgnKlineResponse = GmgnResponse<Vec<KlineData>>;
+
+/// One candle of `/defi/quotation/v1/tokens/kline/{chain}/{address}`. GMGN
+/// sends the values as decimal strings; they are parsed on the way in.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct KlineData {
+    #[serde(deserialize_with = "f64_from_any")]
+    pub open: f64,
+    #[serde(deserialize_with = "f64_from_any")]
+    pub high: f64,
+    #[serde(deserialize_with = "f64_from_any")]
+    pub low: f64,
+    #[serde(deserialize_with = "f64_from_any")]
+    pub close: f64,
+    #[serde(deserialize_with = "f64_from_any")]
+    pub volume: f64,
+    /// Candle open time in milliseconds.
+    #[serde(deserialize_with = "i64_from_any")]
+    pub time: i64,
+}
+
+/// `data` of `/defi/quotation/v1/tokens/{chain}/{address}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenInfoData {
+    pub token: TokenInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenInfo {
+    pub address: String,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: Option<u8>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub market_cap: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub liquidity: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub volume_24h: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub price_change_24h: Option<f64>,
+    pub holder_count: Option<u64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub total_supply: Option<f64>,
+    /// Unix seconds.
+    pub creation_timestamp: Option<i64>,
+    pub creator_address: Option<String>,
+    pub logo: Option<String>,
+}
+
+/// One entry of `/defi/quotation/v1/tokens/top_holders/{chain}/{address}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopHolder {
+    pub address: String,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub amount_cur: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub usd_value: Option<f64>,
+    /// Share of the supply held, as a fraction (0.05 is 5%).
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub amount_percentage: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub realized_profit: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub unrealized_profit: Option<f64>,
+    /// GMGN wallet labels such as `smart_degen` or `sniper`.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// `data` of `/defi/quotation/v1/wallet_activity/{chain}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletActivityData {
+    #[serde(default)]
+    pub activities: Vec<WalletActivity>,
+    /// Cursor for the next page, absent on the last one.
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletActivity {
+    pub wallet: String,
+    pub tx_hash: String,
+    /// Unix seconds.
+    pub timestamp: i64,
+    /// `buy`, `sell`, `transferIn`, `transferOut`, ...
+    pub event_type: String,
+    pub token: ActivityToken,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub token_amount: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub cost_usd: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub price_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityToken {
+    pub address: String,
+    pub symbol: Option<String>,
+}
+
+/// `data` of `/defi/quotation/v1/pairs/{chain}/new_pairs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewPairsData {
+    #[serde(default)]
+    pub pairs: Vec<NewPair>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewPair {
+    pub address: String,
+    pub base_address: String,
+    pub quote_address: String,
+    pub quote_symbol: Option<String>,
+    /// Unix seconds at which the pool opened.
+    pub open_timestamp: Option<i64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub initial_liquidity: Option<f64>,
+    pub launchpad: Option<String>,
+    pub base_token_info: Option<NewPairToken>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewPairToken {
+    pub symbol: Option<String>,
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub price: Option<f64>,
+    #[serde(default, deserialize_with = "opt_f64_from_any")]
+    pub market_cap: Option<f64>,
+    pub holder_count: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl NumberOrString {
+    fn into_f64<E: de::Error>(self) -> Result<f64, E> {
+        match self {
+            NumberOrString::Int(value) => Ok(value as f64),
+            NumberOrString::Float(value) => Ok(value),
+            NumberOrString::String(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| E::custom(format!("invalid number '{}'", value))),
+        }
+    }
+}
+
+fn f64_from_any<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    NumberOrString::deserialize(deserializer)?.into_f64()
+}
+
+fn opt_f64_from_any<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    match Option::<NumberOrString>::deserialize(deserializer)? {
+        Some(NumberOrString::String(value)) if value.trim().is_empty() => Ok(None),
+        Some(value) => value.into_f64().map(Some),
+        None => Ok(None),
+    }
+}
+
+fn i64_from_any<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Int(value) => Ok(value),
+        NumberOrString::Float(value) => Ok(value as i64),
+        NumberOrString::String(value) => value
+            .trim()
+            .parse()
+            .map_err(|_| de::Error::custom(format!("invalid integer '{}'", value))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kline_from_strings() {
+        let json = r#"{"code":0,"msg":"success","data":[
+            {"open":"146.21","high":"148.9","low":"145.02","close":"147.85","volume":"1250345.5","time":"1700002800000"},
+            {"open":1.5,"high":2,"low":1,"close":1.75,"volume":10,"time":1700006400000}
+        ]}"#;
+        let response: GmgnKlineResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.data[0].open, 146.21);
+        assert_eq!(response.data[0].time, 1_700_002_800_000);
+        assert_eq!(response.data[1].high, 2.0);
+        assert_eq!(response.data[1].time, 1_700_006_400_000);
+    }
+
+    #[test]
+    fn test_kline_rejects_garbage() {
+        let json = r#"{"code":0,"msg":"","data":[
+            {"open":"n/a","high":"1","low":"1","close":"1","volume":"1","time":"1"}
+        ]}"#;
+        assert!(serde_json::from_str::<GmgnKlineResponse>(json).is_err());
+    }
+
+    #[test]
+    fn test_chain_and_resolution_parsing() {
+        assert_eq!("solana".parse::<GmgnChain>(), Ok(GmgnChain::Sol));
+        assert_eq!("ETH".parse::<GmgnChain>(), Ok(GmgnChain::Eth));
+        assert!("polygon".parse::<GmgnChain>().is_err());
+        assert_eq!(
+            "4h".parse::<GmgnResolution>(),
+            Ok(GmgnResolution::FourHours)
+        );
+        assert_eq!(
+            "15".parse::<GmgnResolution>(),
+            Ok(GmgnResolution::FifteenMinutes)
+        );
+        assert!("3h".parse::<GmgnResolution>().is_err());
+    }
+}
@@ -0,0 +1,571 @@
+//! Shared HTTP layer for the data-provider clients.
+//!
+//! Every provider goes through one pooled `reqwest::Client`. Each provider gets
+//! a token-bucket [`RateLimiter`] sized to its documented quota, and transient
+//! failures (429, 5xx, timeouts, refused connections) are retried with
+//! exponential backoff.
+
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    StatusCode,
+};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::cache::{self, ResponseCache};
+
+/// Birdeye Standard plan: 1 request per second.
+pub const BIRDEYE_QUOTA: Quota = Quota::per_second(1);
+/// DexScreener token profile, boost and order endpoints: 60 requests per minute.
+pub const DEXSCREENER_QUOTA: Quota = Quota::per_minute(60);
+/// DexScreener pair, search and token endpoints: 300 requests per minute.
+pub const DEXSCREENER_PAIRS_QUOTA: Quota = Quota::per_minute(300);
+/// CoinGecko keyless public API: roughly 10 requests per minute.
+pub const COINGECKO_PUBLIC_QUOTA: Quota = Quota::per_minute(10);
+/// CoinGecko Demo plan: 30 requests per minute.
+pub const COINGECKO_DEMO_QUOTA: Quota = Quota::per_minute(30);
+/// CoinGecko Pro (Analyst) plan: 500 requests per minute.
+pub const COINGECKO_PRO_QUOTA: Quota = Quota::per_minute(500);
+/// GMGN publishes no quota; stay well under what its public site tolerates.
+pub const GMGN_QUOTA: Quota = Quota::per_second(2);
+/// Jupiter keyless API: 60 requests per minute.
+pub const JUPITER_QUOTA: Quota = Quota::per_minute(60);
+/// Public Solana mainnet RPC: 100 requests per 10 seconds per IP.
+pub const SOLANA_RPC_QUOTA: Quota = Quota::per_second(10);
+/// Pump.fun frontend API publishes no quota; keep to a browser's pace.
+pub const PUMP_QUOTA: Quota = Quota::per_second(1);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Returns the process-wide client, so all providers share one connection pool.
+pub fn shared_client() -> reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT
+        .get_or_init(|| {
+            reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+                .build()
+                .expect("failed to build HTTP client")
+        })
+        .clone()
+}
+
+/// Timeout and retry settings for a provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpConfig {
+    /// Total time allowed for a single attempt, including reading the body.
+    pub timeout: Duration,
+    /// Retries after the first attempt. `0` disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled on each subsequent retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for a single delay. A `Retry-After` longer than this is not
+    /// waited out; the error is returned to the caller instead.
+    pub max_backoff: Duration,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+/// A request budget: `requests` per `period`, with bursts up to `requests`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    pub const fn per_second(requests: u32) -> Self {
+        Self {
+            requests,
+            period: Duration::from_secs(1),
+        }
+    }
+
+    pub const fn per_minute(requests: u32) -> Self {
+        Self {
+            requests,
+            period: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token-bucket rate limiter. Callers wait in [`RateLimiter::acquire`] until a
+/// token is available.
+#[derive(Debug)]
+pub struct RateLimiter {
+    quota: Quota,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(quota: Quota) -> Self {
+        Self {
+            quota,
+            bucket: Mutex::new(Bucket {
+                tokens: quota.requests as f64,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Returns the limiter registered under `key`, creating it with `quota` on
+    /// first use. Clients for the same provider share one budget this way.
+    pub fn shared(key: &str, quota: Quota) -> Arc<Self> {
+        static LIMITERS: OnceLock<Mutex<HashMap<String, Arc<RateLimiter>>>> = OnceLock::new();
+        LIMITERS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_insert_with(|| Arc::new(RateLimiter::new(quota)))
+            .clone()
+    }
+
+    pub fn quota(&self) -> Quota {
+        self.quota
+    }
+
+    /// Waits until a request may be sent and consumes one token.
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes a token if one is available, otherwise returns how long until one is.
+    fn try_acquire(&self) -> Option<Duration> {
+        let capacity = self.quota.requests.max(1) as f64;
+        let rate = capacity / self.quota.period.as_secs_f64();
+
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+/// Errors returned by [`ProviderHttp`] once retries are exhausted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HttpError {
+    /// The attempt exceeded [`HttpConfig::timeout`].
+    Timeout(String),
+    /// The connection could not be established.
+    Connect(String),
+    /// Any other failure before a response arrived (bad URL, TLS, body read).
+    Transport(String),
+    /// The provider answered with a non-2xx status.
+    Status {
+        status: StatusCode,
+        retry_after: Option<Duration>,
+        body: String,
+    },
+}
+
+impl HttpError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            HttpError::Timeout(_) | HttpError::Connect(_) => true,
+            HttpError::Transport(_) => false,
+            HttpError::Status { status, .. } => {
+                *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+            }
+        }
+    }
+}
+
+impl From<reqwest::Error> for HttpError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            HttpError::Timeout(error.to_string())
+        } else if error.is_connect() {
+            HttpError::Connect(error.to_string())
+        } else {
+            HttpError::Transport(error.to_string())
+        }
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::Timeout(message) => write!(f, "Request timed out: {}", message),
+            HttpError::Connect(message) => write!(f, "Connection failed: {}", message),
+            HttpError::Transport(message) => write!(f, "Request failed: {}", message),
+            HttpError::Status { status, .. } => {
+                write!(f, "Request failed with status: {}", status)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+/// HTTP access for one provider: shared pool, the provider's rate limiter and
+/// its retry policy.
+#[derive(Debug, Clone)]
+pub struct ProviderHttp {
+    provider: &'static str,
+    client: reqwest::Client,
+    limiter: Arc<RateLimiter>,
+    config: HttpConfig,
+    cache: Option<ResponseCache>,
+}
+
+impl ProviderHttp {
+    /// Uses the limiter shared by every client of `provider`, and the
+    /// process-wide cache if one is installed.
+    pub fn new(provider: &'static str, quota: Quota) -> Self {
+        Self {
+            provider,
+            client: shared_client(),
+            limiter: RateLimiter::shared(provider, quota),
+            config: HttpConfig::default(),
+            cache: cache::global().cloned(),
+        }
+    }
+
+    pub fn with_config(mut self, config: HttpConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Replaces the shared limiter, e.g. for a plan with a higher quota.
+    pub fn with_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn config(&self) -> &HttpConfig {
+        &self.config
+    }
+
+    pub async fn get(&self, url: &str, headers: &HeaderMap) -> Result<String, HttpError> {
+        self.get_with_query(url, headers, &[] as &[(&str, &str)])
+            .await
+    }
+
+    /// Sends a GET, retrying transient failures, and returns the body of the
+    /// first successful response. Fresh cached responses are returned without
+    /// a request.
+    pub async fn get_with_query<Q: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        headers: &HeaderMap,
+        query: &Q,
+    ) -> Result<String, HttpError> {
+        let request = self
+            .client
+            .get(url)
+            .headers(headers.clone())
+            .query(query)
+            .timeout(self.config.timeout)
+            .build()?;
+
+        if let Some(body) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.get(self.provider, request.url()))
+        {
+            log::debug!("{} cache hit for {}", self.provider, request.url());
+            return Ok(body);
+        }
+        self.execute(request, true).await
+    }
+
+    /// Sends a POST with a JSON body, retrying transient failures. Responses
+    /// are never cached. Only use this for requests that are safe to repeat,
+    /// such as quotes, reads and rebroadcasts of a signed transaction.
+    pub async fn post_json<B: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        headers: &HeaderMap,
+        body: &B,
+    ) -> Result<String, HttpError> {
+        let request = self
+            .client
+            .post(url)
+            .headers(headers.clone())
+            .json(body)
+            .timeout(self.config.timeout)
+            .build()?;
+        self.execute(request, false).await
+    }
+
+    /// Sends `request` until it succeeds or fails for good, storing successful
+    /// bodies in the cache when `cacheable`.
+    async fn execute(
+        &self,
+        request: reqwest::Request,
+        cacheable: bool,
+    ) -> Result<String, HttpError> {
+        let mut attempt = 0;
+        loop {
+            self.limiter.acquire().await;
+            log::debug!("{} request to {}", self.provider, request.url());
+            // Requests carry no body or an in-memory one, so cloning always succeeds.
+            let attempt_request = request.try_clone().expect("request is cloneable");
+            let error = match self.send(attempt_request).await {
+                Ok(body) => {
+                    if let Some(cache) = self.cache.as_ref().filter(|_| cacheable) {
+                        cache.put(self.provider, request.url(), &body);
+                    }
+                    return Ok(body);
+                }
+                Err(error) => error,
+            };
+            let Some(delay) = self.retry_delay(&error, attempt) else {
+                log::warn!(
+                    "{} request to {} failed: {}",
+                    self.provider,
+                    request.url(),
+                    error
+                );
+                return Err(error);
+            };
+            log::warn!(
+                "{} request to {} failed ({}), retrying in {:?}",
+                self.provider,
+                request.url(),
+                error,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn send(&self, request: reqwest::Request) -> Result<String, HttpError> {
+        let response = self.client.execute(request).await?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.text().await?);
+        }
+
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+        Err(HttpError::Status {
+            status,
+            retry_after,
+            body,
+        })
+    }
+
+    /// How long to wait before retrying `error`, or `None` to give up.
+    fn retry_delay(&self, error: &HttpError, attempt: u32) -> Option<Duration> {
+        if attempt >= self.config.max_retries || !error.is_retryable() {
+            return None;
+        }
+        if let HttpError::Status {
+            retry_after: Some(retry_after),
+            ..
+        } = error
+        {
+            return (*retry_after <= self.config.max_backoff).then_some(*retry_after);
+        }
+        let backoff = self
+            .config
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.config.max_backoff);
+        Some(backoff + jitter(backoff / 4))
+    }
+}
+
+/// Cheap jitter so concurrent callers don't retry in lockstep.
+fn jitter(max: Duration) -> Duration {
+    let max_nanos = max.as_nanos() as u64;
+    if max_nanos == 0 {
+        return Duration::ZERO;
+    }
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos() as u64)
+        .unwrap_or_default();
+    Duration::from_nanos(seed % max_nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_http(quota: Quota) -> ProviderHttp {
+        ProviderHttp::new("test", quota)
+            .with_limiter(Arc::new(RateLimiter::new(quota)))
+            .with_config(HttpConfig {
+                timeout: Duration::from_secs(5),
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(10),
+                max_backoff: Duration::from_millis(100),
+            })
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors() {
+        let mut server = mockito::Server::new_async().await;
+        let failing = server
+            .mock("GET", "/flaky")
+            .with_status(503)
+            .expect(3)
+            .create_async()
+            .await;
+        let url = format!("{}/flaky", server.url());
+        let http = test_http(Quota::per_second(100));
+
+        let result = http.get(&url, &HeaderMap::new()).await;
+        failing.assert_async().await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Status { status, .. }) if status == StatusCode::SERVICE_UNAVAILABLE
+        ));
+
+        failing.remove_async().await;
+        server
+            .mock("GET", "/flaky")
+            .with_status(200)
+            .with_body("ok")
+            .create_async()
+            .await;
+        assert_eq!(http.get(&url, &HeaderMap::new()).await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_client_errors() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/missing")
+            .with_status(404)
+            .expect(1)
+            .create_async()
+            .await;
+        let http = test_http(Quota::per_second(100));
+
+        let result = http
+            .get(&format!("{}/missing", server.url()), &HeaderMap::new())
+            .await;
+        mock.assert_async().await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Status { status, .. }) if status == StatusCode::NOT_FOUND
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_long_retry_after_is_surfaced() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/limited")
+            .with_status(429)
+            .with_header("retry-after", "60")
+            .expect(1)
+            .create_async()
+            .await;
+        let http = test_http(Quota::per_second(100));
+
+        let result = http
+            .get(&format!("{}/limited", server.url()), &HeaderMap::new())
+            .await;
+        mock.assert_async().await;
+        assert!(matches!(
+            result,
+            Err(HttpError::Status { retry_after: Some(retry_after), .. })
+                if retry_after == Duration::from_secs(60)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cached_responses_skip_the_network() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/defi/price")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body("{\"value\":1}")
+            .expect(1)
+            .create_async()
+            .await;
+        let cache = ResponseCache::new(
+            Arc::new(crate::cache::MemoryBackend::default()),
+            crate::cache::CachePolicy::new().rule("test", "/defi/price", Duration::from_secs(60)),
+        );
+        let http = test_http(Quota::per_second(100)).with_cache(cache);
+        let url = format!("{}/defi/price", server.url());
+
+        let (bodies, hits) = crate::cache::collect_hits(async {
+            let mut bodies = Vec::new();
+            for _ in 0..2 {
+                bodies.push(
+                    http.get_with_query(&url, &HeaderMap::new(), &[("address", "abc")])
+                        .await
+                        .unwrap(),
+                );
+            }
+            bodies
+        })
+        .await;
+        mock.assert_async().await;
+        assert_eq!(bodies, vec!["{\"value\":1}", "{\"value\":1}"]);
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_requests() {
+        let limiter = RateLimiter::new(Quota::per_second(10));
+        let start = Instant::now();
+        // the first 10 come from the initial burst, the next 2 wait ~100ms each
+        for _ in 0..12 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[test]
+    fn test_shared_limiter_is_per_key() {
+        let a = RateLimiter::shared("shared-test-a", Quota::per_second(1));
+        let b = RateLimiter::shared("shared-test-a", Quota::per_second(5));
+        let c = RateLimiter::shared("shared-test-c", Quota::per_second(5));
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert_eq!(b.quota(), Quota::per_second(1));
+    }
+}
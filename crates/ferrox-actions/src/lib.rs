@@ -1,19 +1,39 @@
 mod action;
 pub mod birdeye;
+pub mod cache;
+pub mod candles;
+pub mod chart;
 pub mod coingecko;
 pub mod dexscreener;
 pub mod gmgn;
+pub mod http;
+pub mod output;
+pub mod solana;
+pub mod ta;
 
 use std::sync::Arc;
 
 pub use action::{
-    ActionBuilder, ActionDefinition, ActionGroup, ActionParameter, ConfirmHandler, EmptyParams,
-    FunctionAction,
+    ActionBuilder, ActionDefinition, ActionFuture, ActionGroup, ActionParameter, ConfirmHandler,
+    EmptyParams, FunctionAction,
 };
 pub use birdeye::BirdeyeActionGroup;
+pub use candles::CandleActionGroup;
+pub use chart::ChartActionGroup;
 pub use coingecko::CoinGeckoActionGroup;
 pub use dexscreener::DexScreenerActionGroup;
 pub use gmgn::GmgnActionGroup;
+pub use output::{ActionOutput, Artifact, Attachment, AttachmentKind, Table, UiHint};
+pub use solana::{
+    accounts::WalletAccountsActionGroup,
+    address_book::{AddressBook, AddressBookActionGroup},
+    backup::WalletBackupActionGroup,
+    jupiter::{dca::JupiterDcaActionGroup, JupiterActionGroup},
+    pump::PumpActionGroup,
+    rpc::SolanaRpcActionGroup,
+    transfer::TransferActionGroup,
+};
+pub use ta::TechnicalAnalysisActionGroup;
 
 pub type AgentState<S> = Arc<Mutex<S>>;
 use tokio::sync::Mutex;
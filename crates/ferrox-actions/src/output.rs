@@ -0,0 +1,320 @@
+//! What an action produces besides the string the model reads: artifacts for
+//! the user, such as a rendered chart or a table, and hints for the front-end,
+//! such as buttons and links.
+//!
+//! Handlers still return a result for the model; alongside it they call
+//! [`attach`], [`attach_table`] or [`hint`], and whoever ran the action through
+//! [`FunctionAction::execute_rich`](crate::FunctionAction::execute_rich)
+//! receives all of it as an [`ActionOutput`]. Plain `execute` returns the same
+//! string as before and drops the rest.
+
+use std::{fmt, future::Future, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttachmentKind {
+    /// Shown inline, e.g. sent as a Telegram photo.
+    Image,
+    /// Offered as a download.
+    File,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Attachment {
+    pub kind: AttachmentKind,
+    pub file_name: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    pub fn png(file_name: impl Into<String>, data: Vec<u8>) -> Self {
+        Self {
+            kind: AttachmentKind::Image,
+            file_name: file_name.into(),
+            mime_type: "image/png".to_string(),
+            data,
+        }
+    }
+
+    pub fn file(file_name: impl Into<String>, mime_type: impl Into<String>, data: Vec<u8>) -> Self {
+        Self {
+            kind: AttachmentKind::File,
+            file_name: file_name.into(),
+            mime_type: mime_type.into(),
+            data,
+        }
+    }
+}
+
+// Attachments can be megabytes; keep them out of debug logs
+impl fmt::Debug for Attachment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Attachment")
+            .field("kind", &self.kind)
+            .field("file_name", &self.file_name)
+            .field("mime_type", &self.mime_type)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+/// Rows of text under named columns. Front-ends that cannot draw tables can
+/// fall back to [`to_text`](Self::to_text) or [`to_csv`](Self::to_csv).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Table {
+    pub title: Option<String>,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<C: Into<String>>(columns: impl IntoIterator<Item = C>) -> Self {
+        Self {
+            title: None,
+            columns: columns.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn row<C: ToString>(mut self, cells: impl IntoIterator<Item = C>) -> Self {
+        self.rows
+            .push(cells.into_iter().map(|cell| cell.to_string()).collect());
+        self
+    }
+
+    /// The table as aligned plain text, for monospaced display.
+    pub fn to_text(&self) -> String {
+        fn cell(row: &[String], i: usize) -> &str {
+            row.get(i).map(String::as_str).unwrap_or("")
+        }
+        let widths: Vec<usize> = (0..self.columns.len())
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .map(|row| cell(row, i).chars().count())
+                    .chain([self.columns[i].chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let line = |row: &[String]| {
+            widths
+                .iter()
+                .enumerate()
+                .map(|(i, width)| format!("{:<width$}", cell(row, i), width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let mut lines = vec![line(&self.columns)];
+        lines.push(
+            widths
+                .iter()
+                .map(|width| "-".repeat(*width))
+                .collect::<Vec<_>>()
+                .join("  "),
+        );
+        lines.extend(self.rows.iter().map(|row| line(row)));
+        lines.join("\n")
+    }
+
+    /// The table as CSV, header first.
+    pub fn to_csv(&self) -> String {
+        let field = |value: &String| {
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.clone()
+            }
+        };
+        std::iter::once(&self.columns)
+            .chain(&self.rows)
+            .map(|row| row.iter().map(field).collect::<Vec<_>>().join(",") + "\n")
+            .collect()
+    }
+}
+
+/// Something an action produced for the user rather than the model.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Artifact {
+    Attachment(Attachment),
+    Table(Table),
+}
+
+/// A suggestion to the front-end on how the user can follow up.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UiHint {
+    /// A button that sends `prompt` to the agent as if the user typed it.
+    Button { label: String, prompt: String },
+    /// A button that opens `url`.
+    Link { label: String, url: String },
+}
+
+impl UiHint {
+    pub fn button(label: impl Into<String>, prompt: impl Into<String>) -> Self {
+        UiHint::Button {
+            label: label.into(),
+            prompt: prompt.into(),
+        }
+    }
+
+    pub fn link(label: impl Into<String>, url: impl Into<String>) -> Self {
+        UiHint::Link {
+            label: label.into(),
+            url: url.into(),
+        }
+    }
+}
+
+/// The full result of an action: `content` is exactly what `execute` returns
+/// and what the model sees, the rest is for the user.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActionOutput {
+    pub content: String,
+    pub artifacts: Vec<Artifact>,
+    pub hints: Vec<UiHint>,
+}
+
+impl ActionOutput {
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn attachments(&self) -> impl Iterator<Item = &Attachment> {
+        self.artifacts.iter().filter_map(|artifact| match artifact {
+            Artifact::Attachment(attachment) => Some(attachment),
+            Artifact::Table(_) => None,
+        })
+    }
+
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.artifacts.iter().filter_map(|artifact| match artifact {
+            Artifact::Table(table) => Some(table),
+            Artifact::Attachment(_) => None,
+        })
+    }
+}
+
+impl From<String> for ActionOutput {
+    fn from(content: String) -> Self {
+        Self::new(content)
+    }
+}
+
+impl From<ActionOutput> for String {
+    fn from(output: ActionOutput) -> Self {
+        output.content
+    }
+}
+
+impl fmt::Display for ActionOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.content)
+    }
+}
+
+tokio::task_local! {
+    static OUTPUT: Mutex<ActionOutput>;
+}
+
+fn push(update: impl FnOnce(&mut ActionOutput)) -> bool {
+    OUTPUT
+        .try_with(|output| update(&mut output.lock().unwrap()))
+        .is_ok()
+}
+
+/// Hands `attachment` to the user. Returns `false` (and drops it) when the
+/// action was run through plain `execute`, which has nowhere to deliver it.
+pub fn attach(attachment: Attachment) -> bool {
+    push(|output| output.artifacts.push(Artifact::Attachment(attachment)))
+}
+
+/// Shows `table` to the user. Returns `false` under plain `execute`.
+pub fn attach_table(table: Table) -> bool {
+    push(|output| output.artifacts.push(Artifact::Table(table)))
+}
+
+/// Suggests a follow-up to the front-end. Returns `false` under plain
+/// `execute`.
+pub fn hint(hint: UiHint) -> bool {
+    push(|output| output.hints.push(hint))
+}
+
+/// Runs `future`, gathering everything it attached or hinted alongside its
+/// result. Front-ends run confirm handlers through it to deliver what they
+/// attach.
+pub async fn collect<F>(future: F) -> Result<ActionOutput, String>
+where
+    F: Future<Output = Result<String, String>>,
+{
+    OUTPUT
+        .scope(Mutex::new(ActionOutput::default()), async move {
+            let content = future.await?;
+            let output = OUTPUT.with(|output| std::mem::take(&mut *output.lock().unwrap()));
+            Ok(ActionOutput { content, ..output })
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Table {
+        Table::new(["Level", "Price", "Touches"])
+            .title("Support")
+            .row(["S1", "142.5", "3"])
+            .row(["S2", "131", "12"])
+    }
+
+    #[test]
+    fn test_table_to_text() {
+        assert_eq!(
+            table().to_text(),
+            "Level  Price  Touches\n-----  -----  -------\nS1     142.5  3\nS2     131    12"
+        );
+    }
+
+    #[test]
+    fn test_table_to_csv() {
+        let table = table().row(["S3, weak", "say \"hi\"", ""]);
+        assert_eq!(
+            table.to_csv(),
+            "Level,Price,Touches\nS1,142.5,3\nS2,131,12\n\"S3, weak\",\"say \"\"hi\"\"\",\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_gathers_artifacts_and_hints() {
+        let output = collect(async {
+            attach_table(table());
+            hint(UiHint::link("Explorer", "https://solscan.io"));
+            Ok("done".to_string())
+        })
+        .await
+        .unwrap();
+        assert_eq!(output.content, "done");
+        assert_eq!(output.tables().collect::<Vec<_>>(), vec![&table()]);
+        assert_eq!(
+            output.hints,
+            vec![UiHint::link("Explorer", "https://solscan.io")]
+        );
+        assert_eq!(String::from(output), "done");
+
+        // Outside of a collecting run there is nowhere to put them
+        assert!(!hint(UiHint::button("Again", "again")));
+    }
+}
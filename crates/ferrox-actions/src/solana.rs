@@ -1,2 +1,119 @@
+pub mod accounts;
+pub mod address_book;
+pub mod backup;
 pub mod jupiter;
+pub mod metadata;
+pub mod policy;
 pub mod pump;
+pub mod rpc;
+pub mod simulation;
+pub mod sns;
+pub mod submit;
+#[cfg(test)]
+mod test_support;
+pub mod token;
+pub mod transfer;
+pub mod wallet;
+
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// Wrapped SOL, the mint DEX aggregators use for native SOL.
+pub const NATIVE_MINT: &str = "So11111111111111111111111111111111111111112";
+pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+pub const USDT_MINT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+
+/// Parses a mint address, also accepting the symbols SOL, USDC and USDT.
+pub fn parse_mint(mint: &str) -> Result<Pubkey, String> {
+    let address = match mint.trim().to_uppercase().as_str() {
+        "SOL" | "WSOL" => NATIVE_MINT,
+        "USDC" => USDC_MINT,
+        "USDT" => USDT_MINT,
+        _ => mint.trim(),
+    };
+    Pubkey::from_str(address).map_err(|e| format!("Invalid mint address '{}': {}", mint, e))
+}
+
+/// Short name for a mint in messages: the symbol of the well-known ones,
+/// otherwise the abbreviated address.
+pub fn mint_label(mint: &str) -> String {
+    match mint {
+        NATIVE_MINT => "SOL".to_string(),
+        USDC_MINT => "USDC".to_string(),
+        USDT_MINT => "USDT".to_string(),
+        _ if mint.len() > 10 && mint.is_ascii() => {
+            format!("{}…{}", &mint[..4], &mint[mint.len() - 4..])
+        }
+        _ => mint.to_string(),
+    }
+}
+
+/// Converts a UI amount such as `1.5` into the token's base units.
+pub fn to_base_units(amount: f64, decimals: u8) -> Result<u64, String> {
+    let units = (amount * 10f64.powi(decimals as i32)).round();
+    if !units.is_finite() || units <= 0.0 || units > u64::MAX as f64 {
+        return Err(format!("Invalid amount {}", amount));
+    }
+    Ok(units as u64)
+}
+
+/// Most slippage a trade may be given, 50%. More than that lets a swap
+/// fill at almost any price.
+pub const MAX_SLIPPAGE_BPS: u16 = 5_000;
+
+/// Refuses a slippage tolerance above [`MAX_SLIPPAGE_BPS`].
+pub fn check_slippage(slippage_bps: u16) -> Result<u16, String> {
+    if slippage_bps > MAX_SLIPPAGE_BPS {
+        return Err(format!(
+            "A slippage of {} bps is above the limit of {} bps",
+            slippage_bps, MAX_SLIPPAGE_BPS
+        ));
+    }
+    Ok(slippage_bps)
+}
+
+pub fn to_ui_amount(units: u64, decimals: u8) -> f64 {
+    units as f64 / 10f64.powi(decimals as i32)
+}
+
+/// Where a user can look a transaction up.
+pub fn explorer_url(signature: &str) -> String {
+    format!("https://solscan.io/tx/{}", signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mint() {
+        assert_eq!(parse_mint("sol").unwrap().to_string(), NATIVE_MINT);
+        assert_eq!(parse_mint(USDC_MINT).unwrap().to_string(), USDC_MINT);
+        assert!(parse_mint("not-a-mint").is_err());
+    }
+
+    #[test]
+    fn test_mint_label() {
+        assert_eq!(mint_label(NATIVE_MINT), "SOL");
+        assert_eq!(
+            mint_label("JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN"),
+            "JUPy…DvCN"
+        );
+    }
+
+    #[test]
+    fn test_check_slippage() {
+        assert_eq!(check_slippage(50), Ok(50));
+        assert_eq!(check_slippage(MAX_SLIPPAGE_BPS), Ok(MAX_SLIPPAGE_BPS));
+        assert!(check_slippage(MAX_SLIPPAGE_BPS + 1).is_err());
+    }
+
+    #[test]
+    fn test_amount_conversion() {
+        assert_eq!(to_base_units(1.5, 9), Ok(1_500_000_000));
+        assert_eq!(to_base_units(0.1, 6), Ok(100_000));
+        assert!(to_base_units(0.0, 6).is_err());
+        assert!(to_base_units(-1.0, 6).is_err());
+        assert_eq!(to_ui_amount(2_500_000, 6), 2.5);
+    }
+}
@@ -0,0 +1,246 @@
+//! Listing, creating and choosing the named accounts of the user's wallet.
+
+use crate::{
+    action::{with_client, ActionBuilder, ActionGroup, EmptyParams, FunctionAction},
+    output::{self, Table},
+    solana::wallet::sender_id,
+    AgentState,
+};
+use ferrox_wallet::{WalletAccount, WalletManager, DEFAULT_ACCOUNT};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct AccountParams {
+    label: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSummary {
+    pub label: String,
+    pub address: String,
+    pub derivation_path: Option<String>,
+    pub default: bool,
+}
+
+impl From<&WalletAccount> for AccountSummary {
+    fn from(account: &WalletAccount) -> Self {
+        Self {
+            label: account.label.clone(),
+            address: account.wallet.address(),
+            derivation_path: account.derivation_path.clone(),
+            default: account.is_default,
+        }
+    }
+}
+
+pub(crate) fn accounts_table(accounts: &[AccountSummary]) -> Table {
+    accounts.iter().fold(
+        Table::new(["Account", "Address", "Default"]).title("Wallet accounts"),
+        |table, account| {
+            table.row([
+                account.label.clone(),
+                account.address.clone(),
+                if account.default { "yes" } else { "" }.to_string(),
+            ])
+        },
+    )
+}
+
+pub(crate) async fn summaries<W: WalletManager>(
+    wallets: &W,
+    user_id: &str,
+) -> Result<Vec<AccountSummary>, String> {
+    Ok(wallets
+        .list_accounts(user_id)
+        .await?
+        .iter()
+        .map(AccountSummary::from)
+        .collect())
+}
+
+pub struct WalletAccountsActionGroup<S: Send + Sync + Clone + 'static> {
+    actions: Vec<Arc<FunctionAction<S>>>,
+}
+
+impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for WalletAccountsActionGroup<S> {
+    fn actions(&self) -> &[Arc<FunctionAction<S>>] {
+        &self.actions
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> WalletAccountsActionGroup<S> {
+    pub fn new<W: WalletManager + 'static>(wallets: W) -> Self {
+        let mut actions = Vec::new();
+
+        // Add list accounts action
+        {
+            async fn list_wallet_accounts<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                wallets: W,
+                _params: EmptyParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let accounts = summaries(&wallets, &sender_id(&send_state)?).await?;
+                if accounts.is_empty() {
+                    return Ok(
+                        "The user has no wallet yet; create an account to get one".to_string()
+                    );
+                }
+                output::attach_table(accounts_table(&accounts));
+                serde_json::to_string(&accounts)
+                    .map_err(|e| format!("Failed to serialize accounts: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "list_wallet_accounts",
+                with_client(&wallets, list_wallet_accounts),
+                None,
+            )
+            .description("List the named accounts of the user's wallet with their addresses and which one is used by default")
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add create account action
+        {
+            async fn create_wallet_account<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                wallets: W,
+                params: AccountParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let user_id = sender_id(&send_state)?;
+                // The first account also creates the user's recovery phrase
+                if wallets.list_accounts(&user_id).await?.is_empty() {
+                    wallets.create_wallet(&user_id).await?;
+                }
+                let label = params.label.trim().to_lowercase();
+                if label != DEFAULT_ACCOUNT {
+                    wallets.create_account(&user_id, &label).await?;
+                }
+                let accounts = summaries(&wallets, &user_id).await?;
+                output::attach_table(accounts_table(&accounts));
+                let account = accounts
+                    .iter()
+                    .find(|account| account.label == label)
+                    .ok_or_else(|| format!("Account '{}' was not created", label))?;
+                serde_json::to_string(account)
+                    .map_err(|e| format!("Failed to serialize account: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "create_wallet_account",
+                with_client(&wallets, create_wallet_account),
+                None,
+            )
+            .description("Add a named account, such as \"trading\" or \"savings\", to the user's wallet. Each account has its own address derived from the user's recovery phrase; the user's first account is created along with the wallet")
+            .parameter(
+                "label",
+                "Name of the account: letters, digits, '-' and '_'",
+                "string",
+                true,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add default account action
+        {
+            async fn set_default_wallet_account<
+                S: Send + Sync + Clone + 'static,
+                W: WalletManager,
+            >(
+                wallets: W,
+                params: AccountParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let user_id = sender_id(&send_state)?;
+                wallets.set_default_account(&user_id, &params.label).await?;
+                let accounts = summaries(&wallets, &user_id).await?;
+                output::attach_table(accounts_table(&accounts));
+                Ok(format!(
+                    "Trades and transfers now use the account '{}' unless another one is named",
+                    params.label.trim().to_lowercase()
+                ))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "set_default_wallet_account",
+                with_client(&wallets, set_default_wallet_account),
+                None,
+            )
+            .description(
+                "Choose which of the user's wallet accounts trades and transfers use by default",
+            )
+            .parameter("label", "Name of an existing account", "string", true)
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        Self { actions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrox_wallet::simple_wallet_manager::SimpleWalletManager;
+    use tokio::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_accounts_are_created_and_chosen() {
+        let wallets = SimpleWalletManager::new();
+        let group = WalletAccountsActionGroup::<()>::new(wallets.clone());
+        let [list, create, set_default] = group.actions() else {
+            panic!("unexpected actions");
+        };
+        let message = serde_json::json!({"from": {"id": 42}});
+        let state = Arc::new(Mutex::new(()));
+
+        let created = create
+            .execute(
+                serde_json::json!({"label": "Trading"}),
+                message.clone(),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        let created: AccountSummary =
+            serde_json::from_str(&serde_json::from_str::<String>(&created).unwrap()).unwrap();
+        assert_eq!(created.label, "trading");
+        assert_eq!(created.derivation_path.as_deref(), Some("m/44'/501'/1'/0'"));
+        assert!(!created.default);
+
+        set_default
+            .execute(
+                serde_json::json!({"label": "trading"}),
+                message.clone(),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            wallets.get_wallet("42").await.unwrap().address(),
+            created.address
+        );
+
+        let listed = list
+            .execute(serde_json::json!({}), message.clone(), state.clone())
+            .await
+            .unwrap();
+        let listed: Vec<AccountSummary> =
+            serde_json::from_str(&serde_json::from_str::<String>(&listed).unwrap()).unwrap();
+        let labels: Vec<&str> = listed.iter().map(|a| a.label.as_str()).collect();
+        assert_eq!(labels, vec!["trading", DEFAULT_ACCOUNT]);
+
+        assert!(set_default
+            .execute(serde_json::json!({"label": "savings"}), message, state)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,532 @@
+//! Each user's saved addresses, and turning what they call a recipient into
+//! an address.
+//!
+//! Users save addresses under names such as "mom" or "exchange", either as
+//! an address or as a `.sol` domain, which is resolved when it is saved so
+//! the contact keeps pointing at the same wallet. The book also remembers
+//! who each user has sent to, so transfers to anyone new can be flagged.
+
+use crate::{
+    action::{with_client, ActionBuilder, ActionGroup, EmptyParams, FunctionAction},
+    output::{self, Table},
+    solana::{rpc::client::SolanaRpcClient, sns, wallet::sender_id},
+    AgentState,
+};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+const MAX_NAME_LEN: usize = 32;
+
+#[derive(Debug, Deserialize)]
+pub struct SaveAddressParams {
+    name: String,
+    address: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RemoveAddressParams {
+    name: String,
+}
+
+/// A saved address.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    pub name: String,
+    pub address: String,
+    /// The `.sol` domain the address was saved as.
+    pub domain: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UserBook {
+    contacts: BTreeMap<String, Contact>,
+    /// Addresses the user has sent to.
+    #[serde(default)]
+    sent: BTreeSet<String>,
+}
+
+/// Saved addresses of every user, in memory or in a JSON file. Clones share
+/// the same book.
+#[derive(Debug, Clone, Default)]
+pub struct AddressBook {
+    users: Arc<Mutex<HashMap<String, UserBook>>>,
+    path: Option<Arc<PathBuf>>,
+}
+
+impl AddressBook {
+    /// A book kept in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A book stored at `path`, which is created on the first change.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let users = if path.exists() {
+            let text = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            serde_json::from_str(&text)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+        } else {
+            HashMap::new()
+        };
+        Ok(Self {
+            users: Arc::new(Mutex::new(users)),
+            path: Some(Arc::new(path)),
+        })
+    }
+
+    pub fn contacts(&self, user_id: &str) -> Vec<Contact> {
+        self.users
+            .lock()
+            .unwrap()
+            .get(user_id)
+            .map(|book| book.contacts.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn contact(&self, user_id: &str, name: &str) -> Option<Contact> {
+        let name = name.trim().to_lowercase();
+        self.users
+            .lock()
+            .unwrap()
+            .get(user_id)
+            .and_then(|book| book.contacts.get(&name).cloned())
+    }
+
+    /// The contact saved with `address`, if any.
+    pub fn contact_for(&self, user_id: &str, address: &str) -> Option<Contact> {
+        self.users.lock().unwrap().get(user_id).and_then(|book| {
+            book.contacts
+                .values()
+                .find(|contact| contact.address == address)
+                .cloned()
+        })
+    }
+
+    /// Saves `address` as `name`, replacing a contact of the same name.
+    pub fn save(
+        &self,
+        user_id: &str,
+        name: &str,
+        address: &Pubkey,
+        domain: Option<&str>,
+    ) -> Result<Contact, String> {
+        let contact = Contact {
+            name: normalize_name(name)?,
+            address: address.to_string(),
+            domain: domain.map(|domain| domain.trim().to_lowercase()),
+        };
+        self.update(|users| {
+            users
+                .entry(user_id.to_string())
+                .or_default()
+                .contacts
+                .insert(contact.name.clone(), contact.clone());
+            Ok(())
+        })?;
+        Ok(contact)
+    }
+
+    pub fn remove(&self, user_id: &str, name: &str) -> Result<Contact, String> {
+        let name = name.trim().to_lowercase();
+        self.update(|users| {
+            users
+                .get_mut(user_id)
+                .and_then(|book| book.contacts.remove(&name))
+                .ok_or_else(|| format!("No address is saved as '{}'", name))
+        })
+    }
+
+    /// Whether the user has sent to `address` or saved it.
+    pub fn knows(&self, user_id: &str, address: &str) -> bool {
+        self.users.lock().unwrap().get(user_id).is_some_and(|book| {
+            book.sent.contains(address)
+                || book
+                    .contacts
+                    .values()
+                    .any(|contact| contact.address == address)
+        })
+    }
+
+    /// Remembers that the user sent to `address`.
+    pub fn record_sent(&self, user_id: &str, address: &str) -> Result<(), String> {
+        if self.knows(user_id, address) {
+            return Ok(());
+        }
+        self.update(|users| {
+            users
+                .entry(user_id.to_string())
+                .or_default()
+                .sent
+                .insert(address.to_string());
+            Ok(())
+        })
+    }
+
+    /// Applies `change` and writes the book back to its file. A change that
+    /// cannot be written is undone.
+    fn update<T>(
+        &self,
+        change: impl FnOnce(&mut HashMap<String, UserBook>) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut users = self.users.lock().unwrap();
+        let Some(path) = &self.path else {
+            return change(&mut users);
+        };
+        let before = serde_json::to_string(&*users)
+            .map_err(|e| format!("Failed to serialize address book: {}", e))?;
+        let result = change(&mut users)?;
+        if let Err(e) = write_book(path, &users) {
+            *users = serde_json::from_str(&before).unwrap_or_default();
+            return Err(e);
+        }
+        Ok(result)
+    }
+}
+
+/// Writes to a temporary file first, so a crash never leaves half a book.
+fn write_book(path: &Path, users: &HashMap<String, UserBook>) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(users)
+        .map_err(|e| format!("Failed to serialize address book: {}", e))?;
+    let temporary = path.with_extension("json.tmp");
+    fs::write(&temporary, text)
+        .map_err(|e| format!("Failed to write {}: {}", temporary.display(), e))?;
+    fs::rename(&temporary, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+/// `name` as contacts are stored: trimmed and lowercase, made of letters,
+/// digits, spaces, `-` and `_`.
+fn normalize_name(name: &str) -> Result<String, String> {
+    let name = name.trim().to_lowercase();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(format!(
+            "Contact names must be 1 to {} characters long",
+            MAX_NAME_LEN
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid contact name '{}': use letters, digits, spaces, '-' and '_'",
+            name
+        ));
+    }
+    Ok(name)
+}
+
+/// A recipient as the user named it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipient {
+    pub address: Pubkey,
+    /// The contact or `.sol` domain the user gave instead of the address.
+    pub name: Option<String>,
+}
+
+/// Turns `recipient` into an address: a saved contact, a `.sol` domain, or
+/// an address itself.
+pub async fn resolve_recipient(
+    rpc: &SolanaRpcClient,
+    book: &AddressBook,
+    user_id: &str,
+    recipient: &str,
+) -> Result<Recipient, String> {
+    let recipient = recipient.trim();
+    if let Some(contact) = book.contact(user_id, recipient) {
+        let address = Pubkey::from_str(&contact.address)
+            .map_err(|e| format!("The saved address of '{}' is invalid: {}", contact.name, e))?;
+        return Ok(Recipient {
+            address,
+            name: Some(contact.name),
+        });
+    }
+    if sns::is_domain(recipient) {
+        return Ok(Recipient {
+            address: sns::resolve(rpc, recipient).await?,
+            name: Some(recipient.to_lowercase()),
+        });
+    }
+    let address = Pubkey::from_str(recipient).map_err(|_| {
+        format!(
+            "'{}' is not a Solana address, a .sol domain or a saved contact; check it for typos",
+            recipient
+        )
+    })?;
+    Ok(Recipient {
+        address,
+        name: book
+            .contact_for(user_id, &address.to_string())
+            .map(|contact| contact.name),
+    })
+}
+
+fn contacts_table(contacts: &[Contact]) -> Table {
+    contacts.iter().fold(
+        Table::new(["Name", "Address", "Domain"]).title("Address book"),
+        |table, contact| {
+            table.row([
+                contact.name.clone(),
+                contact.address.clone(),
+                contact.domain.clone().unwrap_or_default(),
+            ])
+        },
+    )
+}
+
+/// Everything the address book actions need, cloned into each invocation.
+#[derive(Clone)]
+struct AddressBookContext {
+    rpc: SolanaRpcClient,
+    book: AddressBook,
+}
+
+pub struct AddressBookActionGroup<S: Send + Sync + Clone + 'static> {
+    actions: Vec<Arc<FunctionAction<S>>>,
+}
+
+impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for AddressBookActionGroup<S> {
+    fn actions(&self) -> &[Arc<FunctionAction<S>>] {
+        &self.actions
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> AddressBookActionGroup<S> {
+    /// Resolves `.sol` domains on the endpoint configured in the environment
+    /// (see [`SolanaRpcClient::from_env`]).
+    pub fn new(book: AddressBook) -> Self {
+        Self::with_client(SolanaRpcClient::from_env(), book)
+    }
+
+    pub fn with_client(rpc: SolanaRpcClient, book: AddressBook) -> Self {
+        let context = AddressBookContext { rpc, book };
+        let mut actions = Vec::new();
+
+        // Add save address action
+        {
+            async fn save_address<S: Send + Sync + Clone + 'static>(
+                context: AddressBookContext,
+                params: SaveAddressParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let user_id = sender_id(&send_state)?;
+                let address = params.address.trim();
+                let (resolved, domain) = if sns::is_domain(address) {
+                    (sns::resolve(&context.rpc, address).await?, Some(address))
+                } else {
+                    let resolved = Pubkey::from_str(address).map_err(|_| {
+                        format!(
+                            "'{}' is not a Solana address or a .sol domain; check it for typos",
+                            address
+                        )
+                    })?;
+                    (resolved, None)
+                };
+                let contact = context
+                    .book
+                    .save(&user_id, &params.name, &resolved, domain)?;
+                let mut saved = match &contact.domain {
+                    Some(domain) => format!(
+                        "Saved {} ({}) as '{}'",
+                        domain, contact.address, contact.name
+                    ),
+                    None => format!("Saved {} as '{}'", contact.address, contact.name),
+                };
+                if !resolved.is_on_curve() {
+                    saved.push_str(". It is a program-derived address that no private key controls, so make sure it can receive funds");
+                }
+                Ok(saved)
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "save_address",
+                with_client(&context, save_address),
+                None,
+            )
+            .description("Save a Solana address or .sol domain in the user's address book under a name, such as \"mom\" or \"exchange\", so they can send to it by name. A domain is resolved when it is saved. Saving a name again replaces it")
+            .parameter("name", "Name to save the address as", "string", true)
+            .parameter(
+                "address",
+                "The Solana address or .sol domain, exactly as the user gave it",
+                "string",
+                true,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add list addresses action
+        {
+            async fn list_addresses<S: Send + Sync + Clone + 'static>(
+                context: AddressBookContext,
+                _params: EmptyParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let contacts = context.book.contacts(&sender_id(&send_state)?);
+                if contacts.is_empty() {
+                    return Ok("The user has no saved addresses".to_string());
+                }
+                output::attach_table(contacts_table(&contacts));
+                serde_json::to_string(&contacts)
+                    .map_err(|e| format!("Failed to serialize contacts: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "list_addresses",
+                with_client(&context, list_addresses),
+                None,
+            )
+            .description("List the addresses saved in the user's address book")
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add remove address action
+        {
+            async fn remove_address<S: Send + Sync + Clone + 'static>(
+                context: AddressBookContext,
+                params: RemoveAddressParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let contact = context
+                    .book
+                    .remove(&sender_id(&send_state)?, &params.name)?;
+                Ok(format!("Removed '{}' ({})", contact.name, contact.address))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "remove_address",
+                with_client(&context, remove_address),
+                None,
+            )
+            .description("Remove a saved address from the user's address book")
+            .parameter("name", "Name the address is saved as", "string", true)
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        Self { actions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use mockito::Matcher;
+    use solana_sdk::{signature::Keypair, signer::Signer};
+    use tokio::sync::Mutex;
+
+    #[test]
+    fn test_book_is_saved_to_its_file() {
+        let path = std::env::temp_dir().join(format!(
+            "ferrox-address-book-{}.json",
+            Keypair::new().pubkey()
+        ));
+        let friend = Keypair::new().pubkey();
+        let book = AddressBook::open(&path).unwrap();
+        assert!(book.save("42", "not.allowed", &friend, None).is_err());
+        book.save("42", " Mom ", &friend, None).unwrap();
+        book.record_sent("42", "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM")
+            .unwrap();
+
+        let reopened = AddressBook::open(&path).unwrap();
+        assert_eq!(
+            reopened.contact("42", "MOM").unwrap().address,
+            friend.to_string()
+        );
+        assert!(reopened.knows("42", "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"));
+        assert!(!reopened.knows("7", &friend.to_string()));
+        assert!(reopened.contacts("7").is_empty());
+
+        reopened.remove("42", "mom").unwrap();
+        assert!(reopened.remove("42", "mom").is_err());
+        assert!(AddressBook::open(&path).unwrap().contacts("42").is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_domains_are_resolved_when_saved() {
+        let mut server = mockito::Server::new_async().await;
+        let owner = Keypair::new().pubkey();
+        let mut record = vec![0u8; 96];
+        record[..32].copy_from_slice(sns::SOL_TLD.as_ref());
+        record[32..64].copy_from_slice(owner.as_ref());
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "method": "getAccountInfo",
+                "params": [sns::domain_key("bonfida.sol").unwrap().to_string()],
+            })))
+            .with_body(
+                serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": {"context": {"slot": 1}, "value": {
+                    "data": [BASE64.encode(&record), "base64"],
+                    "executable": false,
+                    "lamports": 1_000_000,
+                    "owner": sns::NAME_PROGRAM_ID.to_string(),
+                    "space": 96
+                }}})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let rpc = SolanaRpcClient::new(server.url());
+        let book = AddressBook::new();
+        let group = AddressBookActionGroup::<()>::with_client(rpc.clone(), book.clone());
+        let [save, list, _remove] = group.actions() else {
+            panic!("unexpected actions");
+        };
+        let send_state = serde_json::json!({"from": {"id": 42}});
+        let state = Arc::new(Mutex::new(()));
+
+        let saved: String = serde_json::from_str(
+            &save
+                .execute(
+                    serde_json::json!({"name": "bonfida", "address": "Bonfida.sol"}),
+                    send_state.clone(),
+                    state.clone(),
+                )
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(saved, format!("Saved bonfida.sol ({}) as 'bonfida'", owner));
+        let listed: String = serde_json::from_str(
+            &list
+                .execute(serde_json::json!({}), send_state, state)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        let contacts: Vec<Contact> = serde_json::from_str(&listed).unwrap();
+        assert_eq!(contacts[0].domain.as_deref(), Some("bonfida.sol"));
+
+        // Saved names and domains resolve alike
+        let by_name = resolve_recipient(&rpc, &book, "42", "Bonfida")
+            .await
+            .unwrap();
+        let by_domain = resolve_recipient(&rpc, &book, "7", "bonfida.sol")
+            .await
+            .unwrap();
+        assert_eq!(by_name.address, owner);
+        assert_eq!(by_domain.address, owner);
+        assert_eq!(by_name.name.as_deref(), Some("bonfida"));
+        assert!(resolve_recipient(&rpc, &book, "42", "bonfida-typo")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,434 @@
+//! Importing keys into the user's wallet and exporting or backing it up.
+//! Both only run in a private chat with the bot, and exports need the user
+//! to press Confirm before the file is sent.
+
+use crate::{
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
+    output::{self, Attachment},
+    solana::{
+        accounts::{accounts_table, summaries},
+        wallet::{check_confirmer, private_chat, sender_id, solana_signer},
+    },
+    AgentState,
+};
+use ferrox_wallet::{backup::MIN_PASSWORD_LEN, ExportFormat, WalletImport, WalletManager};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use zeroize::Zeroizing;
+
+/// Label of imported keypairs when the user does not name them.
+const IMPORTED_ACCOUNT: &str = "imported";
+
+#[derive(Deserialize)]
+pub struct ImportParams {
+    secret: String,
+    label: Option<String>,
+    password: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportParams {
+    format: ExportKind,
+    account: Option<String>,
+    password: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportKind {
+    /// One account as a Solana keypair file.
+    KeypairFile,
+    /// The recovery phrase and every account, encrypted with a password.
+    Backup,
+}
+
+/// What the user confirms before an export is sent.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExportPreview {
+    pub user_id: String,
+    /// The private chat the file is sent to.
+    pub chat_id: i64,
+    pub format: ExportKind,
+    /// Name of the wallet account the user picked; `None` for their default.
+    pub account: Option<String>,
+    /// Address of the exported account; `None` for backups, which hold
+    /// every account.
+    pub wallet: Option<String>,
+    /// Whether a backup password was given. The password itself stays with
+    /// the bot, as previews are shown to the model.
+    pub password_set: bool,
+    /// Id of the pending password in [`BackupPasswords`].
+    pub password_id: Option<u64>,
+    pub warnings: Vec<String>,
+}
+
+/// Backup passwords waiting for the user to confirm their export, at most
+/// one per user. A newer preview replaces the password of an older one.
+#[derive(Clone, Default)]
+struct BackupPasswords {
+    pending: Arc<Mutex<HashMap<String, PendingPassword>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+struct PendingPassword {
+    id: u64,
+    password: Zeroizing<String>,
+}
+
+impl BackupPasswords {
+    fn hold(&self, user_id: &str, password: String) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.pending.lock().unwrap().insert(
+            user_id.to_string(),
+            PendingPassword {
+                id,
+                password: Zeroizing::new(password),
+            },
+        );
+        id
+    }
+
+    fn take(&self, user_id: &str, id: u64) -> Option<Zeroizing<String>> {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get(user_id) {
+            Some(held) if held.id == id => pending.remove(user_id).map(|held| held.password),
+            _ => None,
+        }
+    }
+}
+
+/// Everything the export action needs, cloned into each invocation.
+#[derive(Clone)]
+struct ExportContext<W> {
+    wallets: W,
+    passwords: BackupPasswords,
+}
+
+pub struct WalletBackupActionGroup<S: Send + Sync + Clone + 'static> {
+    actions: Vec<Arc<FunctionAction<S>>>,
+}
+
+impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for WalletBackupActionGroup<S> {
+    fn actions(&self) -> &[Arc<FunctionAction<S>>] {
+        &self.actions
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> WalletBackupActionGroup<S> {
+    pub fn new<W: WalletManager + 'static>(wallets: W) -> Self {
+        let mut actions = Vec::new();
+
+        // Add import action
+        {
+            async fn import_wallet<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                wallets: W,
+                params: ImportParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                private_chat(&send_state).map_err(|e| {
+                    format!(
+                        "{}. Anyone in this chat has seen the key, so move its funds to a new wallet",
+                        e
+                    )
+                })?;
+                let user_id = sender_id(&send_state)?;
+                let import = WalletImport::parse(
+                    &params.secret,
+                    params.label.as_deref().unwrap_or(IMPORTED_ACCOUNT),
+                    params.password.as_deref(),
+                )?;
+                let wallet = wallets.import_wallet(&user_id, import).await?;
+                output::attach_table(accounts_table(&summaries(&wallets, &user_id).await?));
+                Ok(format!(
+                    "Imported {}. Delete the message with the key from this chat",
+                    wallet.address()
+                ))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "import_wallet",
+                with_client(&wallets, import_wallet),
+                None,
+            )
+            .description("Import a wallet the user already has, in a private chat only. A Solana keypair file or base58 secret key is added as a new account; a recovery phrase or a backup made by export_wallet restores the whole wallet, which only works for users without one")
+            .parameter(
+                "secret",
+                "The keypair file contents, base58 secret key, recovery phrase or backup, exactly as the user sent it",
+                "string",
+                true,
+            )
+            .parameter(
+                "label",
+                "Name of the account an imported keypair becomes; defaults to \"imported\"",
+                "string",
+                false,
+            )
+            .parameter("password", "Password of a backup", "string", false)
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add export action
+        {
+            async fn preview_export<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: ExportContext<W>,
+                params: ExportParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<ExportPreview, String> {
+                let wallets = &context.wallets;
+                let chat_id = private_chat(&send_state)?;
+                let user_id = sender_id(&send_state)?;
+                let (wallet, password_id, warning) = match params.format {
+                    ExportKind::KeypairFile => {
+                        let signer =
+                            solana_signer(wallets, &user_id, params.account.as_deref()).await?;
+                        (
+                            Some(signer.pubkey().to_string()),
+                            None,
+                            "Anyone with the file controls the account: never share it or upload it to a website",
+                        )
+                    }
+                    ExportKind::Backup => {
+                        let password = params
+                            .password
+                            .filter(|password| password.chars().count() >= MIN_PASSWORD_LEN)
+                            .ok_or_else(|| {
+                                format!(
+                                    "Backups need a password of at least {} characters",
+                                    MIN_PASSWORD_LEN
+                                )
+                            })?;
+                        if wallets.get_wallets(&user_id).await?.is_empty() {
+                            return Err("The user has no wallet to back up".to_string());
+                        }
+                        (
+                            None,
+                            Some(context.passwords.hold(&user_id, password)),
+                            "The backup can only be restored with its password, which cannot be recovered",
+                        )
+                    }
+                };
+                Ok(ExportPreview {
+                    user_id,
+                    chat_id,
+                    format: params.format,
+                    account: params.account,
+                    wallet,
+                    password_set: password_id.is_some(),
+                    password_id,
+                    warnings: vec![warning.to_string()],
+                })
+            }
+
+            async fn confirm_export<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: ExportContext<W>,
+                preview: ExportPreview,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                check_confirmer(&send_state, &preview.user_id)?;
+                // The confirmation comes from the chat the preview was shown in
+                if private_chat(&send_state)? != preview.chat_id {
+                    return Err("Exports are only sent to the chat they were asked in".to_string());
+                }
+                let wallets = &context.wallets;
+                let (format, file_name) = match preview.format {
+                    ExportKind::KeypairFile => {
+                        let signer =
+                            solana_signer(wallets, &preview.user_id, preview.account.as_deref())
+                                .await?;
+                        let address = signer.pubkey().to_string();
+                        if preview.wallet.as_ref() != Some(&address) {
+                            return Err(
+                                "The preview does not match this wallet; ask again".to_string()
+                            );
+                        }
+                        (ExportFormat::KeypairFile, format!("{}.json", address))
+                    }
+                    ExportKind::Backup => {
+                        let password = preview
+                            .password_id
+                            .and_then(|id| context.passwords.take(&preview.user_id, id))
+                            .ok_or(
+                                "The backup password is no longer held; ask for the backup again",
+                            )?;
+                        (
+                            ExportFormat::Backup { password },
+                            "ferrox-wallet-backup.json".to_string(),
+                        )
+                    }
+                };
+                let exported = wallets
+                    .export_wallet(&preview.user_id, preview.account.as_deref(), format)
+                    .await?;
+                let attachment =
+                    Attachment::file(&file_name, "application/json", exported.as_bytes().to_vec());
+                if !output::attach(attachment) {
+                    return Err("This chat cannot receive files".to_string());
+                }
+                Ok(format!(
+                    "Sent {}. Keep it somewhere offline and delete it from this chat once saved",
+                    file_name
+                ))
+            }
+
+            let context = ExportContext {
+                wallets: wallets.clone(),
+                passwords: BackupPasswords::default(),
+            };
+            let action =
+                ActionBuilder::<_, ExportParams, serde_json::Value, S, ExportPreview, _>::new(
+                    "export_wallet",
+                    with_client(&context, preview_export),
+                    Some(with_client(&context, confirm_export)),
+                )
+                .description("Prepare exporting the user's wallet as a file, in a private chat only. A keypair file holds one account and imports into the Solana CLI, Phantom or Solflare; a backup holds the recovery phrase and every account, encrypted with a password, and restores here with import_wallet. Nothing is sent until the user presses Confirm")
+                .parameter(
+                    "format",
+                    "\"keypair_file\" or \"backup\"",
+                    "string",
+                    true,
+                )
+                .parameter(
+                    "account",
+                    "Name of the user's wallet account to export as a keypair file, such as \"trading\"; leave out for their default account",
+                    "string",
+                    false,
+                )
+                .parameter(
+                    "password",
+                    "Password to encrypt a backup with, at least 8 characters",
+                    "string",
+                    false,
+                )
+                .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        Self { actions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrox_wallet::simple_wallet_manager::SimpleWalletManager;
+    use solana_sdk::{signature::Keypair, signer::Signer};
+    use tokio::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_keys_only_move_in_private_chats() {
+        let wallets = SimpleWalletManager::new();
+        let group = WalletBackupActionGroup::<()>::new(wallets.clone());
+        let [import, export] = group.actions() else {
+            panic!("unexpected actions");
+        };
+        let private =
+            serde_json::json!({"chat": {"id": 42, "type": "private"}, "from": {"id": 42}});
+        let public = serde_json::json!({"chat": {"id": -100, "type": "group"}, "from": {"id": 42}});
+        let state = Arc::new(Mutex::new(()));
+        let keypair = Keypair::new();
+        let params = serde_json::json!({"secret": keypair.to_base58_string(), "label": "old"});
+
+        assert!(import
+            .execute(params.clone(), public.clone(), state.clone())
+            .await
+            .is_err());
+        let imported = import
+            .execute(params, private.clone(), state.clone())
+            .await
+            .unwrap();
+        assert!(
+            imported.contains(&keypair.pubkey().to_string()),
+            "{}",
+            imported
+        );
+
+        let params = serde_json::json!({"format": "keypair_file", "account": "old"});
+        assert!(export
+            .execute(params.clone(), public.clone(), state.clone())
+            .await
+            .is_err());
+        let preview: serde_json::Value = serde_json::from_str(
+            &export
+                .execute(params, private.clone(), state.clone())
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(preview["wallet"], keypair.pubkey().to_string());
+
+        // Confirming from another chat delivers nothing
+        assert!(export
+            .confirm(preview.clone(), public, state.clone())
+            .unwrap()
+            .await
+            .is_err());
+        let sent = output::collect(export.confirm(preview, private, state).unwrap())
+            .await
+            .unwrap();
+        let file = sent.attachments().next().unwrap();
+        assert_eq!(file.file_name, format!("{}.json", keypair.pubkey()));
+        let bytes: Vec<u8> = serde_json::from_slice(&file.data).unwrap();
+        assert_eq!(bytes, keypair.to_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_backup_password_stays_out_of_the_preview() {
+        let wallets = SimpleWalletManager::new();
+        let wallet = wallets.create_wallet("42").await.unwrap();
+        let group = WalletBackupActionGroup::<()>::new(wallets.clone());
+        let [_, export] = group.actions() else {
+            panic!("unexpected actions");
+        };
+        let private =
+            serde_json::json!({"chat": {"id": 42, "type": "private"}, "from": {"id": 42}});
+        let state = Arc::new(Mutex::new(()));
+
+        let preview = export
+            .execute(
+                serde_json::json!({"format": "backup", "password": "correct horse"}),
+                private.clone(),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        assert!(!preview.contains("correct horse"), "{}", preview);
+        let preview: serde_json::Value = serde_json::from_str(&preview).unwrap();
+        assert_eq!(preview["password_set"], true);
+
+        let sent = output::collect(
+            export
+                .confirm(preview.clone(), private.clone(), state.clone())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        let backup = String::from_utf8(sent.attachments().next().unwrap().data.clone()).unwrap();
+        let restored = SimpleWalletManager::new()
+            .import_wallet(
+                "7",
+                WalletImport::parse(&backup, "imported", Some("correct horse")).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(restored.address(), wallet.address());
+
+        // The password is handed out once
+        assert!(export
+            .confirm(preview, private, state)
+            .unwrap()
+            .await
+            .is_err());
+    }
+}
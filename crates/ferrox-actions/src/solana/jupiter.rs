@@ -0,0 +1,696 @@
+pub mod client;
+pub mod dca;
+pub mod models;
+
+use crate::{
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
+    solana::{
+        check_slippage, explorer_url, parse_mint,
+        policy::{Spend, SpendingPolicy},
+        rpc::client::SolanaRpcClient,
+        simulation::{self, SimulationPreview},
+        submit::TransactionSubmitter,
+        to_base_units, to_ui_amount,
+        wallet::{check_confirmer, sender_id, solana_signer},
+    },
+    AgentState,
+};
+use client::JupiterClient;
+use ferrox_wallet::WalletManager;
+use models::QuoteResponse;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Base fee of a transaction with one signature.
+const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+/// Price impact above which the preview warns the user.
+const HIGH_PRICE_IMPACT_PERCENT: f64 = 1.0;
+/// Price impact above which no swap is offered at all.
+const MAX_PRICE_IMPACT_PERCENT: f64 = 10.0;
+const DEFAULT_SLIPPAGE_BPS: u16 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct QuoteParams {
+    input_mint: String,
+    output_mint: String,
+    amount: f64,
+    slippage_bps: Option<u16>,
+    /// Wallet account to swap from; quotes ignore it.
+    account: Option<String>,
+}
+
+/// One hop of a route, with the fee the AMM takes in base units of
+/// `fee_mint`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteHop {
+    pub label: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub percent: u8,
+    pub fee_amount: Option<String>,
+    pub fee_mint: Option<String>,
+}
+
+/// A quote in UI amounts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteSummary {
+    pub input_mint: String,
+    pub output_mint: String,
+    pub in_amount: f64,
+    pub out_amount: f64,
+    /// Least the swap returns once slippage is applied.
+    pub min_out_amount: f64,
+    /// Output tokens per input token.
+    pub price: f64,
+    pub price_impact_percent: f64,
+    pub slippage_bps: u16,
+    pub route: Vec<RouteHop>,
+}
+
+impl QuoteSummary {
+    fn new(quote: &QuoteResponse, in_decimals: u8, out_decimals: u8) -> Self {
+        let in_amount = to_ui_amount(quote.in_amount(), in_decimals);
+        let out_amount = to_ui_amount(quote.out_amount(), out_decimals);
+        Self {
+            input_mint: quote.input_mint.clone(),
+            output_mint: quote.output_mint.clone(),
+            in_amount,
+            out_amount,
+            min_out_amount: to_ui_amount(quote.min_out_amount(), out_decimals),
+            price: if in_amount > 0.0 {
+                out_amount / in_amount
+            } else {
+                0.0
+            },
+            price_impact_percent: quote.price_impact_percent(),
+            slippage_bps: quote.slippage_bps,
+            route: quote
+                .route_plan
+                .iter()
+                .map(|step| RouteHop {
+                    label: step
+                        .swap_info
+                        .label
+                        .clone()
+                        .unwrap_or_else(|| step.swap_info.amm_key.clone()),
+                    input_mint: step.swap_info.input_mint.clone(),
+                    output_mint: step.swap_info.output_mint.clone(),
+                    percent: step.percent,
+                    fee_amount: step.swap_info.fee_amount.clone(),
+                    fee_mint: step.swap_info.fee_mint.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// What the user confirms. It is handed back to the confirm handler, which
+/// re-quotes and refuses to swap for less than `min_out_units`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapPreview {
+    pub user_id: String,
+    pub wallet: String,
+    /// Name of the wallet account the user picked; `None` for their default.
+    pub account: Option<String>,
+    #[serde(flatten)]
+    pub quote: QuoteSummary,
+    pub amount_units: u64,
+    pub min_out_units: u64,
+    pub network_fee_sol: f64,
+    pub max_priority_fee_sol: f64,
+    pub warnings: Vec<String>,
+    /// What the spending policy refuses about the swap; it cannot be
+    /// confirmed while there are any.
+    #[serde(default)]
+    pub violations: Vec<String>,
+    pub simulation: SimulationPreview,
+}
+
+impl SwapPreview {
+    /// The tokens sold, valued by the tokens bought when the policy cannot
+    /// price them.
+    fn spend(&self) -> Spend {
+        let spend = Spend::new(&self.user_id).amount(&self.quote.input_mint, self.quote.in_amount);
+        if spend.is_valued() {
+            spend.token(&self.quote.output_mint)
+        } else {
+            spend.amount(&self.quote.output_mint, self.quote.out_amount)
+        }
+    }
+}
+
+/// Everything the Jupiter actions need, cloned into each invocation.
+#[derive(Clone)]
+struct JupiterContext<W> {
+    jupiter: JupiterClient,
+    rpc: SolanaRpcClient,
+    submitter: TransactionSubmitter,
+    wallets: W,
+    policy: SpendingPolicy,
+}
+
+impl<W> JupiterContext<W> {
+    async fn quote(&self, params: &QuoteParams) -> Result<(QuoteResponse, QuoteSummary), String> {
+        let input_mint = parse_mint(&params.input_mint)?;
+        let output_mint = parse_mint(&params.output_mint)?;
+        let slippage_bps = check_slippage(params.slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS))?;
+        if input_mint == output_mint {
+            return Err("Input and output mints are the same".to_string());
+        }
+        let in_decimals = self.rpc.get_token_decimals(&input_mint).await?;
+        let out_decimals = self.rpc.get_token_decimals(&output_mint).await?;
+        let amount = to_base_units(params.amount, in_decimals)?;
+        let quote = self
+            .jupiter
+            .get_quote(&input_mint, &output_mint, amount, slippage_bps)
+            .await?;
+        let summary = QuoteSummary::new(&quote, in_decimals, out_decimals);
+        Ok((quote, summary))
+    }
+}
+
+pub struct JupiterActionGroup<S: Send + Sync + Clone + 'static> {
+    actions: Vec<Arc<FunctionAction<S>>>,
+}
+
+impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for JupiterActionGroup<S> {
+    fn actions(&self) -> &[Arc<FunctionAction<S>>] {
+        &self.actions
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> JupiterActionGroup<S> {
+    /// Builds the group with clients configured from the environment (see
+    /// [`JupiterClient::from_env`] and [`SolanaRpcClient::from_env`]), signing
+    /// swaps with the wallets in `wallets` within the limits of `policy`.
+    pub fn new<W: WalletManager + 'static>(wallets: W, policy: SpendingPolicy) -> Self {
+        Self::with_clients(
+            JupiterClient::from_env(),
+            SolanaRpcClient::from_env(),
+            wallets,
+            policy,
+        )
+    }
+
+    /// Builds the group around configured clients, e.g. ones pointing at a
+    /// mock server.
+    pub fn with_clients<W: WalletManager + 'static>(
+        jupiter: JupiterClient,
+        rpc: SolanaRpcClient,
+        wallets: W,
+        policy: SpendingPolicy,
+    ) -> Self {
+        let context = JupiterContext {
+            jupiter,
+            submitter: TransactionSubmitter::new(rpc.clone()),
+            rpc,
+            wallets,
+            policy,
+        };
+        let mut actions = Vec::new();
+
+        // Add quote action
+        {
+            async fn get_jupiter_quote<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: JupiterContext<W>,
+                params: QuoteParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let (_, summary) = context.quote(&params).await?;
+                serde_json::to_string(&summary)
+                    .map_err(|e| format!("Failed to serialize quote: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_jupiter_quote",
+                with_client(&context, get_jupiter_quote),
+                None,
+            )
+            .description(
+                "Get a Jupiter quote for swapping one Solana token into another: expected and minimum output, price, price impact and the route with its fees. Does not trade",
+            )
+            .parameter(
+                "input_mint",
+                "Mint address of the token to sell, or SOL, USDC or USDT",
+                "string",
+                true,
+            )
+            .parameter(
+                "output_mint",
+                "Mint address of the token to buy, or SOL, USDC or USDT",
+                "string",
+                true,
+            )
+            .parameter(
+                "amount",
+                "Amount of the input token to sell, in whole tokens (e.g. 1.5)",
+                "number",
+                true,
+            )
+            .parameter(
+                "slippage_bps",
+                "Slippage tolerance in basis points (default 50, at most 5000)",
+                "integer",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add swap action
+        {
+            async fn preview_swap<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: JupiterContext<W>,
+                params: QuoteParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<SwapPreview, String> {
+                let user_id = sender_id(&send_state)?;
+                let signer =
+                    solana_signer(&context.wallets, &user_id, params.account.as_deref()).await?;
+                let (quote, summary) = context.quote(&params).await?;
+                if summary.price_impact_percent > MAX_PRICE_IMPACT_PERCENT {
+                    return Err(format!(
+                        "Price impact of {:.2}% is above the {}% limit; try a smaller amount",
+                        summary.price_impact_percent, MAX_PRICE_IMPACT_PERCENT
+                    ));
+                }
+                let mut warnings = Vec::new();
+                if summary.price_impact_percent > HIGH_PRICE_IMPACT_PERCENT {
+                    warnings.push(format!(
+                        "High price impact: {:.2}%",
+                        summary.price_impact_percent
+                    ));
+                }
+                let (transaction, _) = context
+                    .jupiter
+                    .get_swap_transaction(&quote, &signer.pubkey())
+                    .await?;
+                let simulation = simulation::simulate(&context.rpc, &transaction)
+                    .await?
+                    .confirmable()?;
+
+                let mut preview = SwapPreview {
+                    user_id,
+                    wallet: signer.pubkey().to_string(),
+                    account: params.account,
+                    amount_units: quote.in_amount(),
+                    min_out_units: quote.min_out_amount(),
+                    network_fee_sol: to_ui_amount(SIGNATURE_FEE_LAMPORTS, 9),
+                    max_priority_fee_sol: to_ui_amount(
+                        context.jupiter.max_priority_fee_lamports(),
+                        9,
+                    ),
+                    warnings,
+                    violations: Vec::new(),
+                    simulation,
+                    quote: summary,
+                };
+                preview.violations = context.policy.check(&preview.spend());
+                Ok(preview)
+            }
+
+            async fn confirm_swap<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: JupiterContext<W>,
+                preview: SwapPreview,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                check_confirmer(&send_state, &preview.user_id)?;
+                let signer = solana_signer(
+                    &context.wallets,
+                    &preview.user_id,
+                    preview.account.as_deref(),
+                )
+                .await?;
+                if signer.pubkey().to_string() != preview.wallet {
+                    return Err(
+                        "The wallet changed since the preview; ask for a new quote".to_string()
+                    );
+                }
+                let input_mint = parse_mint(&preview.quote.input_mint)?;
+                let output_mint = parse_mint(&preview.quote.output_mint)?;
+                // The approved quote has likely expired; swap on a fresh one
+                // only if it still honours the approved minimum
+                let quote = context
+                    .jupiter
+                    .get_quote(
+                        &input_mint,
+                        &output_mint,
+                        preview.amount_units,
+                        check_slippage(preview.quote.slippage_bps)?,
+                    )
+                    .await?;
+                if quote.min_out_amount() < preview.min_out_units {
+                    return Err(format!(
+                        "The price moved since the preview: the swap would now return at least {} base units instead of the approved {}. Ask for a new quote",
+                        quote.min_out_amount(),
+                        preview.min_out_units
+                    ));
+                }
+
+                let approval = context.policy.approve(&preview.spend())?;
+                let (transaction, _) = context
+                    .jupiter
+                    .get_swap_transaction(&quote, &signer.pubkey())
+                    .await?;
+                let transaction = signer
+                    .sign_transaction(transaction)
+                    .await
+                    .map_err(|e| format!("Failed to sign swap: {}", e))?;
+                let outcome = context.submitter.submit_signed(&transaction).await?;
+                if outcome.may_have_landed() {
+                    approval.commit();
+                }
+                let signature = outcome.into_result()?;
+
+                Ok(format!(
+                    "Swapped {} {} for at least {} {}. Transaction: {}",
+                    preview.quote.in_amount,
+                    preview.quote.input_mint,
+                    preview.quote.min_out_amount,
+                    preview.quote.output_mint,
+                    explorer_url(&signature.to_string())
+                ))
+            }
+
+            let action =
+                ActionBuilder::<_, QuoteParams, serde_json::Value, S, SwapPreview, _>::new(
+                    "swap_tokens",
+                    with_client(&context, preview_swap),
+                    Some(with_client(&context, confirm_swap)),
+                )
+                .description(
+                    "Prepare a Jupiter swap from the user's Solana wallet. This does not swap by itself: the swap is simulated and the user is shown the quote, price impact, fees and balance changes with a Confirm button. Never say the swap has happened; ask the user to confirm",
+                )
+                .parameter(
+                    "input_mint",
+                    "Mint address of the token to sell, or SOL, USDC or USDT",
+                    "string",
+                    true,
+                )
+                .parameter(
+                    "output_mint",
+                    "Mint address of the token to buy, or SOL, USDC or USDT",
+                    "string",
+                    true,
+                )
+                .parameter(
+                    "amount",
+                    "Amount of the input token to sell, in whole tokens (e.g. 1.5)",
+                    "number",
+                    true,
+                )
+                .parameter(
+                    "slippage_bps",
+                    "Slippage tolerance in basis points (default 50, at most 5000)",
+                    "integer",
+                    false,
+                )
+                .parameter(
+                    "account",
+                    "Name of the user's wallet account to use, such as \"trading\"; leave out for their default account",
+                    "string",
+                    false,
+                )
+                .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        Self { actions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::{
+        test_support::{limiter, mock_method, rpc_client, rpc_url, unsigned_transaction, wallet},
+        NATIVE_MINT, USDC_MINT,
+    };
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use ferrox_wallet::simple_wallet_manager::SimpleWalletManager;
+    use mockito::Matcher;
+    use solana_sdk::transaction::VersionedTransaction;
+    use tokio::sync::Mutex;
+
+    fn quote_body(out_amount: u64, min_out_amount: u64) -> String {
+        serde_json::json!({
+            "inputMint": NATIVE_MINT,
+            "inAmount": "1500000000",
+            "outputMint": USDC_MINT,
+            "outAmount": out_amount.to_string(),
+            "otherAmountThreshold": min_out_amount.to_string(),
+            "swapMode": "ExactIn",
+            "slippageBps": 50,
+            "platformFee": null,
+            "priceImpactPct": "0.0012",
+            "routePlan": [{
+                "swapInfo": {
+                    "ammKey": "HJPjoWUrhoZzkNfRpHuieeFk9WcZWjwy6PBjZ81ngndJ",
+                    "label": "Raydium CLMM",
+                    "inputMint": NATIVE_MINT,
+                    "outputMint": USDC_MINT,
+                    "inAmount": "1500000000",
+                    "outAmount": out_amount.to_string(),
+                    "feeAmount": "375000",
+                    "feeMint": NATIVE_MINT
+                },
+                "percent": 100
+            }],
+            "contextSlot": 299283761,
+            "timeTaken": 0.003
+        })
+        .to_string()
+    }
+
+    async fn mock_quote(
+        server: &mut mockito::Server,
+        out_amount: u64,
+        min_out: u64,
+    ) -> mockito::Mock {
+        server
+            .mock("GET", "/swap/v1/quote")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("inputMint".into(), NATIVE_MINT.into()),
+                Matcher::UrlEncoded("outputMint".into(), USDC_MINT.into()),
+                Matcher::UrlEncoded("amount".into(), "1500000000".into()),
+                Matcher::UrlEncoded("slippageBps".into(), "50".into()),
+            ]))
+            .with_body(quote_body(out_amount, min_out))
+            .create_async()
+            .await
+    }
+
+    async fn mock_usdc_decimals(server: &mut mockito::Server) -> mockito::Mock {
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "getTokenSupply", "params": [USDC_MINT]}),
+            ))
+            .with_body(r#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":1},"value":{"amount":"1","decimals":6,"uiAmountString":"0.000001"}}}"#)
+            .create_async()
+            .await
+    }
+
+    fn group(server: &mockito::Server) -> JupiterActionGroup<()> {
+        JupiterActionGroup::with_clients(
+            JupiterClient::new()
+                .with_base_url(server.url())
+                .with_rate_limiter(limiter()),
+            rpc_client(rpc_url(server)),
+            SimpleWalletManager::new(),
+            SpendingPolicy::default(),
+        )
+    }
+
+    fn action(group: &JupiterActionGroup<()>, name: &str) -> Arc<FunctionAction<()>> {
+        group
+            .actions()
+            .iter()
+            .find(|action| action.definition().name == name)
+            .unwrap()
+            .clone()
+    }
+
+    fn params() -> serde_json::Value {
+        serde_json::json!({"input_mint": "SOL", "output_mint": "USDC", "amount": 1.5})
+    }
+
+    #[tokio::test]
+    async fn test_get_quote_in_ui_amounts() {
+        let mut server = mockito::Server::new_async().await;
+        let quote = mock_quote(&mut server, 212_340_000, 211_278_300).await;
+        let decimals = mock_usdc_decimals(&mut server).await;
+
+        let state = Arc::new(Mutex::new(()));
+        let result = action(&group(&server), "get_jupiter_quote")
+            .execute(params(), serde_json::json!({}), state)
+            .await
+            .unwrap();
+        quote.assert_async().await;
+        decimals.assert_async().await;
+
+        let body: String = serde_json::from_str(&result).unwrap();
+        let summary: QuoteSummary = serde_json::from_str(&body).unwrap();
+        assert_eq!(summary.in_amount, 1.5);
+        assert_eq!(summary.out_amount, 212.34);
+        assert_eq!(summary.min_out_amount, 211.2783);
+        assert!((summary.price - 141.56).abs() < 1e-9);
+        assert!((summary.price_impact_percent - 0.12).abs() < 1e-9);
+        assert_eq!(summary.route[0].label, "Raydium CLMM");
+        assert_eq!(summary.route[0].fee_amount.as_deref(), Some("375000"));
+    }
+
+    #[tokio::test]
+    async fn test_excessive_slippage_is_refused() {
+        let server = mockito::Server::new_async().await;
+        let mut params = params();
+        params["slippage_bps"] = serde_json::json!(9_000);
+
+        let error = action(&group(&server), "get_jupiter_quote")
+            .execute(params, serde_json::json!({}), Arc::new(Mutex::new(())))
+            .await
+            .unwrap_err();
+        assert!(error.contains("above the limit of 5000 bps"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_swap_is_signed_by_the_user_and_submitted() {
+        let mut server = mockito::Server::new_async().await;
+        let quote = mock_quote(&mut server, 212_340_000, 211_278_300)
+            .await
+            .expect(2);
+        mock_usdc_decimals(&mut server).await;
+
+        let wallet = wallet().await;
+        let swap = server
+            .mock("POST", "/swap/v1/swap")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "userPublicKey": wallet.to_string(),
+                "wrapAndUnwrapSol": true,
+                "quoteResponse": {"otherAmountThreshold": "211278300", "contextSlot": 299283761},
+            })))
+            .with_body(
+                serde_json::json!({
+                    "swapTransaction": unsigned_transaction(&wallet),
+                    "lastValidBlockHeight": 279632475,
+                    "prioritizationFeeLamports": 9999
+                })
+                .to_string(),
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        mock_method(
+            &mut server,
+            "getMultipleAccounts",
+            serde_json::json!({"context":{"slot":1},"value":[]}),
+        )
+        .await;
+        let simulate = mock_method(&mut server, "simulateTransaction", serde_json::json!({"context":{"slot":1},"value":{"err":null,"logs":[],"unitsConsumed":150000,"accounts":[]}})).await;
+        let send = server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "sendTransaction"}),
+            ))
+            .with_body_from_request(|request| {
+                let body: serde_json::Value =
+                    serde_json::from_slice(request.body().unwrap()).unwrap();
+                let bytes = BASE64.decode(body["params"][0].as_str().unwrap()).unwrap();
+                let transaction: VersionedTransaction = bincode::deserialize(&bytes).unwrap();
+                assert!(transaction.verify_with_results().iter().all(|ok| *ok));
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": transaction.signatures[0].to_string()
+                })
+                .to_string()
+                .into()
+            })
+            .create_async()
+            .await;
+
+        mock_method(&mut server, "getSignatureStatuses", serde_json::json!({"context":{"slot":2},"value":[{"slot":2,"err":null,"confirmationStatus":"confirmed"}]})).await;
+        let group = group(&server);
+        let swap_action = action(&group, "swap_tokens");
+        let state = Arc::new(Mutex::new(()));
+        let preview = swap_action
+            .execute(
+                params(),
+                serde_json::json!({"from": {"id": 42}}),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        let preview: serde_json::Value = serde_json::from_str(&preview).unwrap();
+        assert_eq!(preview["wallet"], wallet.to_string());
+        assert_eq!(preview["min_out_units"], 211_278_300);
+        assert_eq!(preview["warnings"], serde_json::json!([]));
+        assert_eq!(preview["simulation"]["units_consumed"], 150_000);
+        simulate.assert_async().await;
+
+        let result = swap_action
+            .confirm(preview, serde_json::json!({"from": {"id": 42}}), state)
+            .unwrap()
+            .await
+            .unwrap();
+        quote.assert_async().await;
+        swap.assert_async().await;
+        send.assert_async().await;
+        assert!(result.contains("https://solscan.io/tx/"), "{}", result);
+    }
+
+    #[tokio::test]
+    async fn test_swap_refused_when_price_moved() {
+        let mut server = mockito::Server::new_async().await;
+        mock_quote(&mut server, 200_000_000, 199_000_000).await;
+        let swap = server
+            .mock("POST", "/swap/v1/swap")
+            .expect(0)
+            .create_async()
+            .await;
+
+        let wallet = wallet().await;
+        let preview = serde_json::json!({
+            "user_id": "42",
+            "wallet": wallet.to_string(),
+            "input_mint": NATIVE_MINT,
+            "output_mint": USDC_MINT,
+            "in_amount": 1.5,
+            "out_amount": 212.34,
+            "min_out_amount": 211.2783,
+            "price": 141.56,
+            "price_impact_percent": 0.12,
+            "slippage_bps": 50,
+            "route": [],
+            "amount_units": 1_500_000_000u64,
+            "min_out_units": 211_278_300u64,
+            "network_fee_sol": 0.000005,
+            "max_priority_fee_sol": 0.001,
+            "warnings": [],
+            "simulation": {
+                "success": true,
+                "error": null,
+                "units_consumed": 150000,
+                "sol_changes": [],
+                "token_changes": [],
+                "logs": []
+            }
+        });
+        let error = action(&group(&server), "swap_tokens")
+            .confirm(
+                preview,
+                serde_json::json!({"from": {"id": 42}}),
+                Arc::new(Mutex::new(())),
+            )
+            .unwrap()
+            .await
+            .unwrap_err();
+        assert!(error.starts_with("The price moved"), "{}", error);
+        swap.assert_async().await;
+    }
+}
@@ -0,0 +1,285 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use reqwest::header::{HeaderMap, HeaderValue};
+use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
+use std::sync::Arc;
+
+use super::models::{
+    CancelRecurringRequest, CreateRecurringRequest, ExecuteRequest, ExecuteResponse,
+    PrioritizationFee, PriorityLevel, QuoteResponse, RecurringOrder, RecurringOrders,
+    RecurringParams, RecurringTransaction, SwapRequest, SwapResponse, TimeParams,
+};
+use crate::http::{HttpConfig, ProviderHttp, RateLimiter, JUPITER_QUOTA};
+
+const BASE_URL: &str = "https://lite-api.jup.ag";
+const PRO_BASE_URL: &str = "https://api.jup.ag";
+
+/// Most the swap transaction may pay in priority fees by default, 0.001 SOL.
+const DEFAULT_MAX_PRIORITY_FEE_LAMPORTS: u64 = 1_000_000;
+
+#[derive(Debug, Clone)]
+pub struct JupiterClient {
+    api_key: Option<String>,
+    http: ProviderHttp,
+    base_url: String,
+    max_priority_fee_lamports: u64,
+}
+
+impl Default for JupiterClient {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+impl JupiterClient {
+    /// Uses the keyless API.
+    pub fn new() -> Self {
+        Self {
+            api_key: None,
+            http: ProviderHttp::new("jupiter", JUPITER_QUOTA),
+            base_url: BASE_URL.to_string(),
+            max_priority_fee_lamports: DEFAULT_MAX_PRIORITY_FEE_LAMPORTS,
+        }
+    }
+
+    /// Uses the paid API with the key in `JUPITER_API_KEY`, or the keyless API
+    /// when it is not set.
+    pub fn from_env() -> Self {
+        match std::env::var("JUPITER_API_KEY") {
+            Ok(api_key) => Self::new().with_api_key(api_key),
+            Err(_) => Self::new(),
+        }
+    }
+
+    /// Switches to the paid API host and authenticates with `api_key`.
+    pub fn with_api_key(mut self, api_key: String) -> Self {
+        self.api_key = Some(api_key);
+        self.base_url = PRO_BASE_URL.to_string();
+        self
+    }
+
+    /// Overrides the API host, e.g. to point the client at a mock server.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn with_http_config(mut self, config: HttpConfig) -> Self {
+        self.http = self.http.with_config(config);
+        self
+    }
+
+    /// Replaces the shared rate limiter, e.g. for a plan with a higher quota.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.http = self.http.with_limiter(limiter);
+        self
+    }
+
+    /// Caps the priority fee Jupiter may add to swap transactions.
+    pub fn with_max_priority_fee_lamports(mut self, lamports: u64) -> Self {
+        self.max_priority_fee_lamports = lamports;
+        self
+    }
+
+    pub fn max_priority_fee_lamports(&self) -> u64 {
+        self.max_priority_fee_lamports
+    }
+
+    fn headers(&self) -> Result<HeaderMap, String> {
+        let mut headers = HeaderMap::new();
+        if let Some(api_key) = &self.api_key {
+            let api_key = HeaderValue::from_str(api_key)
+                .map_err(|e| format!("Invalid Jupiter API key: {}", e))?;
+            headers.insert("x-api-key", api_key);
+        }
+        Ok(headers)
+    }
+
+    /// Best route for swapping `amount` base units of `input_mint` into
+    /// `output_mint`.
+    pub async fn get_quote(
+        &self,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        amount: u64,
+        slippage_bps: u16,
+    ) -> Result<QuoteResponse, String> {
+        let url = format!("{}/swap/v1/quote", self.base_url);
+        let body = self
+            .http
+            .get_with_query(
+                &url,
+                &self.headers()?,
+                &[
+                    ("inputMint", input_mint.to_string()),
+                    ("outputMint", output_mint.to_string()),
+                    ("amount", amount.to_string()),
+                    ("slippageBps", slippage_bps.to_string()),
+                ],
+            )
+            .await
+            .map_err(|e| format!("Failed to fetch Jupiter quote: {}", e))?;
+        serde_json::from_str(&body).map_err(|e| format!("Error parsing Jupiter quote: {}", e))
+    }
+
+    /// The unsigned transaction that executes `quote` for `user`.
+    pub async fn get_swap_transaction(
+        &self,
+        quote: &QuoteResponse,
+        user: &Pubkey,
+    ) -> Result<(VersionedTransaction, SwapResponse), String> {
+        let url = format!("{}/swap/v1/swap", self.base_url);
+        let request = SwapRequest {
+            quote_response: quote,
+            user_public_key: user.to_string(),
+            wrap_and_unwrap_sol: true,
+            dynamic_compute_unit_limit: true,
+            prioritization_fee_lamports: PrioritizationFee {
+                priority_level_with_max_lamports: PriorityLevel {
+                    max_lamports: self.max_priority_fee_lamports,
+                    priority_level: "high".to_string(),
+                },
+            },
+        };
+        let body = self
+            .http
+            .post_json(&url, &self.headers()?, &request)
+            .await
+            .map_err(|e| format!("Failed to build Jupiter swap: {}", e))?;
+        let response: SwapResponse = serde_json::from_str(&body)
+            .map_err(|e| format!("Error parsing Jupiter swap: {}", e))?;
+        let transaction = decode_transaction(&response.swap_transaction)?;
+        Ok((transaction, response))
+    }
+
+    /// The unsigned transaction opening a recurring order that swaps
+    /// `in_amount` base units of `input_mint` into `output_mint` over
+    /// `number_of_orders` cycles, `interval` seconds apart. Returns it with the
+    /// request id [`Self::execute_recurring`] needs.
+    pub async fn create_recurring_order(
+        &self,
+        user: &Pubkey,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        in_amount: u64,
+        number_of_orders: u64,
+        interval: u64,
+    ) -> Result<(VersionedTransaction, String), String> {
+        let url = format!("{}/recurring/v1/createOrder", self.base_url);
+        let request = CreateRecurringRequest {
+            user: user.to_string(),
+            input_mint: input_mint.to_string(),
+            output_mint: output_mint.to_string(),
+            params: RecurringParams {
+                time: TimeParams {
+                    in_amount,
+                    number_of_orders,
+                    interval,
+                    min_price: None,
+                    max_price: None,
+                    start_at: None,
+                },
+            },
+        };
+        let body = self
+            .http
+            .post_json(&url, &self.headers()?, &request)
+            .await
+            .map_err(|e| format!("Failed to create Jupiter recurring order: {}", e))?;
+        self.recurring_transaction(&body)
+    }
+
+    /// The unsigned transaction closing `order` and returning what is left of
+    /// its input to `user`.
+    pub async fn cancel_recurring_order(
+        &self,
+        user: &Pubkey,
+        order: &str,
+    ) -> Result<(VersionedTransaction, String), String> {
+        let url = format!("{}/recurring/v1/cancelOrder", self.base_url);
+        let request = CancelRecurringRequest {
+            order: order.to_string(),
+            user: user.to_string(),
+            recurring_type: "time".to_string(),
+        };
+        let body = self
+            .http
+            .post_json(&url, &self.headers()?, &request)
+            .await
+            .map_err(|e| format!("Failed to cancel Jupiter recurring order: {}", e))?;
+        self.recurring_transaction(&body)
+    }
+
+    fn recurring_transaction(&self, body: &str) -> Result<(VersionedTransaction, String), String> {
+        let response: RecurringTransaction = serde_json::from_str(body)
+            .map_err(|e| format!("Error parsing Jupiter recurring order: {}", e))?;
+        let transaction = decode_transaction(&response.transaction)?;
+        Ok((transaction, response.request_id))
+    }
+
+    /// Has Jupiter submit a signed recurring-order transaction and waits for
+    /// the outcome. Failed transactions are returned as `Err`.
+    pub async fn execute_recurring(
+        &self,
+        request_id: &str,
+        transaction: &VersionedTransaction,
+    ) -> Result<ExecuteResponse, String> {
+        let url = format!("{}/recurring/v1/execute", self.base_url);
+        let bytes = bincode::serialize(transaction)
+            .map_err(|e| format!("Failed to serialize transaction: {}", e))?;
+        let request = ExecuteRequest {
+            request_id: request_id.to_string(),
+            signed_transaction: BASE64.encode(bytes),
+        };
+        let body = self
+            .http
+            .post_json(&url, &self.headers()?, &request)
+            .await
+            .map_err(|e| format!("Failed to execute Jupiter recurring order: {}", e))?;
+        let response: ExecuteResponse = serde_json::from_str(&body)
+            .map_err(|e| format!("Error parsing Jupiter execute response: {}", e))?;
+        if response.status != "Success" {
+            return Err(format!(
+                "Jupiter recurring order transaction failed: {}",
+                response.error.as_deref().unwrap_or(&response.status)
+            ));
+        }
+        Ok(response)
+    }
+
+    /// Time-based orders of `user`: the active ones, or closed and completed
+    /// ones when `history` is set.
+    pub async fn get_recurring_orders(
+        &self,
+        user: &Pubkey,
+        history: bool,
+    ) -> Result<Vec<RecurringOrder>, String> {
+        let url = format!("{}/recurring/v1/getRecurringOrders", self.base_url);
+        let body = self
+            .http
+            .get_with_query(
+                &url,
+                &self.headers()?,
+                &[
+                    ("user", user.to_string()),
+                    ("recurringType", "time".to_string()),
+                    (
+                        "orderStatus",
+                        if history { "history" } else { "active" }.to_string(),
+                    ),
+                    ("includeFailedTx", "false".to_string()),
+                ],
+            )
+            .await
+            .map_err(|e| format!("Failed to fetch Jupiter recurring orders: {}", e))?;
+        let orders: RecurringOrders = serde_json::from_str(&body)
+            .map_err(|e| format!("Error parsing Jupiter recurring orders: {}", e))?;
+        Ok(orders.time)
+    }
+}
+
+fn decode_transaction(encoded: &str) -> Result<VersionedTransaction, String> {
+    let bytes = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("Invalid transaction encoding: {}", e))?;
+    bincode::deserialize(&bytes).map_err(|e| format!("Invalid transaction: {}", e))
+}
@@ -1,31 +1,853 @@
-use crate::action::{ActionBuilder, ActionFeedback, ActionResult};
-use std::pin::Pin;
+//! Dollar-cost averaging through Jupiter's time-based recurring orders.
 
-pub struct DcaResponse;
+use super::{client::JupiterClient, JupiterContext};
+use crate::{
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
+    output::{self, Table},
+    solana::{
+        explorer_url, mint_label, parse_mint,
+        policy::{Spend, SpendingPolicy},
+        rpc::client::SolanaRpcClient,
+        simulation::{self, SimulationPreview},
+        submit::TransactionSubmitter,
+        to_base_units,
+        wallet::{check_confirmer, sender_id, solana_signer},
+    },
+    AgentState,
+};
+use ferrox_wallet::WalletManager;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-pub fn preview_dca_action() -> Result<String, String> {}
-pub fn execute_dca_action() -> Result<String, String> {}
+/// Jupiter needs at least two cycles per order.
+const MIN_ORDERS: u64 = 2;
+const MIN_INTERVAL_SECONDS: u64 = 60;
 
-pub async fn preview_dca(
-    params: DcaParams,
-    state: AgentState<S>,
-) -> Result<ActionFeedback, String> {
-    let preview = preview_dca_action()?;
+#[derive(Debug, Deserialize)]
+pub struct CreateDcaParams {
+    input_mint: String,
+    output_mint: String,
+    total_amount: f64,
+    amount_per_cycle: f64,
+    interval: String,
+    account: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListDcaParams {
+    history: Option<bool>,
+    account: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloseDcaParams {
+    order_key: String,
+    account: Option<String>,
+}
+
+/// The schedule the user approves before the order is opened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DcaPreview {
+    pub user_id: String,
+    pub wallet: String,
+    /// Name of the wallet account the user picked; `None` for their default.
+    pub account: Option<String>,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub total_amount: f64,
+    pub amount_per_cycle: f64,
+    pub number_of_orders: u64,
+    pub interval_seconds: u64,
+    pub interval: String,
+    /// Time from the first cycle to the last.
+    pub duration: String,
+    pub total_units: u64,
+    /// What the spending policy refuses about the order; it cannot be
+    /// confirmed while there are any.
+    #[serde(default)]
+    pub violations: Vec<String>,
+    pub simulation: SimulationPreview,
+}
+
+impl DcaPreview {
+    /// The whole order counts when it is opened, since the deposit leaves
+    /// the wallet then.
+    fn spend(&self) -> Spend {
+        Spend::new(&self.user_id)
+            .amount(&self.input_mint, self.total_amount)
+            .token(&self.output_mint)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloseDcaPreview {
+    pub user_id: String,
+    pub wallet: String,
+    /// Name of the wallet account the user picked; `None` for their default.
+    pub account: Option<String>,
+    pub order_key: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    /// Unspent input that closing the order returns to the wallet.
+    pub in_remaining: f64,
+    pub out_received: f64,
+    pub simulation: SimulationPreview,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DcaOrderSummary {
+    pub order_key: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub deposited: f64,
+    pub spent: f64,
+    pub remaining: f64,
+    pub amount_per_cycle: f64,
+    pub interval: String,
+    pub received: f64,
+    pub created_at: String,
+}
+
+/// Parses an interval such as `90`, `30m`, `4h`, `1d` or `1w` into seconds.
+fn parse_interval(interval: &str) -> Result<u64, String> {
+    let interval = interval.trim().to_lowercase();
+    let (number, unit) = match interval.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => interval.split_at(index),
+        None => (interval.as_str(), "s"),
+    };
+    let multiplier = match unit.trim() {
+        "s" | "sec" | "second" | "seconds" => 1,
+        "m" | "min" | "minute" | "minutes" => 60,
+        "h" | "hour" | "hours" => 3_600,
+        "d" | "day" | "days" => 86_400,
+        "w" | "week" | "weeks" => 604_800,
+        _ => return Err(format!("Invalid interval '{}'", interval)),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .filter(|seconds| *seconds > 0)
+        .ok_or_else(|| format!("Invalid interval '{}'", interval))
+}
+
+/// Describes a number of seconds in the largest unit that divides it.
+fn describe_seconds(seconds: u64) -> String {
+    let (count, unit) = [
+        (604_800, "week"),
+        (86_400, "day"),
+        (3_600, "hour"),
+        (60, "minute"),
+    ]
+    .into_iter()
+    .find(|(size, _)| seconds.is_multiple_of(*size))
+    .map(|(size, unit)| (seconds / size, unit))
+    .unwrap_or((seconds, "second"));
+    if count == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
+
+fn dca_table(orders: &[DcaOrderSummary]) -> Table {
+    orders.iter().fold(
+        Table::new(["Order", "Pair", "Per cycle", "Every", "Spent", "Received"])
+            .title("DCA orders"),
+        |table, order| {
+            table.row([
+                mint_label(&order.order_key),
+                format!(
+                    "{} → {}",
+                    mint_label(&order.input_mint),
+                    mint_label(&order.output_mint)
+                ),
+                order.amount_per_cycle.to_string(),
+                order.interval.clone(),
+                format!("{} / {}", order.spent, order.deposited),
+                order.received.to_string(),
+            ])
+        },
+    )
+}
+
+fn parse_amount(value: &str) -> f64 {
+    value.parse().unwrap_or_default()
+}
 
-    Ok(ActionFeedback {
-        message: format!("DCA Preview: {}", preview),
-        confirmation_data: serde_json::to_value(params)?,
-    })
+pub struct JupiterDcaActionGroup<S: Send + Sync + Clone + 'static> {
+    actions: Vec<Arc<FunctionAction<S>>>,
 }
 
-pub async fn execute_dca(params: DcaParams, state: AgentState<S>) -> Result<String, String> {
-    execute_dca_action()
+impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for JupiterDcaActionGroup<S> {
+    fn actions(&self) -> &[Arc<FunctionAction<S>>] {
+        &self.actions
+    }
 }
 
-pub fn create_dca_action<S: Send + Sync + Clone + 'static>() -> FunctionAction<S> {
-    ActionBuilder::<_, DcaParams, DcaParams, S>::new("dca", execute_dca, Some(preview_dca))
-        .description("Dollar Cost Average into a token")
-        .parameter("token", "Token to DCA into", "string", true)
-        .parameter("amount", "Amount to invest", "number", true)
-        .build()
+impl<S: Send + Sync + Clone + 'static> JupiterDcaActionGroup<S> {
+    /// Builds the group with clients configured from the environment, signing
+    /// orders with the wallets in `wallets` within the limits of `policy`.
+    pub fn new<W: WalletManager + 'static>(wallets: W, policy: SpendingPolicy) -> Self {
+        Self::with_clients(
+            JupiterClient::from_env(),
+            SolanaRpcClient::from_env(),
+            wallets,
+            policy,
+        )
+    }
+
+    pub fn with_clients<W: WalletManager + 'static>(
+        jupiter: JupiterClient,
+        rpc: SolanaRpcClient,
+        wallets: W,
+        policy: SpendingPolicy,
+    ) -> Self {
+        let context = JupiterContext {
+            jupiter,
+            submitter: TransactionSubmitter::new(rpc.clone()),
+            rpc,
+            wallets,
+            policy,
+        };
+        let mut actions = Vec::new();
+
+        // Add create DCA order action
+        {
+            async fn preview_create_dca<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: JupiterContext<W>,
+                params: CreateDcaParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<DcaPreview, String> {
+                let user_id = sender_id(&send_state)?;
+                let signer =
+                    solana_signer(&context.wallets, &user_id, params.account.as_deref()).await?;
+                let input_mint = parse_mint(&params.input_mint)?;
+                let output_mint = parse_mint(&params.output_mint)?;
+                if input_mint == output_mint {
+                    return Err("Input and output mints are the same".to_string());
+                }
+                let interval_seconds = parse_interval(&params.interval)?;
+                if interval_seconds < MIN_INTERVAL_SECONDS {
+                    return Err(format!(
+                        "The interval must be at least {} seconds",
+                        MIN_INTERVAL_SECONDS
+                    ));
+                }
+
+                let decimals = context.rpc.get_token_decimals(&input_mint).await?;
+                let total_units = to_base_units(params.total_amount, decimals)?;
+                let cycle_units = to_base_units(params.amount_per_cycle, decimals)?;
+                if !total_units.is_multiple_of(cycle_units) {
+                    return Err(format!(
+                        "The total amount {} is not a whole number of {} cycles",
+                        params.total_amount, params.amount_per_cycle
+                    ));
+                }
+                let number_of_orders = total_units / cycle_units;
+                if number_of_orders < MIN_ORDERS {
+                    return Err(format!(
+                        "A DCA order needs at least {} cycles; lower the amount per cycle",
+                        MIN_ORDERS
+                    ));
+                }
+                let (transaction, _) = context
+                    .jupiter
+                    .create_recurring_order(
+                        &signer.pubkey(),
+                        &input_mint,
+                        &output_mint,
+                        total_units,
+                        number_of_orders,
+                        interval_seconds,
+                    )
+                    .await?;
+                let simulation = simulation::simulate(&context.rpc, &transaction)
+                    .await?
+                    .confirmable()?;
+
+                let mut preview = DcaPreview {
+                    user_id,
+                    wallet: signer.pubkey().to_string(),
+                    account: params.account,
+                    input_mint: input_mint.to_string(),
+                    output_mint: output_mint.to_string(),
+                    total_amount: params.total_amount,
+                    amount_per_cycle: params.amount_per_cycle,
+                    number_of_orders,
+                    interval_seconds,
+                    interval: describe_seconds(interval_seconds),
+                    duration: describe_seconds(interval_seconds * (number_of_orders - 1)),
+                    total_units,
+                    violations: Vec::new(),
+                    simulation,
+                };
+                preview.violations = context.policy.check(&preview.spend());
+                Ok(preview)
+            }
+
+            async fn confirm_create_dca<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: JupiterContext<W>,
+                preview: DcaPreview,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                check_confirmer(&send_state, &preview.user_id)?;
+                let signer = solana_signer(
+                    &context.wallets,
+                    &preview.user_id,
+                    preview.account.as_deref(),
+                )
+                .await?;
+                if signer.pubkey().to_string() != preview.wallet {
+                    return Err(
+                        "The wallet changed since the preview; set the order up again".to_string(),
+                    );
+                }
+                let approval = context.policy.approve(&preview.spend())?;
+                let (transaction, request_id) = context
+                    .jupiter
+                    .create_recurring_order(
+                        &signer.pubkey(),
+                        &parse_mint(&preview.input_mint)?,
+                        &parse_mint(&preview.output_mint)?,
+                        preview.total_units,
+                        preview.number_of_orders,
+                        preview.interval_seconds,
+                    )
+                    .await?;
+                let transaction = signer
+                    .sign_transaction(transaction)
+                    .await
+                    .map_err(|e| format!("Failed to sign DCA order: {}", e))?;
+                // Jupiter lands recurring order transactions itself and
+                // reports the order they open. It may have sent the
+                // transaction even when it answers with an error, so the
+                // spend stays counted either way
+                let response = context
+                    .jupiter
+                    .execute_recurring(&request_id, &transaction)
+                    .await;
+                approval.commit();
+                let response = response.map_err(|e| {
+                    format!(
+                        "{}. The order transaction {} may still have been sent; check it before trying again",
+                        e,
+                        explorer_url(&transaction.signatures[0].to_string())
+                    )
+                })?;
+
+                Ok(format!(
+                    "DCA order {} created: {} {} into {} every {}, {} times. Transaction: {}",
+                    response.order.as_deref().unwrap_or("(pending)"),
+                    preview.amount_per_cycle,
+                    mint_label(&preview.input_mint),
+                    mint_label(&preview.output_mint),
+                    preview.interval,
+                    preview.number_of_orders,
+                    explorer_url(
+                        &response
+                            .signature
+                            .unwrap_or_else(|| transaction.signatures[0].to_string())
+                    )
+                ))
+            }
+
+            let action =
+                ActionBuilder::<_, CreateDcaParams, serde_json::Value, S, DcaPreview, _>::new(
+                    "create_dca_order",
+                    with_client(&context, preview_create_dca),
+                    Some(with_client(&context, confirm_create_dca)),
+                )
+                .description(
+                    "Prepare a Jupiter DCA (recurring) order that buys a token with a fixed amount every interval. This does not open the order by itself: the order transaction is simulated and the user is shown the schedule and balance changes with a Confirm button. Never say the order exists before they confirm",
+                )
+                .parameter(
+                    "input_mint",
+                    "Mint address of the token to spend, or SOL, USDC or USDT",
+                    "string",
+                    true,
+                )
+                .parameter(
+                    "output_mint",
+                    "Mint address of the token to buy, or SOL, USDC or USDT",
+                    "string",
+                    true,
+                )
+                .parameter(
+                    "total_amount",
+                    "Total amount of the input token to spend, in whole tokens",
+                    "number",
+                    true,
+                )
+                .parameter(
+                    "amount_per_cycle",
+                    "Amount of the input token to spend each cycle; must divide the total",
+                    "number",
+                    true,
+                )
+                .parameter(
+                    "interval",
+                    "Time between cycles, e.g. 30m, 4h, 1d or 1w",
+                    "string",
+                    true,
+                )
+                .parameter(
+                    "account",
+                    "Name of the user's wallet account to use, such as \"trading\"; leave out for their default account",
+                    "string",
+                    false,
+                )
+                .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add list DCA orders action
+        {
+            async fn get_dca_orders<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: JupiterContext<W>,
+                params: ListDcaParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let user_id = sender_id(&send_state)?;
+                let signer =
+                    solana_signer(&context.wallets, &user_id, params.account.as_deref()).await?;
+                let orders = context
+                    .jupiter
+                    .get_recurring_orders(&signer.pubkey(), params.history.unwrap_or(false))
+                    .await?;
+                let summaries: Vec<DcaOrderSummary> = orders
+                    .iter()
+                    .map(|order| DcaOrderSummary {
+                        order_key: order.order_key.clone(),
+                        input_mint: order.input_mint.clone(),
+                        output_mint: order.output_mint.clone(),
+                        deposited: parse_amount(&order.in_deposited),
+                        spent: parse_amount(&order.in_used),
+                        remaining: order.in_remaining(),
+                        amount_per_cycle: parse_amount(&order.in_amount_per_cycle),
+                        interval: order
+                            .cycle_frequency
+                            .parse()
+                            .map(describe_seconds)
+                            .unwrap_or_else(|_| order.cycle_frequency.clone()),
+                        received: parse_amount(&order.out_received),
+                        created_at: order.created_at.clone(),
+                    })
+                    .collect();
+                if !summaries.is_empty() {
+                    output::attach_table(dca_table(&summaries));
+                }
+                serde_json::to_string(&summaries)
+                    .map_err(|e| format!("Failed to serialize DCA orders: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_dca_orders",
+                with_client(&context, get_dca_orders),
+                None,
+            )
+            .description(
+                "List the user's Jupiter DCA orders with how much has been spent and received",
+            )
+            .parameter(
+                "history",
+                "List closed and completed orders instead of active ones",
+                "boolean",
+                false,
+            )
+            .parameter(
+                "account",
+                "Name of the user's wallet account to use, such as \"trading\"; leave out for their default account",
+                "string",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add close DCA order action
+        {
+            async fn preview_close_dca<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: JupiterContext<W>,
+                params: CloseDcaParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<CloseDcaPreview, String> {
+                let user_id = sender_id(&send_state)?;
+                let signer =
+                    solana_signer(&context.wallets, &user_id, params.account.as_deref()).await?;
+                let order = context
+                    .jupiter
+                    .get_recurring_orders(&signer.pubkey(), false)
+                    .await?
+                    .into_iter()
+                    .find(|order| order.order_key == params.order_key.trim())
+                    .ok_or_else(|| {
+                        format!("No active DCA order {} in this wallet", params.order_key)
+                    })?;
+                let (transaction, _) = context
+                    .jupiter
+                    .cancel_recurring_order(&signer.pubkey(), &order.order_key)
+                    .await?;
+                let simulation = simulation::simulate(&context.rpc, &transaction)
+                    .await?
+                    .confirmable()?;
+
+                Ok(CloseDcaPreview {
+                    user_id,
+                    wallet: signer.pubkey().to_string(),
+                    account: params.account,
+                    in_remaining: order.in_remaining(),
+                    out_received: parse_amount(&order.out_received),
+                    order_key: order.order_key,
+                    input_mint: order.input_mint,
+                    output_mint: order.output_mint,
+                    simulation,
+                })
+            }
+
+            async fn confirm_close_dca<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: JupiterContext<W>,
+                preview: CloseDcaPreview,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                check_confirmer(&send_state, &preview.user_id)?;
+                let signer = solana_signer(
+                    &context.wallets,
+                    &preview.user_id,
+                    preview.account.as_deref(),
+                )
+                .await?;
+                if signer.pubkey().to_string() != preview.wallet {
+                    return Err("The wallet changed since the preview".to_string());
+                }
+                let (transaction, request_id) = context
+                    .jupiter
+                    .cancel_recurring_order(&signer.pubkey(), &preview.order_key)
+                    .await?;
+                let transaction = signer
+                    .sign_transaction(transaction)
+                    .await
+                    .map_err(|e| format!("Failed to sign DCA cancellation: {}", e))?;
+                let response = context
+                    .jupiter
+                    .execute_recurring(&request_id, &transaction)
+                    .await?;
+
+                Ok(format!(
+                    "DCA order {} closed; about {} {} returned to the wallet. Transaction: {}",
+                    preview.order_key,
+                    preview.in_remaining,
+                    mint_label(&preview.input_mint),
+                    explorer_url(
+                        &response
+                            .signature
+                            .unwrap_or_else(|| transaction.signatures[0].to_string())
+                    )
+                ))
+            }
+
+            let action =
+                ActionBuilder::<_, CloseDcaParams, serde_json::Value, S, CloseDcaPreview, _>::new(
+                    "close_dca_order",
+                    with_client(&context, preview_close_dca),
+                    Some(with_client(&context, confirm_close_dca)),
+                )
+                .description(
+                    "Prepare closing one of the user's Jupiter DCA orders, returning the unspent amount. This does not close it by itself: the cancellation is simulated and the user is shown what is returned with a Confirm button",
+                )
+                .parameter(
+                    "order_key",
+                    "Order key from get_dca_orders",
+                    "string",
+                    true,
+                )
+                .parameter(
+                    "account",
+                    "Name of the user's wallet account to use, such as \"trading\"; leave out for their default account",
+                    "string",
+                    false,
+                )
+                .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        Self { actions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::{
+        test_support::{
+            limiter, mock_simulation, rpc_client, rpc_url, unsigned_transaction, wallet,
+        },
+        NATIVE_MINT, USDC_MINT,
+    };
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use ferrox_wallet::simple_wallet_manager::SimpleWalletManager;
+    use mockito::Matcher;
+    use solana_sdk::transaction::VersionedTransaction;
+    use tokio::sync::Mutex;
+
+    const ORDER_KEY: &str = "8s3Jh1WcJ5kSgXbKnsLdeaRhD5PX6iHqxRbhMLz7ARxG";
+
+    fn group(server: &mockito::Server) -> JupiterDcaActionGroup<()> {
+        JupiterDcaActionGroup::with_clients(
+            JupiterClient::new()
+                .with_base_url(server.url())
+                .with_rate_limiter(limiter()),
+            rpc_client(rpc_url(server)),
+            SimpleWalletManager::new(),
+            SpendingPolicy::default(),
+        )
+    }
+
+    fn action(group: &JupiterDcaActionGroup<()>, name: &str) -> Arc<FunctionAction<()>> {
+        group
+            .actions()
+            .iter()
+            .find(|action| action.definition().name == name)
+            .unwrap()
+            .clone()
+    }
+
+    async fn mock_execute(server: &mut mockito::Server, request_id: &str) -> mockito::Mock {
+        server
+            .mock("POST", "/recurring/v1/execute")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"requestId": request_id}),
+            ))
+            .with_body_from_request(|request| {
+                let body: serde_json::Value =
+                    serde_json::from_slice(request.body().unwrap()).unwrap();
+                let bytes = BASE64
+                    .decode(body["signedTransaction"].as_str().unwrap())
+                    .unwrap();
+                let transaction: VersionedTransaction = bincode::deserialize(&bytes).unwrap();
+                assert!(transaction.verify_with_results().iter().all(|ok| *ok));
+                serde_json::json!({
+                    "signature": transaction.signatures[0].to_string(),
+                    "status": "Success",
+                    "order": ORDER_KEY,
+                    "error": null
+                })
+                .to_string()
+                .into()
+            })
+            .create_async()
+            .await
+    }
+
+    fn orders_body() -> String {
+        serde_json::json!({
+            "user": "ignored",
+            "orderStatus": "active",
+            "time": [{
+                "userPubkey": "ignored",
+                "orderKey": ORDER_KEY,
+                "inputMint": USDC_MINT,
+                "outputMint": NATIVE_MINT,
+                "inDeposited": "100",
+                "inWithdrawn": "0",
+                "rawInDeposited": "100000000",
+                "cycleFrequency": "86400",
+                "inAmountPerCycle": "25",
+                "inUsed": "50",
+                "outReceived": "0.35",
+                "userClosed": false,
+                "createdAt": "2025-06-01T12:00:00",
+                "updatedAt": "2025-06-02T12:00:00",
+                "trades": []
+            }],
+            "totalPages": 1,
+            "page": 1
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("90"), Ok(90));
+        assert_eq!(parse_interval("30m"), Ok(1_800));
+        assert_eq!(parse_interval("4 hours"), Ok(14_400));
+        assert_eq!(parse_interval("1W"), Ok(604_800));
+        assert!(parse_interval("0d").is_err());
+        assert!(parse_interval("soon").is_err());
+        assert_eq!(describe_seconds(86_400), "1 day");
+        assert_eq!(describe_seconds(5_400), "90 minutes");
+        assert_eq!(describe_seconds(90), "90 seconds");
+    }
+
+    #[tokio::test]
+    async fn test_create_dca_order_after_confirmation() {
+        let mut server = mockito::Server::new_async().await;
+        let decimals = server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "getTokenSupply", "params": [USDC_MINT]}),
+            ))
+            .with_body(r#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":1},"value":{"amount":"1","decimals":6,"uiAmountString":"0.000001"}}}"#)
+            .create_async()
+            .await;
+        let wallet = wallet().await;
+        let create = server
+            .mock("POST", "/recurring/v1/createOrder")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "user": wallet.to_string(),
+                "inputMint": USDC_MINT,
+                "outputMint": NATIVE_MINT,
+                "params": {"time": {"inAmount": 100_000_000, "numberOfOrders": 4, "interval": 86_400}},
+            })))
+            .with_body(
+                serde_json::json!({
+                    "requestId": "req-1",
+                    "transaction": unsigned_transaction(&wallet)
+                })
+                .to_string(),
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let execute = mock_execute(&mut server, "req-1").await;
+        mock_simulation(&mut server, serde_json::json!([]), serde_json::json!([])).await;
+
+        let group = group(&server);
+        let create_action = action(&group, "create_dca_order");
+        let state = Arc::new(Mutex::new(()));
+        let params = serde_json::json!({
+            "input_mint": "USDC",
+            "output_mint": "SOL",
+            "total_amount": 100,
+            "amount_per_cycle": 25,
+            "interval": "1d"
+        });
+        let preview = create_action
+            .execute(
+                params,
+                serde_json::json!({"from": {"id": 42}}),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        let preview: serde_json::Value = serde_json::from_str(&preview).unwrap();
+        assert_eq!(preview["number_of_orders"], 4);
+        assert_eq!(preview["interval"], "1 day");
+        assert_eq!(preview["duration"], "3 days");
+        assert_eq!(preview["simulation"]["success"], true);
+        // Nothing is sent before the user confirms
+        assert!(!execute.matched_async().await);
+
+        let result = create_action
+            .confirm(preview, serde_json::json!({"from": {"id": 42}}), state)
+            .unwrap()
+            .await
+            .unwrap();
+        decimals.assert_async().await;
+        create.assert_async().await;
+        execute.assert_async().await;
+        assert!(
+            result.starts_with(&format!("DCA order {} created", ORDER_KEY)),
+            "{}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn test_uneven_cycles_are_rejected() {
+        let server = mockito::Server::new_async().await;
+        let error = action(&group(&server), "create_dca_order")
+            .execute(
+                serde_json::json!({
+                    "input_mint": "SOL",
+                    "output_mint": "USDC",
+                    "total_amount": 1,
+                    "amount_per_cycle": 0.3,
+                    "interval": "1h"
+                }),
+                serde_json::json!({"from": {"id": 42}}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.contains("not a whole number"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_list_and_close_dca_order() {
+        let mut server = mockito::Server::new_async().await;
+        let wallet = wallet().await;
+        server
+            .mock("GET", "/recurring/v1/getRecurringOrders")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("user".into(), wallet.to_string()),
+                Matcher::UrlEncoded("orderStatus".into(), "active".into()),
+                Matcher::UrlEncoded("recurringType".into(), "time".into()),
+            ]))
+            .with_body(orders_body())
+            .expect(2)
+            .create_async()
+            .await;
+        let cancel = server
+            .mock("POST", "/recurring/v1/cancelOrder")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "order": ORDER_KEY,
+                "user": wallet.to_string(),
+                "recurringType": "time",
+            })))
+            .with_body(
+                serde_json::json!({
+                    "requestId": "req-2",
+                    "transaction": unsigned_transaction(&wallet)
+                })
+                .to_string(),
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let execute = mock_execute(&mut server, "req-2").await;
+        mock_simulation(&mut server, serde_json::json!([]), serde_json::json!([])).await;
+
+        let group = group(&server);
+        let state = Arc::new(Mutex::new(()));
+        let sender = serde_json::json!({"from": {"id": 42}});
+        let listed = action(&group, "get_dca_orders")
+            .execute_rich(serde_json::json!({}), sender.clone(), state.clone())
+            .await
+            .unwrap();
+        let body: String = serde_json::from_str(&listed.content).unwrap();
+        let orders: Vec<DcaOrderSummary> = serde_json::from_str(&body).unwrap();
+        assert_eq!(orders[0].remaining, 50.0);
+        assert_eq!(orders[0].interval, "1 day");
+        assert_eq!(listed.tables().next().unwrap().rows.len(), 1);
+
+        let close_action = action(&group, "close_dca_order");
+        let preview = close_action
+            .execute(
+                serde_json::json!({"order_key": ORDER_KEY}),
+                sender,
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        let preview: serde_json::Value = serde_json::from_str(&preview).unwrap();
+        assert_eq!(preview["in_remaining"], 50.0);
+
+        let result = close_action
+            .confirm(preview, serde_json::json!({"from": {"id": 42}}), state)
+            .unwrap()
+            .await
+            .unwrap();
+        cancel.assert_async().await;
+        execute.assert_async().await;
+        assert!(result.contains("50 USDC returned"), "{}", result);
+    }
 }
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+/// A quote from `/quote`. Amounts are strings of base units, as Jupiter sends
+/// them, and unknown fields are kept so the quote can be posted back to
+/// `/swap` unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteResponse {
+    pub input_mint: String,
+    pub in_amount: String,
+    pub output_mint: String,
+    pub out_amount: String,
+    /// Least the swap may return after slippage.
+    pub other_amount_threshold: String,
+    pub swap_mode: String,
+    pub slippage_bps: u16,
+    pub platform_fee: Option<PlatformFee>,
+    pub price_impact_pct: String,
+    pub route_plan: Vec<RoutePlanStep>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl QuoteResponse {
+    pub fn in_amount(&self) -> u64 {
+        self.in_amount.parse().unwrap_or_default()
+    }
+
+    pub fn out_amount(&self) -> u64 {
+        self.out_amount.parse().unwrap_or_default()
+    }
+
+    pub fn min_out_amount(&self) -> u64 {
+        self.other_amount_threshold.parse().unwrap_or_default()
+    }
+
+    /// Price impact in percent (Jupiter reports a fraction).
+    pub fn price_impact_percent(&self) -> f64 {
+        self.price_impact_pct.parse::<f64>().unwrap_or_default() * 100.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlatformFee {
+    pub amount: String,
+    pub fee_bps: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutePlanStep {
+    #[serde(rename = "swapInfo")]
+    pub swap_info: SwapInfo,
+    pub percent: u8,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// One hop through an AMM.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapInfo {
+    pub amm_key: String,
+    pub label: Option<String>,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub in_amount: String,
+    pub out_amount: String,
+    pub fee_amount: Option<String>,
+    pub fee_mint: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapRequest<'a> {
+    pub quote_response: &'a QuoteResponse,
+    pub user_public_key: String,
+    pub wrap_and_unwrap_sol: bool,
+    pub dynamic_compute_unit_limit: bool,
+    pub prioritization_fee_lamports: PrioritizationFee,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrioritizationFee {
+    pub priority_level_with_max_lamports: PriorityLevel,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriorityLevel {
+    pub max_lamports: u64,
+    pub priority_level: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapResponse {
+    /// Base64 of the unsigned, bincode-serialized versioned transaction.
+    pub swap_transaction: String,
+    pub last_valid_block_height: u64,
+    pub prioritization_fee_lamports: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRecurringRequest {
+    pub user: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub params: RecurringParams,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecurringParams {
+    pub time: TimeParams,
+}
+
+/// A time-based order: `in_amount` base units split into `number_of_orders`
+/// swaps, one every `interval` seconds.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeParams {
+    pub in_amount: u64,
+    pub number_of_orders: u64,
+    pub interval: u64,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub start_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelRecurringRequest {
+    pub order: String,
+    pub user: String,
+    pub recurring_type: String,
+}
+
+/// An unsigned transaction to be signed and passed back to `/execute` with
+/// its request id.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecurringTransaction {
+    pub request_id: String,
+    pub transaction: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteRequest {
+    pub request_id: String,
+    pub signed_transaction: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteResponse {
+    pub status: String,
+    pub signature: Option<String>,
+    /// Key of the order account the transaction created or closed.
+    pub order: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecurringOrders {
+    #[serde(default)]
+    pub time: Vec<RecurringOrder>,
+}
+
+/// A time-based order. Amounts are UI amounts as strings; the `raw_` fields
+/// hold base units.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecurringOrder {
+    pub order_key: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub in_deposited: String,
+    pub in_used: String,
+    #[serde(default)]
+    pub in_withdrawn: String,
+    pub out_received: String,
+    pub in_amount_per_cycle: String,
+    /// Seconds between cycles.
+    pub cycle_frequency: String,
+    #[serde(default)]
+    pub user_closed: bool,
+    pub created_at: String,
+}
+
+impl RecurringOrder {
+    /// Input not yet swapped or withdrawn, which closing the order returns.
+    pub fn in_remaining(&self) -> f64 {
+        let amount = |value: &str| value.parse::<f64>().unwrap_or_default();
+        (amount(&self.in_deposited) - amount(&self.in_used) - amount(&self.in_withdrawn)).max(0.0)
+    }
+}
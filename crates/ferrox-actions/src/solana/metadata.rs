@@ -0,0 +1,82 @@
+//! Token names and symbols from Metaplex metadata accounts.
+
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+pub const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzJb6KiS6WHhE8A");
+
+/// Metadata a mint's creator published for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+impl TokenMetadata {
+    /// Reads the name, symbol and URI from a metadata account: a key byte,
+    /// the update authority and mint, then three length-prefixed strings the
+    /// program pads with NULs.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut offset = 1 + 32 + 32;
+        let mut string = || -> Result<String, String> {
+            let length = data
+                .get(offset..offset + 4)
+                .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+                .ok_or_else(|| "Truncated token metadata".to_string())?;
+            let bytes = data
+                .get(offset + 4..offset + 4 + length)
+                .ok_or_else(|| "Truncated token metadata".to_string())?;
+            offset += 4 + length;
+            Ok(String::from_utf8_lossy(bytes)
+                .trim_end_matches('\0')
+                .trim()
+                .to_string())
+        };
+        Ok(Self {
+            name: string()?,
+            symbol: string()?,
+            uri: string()?,
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn encode_metadata(mint: &Pubkey, name: &str, symbol: &str) -> Vec<u8> {
+    let mut data = vec![4];
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(mint.as_ref());
+    for (value, padded) in [
+        (name, 32),
+        (symbol, 10),
+        ("https://example.com/meta.json", 200),
+    ] {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.resize(padded, 0);
+        data.extend_from_slice(&(padded as u32).to_le_bytes());
+        data.extend_from_slice(&bytes);
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_padded_metadata() {
+        let mint = Pubkey::new_unique();
+        let metadata = TokenMetadata::parse(&encode_metadata(&mint, "Bonk", "BONK")).unwrap();
+        assert_eq!(metadata.name, "Bonk");
+        assert_eq!(metadata.symbol, "BONK");
+        assert_eq!(metadata.uri, "https://example.com/meta.json");
+        assert!(TokenMetadata::parse(&[4; 70]).is_err());
+    }
+}
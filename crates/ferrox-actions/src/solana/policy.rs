@@ -0,0 +1,571 @@
+//! Spending limits in front of every action that signs with a user's wallet.
+//!
+//! A [`SpendingPolicy`] holds the limits of a [`PolicyConfig`]: daily caps
+//! per user over a rolling 24 hours, per-transaction maximums, recipient
+//! allow and deny lists, denied tokens and a cooldown between transactions.
+//! Previews list what a transaction would break as `violations`, and the
+//! confirm handlers refuse it through [`SpendingPolicy::approve`], which also
+//! counts the spend once the transaction is sent.
+//!
+//! Every group of wallet-signing actions is built with the policy it
+//! enforces. Give them clones of one policy so daily limits add up across
+//! them; [`SpendingPolicy::default`] limits nothing. The policy can be
+//! [`reload`](SpendingPolicy::reload)ed while the bot runs.
+//!
+//! Limits are in SOL or USD. SOL counts at face value, as do USDC and USDT
+//! in dollars; the two are converted with the SOL price of the config or
+//! [`set_sol_price`](SpendingPolicy::set_sol_price). Other tokens only count
+//! through the SOL or stablecoin side of the trade they are in; spends with
+//! no such side are refused while any amount limit is set.
+
+use crate::solana::{mint_label, parse_mint, NATIVE_MINT, USDC_MINT, USDT_MINT};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The window daily limits are counted over.
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// The limits, as read from a JSON policy file. Every limit is off when left
+/// out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolicyConfig {
+    /// Most a user may spend in 24 hours, in SOL.
+    pub daily_limit_sol: Option<f64>,
+    /// Most a user may spend in 24 hours, in USD.
+    pub daily_limit_usd: Option<f64>,
+    pub max_per_transaction_sol: Option<f64>,
+    pub max_per_transaction_usd: Option<f64>,
+    /// When not empty, transfers may only go to these addresses.
+    pub allowed_recipients: Vec<String>,
+    pub denied_recipients: Vec<String>,
+    /// Mints, or SOL, USDC and USDT, that may not be sent, sold or bought.
+    pub denied_tokens: Vec<String>,
+    /// Seconds a user has to wait after one transaction before the next.
+    pub cooldown_secs: u64,
+    /// USD per SOL, to hold SOL against USD limits and stablecoins against
+    /// SOL limits.
+    pub sol_price_usd: Option<f64>,
+}
+
+/// The config with its addresses parsed, so lookups compare canonical
+/// base58.
+#[derive(Debug, Default)]
+struct Rules {
+    config: PolicyConfig,
+    allowed_recipients: HashSet<String>,
+    denied_recipients: HashSet<String>,
+    denied_tokens: HashSet<String>,
+}
+
+impl Rules {
+    fn new(config: PolicyConfig) -> Result<Self, String> {
+        let recipients = |addresses: &[String]| {
+            addresses
+                .iter()
+                .map(|address| {
+                    Pubkey::from_str(address.trim())
+                        .map(|address| address.to_string())
+                        .map_err(|e| format!("Invalid address '{}' in policy: {}", address, e))
+                })
+                .collect::<Result<HashSet<_>, _>>()
+        };
+        let limits = [
+            config.daily_limit_sol,
+            config.daily_limit_usd,
+            config.max_per_transaction_sol,
+            config.max_per_transaction_usd,
+            config.sol_price_usd,
+        ];
+        if limits
+            .iter()
+            .flatten()
+            .any(|limit| limit.is_nan() || *limit < 0.0)
+        {
+            return Err("Policy limits and prices cannot be negative".to_string());
+        }
+        Ok(Self {
+            allowed_recipients: recipients(&config.allowed_recipients)?,
+            denied_recipients: recipients(&config.denied_recipients)?,
+            denied_tokens: config
+                .denied_tokens
+                .iter()
+                .map(|mint| parse_mint(mint).map(|mint| mint.to_string()))
+                .collect::<Result<_, _>>()?,
+            config,
+        })
+    }
+}
+
+/// What a transaction takes out of the user's wallet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Spend {
+    pub user_id: String,
+    pub sol: f64,
+    /// USDC and USDT, in dollars.
+    pub usd: f64,
+    /// Who receives it, for transfers.
+    pub recipient: Option<String>,
+    /// Mints the transaction sends, sells or buys.
+    pub tokens: Vec<String>,
+    /// Mints added with an amount the policy has no price for.
+    #[serde(default)]
+    pub unvalued: Vec<String>,
+}
+
+impl Spend {
+    pub fn new(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            sol: 0.0,
+            usd: 0.0,
+            recipient: None,
+            tokens: Vec::new(),
+            unvalued: Vec::new(),
+        }
+    }
+
+    pub fn to(mut self, recipient: &str) -> Self {
+        self.recipient = Some(recipient.to_string());
+        self
+    }
+
+    /// Adds `mint` to the tokens the transaction involves.
+    pub fn token(mut self, mint: &str) -> Self {
+        if !self.tokens.iter().any(|token| token == mint) {
+            self.tokens.push(mint.to_string());
+        }
+        self
+    }
+
+    /// Adds `amount` of `mint`, counting it if it is SOL, USDC or USDT.
+    pub fn amount(mut self, mint: &str, amount: f64) -> Self {
+        match mint {
+            NATIVE_MINT => self.sol += amount,
+            USDC_MINT | USDT_MINT => self.usd += amount,
+            _ if amount > 0.0 && !self.unvalued.iter().any(|token| token == mint) => {
+                self.unvalued.push(mint.to_string())
+            }
+            _ => {}
+        }
+        self.token(mint)
+    }
+
+    /// Whether any of the spend is in SOL or stablecoins.
+    pub fn is_valued(&self) -> bool {
+        self.sol > 0.0 || self.usd > 0.0
+    }
+
+    /// The spend in SOL, if the stablecoins in it can be converted.
+    fn in_sol(&self, sol_price: Option<f64>) -> Option<f64> {
+        match sol_price {
+            _ if self.usd == 0.0 => Some(self.sol),
+            Some(price) if price > 0.0 => Some(self.sol + self.usd / price),
+            _ => None,
+        }
+    }
+
+    /// The spend in USD, if the SOL in it can be converted.
+    fn in_usd(&self, sol_price: Option<f64>) -> Option<f64> {
+        match sol_price {
+            _ if self.sol == 0.0 => Some(self.usd),
+            Some(price) => Some(self.usd + self.sol * price),
+            None => None,
+        }
+    }
+}
+
+/// A spend counted toward its user's limits.
+#[derive(Debug, Clone)]
+struct Entry {
+    id: u64,
+    at: u64,
+    sol: f64,
+    usd: f64,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    rules: RwLock<Rules>,
+    /// Overrides the price of the config when set.
+    sol_price: RwLock<Option<f64>>,
+    ledger: Mutex<HashMap<String, Vec<Entry>>>,
+    next_id: AtomicU64,
+}
+
+/// Limits on what users spend from their wallets, shared by every action
+/// through clones.
+#[derive(Debug, Clone, Default)]
+pub struct SpendingPolicy {
+    inner: Arc<Inner>,
+}
+
+impl SpendingPolicy {
+    pub fn new(config: PolicyConfig) -> Result<Self, String> {
+        let policy = Self::default();
+        policy.reload(config)?;
+        Ok(policy)
+    }
+
+    /// Reads the config from the JSON file at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        Self::new(read_config(path.as_ref())?)
+    }
+
+    /// Replaces the limits. What users spent so far keeps counting against
+    /// the new ones. An invalid config leaves the old one in place.
+    pub fn reload(&self, config: PolicyConfig) -> Result<(), String> {
+        let rules = Rules::new(config)?;
+        *self.inner.rules.write().unwrap() = rules;
+        Ok(())
+    }
+
+    pub fn reload_from_file(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        self.reload(read_config(path.as_ref())?)
+    }
+
+    pub fn config(&self) -> PolicyConfig {
+        self.inner.rules.read().unwrap().config.clone()
+    }
+
+    /// Values SOL at `price` USD from now on, instead of the price in the
+    /// config.
+    pub fn set_sol_price(&self, price: f64) {
+        *self.inner.sol_price.write().unwrap() = Some(price).filter(|price| *price > 0.0);
+    }
+
+    /// What `spend` would break, as messages for the user. Empty when it is
+    /// allowed.
+    pub fn check(&self, spend: &Spend) -> Vec<String> {
+        self.check_at(spend, now_secs())
+    }
+
+    /// Refuses `spend` if it breaks the policy, and otherwise counts it until
+    /// the returned approval is dropped without being
+    /// [`commit`](Approval::commit)ted. Counting it up front keeps two
+    /// confirmations at once from both fitting under a limit.
+    pub fn approve(&self, spend: &Spend) -> Result<Approval, String> {
+        self.approve_at(spend, now_secs())
+    }
+
+    fn approve_at(&self, spend: &Spend, now: u64) -> Result<Approval, String> {
+        let mut ledger = self.inner.ledger.lock().unwrap();
+        let violations = self.violations(spend, ledger.get(&spend.user_id), now);
+        if !violations.is_empty() {
+            return Err(format!(
+                "Refused by the spending policy: {}",
+                violations.join("; ")
+            ));
+        }
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let kept = self.kept_secs();
+        let entries = ledger.entry(spend.user_id.clone()).or_default();
+        entries.retain(|entry| entry.at + kept > now);
+        entries.push(Entry {
+            id,
+            at: now,
+            sol: spend.sol,
+            usd: spend.usd,
+        });
+        Ok(Approval {
+            reservation: Some((self.clone(), spend.user_id.clone(), id)),
+        })
+    }
+
+    fn check_at(&self, spend: &Spend, now: u64) -> Vec<String> {
+        let ledger = self.inner.ledger.lock().unwrap();
+        self.violations(spend, ledger.get(&spend.user_id), now)
+    }
+
+    /// How long spends matter for: a day, or the cooldown if longer.
+    fn kept_secs(&self) -> u64 {
+        DAY_SECS.max(self.inner.rules.read().unwrap().config.cooldown_secs)
+    }
+
+    fn sol_price(&self, rules: &Rules) -> Option<f64> {
+        self.inner
+            .sol_price
+            .read()
+            .unwrap()
+            .or(rules.config.sol_price_usd)
+    }
+
+    fn violations(&self, spend: &Spend, history: Option<&Vec<Entry>>, now: u64) -> Vec<String> {
+        let rules = self.inner.rules.read().unwrap();
+        let config = &rules.config;
+        let price = self.sol_price(&rules);
+        let mut violations = Vec::new();
+
+        if let Some(recipient) = &spend.recipient {
+            if rules.denied_recipients.contains(recipient) {
+                violations.push(format!("{} is on the list of denied recipients", recipient));
+            } else if !rules.allowed_recipients.is_empty()
+                && !rules.allowed_recipients.contains(recipient)
+            {
+                violations.push(format!("{} is not an allowed recipient", recipient));
+            }
+        }
+        for token in &spend.tokens {
+            if rules.denied_tokens.contains(token) {
+                violations.push(format!(
+                    "{} is on the list of denied tokens",
+                    mint_label(token)
+                ));
+            }
+        }
+
+        let history: Vec<&Entry> = history
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.at + DAY_SECS.max(config.cooldown_secs) > now)
+            .collect();
+        if let Some(last) = history.iter().map(|entry| entry.at).max() {
+            let ready = last + config.cooldown_secs;
+            if ready > now {
+                violations.push(format!(
+                    "Wait {} more seconds before the next transaction",
+                    ready - now
+                ));
+            }
+        }
+
+        let today = history
+            .iter()
+            .filter(|entry| entry.at + DAY_SECS > now)
+            .fold(Spend::new(&spend.user_id), |mut total, entry| {
+                total.sol += entry.sol;
+                total.usd += entry.usd;
+                total
+            });
+        let has_limits = [
+            config.daily_limit_sol,
+            config.daily_limit_usd,
+            config.max_per_transaction_sol,
+            config.max_per_transaction_usd,
+        ]
+        .iter()
+        .any(Option::is_some);
+        if has_limits && !spend.is_valued() {
+            // Nothing to hold against the limits, which would let any
+            // amount through
+            violations.extend(spend.unvalued.iter().map(|token| {
+                format!(
+                    "{} has no price to check against the spending limits",
+                    mint_label(token)
+                )
+            }));
+        } else if spend.is_valued() {
+            let (sol, usd) = (spend.in_sol(price), spend.in_usd(price));
+            violations.extend(
+                [
+                    over_limit(sol, Some(0.0), config.max_per_transaction_sol, "SOL", false),
+                    over_limit(usd, Some(0.0), config.max_per_transaction_usd, "USD", false),
+                    over_limit(
+                        sol,
+                        today.in_sol(price),
+                        config.daily_limit_sol,
+                        "SOL",
+                        true,
+                    ),
+                    over_limit(
+                        usd,
+                        today.in_usd(price),
+                        config.daily_limit_usd,
+                        "USD",
+                        true,
+                    ),
+                ]
+                .into_iter()
+                .flatten(),
+            );
+        }
+        violations
+    }
+
+    fn release(&self, user_id: &str, id: u64) {
+        let mut ledger = self.inner.ledger.lock().unwrap();
+        if let Some(entries) = ledger.get_mut(user_id) {
+            entries.retain(|entry| entry.id != id);
+        }
+    }
+}
+
+/// A spend the policy allowed. Dropping it uncounts the spend, so a
+/// transaction that is never sent does not use up the user's limits.
+#[must_use = "the spend is uncounted when the approval is dropped"]
+pub struct Approval {
+    reservation: Option<(SpendingPolicy, String, u64)>,
+}
+
+impl Approval {
+    /// Keeps the spend counted, once its transaction was sent and may land.
+    pub fn commit(mut self) {
+        self.reservation = None;
+    }
+}
+
+impl Drop for Approval {
+    fn drop(&mut self) {
+        if let Some((policy, user_id, id)) = self.reservation.take() {
+            policy.release(&user_id, id);
+        }
+    }
+}
+
+/// Why spending `amount` on top of `spent` breaks the limit `max` in `unit`,
+/// if it does. The amounts are `None` when they cannot be converted to `unit`.
+fn over_limit(
+    amount: Option<f64>,
+    spent: Option<f64>,
+    max: Option<f64>,
+    unit: &str,
+    daily: bool,
+) -> Option<String> {
+    let max = max?;
+    let Some((amount, spent)) = amount.zip(spent) else {
+        return Some(format!(
+            "The {} limit cannot be checked without a SOL price",
+            unit
+        ));
+    };
+    let total = round(spent + amount);
+    if total <= max {
+        None
+    } else if daily {
+        Some(format!(
+            "This brings the last 24 hours' spending to {} {}, above the daily limit of {} {}",
+            total, unit, max, unit
+        ))
+    } else {
+        Some(format!(
+            "The transaction is worth {} {}, above the limit of {} {} per transaction",
+            total, unit, max, unit
+        ))
+    }
+}
+
+fn read_config(path: &Path) -> Result<PolicyConfig, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read policy {}: {}", path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid policy {}: {}", path.display(), e))
+}
+
+fn round(amount: f64) -> f64 {
+    (amount * 1e6).round() / 1e6
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRIEND: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+    const STRANGER: &str = "4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T";
+    const BONK: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+    const WIF: &str = "EKpQGSJtjMFqKZ9KQanSqYXRcF8fBopzLHYxdM65zcjm";
+
+    fn policy() -> SpendingPolicy {
+        SpendingPolicy::new(
+            serde_json::from_value(serde_json::json!({
+                "daily_limit_sol": 2,
+                "max_per_transaction_usd": 200,
+                "allowed_recipients": [FRIEND],
+                "denied_tokens": [BONK],
+                "cooldown_secs": 60,
+                "sol_price_usd": 150
+            }))
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_violations() {
+        let policy = policy();
+        let send = |amount: f64| Spend::new("42").to(FRIEND).amount(NATIVE_MINT, amount);
+        assert!(policy.check_at(&send(1.0), 0).is_empty());
+        assert_eq!(
+            policy.check_at(&send(1.5), 0),
+            ["The transaction is worth 225 USD, above the limit of 200 USD per transaction"]
+        );
+        assert_eq!(
+            policy.check_at(&Spend::new("42").to(STRANGER).amount(USDC_MINT, 50.0), 0),
+            [format!("{} is not an allowed recipient", STRANGER)]
+        );
+        // Selling BONK for SOL is valued by the SOL side
+        let sell = Spend::new("42").token(BONK).amount(NATIVE_MINT, 0.5);
+        assert_eq!(
+            policy.check_at(&sell, 0),
+            ["DezX…B263 is on the list of denied tokens"]
+        );
+        // Sending a token without a price would slip past every limit
+        let send_wif = Spend::new("42").to(FRIEND).amount(WIF, 1_000_000.0);
+        assert_eq!(
+            policy.check_at(&send_wif, 0),
+            ["EKpQ…zcjm has no price to check against the spending limits"]
+        );
+        let swap_wif = send_wif.amount(USDC_MINT, 100.0);
+        assert!(policy.check_at(&swap_wif, 0).is_empty());
+
+        assert!(SpendingPolicy::new(PolicyConfig {
+            denied_recipients: vec!["not-an-address".to_string()],
+            ..PolicyConfig::default()
+        })
+        .is_err());
+        // Without a price, USD limits cannot hold SOL
+        policy
+            .reload(PolicyConfig {
+                max_per_transaction_usd: Some(200.0),
+                ..PolicyConfig::default()
+            })
+            .unwrap();
+        assert_eq!(
+            policy.check_at(&send(0.1), 0),
+            ["The USD limit cannot be checked without a SOL price"]
+        );
+        policy.set_sol_price(100.0);
+        assert!(policy.check_at(&send(1.5), 0).is_empty());
+    }
+
+    #[test]
+    fn test_daily_limit_and_cooldown() {
+        let policy = policy();
+        let send = |amount: f64| Spend::new("42").to(FRIEND).amount(NATIVE_MINT, amount);
+
+        policy.approve_at(&send(1.2), 0).unwrap().commit();
+        assert_eq!(
+            policy.approve_at(&send(0.1), 30).err().unwrap(),
+            "Refused by the spending policy: Wait 30 more seconds before the next transaction"
+        );
+        // Other users have their own limits
+        assert!(policy
+            .check_at(&Spend::new("7").to(FRIEND).amount(NATIVE_MINT, 1.2), 30)
+            .is_empty());
+        assert_eq!(
+            policy.check_at(&send(1.0), 60),
+            ["This brings the last 24 hours' spending to 2.2 SOL, above the daily limit of 2 SOL"]
+        );
+
+        // A transaction that did not land does not count
+        drop(policy.approve_at(&send(0.8), 60).unwrap());
+        policy.approve_at(&send(0.8), 120).unwrap().commit();
+        assert!(!policy.check_at(&send(0.1), 1_000).is_empty());
+        assert!(policy.check_at(&send(1.2), DAY_SECS + 120).is_empty());
+    }
+}
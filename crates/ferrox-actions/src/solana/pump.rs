@@ -0,0 +1,1249 @@
+pub mod client;
+pub mod curve;
+pub mod instructions;
+pub mod models;
+
+use crate::{
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
+    output::{self, Table, UiHint},
+    solana::{
+        check_slippage, explorer_url, parse_mint,
+        policy::{Approval, Spend, SpendingPolicy},
+        rpc::client::SolanaRpcClient,
+        simulation::SimulationPreview,
+        submit::TransactionSubmitter,
+        to_base_units, to_ui_amount,
+        token::{
+            associated_token_address, create_associated_token_account_idempotent, is_token_program,
+            token_account_amount,
+        },
+        wallet::{check_confirmer, sender_id, solana_signer},
+        NATIVE_MINT,
+    },
+    AgentState,
+};
+use client::PumpClient;
+use curve::{bonding_curve_address, BondingCurve, TOKEN_DECIMALS, TOKEN_TOTAL_SUPPLY};
+use ferrox_wallet::{WalletManager, WalletSigner};
+use instructions::TradeAccounts;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signature};
+use std::sync::Arc;
+
+/// Bonding curves move fast; 5% keeps most trades from failing.
+const DEFAULT_SLIPPAGE_BPS: u16 = 500;
+const HIGH_PRICE_IMPACT_PERCENT: f64 = 5.0;
+const DEFAULT_LAUNCHES: u32 = 20;
+const MAX_LAUNCHES: u32 = 50;
+/// How many of a creator's launches their track record is judged on.
+const CREATOR_HISTORY: u32 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct LaunchesParams {
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MintParams {
+    mint: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuyParams {
+    mint: String,
+    sol_amount: f64,
+    slippage_bps: Option<u16>,
+    account: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SellParams {
+    mint: String,
+    token_amount: f64,
+    slippage_bps: Option<u16>,
+    account: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchSummary {
+    pub mint: String,
+    pub name: String,
+    pub symbol: String,
+    pub creator: String,
+    pub age_minutes: i64,
+    pub market_cap_usd: Option<f64>,
+    pub progress_percent: f64,
+    pub complete: bool,
+    pub replies: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurveSummary {
+    pub mint: String,
+    pub creator: Option<String>,
+    pub price_sol: f64,
+    pub market_cap_sol: f64,
+    pub progress_percent: f64,
+    pub sol_in_curve: f64,
+    /// SOL that buying out the rest of the curve would take, before fees.
+    pub sol_to_complete: f64,
+    pub tokens_left: f64,
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HolderStats {
+    /// Share of the supply held by the ten largest accounts other than the
+    /// bonding curve.
+    pub top_10_percent: f64,
+    pub largest_holder_percent: f64,
+    pub bonding_curve_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenStats {
+    pub mint: String,
+    pub name: String,
+    pub symbol: String,
+    pub creator: String,
+    pub age_minutes: i64,
+    pub market_cap_usd: Option<f64>,
+    pub progress_percent: f64,
+    pub complete: bool,
+    pub replies: Option<u64>,
+    /// Launches by the same creator among their most recent ones, this one
+    /// included.
+    pub creator_launches: usize,
+    /// How many of those launches completed their curve.
+    pub creator_graduated: usize,
+    pub holders: HolderStats,
+    pub website: Option<String>,
+    pub twitter: Option<String>,
+    pub telegram: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PumpSide {
+    Buy,
+    Sell,
+}
+
+/// What the user confirms. For buys `sol_amount` is spent and `sol_limit`
+/// is the most the trade may cost; for sells `sol_amount` is expected back
+/// and `sol_limit` the least accepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PumpTradePreview {
+    pub user_id: String,
+    pub wallet: String,
+    /// Name of the wallet account the user picked; `None` for their default.
+    pub account: Option<String>,
+    pub mint: String,
+    pub side: PumpSide,
+    pub sol_amount: f64,
+    pub sol_lamports: u64,
+    pub token_amount: f64,
+    pub token_units: u64,
+    pub sol_limit: f64,
+    pub sol_limit_lamports: u64,
+    pub fee_sol: f64,
+    pub price_impact_percent: f64,
+    pub slippage_bps: u16,
+    pub warnings: Vec<String>,
+    /// What the spending policy refuses about the trade; it cannot be
+    /// confirmed while there are any.
+    #[serde(default)]
+    pub violations: Vec<String>,
+    pub simulation: SimulationPreview,
+}
+
+impl PumpTradePreview {
+    fn new(
+        user_id: String,
+        wallet: &Pubkey,
+        mint: &Pubkey,
+        side: PumpSide,
+        quote: curve::TradeQuote,
+        slippage_bps: u16,
+        simulation: SimulationPreview,
+    ) -> Self {
+        let mut warnings = Vec::new();
+        if quote.price_impact_percent > HIGH_PRICE_IMPACT_PERCENT {
+            warnings.push(format!(
+                "High price impact: {:.2}%",
+                quote.price_impact_percent
+            ));
+        }
+        Self {
+            user_id,
+            wallet: wallet.to_string(),
+            account: None,
+            mint: mint.to_string(),
+            side,
+            sol_amount: to_ui_amount(quote.sol_lamports, 9),
+            sol_lamports: quote.sol_lamports,
+            token_amount: to_ui_amount(quote.token_units, TOKEN_DECIMALS),
+            token_units: quote.token_units,
+            sol_limit: to_ui_amount(quote.sol_limit_lamports, 9),
+            sol_limit_lamports: quote.sol_limit_lamports,
+            fee_sol: to_ui_amount(quote.fee_lamports, 9),
+            price_impact_percent: quote.price_impact_percent,
+            slippage_bps,
+            warnings,
+            violations: Vec::new(),
+            simulation,
+        }
+    }
+
+    /// Both sides are valued in SOL: what a buy spends or a sell takes in.
+    fn spend(&self) -> Spend {
+        Spend::new(&self.user_id)
+            .token(&self.mint)
+            .amount(NATIVE_MINT, self.sol_amount)
+    }
+}
+
+fn age_minutes(created_timestamp_ms: i64) -> i64 {
+    (chrono::Utc::now().timestamp_millis() - created_timestamp_ms) / 60_000
+}
+
+fn launches_table(launches: &[LaunchSummary]) -> Table {
+    launches.iter().fold(
+        Table::new(["Token", "Age", "Market cap", "Curve", "Replies"])
+            .title("New Pump.fun launches"),
+        |table, launch| {
+            table.row([
+                launch.symbol.clone(),
+                format!("{}m", launch.age_minutes),
+                launch
+                    .market_cap_usd
+                    .map(|cap| format!("${:.0}", cap))
+                    .unwrap_or_default(),
+                format!("{:.1}%", launch.progress_percent),
+                launch.replies.unwrap_or_default().to_string(),
+            ])
+        },
+    )
+}
+
+/// Refuses trades moving more SOL than the configured limit.
+fn check_trade_limit(lamports: u64, max_sol: f64) -> Result<(), String> {
+    let sol = to_ui_amount(lamports, 9);
+    if sol > max_sol {
+        return Err(format!(
+            "A trade of up to {} SOL is above the {} SOL per-trade limit",
+            sol, max_sol
+        ));
+    }
+    Ok(())
+}
+
+/// Everything the Pump.fun actions need, cloned into each invocation.
+#[derive(Clone)]
+struct PumpContext<W> {
+    pump: PumpClient,
+    rpc: SolanaRpcClient,
+    submitter: TransactionSubmitter,
+    wallets: W,
+    policy: SpendingPolicy,
+}
+
+impl<W> PumpContext<W> {
+    async fn curve(&self, mint: &Pubkey) -> Result<BondingCurve, String> {
+        let account = self
+            .rpc
+            .get_account_info(&bonding_curve_address(mint))
+            .await?
+            .ok_or_else(|| format!("{} is not a Pump.fun token", mint))?;
+        BondingCurve::parse(&account.data)
+    }
+
+    /// The token program that owns `mint`.
+    async fn token_program(&self, mint: &Pubkey) -> Result<Pubkey, String> {
+        let account = self
+            .rpc
+            .get_account_info(mint)
+            .await?
+            .ok_or_else(|| format!("Mint {} does not exist", mint))?;
+        if !is_token_program(&account.owner) {
+            return Err(format!("{} is not a token mint", mint));
+        }
+        Ok(account.owner)
+    }
+
+    /// Tokens of `mint` in the associated account of `owner`.
+    async fn token_balance(
+        &self,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<u64, String> {
+        match self
+            .rpc
+            .get_account_info(&associated_token_address(owner, mint, token_program))
+            .await?
+        {
+            Some(account) => token_account_amount(&account.data),
+            None => Ok(0),
+        }
+    }
+
+    async fn trade_accounts(
+        &self,
+        mint: &Pubkey,
+        user: &Pubkey,
+        curve: &BondingCurve,
+    ) -> Result<TradeAccounts, String> {
+        let creator = curve.creator.ok_or_else(|| {
+            "This bonding curve does not record its creator and cannot be traded".to_string()
+        })?;
+        let token_program = self.token_program(mint).await?;
+        Ok(TradeAccounts::new(mint, user, &creator, &token_program))
+    }
+
+    /// Creates the user's token account if needed and buys `token_units`
+    /// for at most `sol_limit_lamports`.
+    async fn buy_instructions(
+        &self,
+        mint: &Pubkey,
+        user: &Pubkey,
+        curve: &BondingCurve,
+        token_units: u64,
+        sol_limit_lamports: u64,
+    ) -> Result<Vec<Instruction>, String> {
+        let accounts = self.trade_accounts(mint, user, curve).await?;
+        Ok(vec![
+            create_associated_token_account_idempotent(
+                &accounts.user,
+                &accounts.user,
+                mint,
+                &accounts.token_program,
+            ),
+            instructions::buy(&accounts, token_units, sol_limit_lamports),
+        ])
+    }
+
+    /// Sells `token_units` for at least `sol_limit_lamports`.
+    async fn sell_instructions(
+        &self,
+        mint: &Pubkey,
+        user: &Pubkey,
+        curve: &BondingCurve,
+        token_units: u64,
+        sol_limit_lamports: u64,
+    ) -> Result<Vec<Instruction>, String> {
+        let accounts = self.trade_accounts(mint, user, curve).await?;
+        Ok(vec![instructions::sell(
+            &accounts,
+            token_units,
+            sol_limit_lamports,
+        )])
+    }
+
+    /// Simulates `instructions` for the preview, refusing trades that would
+    /// fail.
+    async fn simulate(
+        &self,
+        user: &Pubkey,
+        instructions: &[Instruction],
+    ) -> Result<SimulationPreview, String> {
+        self.submitter
+            .preview(user, instructions)
+            .await?
+            .confirmable()
+    }
+
+    /// Submits a trade, keeping its spend counted once it was sent unless
+    /// it is known not to have moved anything.
+    async fn submit(
+        &self,
+        signer: &dyn WalletSigner,
+        instructions: &[Instruction],
+        approval: Approval,
+    ) -> Result<Signature, String> {
+        let outcome = self.submitter.submit(signer, instructions).await?;
+        if outcome.may_have_landed() {
+            approval.commit();
+        }
+        outcome.into_result()
+    }
+}
+
+pub struct PumpActionGroup<S: Send + Sync + Clone + 'static> {
+    actions: Vec<Arc<FunctionAction<S>>>,
+}
+
+impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for PumpActionGroup<S> {
+    fn actions(&self) -> &[Arc<FunctionAction<S>>] {
+        &self.actions
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> PumpActionGroup<S> {
+    /// Builds the group with clients configured from the environment (see
+    /// [`PumpClient::from_env`] for the per-trade limit), trading from the
+    /// wallets in `wallets` within the limits of `policy`.
+    pub fn new<W: WalletManager + 'static>(wallets: W, policy: SpendingPolicy) -> Self {
+        Self::with_clients(
+            PumpClient::from_env(),
+            SolanaRpcClient::from_env(),
+            wallets,
+            policy,
+        )
+    }
+
+    pub fn with_clients<W: WalletManager + 'static>(
+        pump: PumpClient,
+        rpc: SolanaRpcClient,
+        wallets: W,
+        policy: SpendingPolicy,
+    ) -> Self {
+        let context = PumpContext {
+            pump,
+            submitter: TransactionSubmitter::new(rpc.clone()),
+            rpc,
+            wallets,
+            policy,
+        };
+        let mut actions = Vec::new();
+
+        // Add new launches action
+        {
+            async fn get_pump_launches<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: PumpContext<W>,
+                params: LaunchesParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let limit = params
+                    .limit
+                    .unwrap_or(DEFAULT_LAUNCHES)
+                    .clamp(1, MAX_LAUNCHES);
+                let launches: Vec<LaunchSummary> = context
+                    .pump
+                    .get_latest_coins(limit)
+                    .await?
+                    .into_iter()
+                    .map(|coin| LaunchSummary {
+                        age_minutes: age_minutes(coin.created_timestamp),
+                        progress_percent: coin.progress_percent(),
+                        market_cap_usd: coin.usd_market_cap,
+                        complete: coin.complete,
+                        replies: coin.reply_count,
+                        mint: coin.mint,
+                        name: coin.name,
+                        symbol: coin.symbol,
+                        creator: coin.creator,
+                    })
+                    .collect();
+                if !launches.is_empty() {
+                    output::attach_table(launches_table(&launches));
+                }
+                serde_json::to_string(&launches)
+                    .map_err(|e| format!("Failed to serialize launches: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_pump_launches",
+                with_client(&context, get_pump_launches),
+                None,
+            )
+            .description("Get the newest tokens launched on Pump.fun with their market cap and bonding-curve progress")
+            .parameter(
+                "limit",
+                "How many launches to return (default 20, at most 50)",
+                "integer",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add bonding curve action
+        {
+            async fn get_pump_curve<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: PumpContext<W>,
+                params: MintParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let mint = parse_mint(&params.mint)?;
+                let curve = context.curve(&mint).await?;
+                let summary = CurveSummary {
+                    mint: mint.to_string(),
+                    creator: curve.creator.map(|creator| creator.to_string()),
+                    price_sol: curve.price_sol(),
+                    market_cap_sol: curve.market_cap_sol(),
+                    progress_percent: curve.progress_percent(),
+                    sol_in_curve: to_ui_amount(curve.real_sol_reserves, 9),
+                    sol_to_complete: to_ui_amount(curve.lamports_to_complete(), 9),
+                    tokens_left: to_ui_amount(curve.real_token_reserves, TOKEN_DECIMALS),
+                    complete: curve.complete,
+                };
+                output::hint(UiHint::link(
+                    "Open on Pump.fun",
+                    format!("https://pump.fun/coin/{}", mint),
+                ));
+                serde_json::to_string(&summary)
+                    .map_err(|e| format!("Failed to serialize bonding curve: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_pump_curve",
+                with_client(&context, get_pump_curve),
+                None,
+            )
+            .description("Read a Pump.fun token's bonding curve on chain: price, market cap, progress towards graduation and the SOL needed to complete it")
+            .parameter("mint", "Mint address of the Pump.fun token", "string", true)
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add token stats action
+        {
+            async fn get_pump_token_stats<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: PumpContext<W>,
+                params: MintParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let mint = parse_mint(&params.mint)?;
+                let coin = context.pump.get_coin(&mint.to_string()).await?;
+                let created = context
+                    .pump
+                    .get_created_coins(&coin.creator, CREATOR_HISTORY)
+                    .await?;
+
+                let token_program = context.token_program(&mint).await?;
+                let curve_account =
+                    associated_token_address(&bonding_curve_address(&mint), &mint, &token_program)
+                        .to_string();
+                let accounts = context.rpc.get_token_largest_accounts(&mint).await?;
+                let share = |units: u64| units as f64 / TOKEN_TOTAL_SUPPLY as f64 * 100.0;
+                let (curve_holdings, holders): (Vec<_>, Vec<_>) = accounts
+                    .iter()
+                    .partition(|account| account.address == curve_account);
+                let holders = HolderStats {
+                    top_10_percent: share(
+                        holders
+                            .iter()
+                            .take(10)
+                            .map(|account| account.amount.units())
+                            .sum(),
+                    ),
+                    largest_holder_percent: holders
+                        .first()
+                        .map(|account| share(account.amount.units()))
+                        .unwrap_or_default(),
+                    bonding_curve_percent: curve_holdings
+                        .first()
+                        .map(|account| share(account.amount.units()))
+                        .unwrap_or_default(),
+                };
+
+                let stats = TokenStats {
+                    mint: coin.mint.clone(),
+                    name: coin.name.clone(),
+                    symbol: coin.symbol.clone(),
+                    creator: coin.creator.clone(),
+                    age_minutes: age_minutes(coin.created_timestamp),
+                    market_cap_usd: coin.usd_market_cap,
+                    progress_percent: coin.progress_percent(),
+                    complete: coin.complete,
+                    replies: coin.reply_count,
+                    creator_launches: created.len(),
+                    creator_graduated: created.iter().filter(|coin| coin.complete).count(),
+                    holders,
+                    website: coin.website,
+                    twitter: coin.twitter,
+                    telegram: coin.telegram,
+                };
+                serde_json::to_string(&stats)
+                    .map_err(|e| format!("Failed to serialize token stats: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_pump_token_stats",
+                with_client(&context, get_pump_token_stats),
+                None,
+            )
+            .description("Get a Pump.fun token's details, its creator's track record (launches and how many graduated) and holder concentration")
+            .parameter("mint", "Mint address of the Pump.fun token", "string", true)
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add buy action
+        {
+            async fn preview_buy<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: PumpContext<W>,
+                params: BuyParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<PumpTradePreview, String> {
+                let user_id = sender_id(&send_state)?;
+                let signer =
+                    solana_signer(&context.wallets, &user_id, params.account.as_deref()).await?;
+                let mint = parse_mint(&params.mint)?;
+                let slippage_bps =
+                    check_slippage(params.slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS))?;
+                let curve = context.curve(&mint).await?;
+                let quote = curve.quote_buy(
+                    to_base_units(params.sol_amount, 9)?,
+                    context.pump.fee_bps(),
+                    slippage_bps,
+                )?;
+                check_trade_limit(quote.sol_limit_lamports, context.pump.max_sol_per_trade())?;
+                let instructions = context
+                    .buy_instructions(
+                        &mint,
+                        &signer.pubkey(),
+                        &curve,
+                        quote.token_units,
+                        quote.sol_limit_lamports,
+                    )
+                    .await?;
+                let simulation = context.simulate(&signer.pubkey(), &instructions).await?;
+
+                let mut preview = PumpTradePreview {
+                    account: params.account,
+                    ..PumpTradePreview::new(
+                        user_id,
+                        &signer.pubkey(),
+                        &mint,
+                        PumpSide::Buy,
+                        quote,
+                        slippage_bps,
+                        simulation,
+                    )
+                };
+                preview.violations = context.policy.check(&preview.spend());
+                Ok(preview)
+            }
+
+            async fn confirm_buy<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: PumpContext<W>,
+                preview: PumpTradePreview,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                check_confirmer(&send_state, &preview.user_id)?;
+                let signer = solana_signer(
+                    &context.wallets,
+                    &preview.user_id,
+                    preview.account.as_deref(),
+                )
+                .await?;
+                if signer.pubkey().to_string() != preview.wallet || preview.side != PumpSide::Buy {
+                    return Err("The preview does not match this wallet; ask again".to_string());
+                }
+                check_trade_limit(preview.sol_limit_lamports, context.pump.max_sol_per_trade())?;
+                let mint = parse_mint(&preview.mint)?;
+                let curve = context.curve(&mint).await?;
+                let quote = curve.quote_buy(
+                    preview.sol_lamports,
+                    context.pump.fee_bps(),
+                    check_slippage(preview.slippage_bps)?,
+                )?;
+                let min_tokens = (preview.token_units as u128
+                    * (10_000 - preview.slippage_bps.min(10_000) as u128)
+                    / 10_000) as u64;
+                if quote.token_units < min_tokens {
+                    return Err(format!(
+                        "The price moved since the preview: {} SOL now buys {} tokens instead of {}. Ask for a new quote",
+                        preview.sol_amount,
+                        to_ui_amount(quote.token_units, TOKEN_DECIMALS),
+                        preview.token_amount
+                    ));
+                }
+
+                let instructions = context
+                    .buy_instructions(
+                        &mint,
+                        &signer.pubkey(),
+                        &curve,
+                        quote.token_units,
+                        preview.sol_limit_lamports,
+                    )
+                    .await?;
+                let approval = context.policy.approve(&preview.spend())?;
+                let signature = context
+                    .submit(signer.as_ref(), &instructions, approval)
+                    .await?;
+
+                Ok(format!(
+                    "Bought {} tokens for about {} SOL. Transaction: {}",
+                    to_ui_amount(quote.token_units, TOKEN_DECIMALS),
+                    preview.sol_amount,
+                    explorer_url(&signature.to_string())
+                ))
+            }
+
+            let action =
+                ActionBuilder::<_, BuyParams, serde_json::Value, S, PumpTradePreview, _>::new(
+                    "pump_buy",
+                    with_client(&context, preview_buy),
+                    Some(with_client(&context, confirm_buy)),
+                )
+                .description("Prepare buying a Pump.fun token on its bonding curve with SOL from the user's wallet. This does not trade by itself: the trade is simulated and the user is shown the tokens received, price impact, fees and balance changes with a Confirm button. Trades are capped at a maximum SOL amount")
+                .parameter("mint", "Mint address of the Pump.fun token", "string", true)
+                .parameter("sol_amount", "SOL to spend, fees included", "number", true)
+                .parameter(
+                    "slippage_bps",
+                    "Slippage tolerance in basis points (default 500, at most 5000)",
+                    "integer",
+                    false,
+                )
+                .parameter(
+                    "account",
+                    "Name of the user's wallet account to use, such as \"trading\"; leave out for their default account",
+                    "string",
+                    false,
+                )
+                .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add sell action
+        {
+            async fn preview_sell<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: PumpContext<W>,
+                params: SellParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<PumpTradePreview, String> {
+                let user_id = sender_id(&send_state)?;
+                let signer =
+                    solana_signer(&context.wallets, &user_id, params.account.as_deref()).await?;
+                let mint = parse_mint(&params.mint)?;
+                let slippage_bps =
+                    check_slippage(params.slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS))?;
+                let token_units = to_base_units(params.token_amount, TOKEN_DECIMALS)?;
+                let token_program = context.token_program(&mint).await?;
+                let balance = context
+                    .token_balance(&signer.pubkey(), &mint, &token_program)
+                    .await?;
+                if balance < token_units {
+                    return Err(format!(
+                        "The wallet holds only {} of this token",
+                        to_ui_amount(balance, TOKEN_DECIMALS)
+                    ));
+                }
+                let curve = context.curve(&mint).await?;
+                let quote = curve.quote_sell(token_units, context.pump.fee_bps(), slippage_bps)?;
+                check_trade_limit(quote.sol_lamports, context.pump.max_sol_per_trade())?;
+                let instructions = context
+                    .sell_instructions(
+                        &mint,
+                        &signer.pubkey(),
+                        &curve,
+                        token_units,
+                        quote.sol_limit_lamports,
+                    )
+                    .await?;
+                let simulation = context.simulate(&signer.pubkey(), &instructions).await?;
+
+                let mut preview = PumpTradePreview {
+                    account: params.account,
+                    ..PumpTradePreview::new(
+                        user_id,
+                        &signer.pubkey(),
+                        &mint,
+                        PumpSide::Sell,
+                        quote,
+                        slippage_bps,
+                        simulation,
+                    )
+                };
+                preview.violations = context.policy.check(&preview.spend());
+                Ok(preview)
+            }
+
+            async fn confirm_sell<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: PumpContext<W>,
+                preview: PumpTradePreview,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                check_confirmer(&send_state, &preview.user_id)?;
+                let signer = solana_signer(
+                    &context.wallets,
+                    &preview.user_id,
+                    preview.account.as_deref(),
+                )
+                .await?;
+                if signer.pubkey().to_string() != preview.wallet || preview.side != PumpSide::Sell {
+                    return Err("The preview does not match this wallet; ask again".to_string());
+                }
+                check_trade_limit(preview.sol_lamports, context.pump.max_sol_per_trade())?;
+                let mint = parse_mint(&preview.mint)?;
+                let curve = context.curve(&mint).await?;
+                let quote = curve.quote_sell(
+                    preview.token_units,
+                    context.pump.fee_bps(),
+                    check_slippage(preview.slippage_bps)?,
+                )?;
+                if quote.sol_lamports < preview.sol_limit_lamports {
+                    return Err(format!(
+                        "The price moved since the preview: the tokens would now sell for {} SOL, below the approved minimum of {} SOL. Ask for a new quote",
+                        to_ui_amount(quote.sol_lamports, 9),
+                        preview.sol_limit
+                    ));
+                }
+
+                let instructions = context
+                    .sell_instructions(
+                        &mint,
+                        &signer.pubkey(),
+                        &curve,
+                        preview.token_units,
+                        preview.sol_limit_lamports,
+                    )
+                    .await?;
+                let approval = context.policy.approve(&preview.spend())?;
+                let signature = context
+                    .submit(signer.as_ref(), &instructions, approval)
+                    .await?;
+
+                Ok(format!(
+                    "Sold {} tokens for at least {} SOL. Transaction: {}",
+                    preview.token_amount,
+                    preview.sol_limit,
+                    explorer_url(&signature.to_string())
+                ))
+            }
+
+            let action =
+                ActionBuilder::<_, SellParams, serde_json::Value, S, PumpTradePreview, _>::new(
+                    "pump_sell",
+                    with_client(&context, preview_sell),
+                    Some(with_client(&context, confirm_sell)),
+                )
+                .description("Prepare selling a Pump.fun token from the user's wallet back to its bonding curve. This does not trade by itself: the trade is simulated and the user is shown the SOL received, price impact, fees and balance changes with a Confirm button")
+                .parameter("mint", "Mint address of the Pump.fun token", "string", true)
+                .parameter(
+                    "token_amount",
+                    "Amount of the token to sell, in whole tokens",
+                    "number",
+                    true,
+                )
+                .parameter(
+                    "slippage_bps",
+                    "Slippage tolerance in basis points (default 500, at most 5000)",
+                    "integer",
+                    false,
+                )
+                .parameter(
+                    "account",
+                    "Name of the user's wallet account to use, such as \"trading\"; leave out for their default account",
+                    "string",
+                    false,
+                )
+                .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        Self { actions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::{
+        test_support::{limiter, mock_account, mock_method, rpc_client, rpc_url, wallet},
+        token::TOKEN_PROGRAM_ID,
+    };
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use curve::{
+        INITIAL_REAL_TOKEN_RESERVES, INITIAL_VIRTUAL_SOL_RESERVES, INITIAL_VIRTUAL_TOKEN_RESERVES,
+        PROGRAM_ID,
+    };
+    use ferrox_wallet::{simple_wallet_manager::SimpleWalletManager, LocalSigner};
+    use mockito::Matcher;
+    use solana_sdk::{signature::Keypair, transaction::VersionedTransaction};
+    use tokio::sync::Mutex;
+
+    fn fresh_curve(creator: Pubkey) -> BondingCurve {
+        BondingCurve {
+            virtual_token_reserves: INITIAL_VIRTUAL_TOKEN_RESERVES,
+            virtual_sol_reserves: INITIAL_VIRTUAL_SOL_RESERVES,
+            real_token_reserves: INITIAL_REAL_TOKEN_RESERVES,
+            real_sol_reserves: 0,
+            token_total_supply: TOKEN_TOTAL_SUPPLY,
+            complete: false,
+            creator: Some(creator),
+        }
+    }
+
+    fn group(server: &mockito::Server, max_sol: f64) -> PumpActionGroup<()> {
+        PumpActionGroup::with_clients(
+            PumpClient::new()
+                .with_base_url(server.url())
+                .with_rate_limiter(limiter())
+                .with_max_sol_per_trade(max_sol),
+            rpc_client(rpc_url(server)),
+            SimpleWalletManager::new(),
+            SpendingPolicy::default(),
+        )
+    }
+
+    fn action(group: &PumpActionGroup<()>, name: &str) -> Arc<FunctionAction<()>> {
+        group
+            .actions()
+            .iter()
+            .find(|action| action.definition().name == name)
+            .unwrap()
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn test_launches_are_tabulated() {
+        let mut server = mockito::Server::new_async().await;
+        let created = chrono::Utc::now().timestamp_millis() - 5 * 60_000;
+        let mock = server
+            .mock("GET", "/coins")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("limit".into(), "2".into()),
+                Matcher::UrlEncoded("sort".into(), "created_timestamp".into()),
+            ]))
+            .with_body(
+                serde_json::json!([{
+                    "mint": "7GCihgDB8fe6KNjn2MYtkzZcRjQy3t9GHdC8uHYmW2hr",
+                    "name": "Frog",
+                    "symbol": "FROG",
+                    "creator": "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM",
+                    "created_timestamp": created,
+                    "complete": false,
+                    "virtual_sol_reserves": 45_000_000_000u64,
+                    "virtual_token_reserves": 715_333_333_333_333u64,
+                    "usd_market_cap": 9_812.5,
+                    "reply_count": 3
+                }])
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let output = action(&group(&server, 1.0), "get_pump_launches")
+            .execute_rich(
+                serde_json::json!({"limit": 2}),
+                serde_json::json!({}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+            .unwrap();
+        mock.assert_async().await;
+        let body: String = serde_json::from_str(&output.content).unwrap();
+        let launches: Vec<LaunchSummary> = serde_json::from_str(&body).unwrap();
+        assert_eq!(launches[0].age_minutes, 5);
+        assert!((launches[0].progress_percent - 45.1).abs() < 0.1);
+        let table = output.tables().next().unwrap();
+        assert_eq!(table.rows[0][0], "FROG");
+        assert_eq!(table.rows[0][3], "45.1%");
+    }
+
+    #[tokio::test]
+    async fn test_get_curve_reads_the_account() {
+        let mut server = mockito::Server::new_async().await;
+        let mint = Pubkey::new_unique();
+        let curve = BondingCurve {
+            real_token_reserves: INITIAL_REAL_TOKEN_RESERVES / 2,
+            real_sol_reserves: 20_000_000_000,
+            ..fresh_curve(Pubkey::new_unique())
+        };
+        mock_account(
+            &mut server,
+            &bonding_curve_address(&mint),
+            Some((&curve.encode(), &PROGRAM_ID)),
+        )
+        .await;
+
+        let output = action(&group(&server, 1.0), "get_pump_curve")
+            .execute_rich(
+                serde_json::json!({"mint": mint.to_string()}),
+                serde_json::json!({}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+            .unwrap();
+        let body: String = serde_json::from_str(&output.content).unwrap();
+        let summary: CurveSummary = serde_json::from_str(&body).unwrap();
+        assert_eq!(summary.progress_percent, 50.0);
+        assert_eq!(summary.sol_in_curve, 20.0);
+        assert!(!output.hints.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_buy_above_the_trade_limit_is_refused() {
+        let mut server = mockito::Server::new_async().await;
+        let mint = Pubkey::new_unique();
+        mock_account(
+            &mut server,
+            &bonding_curve_address(&mint),
+            Some((&fresh_curve(Pubkey::new_unique()).encode(), &PROGRAM_ID)),
+        )
+        .await;
+
+        // 0.5 SOL plus 5% slippage may cost more than 0.5 SOL
+        let error = action(&group(&server, 0.5), "pump_buy")
+            .execute(
+                serde_json::json!({"mint": mint.to_string(), "sol_amount": 0.5}),
+                serde_json::json!({"from": {"id": 42}}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.contains("per-trade limit"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_excessive_slippage_is_refused() {
+        let server = mockito::Server::new_async().await;
+        let mint = Pubkey::new_unique();
+
+        for (name, params) in [
+            (
+                "pump_buy",
+                serde_json::json!({"mint": mint.to_string(), "sol_amount": 0.1, "slippage_bps": 10_000}),
+            ),
+            (
+                "pump_sell",
+                serde_json::json!({"mint": mint.to_string(), "token_amount": 100.0, "slippage_bps": 10_000}),
+            ),
+        ] {
+            let error = action(&group(&server, 1.0), name)
+                .execute(
+                    params,
+                    serde_json::json!({"from": {"id": 42}}),
+                    Arc::new(Mutex::new(())),
+                )
+                .await
+                .unwrap_err();
+            assert!(error.contains("above the limit of 5000 bps"), "{}", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buy_is_signed_and_submitted_after_confirmation() {
+        let mut server = mockito::Server::new_async().await;
+        let mint = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let wallet = wallet().await;
+        mock_account(
+            &mut server,
+            &bonding_curve_address(&mint),
+            Some((&fresh_curve(creator).encode(), &PROGRAM_ID)),
+        )
+        .await;
+        mock_account(&mut server, &mint, Some((&[0; 82], &TOKEN_PROGRAM_ID))).await;
+        mock_method(&mut server, "getLatestBlockhash", serde_json::json!({"context":{"slot":1},"value":{"blockhash":"EkSnNWid2cvwEVnVx9aBqawnmiCNiDgp3gUdkDPTKN1N","lastValidBlockHeight":3090}})).await;
+        mock_method(&mut server, "simulateTransaction", serde_json::json!({"context":{"slot":1},"value":{"err":null,"logs":[],"unitsConsumed":60000}})).await;
+        mock_method(
+            &mut server,
+            "getMultipleAccounts",
+            serde_json::json!({"context":{"slot":1},"value":[]}),
+        )
+        .await;
+        mock_method(
+            &mut server,
+            "getRecentPrioritizationFees",
+            serde_json::json!([{"slot":1,"prioritizationFee":1000}]),
+        )
+        .await;
+        mock_method(&mut server, "getSignatureStatuses", serde_json::json!({"context":{"slot":2},"value":[{"slot":2,"err":null,"confirmationStatus":"confirmed"}]})).await;
+        let expected = TradeAccounts::new(&mint, &wallet, &creator, &TOKEN_PROGRAM_ID);
+        let send = server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "sendTransaction"}),
+            ))
+            .with_body_from_request(move |request| {
+                let body: serde_json::Value =
+                    serde_json::from_slice(request.body().unwrap()).unwrap();
+                let bytes = BASE64.decode(body["params"][0].as_str().unwrap()).unwrap();
+                let transaction: VersionedTransaction = bincode::deserialize(&bytes).unwrap();
+                assert!(transaction.verify_with_results().iter().all(|ok| *ok));
+                let keys = transaction.message.static_account_keys();
+                // After the compute budget and the token account
+                let buy = &transaction.message.instructions()[3];
+                assert_eq!(keys[buy.program_id_index as usize], PROGRAM_ID);
+                // The bonding curve, its token account and the creator vault
+                assert_eq!(keys[buy.accounts[3] as usize], expected.bonding_curve);
+                assert_eq!(
+                    keys[buy.accounts[4] as usize],
+                    expected.associated_bonding_curve
+                );
+                assert_eq!(keys[buy.accounts[9] as usize], expected.creator_vault);
+                // Most SOL the buy may cost: 0.1 SOL plus 5%
+                assert_eq!(buy.data[16..], 105_000_000u64.to_le_bytes());
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": transaction.signatures[0].to_string()
+                })
+                .to_string()
+                .into()
+            })
+            .create_async()
+            .await;
+
+        let group = group(&server, 1.0);
+        let buy_action = action(&group, "pump_buy");
+        let state = Arc::new(Mutex::new(()));
+        let preview = buy_action
+            .execute(
+                serde_json::json!({"mint": mint.to_string(), "sol_amount": 0.1}),
+                serde_json::json!({"from": {"id": 42}}),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        let preview: serde_json::Value = serde_json::from_str(&preview).unwrap();
+        assert_eq!(preview["side"], "buy");
+        assert_eq!(preview["sol_limit"], 0.105);
+        assert!(preview["price_impact_percent"].as_f64().unwrap() > 0.0);
+        assert_eq!(preview["simulation"]["success"], true);
+
+        let result = buy_action
+            .confirm(preview, serde_json::json!({"from": {"id": 42}}), state)
+            .unwrap()
+            .await
+            .unwrap();
+        send.assert_async().await;
+        assert!(result.contains("https://solscan.io/tx/"), "{}", result);
+    }
+
+    #[tokio::test]
+    async fn test_sell_needs_the_tokens() {
+        let mut server = mockito::Server::new_async().await;
+        let mint = Pubkey::new_unique();
+        let wallet = wallet().await;
+        mock_account(&mut server, &mint, Some((&[0; 82], &TOKEN_PROGRAM_ID))).await;
+        let mut token_account = vec![0u8; 165];
+        token_account[64..72].copy_from_slice(&5_000_000u64.to_le_bytes());
+        mock_account(
+            &mut server,
+            &associated_token_address(&wallet, &mint, &TOKEN_PROGRAM_ID),
+            Some((&token_account, &TOKEN_PROGRAM_ID)),
+        )
+        .await;
+
+        let error = action(&group(&server, 1.0), "pump_sell")
+            .execute(
+                serde_json::json!({"mint": mint.to_string(), "token_amount": 10}),
+                serde_json::json!({"from": {"id": 42}}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error, "The wallet holds only 5 of this token");
+    }
+
+    /// Buys and sells on a local validator, which checks the instructions
+    /// against the real program during preflight. Start one with the program
+    /// and the accounts a trade touches cloned from mainnet, e.g.
+    ///
+    /// ```text
+    /// solana-test-validator --url mainnet-beta \
+    ///     --clone-upgradeable-program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P \
+    ///     --clone-upgradeable-program pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ \
+    ///     --clone <global> --clone <fee config> --clone <global volume accumulator> \
+    ///     --clone <mint> --clone <bonding curve> --clone <bonding curve token account>
+    /// ```
+    ///
+    /// and set `PUMP_TEST_MINT` to the cloned mint.
+    #[tokio::test]
+    #[ignore = "needs a local validator with the Pump.fun program and a token cloned from mainnet"]
+    async fn test_trades_on_local_validator() {
+        let mint = parse_mint(&std::env::var("PUMP_TEST_MINT").unwrap()).unwrap();
+        let rpc = SolanaRpcClient::new(
+            std::env::var("SOLANA_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8899".to_string()),
+        );
+        let context = PumpContext {
+            pump: PumpClient::new(),
+            submitter: TransactionSubmitter::new(rpc.clone()),
+            rpc,
+            wallets: SimpleWalletManager::new(),
+            policy: SpendingPolicy::default(),
+        };
+        let user = LocalSigner::new(Keypair::new());
+        let _: String = context
+            .rpc
+            .call(
+                "requestAirdrop",
+                serde_json::json!([user.pubkey().to_string(), 1_000_000_000u64]),
+            )
+            .await
+            .unwrap();
+        for _ in 0..30 {
+            let balance: crate::solana::rpc::models::Contextual<u64> = context
+                .rpc
+                .call("getBalance", serde_json::json!([user.pubkey().to_string()]))
+                .await
+                .unwrap();
+            if balance.value > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+
+        let curve = context.curve(&mint).await.unwrap();
+        let buy = curve.quote_buy(10_000_000, 125, 1_000).unwrap();
+        let accounts = context
+            .trade_accounts(&mint, &user.pubkey(), &curve)
+            .await
+            .unwrap();
+        context
+            .submitter
+            .submit(
+                &user,
+                &[
+                    create_associated_token_account_idempotent(
+                        &accounts.user,
+                        &accounts.user,
+                        &mint,
+                        &accounts.token_program,
+                    ),
+                    instructions::buy(&accounts, buy.token_units, buy.sol_limit_lamports),
+                ],
+            )
+            .await
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        let mut balance = 0;
+        for _ in 0..30 {
+            balance = context
+                .token_balance(&user.pubkey(), &mint, &accounts.token_program)
+                .await
+                .unwrap();
+            if balance > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+        let curve = context.curve(&mint).await.unwrap();
+        let sell = curve.quote_sell(balance, 125, 1_000).unwrap();
+        context
+            .submitter
+            .submit(
+                &user,
+                &[instructions::sell(
+                    &accounts,
+                    balance,
+                    sell.sol_limit_lamports,
+                )],
+            )
+            .await
+            .unwrap()
+            .into_result()
+            .unwrap();
+    }
+}
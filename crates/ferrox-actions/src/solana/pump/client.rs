@@ -0,0 +1,138 @@
+use reqwest::header::HeaderMap;
+use std::sync::Arc;
+
+use super::models::Coin;
+use crate::http::{HttpConfig, ProviderHttp, RateLimiter, PUMP_QUOTA};
+
+const BASE_URL: &str = "https://frontend-api-v3.pump.fun";
+
+/// Protocol plus creator fee charged on bonding-curve trades, in basis
+/// points.
+const DEFAULT_FEE_BPS: u16 = 125;
+const DEFAULT_MAX_SOL_PER_TRADE: f64 = 1.0;
+
+/// Pump.fun token listings, plus the limits trades on its bonding curves
+/// are held to.
+#[derive(Debug, Clone)]
+pub struct PumpClient {
+    http: ProviderHttp,
+    base_url: String,
+    fee_bps: u16,
+    max_sol_per_trade: f64,
+}
+
+impl Default for PumpClient {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+impl PumpClient {
+    pub fn new() -> Self {
+        Self {
+            http: ProviderHttp::new("pump", PUMP_QUOTA),
+            base_url: BASE_URL.to_string(),
+            fee_bps: DEFAULT_FEE_BPS,
+            max_sol_per_trade: DEFAULT_MAX_SOL_PER_TRADE,
+        }
+    }
+
+    /// Takes the per-trade limit from `PUMP_MAX_SOL_PER_TRADE` when it is set.
+    pub fn from_env() -> Self {
+        let client = Self::new();
+        match std::env::var("PUMP_MAX_SOL_PER_TRADE")
+            .ok()
+            .and_then(|max| max.parse().ok())
+        {
+            Some(max) => client.with_max_sol_per_trade(max),
+            None => client,
+        }
+    }
+
+    /// Overrides the API host, e.g. to point the client at a mock server.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn with_http_config(mut self, config: HttpConfig) -> Self {
+        self.http = self.http.with_config(config);
+        self
+    }
+
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.http = self.http.with_limiter(limiter);
+        self
+    }
+
+    /// Fee assumed when quoting trades. Slippage covers small differences
+    /// from what the program charges.
+    pub fn with_fee_bps(mut self, fee_bps: u16) -> Self {
+        self.fee_bps = fee_bps;
+        self
+    }
+
+    /// Most SOL a single buy may cost or a single sell may return.
+    pub fn with_max_sol_per_trade(mut self, max_sol: f64) -> Self {
+        self.max_sol_per_trade = max_sol;
+        self
+    }
+
+    pub fn fee_bps(&self) -> u16 {
+        self.fee_bps
+    }
+
+    pub fn max_sol_per_trade(&self) -> f64 {
+        self.max_sol_per_trade
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+        what: &str,
+    ) -> Result<T, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let body = self
+            .http
+            .get_with_query(&url, &HeaderMap::new(), query)
+            .await
+            .map_err(|e| format!("Failed to fetch {}: {}", what, e))?;
+        serde_json::from_str(&body).map_err(|e| format!("Error parsing {}: {}", what, e))
+    }
+
+    /// The most recently launched tokens, newest first.
+    pub async fn get_latest_coins(&self, limit: u32) -> Result<Vec<Coin>, String> {
+        self.get_json(
+            "/coins",
+            &[
+                ("offset", "0".to_string()),
+                ("limit", limit.to_string()),
+                ("sort", "created_timestamp".to_string()),
+                ("order", "DESC".to_string()),
+                ("includeNsfw", "false".to_string()),
+            ],
+            "Pump.fun launches",
+        )
+        .await
+    }
+
+    pub async fn get_coin(&self, mint: &str) -> Result<Coin, String> {
+        self.get_json(&format!("/coins/{}", mint), &[], "Pump.fun token")
+            .await
+    }
+
+    /// Tokens launched by `creator`, newest first.
+    pub async fn get_created_coins(&self, creator: &str, limit: u32) -> Result<Vec<Coin>, String> {
+        self.get_json(
+            &format!("/coins/user-created-coins/{}", creator),
+            &[
+                ("offset", "0".to_string()),
+                ("limit", limit.to_string()),
+                ("includeNsfw", "false".to_string()),
+            ],
+            "Pump.fun creator tokens",
+        )
+        .await
+    }
+}
@@ -0,0 +1,283 @@
+//! Pump.fun bonding-curve accounts and the constant-product math trades on
+//! them follow.
+
+use solana_sdk::{hash::hashv, pubkey, pubkey::Pubkey};
+
+pub const PROGRAM_ID: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
+
+/// Every Pump.fun token has 6 decimals and a supply of one billion.
+pub const TOKEN_DECIMALS: u8 = 6;
+pub const TOKEN_TOTAL_SUPPLY: u64 = 1_000_000_000_000_000;
+pub const INITIAL_VIRTUAL_TOKEN_RESERVES: u64 = 1_073_000_000_000_000;
+pub const INITIAL_VIRTUAL_SOL_RESERVES: u64 = 30_000_000_000;
+/// Tokens sold on the curve before it completes and the token graduates.
+pub const INITIAL_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000;
+
+const BPS: u128 = 10_000;
+
+/// The first 8 bytes of an Anchor account or instruction, derived from its
+/// name.
+pub(crate) fn discriminator(preimage: &str) -> [u8; 8] {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&hashv(&[preimage.as_bytes()]).to_bytes()[..8]);
+    bytes
+}
+
+pub fn bonding_curve_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &PROGRAM_ID).0
+}
+
+/// State of a token's bonding curve. Amounts are base units: lamports for
+/// SOL, millionths for tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BondingCurve {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    /// Set once every real token is sold and the token migrated to an AMM.
+    pub complete: bool,
+    /// Receives the creator fee; absent in curves created before the
+    /// program recorded it.
+    pub creator: Option<Pubkey>,
+}
+
+/// A priced buy or sell. For buys `sol_lamports` is what the user spends,
+/// fee included, and `sol_limit_lamports` the most the program may charge;
+/// for sells they are what the user receives after the fee and the least
+/// the program may pay out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeQuote {
+    pub sol_lamports: u64,
+    pub token_units: u64,
+    pub sol_limit_lamports: u64,
+    pub fee_lamports: u64,
+    pub price_impact_percent: f64,
+}
+
+impl BondingCurve {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 49 || data[..8] != discriminator("account:BondingCurve") {
+            return Err("Not a Pump.fun bonding curve account".to_string());
+        }
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        Ok(Self {
+            virtual_token_reserves: u64_at(8),
+            virtual_sol_reserves: u64_at(16),
+            real_token_reserves: u64_at(24),
+            real_sol_reserves: u64_at(32),
+            token_total_supply: u64_at(40),
+            complete: data[48] != 0,
+            creator: data
+                .get(49..81)
+                .map(|bytes| Pubkey::new_from_array(bytes.try_into().unwrap()))
+                .filter(|creator| *creator != Pubkey::default()),
+        })
+    }
+
+    /// Spot price of one whole token in SOL.
+    pub fn price_sol(&self) -> f64 {
+        if self.virtual_token_reserves == 0 {
+            return 0.0;
+        }
+        (self.virtual_sol_reserves as f64 / 1e9)
+            / (self.virtual_token_reserves as f64 / 10f64.powi(TOKEN_DECIMALS as i32))
+    }
+
+    pub fn market_cap_sol(&self) -> f64 {
+        self.price_sol() * self.token_total_supply as f64 / 10f64.powi(TOKEN_DECIMALS as i32)
+    }
+
+    /// Share of the curve's tokens sold so far.
+    pub fn progress_percent(&self) -> f64 {
+        if self.complete {
+            return 100.0;
+        }
+        let sold = INITIAL_REAL_TOKEN_RESERVES.saturating_sub(self.real_token_reserves);
+        sold as f64 / INITIAL_REAL_TOKEN_RESERVES as f64 * 100.0
+    }
+
+    /// SOL, before fees, that buying the remaining tokens would take.
+    pub fn lamports_to_complete(&self) -> u64 {
+        if self.complete || self.real_token_reserves >= self.virtual_token_reserves {
+            return 0;
+        }
+        (self.virtual_sol_reserves as u128 * self.real_token_reserves as u128
+            / (self.virtual_token_reserves - self.real_token_reserves) as u128) as u64
+    }
+
+    /// Tokens bought by spending `lamports` on the curve itself, after fees.
+    fn tokens_out(&self, lamports: u64) -> u64 {
+        let out = self.virtual_token_reserves as u128 * lamports as u128
+            / (self.virtual_sol_reserves as u128 + lamports as u128);
+        (out as u64).min(self.real_token_reserves)
+    }
+
+    /// SOL the curve pays for `tokens`, before fees.
+    fn lamports_out(&self, tokens: u64) -> u64 {
+        (self.virtual_sol_reserves as u128 * tokens as u128
+            / (self.virtual_token_reserves as u128 + tokens as u128)) as u64
+    }
+
+    /// Buying with `lamports`, fee included, allowing the cost to rise by
+    /// `slippage_bps`.
+    pub fn quote_buy(
+        &self,
+        lamports: u64,
+        fee_bps: u16,
+        slippage_bps: u16,
+    ) -> Result<TradeQuote, String> {
+        if self.complete {
+            return Err(
+                "The bonding curve is complete; the token now trades on an AMM".to_string(),
+            );
+        }
+        let net = (lamports as u128 * BPS / (BPS + fee_bps as u128)) as u64;
+        let tokens = self.tokens_out(net);
+        if tokens == 0 {
+            return Err("The amount is too small to buy any tokens".to_string());
+        }
+        let execution = net as f64 / tokens as f64;
+        let spot = self.virtual_sol_reserves as f64 / self.virtual_token_reserves as f64;
+        Ok(TradeQuote {
+            sol_lamports: lamports,
+            token_units: tokens,
+            sol_limit_lamports: (lamports as u128 * (BPS + slippage_bps as u128) / BPS) as u64,
+            fee_lamports: lamports - net,
+            price_impact_percent: (execution / spot - 1.0) * 100.0,
+        })
+    }
+
+    /// Selling `tokens`, accepting up to `slippage_bps` less SOL.
+    pub fn quote_sell(
+        &self,
+        tokens: u64,
+        fee_bps: u16,
+        slippage_bps: u16,
+    ) -> Result<TradeQuote, String> {
+        if self.complete {
+            return Err(
+                "The bonding curve is complete; the token now trades on an AMM".to_string(),
+            );
+        }
+        let gross = self.lamports_out(tokens);
+        let fee = (gross as u128 * fee_bps as u128 / BPS) as u64;
+        let net = gross - fee;
+        if net == 0 {
+            return Err("The amount is too small to sell for any SOL".to_string());
+        }
+        let execution = gross as f64 / tokens as f64;
+        let spot = self.virtual_sol_reserves as f64 / self.virtual_token_reserves as f64;
+        Ok(TradeQuote {
+            sol_lamports: net,
+            token_units: tokens,
+            sol_limit_lamports: (net as u128 * (BPS - slippage_bps.min(10_000) as u128) / BPS)
+                as u64,
+            fee_lamports: fee,
+            price_impact_percent: (1.0 - execution / spot) * 100.0,
+        })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut data = discriminator("account:BondingCurve").to_vec();
+        for value in [
+            self.virtual_token_reserves,
+            self.virtual_sol_reserves,
+            self.real_token_reserves,
+            self.real_sol_reserves,
+            self.token_total_supply,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.push(self.complete as u8);
+        data.extend_from_slice(self.creator.unwrap_or_default().as_ref());
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fresh_curve() -> BondingCurve {
+        BondingCurve {
+            virtual_token_reserves: INITIAL_VIRTUAL_TOKEN_RESERVES,
+            virtual_sol_reserves: INITIAL_VIRTUAL_SOL_RESERVES,
+            real_token_reserves: INITIAL_REAL_TOKEN_RESERVES,
+            real_sol_reserves: 0,
+            token_total_supply: TOKEN_TOTAL_SUPPLY,
+            complete: false,
+            creator: Some(Pubkey::new_unique()),
+        }
+    }
+
+    #[test]
+    fn test_discriminators() {
+        assert_eq!(
+            discriminator("account:BondingCurve"),
+            [23, 183, 248, 55, 96, 216, 172, 96]
+        );
+        assert_eq!(
+            discriminator("global:buy"),
+            [102, 6, 61, 18, 1, 218, 235, 234]
+        );
+        assert_eq!(
+            discriminator("global:sell"),
+            [51, 230, 133, 164, 1, 127, 131, 173]
+        );
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let curve = fresh_curve();
+        assert_eq!(BondingCurve::parse(&curve.encode()), Ok(curve.clone()));
+        // Curves from before the creator field
+        let legacy = BondingCurve::parse(&curve.encode()[..49]).unwrap();
+        assert_eq!(legacy.creator, None);
+        assert!(BondingCurve::parse(&[0; 81]).is_err());
+    }
+
+    #[test]
+    fn test_fresh_curve_state() {
+        let curve = fresh_curve();
+        assert_eq!(curve.progress_percent(), 0.0);
+        assert!((curve.price_sol() - 2.795_899_3e-8).abs() < 1e-14);
+        assert!((curve.market_cap_sol() - 27.958_993).abs() < 1e-5);
+        // About 85 SOL buys out a fresh curve
+        let to_complete = curve.lamports_to_complete() as f64 / 1e9;
+        assert!((to_complete - 85.005).abs() < 0.01, "{}", to_complete);
+    }
+
+    #[test]
+    fn test_buy_and_sell_quotes() {
+        let curve = fresh_curve();
+        let buy = curve.quote_buy(1_000_000_000, 100, 500).unwrap();
+        assert_eq!(buy.fee_lamports, 9_900_991);
+        assert_eq!(buy.sol_limit_lamports, 1_050_000_000);
+        // 0.99 SOL into 30 SOL of virtual reserves
+        assert_eq!(buy.token_units, 34_281_150_129_545);
+        assert!((buy.price_impact_percent - 3.3).abs() < 0.01);
+
+        let sell = curve.quote_sell(buy.token_units, 100, 500).unwrap();
+        assert!(sell.sol_lamports < 1_000_000_000);
+        assert_eq!(sell.sol_limit_lamports, sell.sol_lamports * 9_500 / 10_000);
+        assert!(sell.price_impact_percent > 0.0);
+
+        let complete = BondingCurve {
+            complete: true,
+            ..curve
+        };
+        assert!(complete.quote_buy(1_000_000_000, 100, 500).is_err());
+        assert_eq!(complete.progress_percent(), 100.0);
+    }
+
+    #[test]
+    fn test_bonding_curve_address_is_deterministic() {
+        let mint = Pubkey::new_unique();
+        assert_eq!(bonding_curve_address(&mint), bonding_curve_address(&mint));
+        assert_ne!(bonding_curve_address(&mint), mint);
+    }
+}
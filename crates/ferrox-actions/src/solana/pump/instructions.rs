@@ -0,0 +1,180 @@
+//! Buy and sell instructions for the Pump.fun program.
+//!
+//! The account lists follow the program's published IDL, including the
+//! creator vault, volume accumulators and fee config it added in 2025. They
+//! are plain functions of their accounts so they can be exercised against a
+//! local validator with the program cloned from mainnet.
+
+use super::curve::{bonding_curve_address, discriminator, PROGRAM_ID};
+use crate::solana::token::associated_token_address;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Collects the protocol fee on every trade.
+pub const FEE_RECIPIENT: Pubkey = pubkey!("CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM");
+pub const FEE_PROGRAM_ID: Pubkey = pubkey!("pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ");
+
+fn program_address(seeds: &[&[u8]], program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(seeds, program).0
+}
+
+pub fn global_address() -> Pubkey {
+    program_address(&[b"global"], &PROGRAM_ID)
+}
+
+pub fn event_authority_address() -> Pubkey {
+    program_address(&[b"__event_authority"], &PROGRAM_ID)
+}
+
+pub fn creator_vault_address(creator: &Pubkey) -> Pubkey {
+    program_address(&[b"creator-vault", creator.as_ref()], &PROGRAM_ID)
+}
+
+fn fee_config_address() -> Pubkey {
+    program_address(&[b"fee_config", PROGRAM_ID.as_ref()], &FEE_PROGRAM_ID)
+}
+
+/// Every account a trade of `user` on `mint` touches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeAccounts {
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    pub user: Pubkey,
+    pub associated_user: Pubkey,
+    pub creator_vault: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl TradeAccounts {
+    /// `creator` is read from the bonding curve and `token_program` is the
+    /// owner of the mint account.
+    pub fn new(mint: &Pubkey, user: &Pubkey, creator: &Pubkey, token_program: &Pubkey) -> Self {
+        let bonding_curve = bonding_curve_address(mint);
+        Self {
+            mint: *mint,
+            bonding_curve,
+            associated_bonding_curve: associated_token_address(&bonding_curve, mint, token_program),
+            user: *user,
+            associated_user: associated_token_address(user, mint, token_program),
+            creator_vault: creator_vault_address(creator),
+            token_program: *token_program,
+        }
+    }
+}
+
+fn data(name: &str, amount: u64, sol_limit: u64) -> Vec<u8> {
+    let mut data = discriminator(&format!("global:{}", name)).to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&sol_limit.to_le_bytes());
+    data
+}
+
+/// Buys exactly `token_units`, failing if that costs more than
+/// `max_sol_cost` lamports including fees. The user's token account must
+/// exist.
+pub fn buy(accounts: &TradeAccounts, token_units: u64, max_sol_cost: u64) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(global_address(), false),
+            AccountMeta::new(FEE_RECIPIENT, false),
+            AccountMeta::new_readonly(accounts.mint, false),
+            AccountMeta::new(accounts.bonding_curve, false),
+            AccountMeta::new(accounts.associated_bonding_curve, false),
+            AccountMeta::new(accounts.associated_user, false),
+            AccountMeta::new(accounts.user, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(accounts.token_program, false),
+            AccountMeta::new(accounts.creator_vault, false),
+            AccountMeta::new_readonly(event_authority_address(), false),
+            AccountMeta::new_readonly(PROGRAM_ID, false),
+            AccountMeta::new(
+                program_address(&[b"global_volume_accumulator"], &PROGRAM_ID),
+                false,
+            ),
+            AccountMeta::new(
+                program_address(
+                    &[b"user_volume_accumulator", accounts.user.as_ref()],
+                    &PROGRAM_ID,
+                ),
+                false,
+            ),
+            AccountMeta::new_readonly(fee_config_address(), false),
+            AccountMeta::new_readonly(FEE_PROGRAM_ID, false),
+        ],
+        data: data("buy", token_units, max_sol_cost),
+    }
+}
+
+/// Sells `token_units`, failing if that pays less than `min_sol_output`
+/// lamports after fees.
+pub fn sell(accounts: &TradeAccounts, token_units: u64, min_sol_output: u64) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(global_address(), false),
+            AccountMeta::new(FEE_RECIPIENT, false),
+            AccountMeta::new_readonly(accounts.mint, false),
+            AccountMeta::new(accounts.bonding_curve, false),
+            AccountMeta::new(accounts.associated_bonding_curve, false),
+            AccountMeta::new(accounts.associated_user, false),
+            AccountMeta::new(accounts.user, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(accounts.creator_vault, false),
+            AccountMeta::new_readonly(accounts.token_program, false),
+            AccountMeta::new_readonly(event_authority_address(), false),
+            AccountMeta::new_readonly(PROGRAM_ID, false),
+            AccountMeta::new_readonly(fee_config_address(), false),
+            AccountMeta::new_readonly(FEE_PROGRAM_ID, false),
+        ],
+        data: data("sell", token_units, min_sol_output),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::token::TOKEN_PROGRAM_ID;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_well_known_addresses() {
+        assert_eq!(
+            global_address(),
+            Pubkey::from_str("4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf").unwrap()
+        );
+        assert_eq!(
+            event_authority_address(),
+            Pubkey::from_str("Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_trade_instructions() {
+        let (mint, user, creator) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let accounts = TradeAccounts::new(&mint, &user, &creator, &TOKEN_PROGRAM_ID);
+
+        let buy = buy(&accounts, 1_000, 2_000);
+        assert_eq!(buy.data[..8], [102, 6, 61, 18, 1, 218, 235, 234]);
+        assert_eq!(buy.data[8..16], 1_000u64.to_le_bytes());
+        assert_eq!(buy.data[16..], 2_000u64.to_le_bytes());
+        // Only the user signs
+        let signers: Vec<_> = buy.accounts.iter().filter(|meta| meta.is_signer).collect();
+        assert_eq!(signers.len(), 1);
+        assert_eq!(signers[0].pubkey, user);
+
+        let sell = sell(&accounts, 1_000, 500);
+        assert_eq!(sell.accounts[8].pubkey, accounts.creator_vault);
+        assert_eq!(sell.accounts[9].pubkey, TOKEN_PROGRAM_ID);
+        assert_eq!(sell.data[16..], 500u64.to_le_bytes());
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+use super::curve::{INITIAL_REAL_TOKEN_RESERVES, INITIAL_VIRTUAL_TOKEN_RESERVES};
+
+/// A token as the Pump.fun frontend API lists it. Reserves are base units
+/// and timestamps are milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Coin {
+    pub mint: String,
+    pub name: String,
+    pub symbol: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub image_uri: Option<String>,
+    pub creator: String,
+    pub created_timestamp: i64,
+    #[serde(default)]
+    pub complete: bool,
+    #[serde(default)]
+    pub virtual_sol_reserves: u64,
+    #[serde(default)]
+    pub virtual_token_reserves: u64,
+    #[serde(default)]
+    pub market_cap: Option<f64>,
+    #[serde(default)]
+    pub usd_market_cap: Option<f64>,
+    #[serde(default)]
+    pub bonding_curve: Option<String>,
+    #[serde(default)]
+    pub king_of_the_hill_timestamp: Option<i64>,
+    #[serde(default)]
+    pub reply_count: Option<u64>,
+    #[serde(default)]
+    pub website: Option<String>,
+    #[serde(default)]
+    pub twitter: Option<String>,
+    #[serde(default)]
+    pub telegram: Option<String>,
+}
+
+impl Coin {
+    /// Share of the curve's tokens sold, estimated from the listed virtual
+    /// reserves.
+    pub fn progress_percent(&self) -> f64 {
+        if self.complete {
+            return 100.0;
+        }
+        let sold = INITIAL_VIRTUAL_TOKEN_RESERVES.saturating_sub(self.virtual_token_reserves);
+        (sold as f64 / INITIAL_REAL_TOKEN_RESERVES as f64 * 100.0).min(100.0)
+    }
+}
@@ -0,0 +1,975 @@
+pub mod client;
+pub mod models;
+
+use crate::{
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
+    output::{self, Table, UiHint},
+    solana::{
+        explorer_url,
+        metadata::{metadata_address, TokenMetadata},
+        mint_label, to_ui_amount,
+        token::TOKEN_2022_PROGRAM_ID,
+        wallet::resolve_address,
+    },
+    AgentState,
+};
+use client::SolanaRpcClient;
+use ferrox_wallet::WalletManager;
+use models::{EncodedTransaction, TokenAmount};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+const DEFAULT_SIGNATURES: usize = 10;
+const MAX_SIGNATURES: usize = 50;
+/// Token accounts listed at most, largest balances first.
+const MAX_TOKEN_ACCOUNTS: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct AddressParams {
+    address: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenAccountsParams {
+    address: Option<String>,
+    include_empty: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignaturesParams {
+    address: Option<String>,
+    limit: Option<usize>,
+    before: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransactionParams {
+    signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountParams {
+    address: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SolBalance {
+    pub address: String,
+    pub lamports: u64,
+    pub sol: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenHolding {
+    pub account: String,
+    pub mint: String,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub amount: f64,
+    pub decimals: u8,
+    pub token_program: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenHoldings {
+    pub address: String,
+    /// Accounts found, including any left out of `holdings`.
+    pub total_accounts: usize,
+    pub holdings: Vec<TokenHolding>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureSummary {
+    pub signature: String,
+    pub slot: u64,
+    pub time: Option<String>,
+    pub success: bool,
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceChange {
+    pub account: String,
+    pub change: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenBalanceChange {
+    pub owner: Option<String>,
+    pub mint: String,
+    pub change: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstructionSummary {
+    pub program: String,
+    /// Instruction name for programs the node decodes, e.g. `transfer`.
+    pub kind: Option<String>,
+    pub info: Option<serde_json::Value>,
+}
+
+/// A transaction reduced to what a user asks about it: who signed, what it
+/// called and whose balances moved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionSummary {
+    pub signature: String,
+    pub slot: u64,
+    pub time: Option<String>,
+    pub success: bool,
+    pub error: Option<serde_json::Value>,
+    pub fee_sol: f64,
+    pub signers: Vec<String>,
+    pub sol_changes: Vec<BalanceChange>,
+    pub token_changes: Vec<TokenBalanceChange>,
+    pub instructions: Vec<InstructionSummary>,
+    /// Program logs, kept only for failed transactions.
+    pub logs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSummary {
+    pub address: String,
+    pub exists: bool,
+    pub sol: f64,
+    pub owner: Option<String>,
+    pub owner_name: Option<String>,
+    pub executable: bool,
+    pub space: Option<u64>,
+    /// What the account is: a wallet, a program, or the type the owning
+    /// program's decoder reports (e.g. `mint`, `account`).
+    pub kind: String,
+    pub parsed: Option<serde_json::Value>,
+}
+
+/// Names of programs users run into often.
+fn program_name(program: &str) -> Option<&'static str> {
+    Some(match program {
+        "11111111111111111111111111111111" => "System Program",
+        "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA" => "Token Program",
+        "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb" => "Token-2022 Program",
+        "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL" => "Associated Token Account Program",
+        "ComputeBudget111111111111111111111111111111" => "Compute Budget Program",
+        "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr" => "Memo Program",
+        "Stake11111111111111111111111111111111111111" => "Stake Program",
+        "Vote111111111111111111111111111111111111111" => "Vote Program",
+        "BPFLoaderUpgradeab1e11111111111111111111111" => "BPF Upgradeable Loader",
+        "metaqbxxUerdq28cj1RbAWkYQm3ybzJb6KiS6WHhE8A" => "Metaplex Token Metadata",
+        "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4" => "Jupiter Aggregator v6",
+        "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P" => "Pump.fun",
+        _ => return None,
+    })
+}
+
+fn format_time(block_time: Option<i64>) -> Option<String> {
+    block_time
+        .and_then(|time| chrono::DateTime::from_timestamp(time, 0))
+        .map(|time| time.to_rfc3339())
+}
+
+fn short(value: &str) -> String {
+    mint_label(value)
+}
+
+fn token_change(pre: Option<&TokenAmount>, post: Option<&TokenAmount>) -> f64 {
+    let decimals = post
+        .or(pre)
+        .map(|amount| amount.decimals)
+        .unwrap_or_default();
+    let units = |amount: Option<&TokenAmount>| amount.map(|amount| amount.units()).unwrap_or(0);
+    (units(post) as f64 - units(pre) as f64) / 10f64.powi(decimals as i32)
+}
+
+impl TransactionSummary {
+    fn new(signature: &Signature, transaction: EncodedTransaction) -> Self {
+        let keys = &transaction.transaction.message.account_keys;
+        let meta = transaction.meta;
+        let success = meta.as_ref().is_some_and(|meta| meta.err.is_none());
+
+        let sol_changes = meta
+            .as_ref()
+            .map(|meta| {
+                keys.iter()
+                    .zip(meta.pre_balances.iter().zip(&meta.post_balances))
+                    .filter(|(_, (pre, post))| pre != post)
+                    .map(|(key, (pre, post))| BalanceChange {
+                        account: key.pubkey.clone(),
+                        change: (*post as f64 - *pre as f64) / 1e9,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let token_changes = meta
+            .as_ref()
+            .map(|meta| {
+                let mut indexes: Vec<usize> = meta
+                    .pre_token_balances
+                    .iter()
+                    .chain(&meta.post_token_balances)
+                    .map(|balance| balance.account_index)
+                    .collect();
+                indexes.sort_unstable();
+                indexes.dedup();
+                indexes
+                    .into_iter()
+                    .filter_map(|index| {
+                        let find = |balances: &[models::TokenBalance]| {
+                            balances
+                                .iter()
+                                .find(|balance| balance.account_index == index)
+                                .cloned()
+                        };
+                        let pre = find(&meta.pre_token_balances);
+                        let post = find(&meta.post_token_balances);
+                        let balance = post.as_ref().or(pre.as_ref())?;
+                        let change = token_change(
+                            pre.as_ref().map(|balance| &balance.ui_token_amount),
+                            post.as_ref().map(|balance| &balance.ui_token_amount),
+                        );
+                        (change != 0.0).then(|| TokenBalanceChange {
+                            owner: balance.owner.clone(),
+                            mint: balance.mint.clone(),
+                            change,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let instructions = transaction
+            .transaction
+            .message
+            .instructions
+            .iter()
+            .map(|instruction| {
+                let program_id = instruction["programId"].as_str().unwrap_or_default();
+                InstructionSummary {
+                    program: program_name(program_id)
+                        .map(str::to_string)
+                        .or_else(|| instruction["program"].as_str().map(str::to_string))
+                        .unwrap_or_else(|| program_id.to_string()),
+                    kind: instruction["parsed"]["type"].as_str().map(str::to_string),
+                    info: instruction["parsed"].get("info").cloned(),
+                }
+            })
+            .collect();
+
+        Self {
+            signature: signature.to_string(),
+            slot: transaction.slot,
+            time: format_time(transaction.block_time),
+            success,
+            error: meta.as_ref().and_then(|meta| meta.err.clone()),
+            fee_sol: meta
+                .as_ref()
+                .map(|meta| to_ui_amount(meta.fee, 9))
+                .unwrap_or_default(),
+            signers: keys
+                .iter()
+                .filter(|key| key.signer)
+                .map(|key| key.pubkey.clone())
+                .collect(),
+            sol_changes,
+            token_changes,
+            instructions,
+            logs: meta
+                .filter(|_| !success)
+                .and_then(|meta| meta.log_messages)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Everything the RPC actions need, cloned into each invocation.
+#[derive(Clone)]
+struct RpcContext<W> {
+    rpc: SolanaRpcClient,
+    wallets: W,
+}
+
+pub struct SolanaRpcActionGroup<S: Send + Sync + Clone + 'static> {
+    actions: Vec<Arc<FunctionAction<S>>>,
+}
+
+impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for SolanaRpcActionGroup<S> {
+    fn actions(&self) -> &[Arc<FunctionAction<S>>] {
+        &self.actions
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> SolanaRpcActionGroup<S> {
+    /// Builds the group on the endpoint in `SOLANA_RPC_URL` (see
+    /// [`SolanaRpcClient::from_env`]). `wallets` resolves "my wallet" to the
+    /// wallet of the user asking.
+    pub fn new<W: WalletManager + 'static>(wallets: W) -> Self {
+        Self::with_client(SolanaRpcClient::from_env(), wallets)
+    }
+
+    pub fn with_client<W: WalletManager + 'static>(rpc: SolanaRpcClient, wallets: W) -> Self {
+        let context = RpcContext { rpc, wallets };
+        let mut actions = Vec::new();
+
+        // Add SOL balance action
+        {
+            async fn get_sol_balance<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: RpcContext<W>,
+                params: AddressParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let address =
+                    resolve_address(&context.wallets, params.address.as_deref(), &send_state)
+                        .await?;
+                let lamports = context.rpc.get_balance(&address).await?;
+                serde_json::to_string(&SolBalance {
+                    address: address.to_string(),
+                    lamports,
+                    sol: to_ui_amount(lamports, 9),
+                })
+                .map_err(|e| format!("Failed to serialize balance: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_sol_balance",
+                with_client(&context, get_sol_balance),
+                None,
+            )
+            .description("Get the SOL balance of a Solana address, or of the user's own wallet")
+            .parameter(
+                "address",
+                "Solana address; leave out for the user's own wallet",
+                "string",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add token accounts action
+        {
+            async fn get_token_accounts<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: RpcContext<W>,
+                params: TokenAccountsParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let address =
+                    resolve_address(&context.wallets, params.address.as_deref(), &send_state)
+                        .await?;
+                let mut accounts = context.rpc.get_token_accounts_by_owner(&address).await?;
+                if !params.include_empty.unwrap_or(false) {
+                    accounts.retain(|account| account.amount.units() > 0);
+                }
+                accounts.sort_by(|a, b| b.amount.ui_amount().total_cmp(&a.amount.ui_amount()));
+                let total_accounts = accounts.len();
+                accounts.truncate(MAX_TOKEN_ACCOUNTS);
+
+                let mut mints: Vec<Pubkey> = accounts
+                    .iter()
+                    .filter_map(|account| Pubkey::from_str(&account.mint).ok())
+                    .collect();
+                mints.sort_unstable();
+                mints.dedup();
+                let metadata_accounts = context
+                    .rpc
+                    .get_multiple_accounts(&mints.iter().map(metadata_address).collect::<Vec<_>>())
+                    .await?;
+                let metadata: HashMap<String, TokenMetadata> = mints
+                    .iter()
+                    .zip(metadata_accounts)
+                    .filter_map(|(mint, account)| {
+                        let metadata = TokenMetadata::parse(&account?.data).ok()?;
+                        Some((mint.to_string(), metadata))
+                    })
+                    .collect();
+
+                let holdings: Vec<TokenHolding> = accounts
+                    .into_iter()
+                    .map(|account| {
+                        let metadata = metadata.get(&account.mint);
+                        TokenHolding {
+                            name: metadata.map(|metadata| metadata.name.clone()),
+                            symbol: metadata.map(|metadata| metadata.symbol.clone()),
+                            amount: account.amount.ui_amount(),
+                            decimals: account.amount.decimals,
+                            token_program: if account.token_program == TOKEN_2022_PROGRAM_ID {
+                                "spl-token-2022".to_string()
+                            } else {
+                                "spl-token".to_string()
+                            },
+                            account: account.address,
+                            mint: account.mint,
+                        }
+                    })
+                    .collect();
+                if !holdings.is_empty() {
+                    output::attach_table(holdings.iter().fold(
+                        Table::new(["Token", "Amount", "Mint"]).title("Token accounts"),
+                        |table, holding| {
+                            table.row([
+                                holding
+                                    .symbol
+                                    .clone()
+                                    .filter(|symbol| !symbol.is_empty())
+                                    .unwrap_or_else(|| short(&holding.mint)),
+                                holding.amount.to_string(),
+                                short(&holding.mint),
+                            ])
+                        },
+                    ));
+                }
+                serde_json::to_string(&TokenHoldings {
+                    address: address.to_string(),
+                    total_accounts,
+                    holdings,
+                })
+                .map_err(|e| format!("Failed to serialize token accounts: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_token_accounts",
+                with_client(&context, get_token_accounts),
+                None,
+            )
+            .description("List the SPL tokens a Solana address holds, with names and symbols from their metadata, largest balances first")
+            .parameter(
+                "address",
+                "Solana address; leave out for the user's own wallet",
+                "string",
+                false,
+            )
+            .parameter(
+                "include_empty",
+                "Also list token accounts with a zero balance",
+                "boolean",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add recent signatures action
+        {
+            async fn get_recent_transactions<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: RpcContext<W>,
+                params: SignaturesParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let address =
+                    resolve_address(&context.wallets, params.address.as_deref(), &send_state)
+                        .await?;
+                let limit = params
+                    .limit
+                    .unwrap_or(DEFAULT_SIGNATURES)
+                    .clamp(1, MAX_SIGNATURES);
+                let signatures: Vec<SignatureSummary> = context
+                    .rpc
+                    .get_signatures_for_address(&address, limit, params.before.as_deref())
+                    .await?
+                    .into_iter()
+                    .map(|info| SignatureSummary {
+                        time: format_time(info.block_time),
+                        success: info.err.is_none(),
+                        signature: info.signature,
+                        slot: info.slot,
+                        memo: info.memo,
+                    })
+                    .collect();
+                if !signatures.is_empty() {
+                    output::attach_table(signatures.iter().fold(
+                        Table::new(["Time", "Status", "Signature"]).title("Recent transactions"),
+                        |table, signature| {
+                            table.row([
+                                signature.time.clone().unwrap_or_default(),
+                                if signature.success { "ok" } else { "failed" }.to_string(),
+                                short(&signature.signature),
+                            ])
+                        },
+                    ));
+                }
+                serde_json::to_string(&signatures)
+                    .map_err(|e| format!("Failed to serialize signatures: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_recent_transactions",
+                with_client(&context, get_recent_transactions),
+                None,
+            )
+            .description("List the most recent transaction signatures of a Solana address, or of the user's own wallet, newest first")
+            .parameter(
+                "address",
+                "Solana address; leave out for the user's own wallet",
+                "string",
+                false,
+            )
+            .parameter(
+                "limit",
+                "How many transactions to list (default 10, at most 50)",
+                "integer",
+                false,
+            )
+            .parameter(
+                "before",
+                "Only list transactions older than this signature, to page back",
+                "string",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add transaction detail action
+        {
+            async fn get_transaction<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: RpcContext<W>,
+                params: TransactionParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let signature = Signature::from_str(params.signature.trim())
+                    .map_err(|e| format!("Invalid signature '{}': {}", params.signature, e))?;
+                let transaction = context
+                    .rpc
+                    .get_transaction(&signature)
+                    .await?
+                    .ok_or_else(|| {
+                        format!(
+                            "Transaction {} not found; it may not be confirmed yet or be older than this node keeps",
+                            signature
+                        )
+                    })?;
+                output::hint(UiHint::link(
+                    "View on Solscan",
+                    explorer_url(&signature.to_string()),
+                ));
+                serde_json::to_string(&TransactionSummary::new(&signature, transaction))
+                    .map_err(|e| format!("Failed to serialize transaction: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_transaction",
+                with_client(&context, get_transaction),
+                None,
+            )
+            .description("Decode a Solana transaction: status, fee, signers, the programs and instructions it called, and the SOL and token balance changes it caused")
+            .parameter("signature", "Transaction signature", "string", true)
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add account info action
+        {
+            async fn get_account_info<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: RpcContext<W>,
+                params: AccountParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let address =
+                    resolve_address(&context.wallets, Some(&params.address), &send_state).await?;
+                let summary = match context.rpc.get_parsed_account(&address).await? {
+                    None => AccountSummary {
+                        address: address.to_string(),
+                        exists: false,
+                        sol: 0.0,
+                        owner: None,
+                        owner_name: None,
+                        executable: false,
+                        space: None,
+                        kind: "empty".to_string(),
+                        parsed: None,
+                    },
+                    Some(account) => {
+                        let parsed = account.data.get("parsed");
+                        let kind = if account.executable {
+                            "program".to_string()
+                        } else if let Some(kind) = parsed.and_then(|parsed| parsed["type"].as_str())
+                        {
+                            kind.to_string()
+                        } else if account.owner == "11111111111111111111111111111111" {
+                            "wallet".to_string()
+                        } else {
+                            "program data".to_string()
+                        };
+                        AccountSummary {
+                            address: address.to_string(),
+                            exists: true,
+                            sol: to_ui_amount(account.lamports, 9),
+                            owner_name: program_name(&account.owner).map(str::to_string),
+                            owner: Some(account.owner.clone()),
+                            executable: account.executable,
+                            space: account.space,
+                            kind,
+                            parsed: parsed.and_then(|parsed| parsed.get("info")).cloned(),
+                        }
+                    }
+                };
+                serde_json::to_string(&summary)
+                    .map_err(|e| format!("Failed to serialize account: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_account_info",
+                with_client(&context, get_account_info),
+                None,
+            )
+            .description("Inspect any Solana account: balance, owner program, whether it is a wallet, program, token mint or token account, and its decoded state")
+            .parameter(
+                "address",
+                "Solana address, or \"my wallet\" for the user's own wallet",
+                "string",
+                true,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        Self { actions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::{
+        metadata::encode_metadata,
+        test_support::{rpc_client, rpc_result, rpc_url, wallet},
+        token::TOKEN_PROGRAM_ID,
+        USDC_MINT,
+    };
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use ferrox_wallet::simple_wallet_manager::SimpleWalletManager;
+    use mockito::Matcher;
+    use tokio::sync::Mutex;
+
+    fn group(url: String) -> SolanaRpcActionGroup<()> {
+        SolanaRpcActionGroup::with_client(rpc_client(url), SimpleWalletManager::new())
+    }
+
+    fn action(group: &SolanaRpcActionGroup<()>, name: &str) -> Arc<FunctionAction<()>> {
+        group
+            .actions()
+            .iter()
+            .find(|action| action.definition().name == name)
+            .unwrap()
+            .clone()
+    }
+
+    async fn run(
+        group: &SolanaRpcActionGroup<()>,
+        name: &str,
+        params: serde_json::Value,
+    ) -> Result<crate::ActionOutput, String> {
+        action(group, name)
+            .execute_rich(
+                params,
+                serde_json::json!({"from": {"id": 42}}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+    }
+
+    fn decode<T: serde::de::DeserializeOwned>(output: &crate::ActionOutput) -> T {
+        let body: String = serde_json::from_str(&output.content).unwrap();
+        serde_json::from_str(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_my_wallet_balance() {
+        let mut server = mockito::Server::new_async().await;
+        let wallet = wallet().await;
+        let mock = server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "method": "getBalance",
+                "params": [wallet.to_string()],
+            })))
+            .with_body(rpc_result(
+                serde_json::json!({"context": {"slot": 1}, "value": 1_500_000_000u64}),
+            ))
+            .create_async()
+            .await;
+
+        let output = run(
+            &group(rpc_url(&server)),
+            "get_sol_balance",
+            serde_json::json!({}),
+        )
+        .await
+        .unwrap();
+        mock.assert_async().await;
+        let balance: SolBalance = decode(&output);
+        assert_eq!(balance.address, wallet.to_string());
+        assert_eq!(balance.sol, 1.5);
+    }
+
+    #[tokio::test]
+    async fn test_token_accounts_with_metadata() {
+        let mut server = mockito::Server::new_async().await;
+        let owner = Pubkey::new_unique();
+        let usdc = Pubkey::from_str(USDC_MINT).unwrap();
+        let token_account = |mint: &str, amount: &str, decimals: u8| {
+            serde_json::json!({
+                "pubkey": Pubkey::new_unique().to_string(),
+                "account": {
+                    "data": {
+                        "program": "spl-token",
+                        "parsed": {
+                            "info": {
+                                "isNative": false,
+                                "mint": mint,
+                                "owner": owner.to_string(),
+                                "state": "initialized",
+                                "tokenAmount": {"amount": amount, "decimals": decimals, "uiAmountString": "0"}
+                            },
+                            "type": "account"
+                        },
+                        "space": 165
+                    },
+                    "executable": false,
+                    "lamports": 2_039_280,
+                    "owner": TOKEN_PROGRAM_ID.to_string(),
+                    "space": 165
+                }
+            })
+        };
+        let unknown = Pubkey::new_unique();
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "method": "getTokenAccountsByOwner",
+                "params": [owner.to_string(), {"programId": TOKEN_PROGRAM_ID.to_string()}, {"encoding": "jsonParsed"}],
+            })))
+            .with_body(rpc_result(serde_json::json!({
+                "context": {"slot": 1},
+                "value": [
+                    token_account(USDC_MINT, "12500000", 6),
+                    token_account(&unknown.to_string(), "0", 9),
+                    token_account(&unknown.to_string(), "300000000000", 9),
+                ]
+            })))
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "method": "getTokenAccountsByOwner",
+                "params": [owner.to_string(), {"programId": TOKEN_2022_PROGRAM_ID.to_string()}, {"encoding": "jsonParsed"}],
+            })))
+            .with_body(rpc_result(
+                serde_json::json!({"context": {"slot": 1}, "value": []}),
+            ))
+            .create_async()
+            .await;
+        let mut mints = [usdc, unknown];
+        mints.sort_unstable();
+        let metadata: Vec<serde_json::Value> = mints
+            .iter()
+            .map(|mint| {
+                if *mint != usdc {
+                    return serde_json::Value::Null;
+                }
+                serde_json::json!({
+                    "data": [BASE64.encode(encode_metadata(mint, "USD Coin", "USDC")), "base64"],
+                    "executable": false,
+                    "lamports": 5_616_720,
+                    "owner": crate::solana::metadata::METADATA_PROGRAM_ID.to_string(),
+                })
+            })
+            .collect();
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "method": "getMultipleAccounts",
+                "params": [mints.iter().map(|mint| metadata_address(mint).to_string()).collect::<Vec<_>>(), {"encoding": "base64"}],
+            })))
+            .with_body(rpc_result(
+                serde_json::json!({"context": {"slot": 1}, "value": metadata}),
+            ))
+            .create_async()
+            .await;
+
+        let output = run(
+            &group(rpc_url(&server)),
+            "get_token_accounts",
+            serde_json::json!({"address": owner.to_string()}),
+        )
+        .await
+        .unwrap();
+        let holdings: TokenHoldings = decode(&output);
+        // The empty account is left out and the largest balance comes first
+        assert_eq!(holdings.total_accounts, 2);
+        assert_eq!(holdings.holdings[0].amount, 300.0);
+        assert_eq!(holdings.holdings[0].symbol, None);
+        assert_eq!(holdings.holdings[1].symbol.as_deref(), Some("USDC"));
+        assert_eq!(holdings.holdings[1].amount, 12.5);
+        assert_eq!(output.tables().next().unwrap().rows[1][0], "USDC");
+    }
+
+    #[tokio::test]
+    async fn test_transaction_is_decoded() {
+        let mut server = mockito::Server::new_async().await;
+        let signature = Signature::new_unique();
+        let (sender, receiver) = (Pubkey::new_unique(), Pubkey::new_unique());
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "method": "getTransaction",
+                "params": [signature.to_string(), {"encoding": "jsonParsed", "maxSupportedTransactionVersion": 0, "commitment": "confirmed"}],
+            })))
+            .with_body(rpc_result(serde_json::json!({
+                "slot": 250_000_000u64,
+                "blockTime": 1_700_000_000,
+                "meta": {
+                    "err": null,
+                    "fee": 5000,
+                    "preBalances": [2_000_000_000u64, 0, 1],
+                    "postBalances": [1_499_995_000u64, 500_000_000u64, 1],
+                    "preTokenBalances": [],
+                    "postTokenBalances": [],
+                    "logMessages": ["Program 11111111111111111111111111111111 invoke [1]"]
+                },
+                "transaction": {
+                    "signatures": [signature.to_string()],
+                    "message": {
+                        "accountKeys": [
+                            {"pubkey": sender.to_string(), "signer": true, "writable": true, "source": "transaction"},
+                            {"pubkey": receiver.to_string(), "signer": false, "writable": true, "source": "transaction"},
+                            {"pubkey": "11111111111111111111111111111111", "signer": false, "writable": false, "source": "transaction"}
+                        ],
+                        "instructions": [{
+                            "program": "system",
+                            "programId": "11111111111111111111111111111111",
+                            "parsed": {
+                                "type": "transfer",
+                                "info": {"source": sender.to_string(), "destination": receiver.to_string(), "lamports": 500_000_000u64}
+                            },
+                            "stackHeight": null
+                        }],
+                        "recentBlockhash": "EkSnNWid2cvwEVnVx9aBqawnmiCNiDgp3gUdkDPTKN1N"
+                    }
+                }
+            })))
+            .create_async()
+            .await;
+
+        let output = run(
+            &group(rpc_url(&server)),
+            "get_transaction",
+            serde_json::json!({"signature": signature.to_string()}),
+        )
+        .await
+        .unwrap();
+        let summary: TransactionSummary = decode(&output);
+        assert!(summary.success);
+        assert_eq!(summary.time.as_deref(), Some("2023-11-14T22:13:20+00:00"));
+        assert_eq!(summary.fee_sol, 0.000005);
+        assert_eq!(summary.signers, vec![sender.to_string()]);
+        assert_eq!(summary.sol_changes.len(), 2);
+        assert_eq!(summary.sol_changes[1].change, 0.5);
+        assert_eq!(summary.instructions[0].program, "System Program");
+        assert_eq!(summary.instructions[0].kind.as_deref(), Some("transfer"));
+        // Logs are only kept for failures
+        assert!(summary.logs.is_empty());
+        assert_eq!(output.hints.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_transaction_and_missing_account() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "getTransaction"}),
+            ))
+            .with_body(rpc_result(serde_json::Value::Null))
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "getAccountInfo"}),
+            ))
+            .with_body(rpc_result(
+                serde_json::json!({"context": {"slot": 1}, "value": null}),
+            ))
+            .create_async()
+            .await;
+        let group = group(rpc_url(&server));
+
+        let error = run(
+            &group,
+            "get_transaction",
+            serde_json::json!({"signature": Signature::new_unique().to_string()}),
+        )
+        .await
+        .unwrap_err();
+        assert!(error.contains("not found"), "{}", error);
+
+        let output = run(
+            &group,
+            "get_account_info",
+            serde_json::json!({"address": Pubkey::new_unique().to_string()}),
+        )
+        .await
+        .unwrap();
+        let account: AccountSummary = decode(&output);
+        assert!(!account.exists);
+        assert_eq!(account.kind, "empty");
+    }
+
+    /// Reads a funded wallet back from a local `solana-test-validator`.
+    #[tokio::test]
+    #[ignore = "needs a running solana-test-validator"]
+    async fn test_reads_on_local_validator() {
+        let url =
+            std::env::var("SOLANA_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8899".to_string());
+        let rpc = SolanaRpcClient::new(url.clone());
+        let address = Pubkey::new_unique();
+        let signature: String = rpc
+            .call(
+                "requestAirdrop",
+                serde_json::json!([address.to_string(), 1_000_000_000u64]),
+            )
+            .await
+            .unwrap();
+        let signature = Signature::from_str(&signature).unwrap();
+        let mut transaction = None;
+        for _ in 0..30 {
+            transaction = rpc.get_transaction(&signature).await.unwrap();
+            if transaction.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+        let summary = TransactionSummary::new(&signature, transaction.unwrap());
+        assert!(summary.success);
+        assert!(summary
+            .sol_changes
+            .iter()
+            .any(|change| change.account == address.to_string() && change.change == 1.0));
+
+        let group = group(url);
+        let params = serde_json::json!({"address": address.to_string()});
+        let balance: SolBalance = decode(
+            &run(&group, "get_sol_balance", params.clone())
+                .await
+                .unwrap(),
+        );
+        assert_eq!(balance.lamports, 1_000_000_000);
+        let signatures: Vec<SignatureSummary> = decode(
+            &run(&group, "get_recent_transactions", params)
+                .await
+                .unwrap(),
+        );
+        assert_eq!(signatures[0].signature, signature.to_string());
+    }
+}
@@ -0,0 +1,728 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use reqwest::header::HeaderMap;
+use serde::{de::DeserializeOwned, Deserialize};
+use solana_sdk::{
+    hash::Hash, message::VersionedMessage, pubkey::Pubkey, signature::Signature,
+    transaction::VersionedTransaction,
+};
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use super::models::{
+    AccountInfo, Commitment, Contextual, EncodedTransaction, ParsedAccount, RpcError,
+    SignatureInfo, SignatureStatus, SimulationResult, TokenAccount, TokenAccountBalance,
+    TokenAmount,
+};
+use crate::{
+    http::{HttpConfig, ProviderHttp, RateLimiter, SOLANA_RPC_QUOTA},
+    solana::{
+        explorer_url,
+        token::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID},
+        NATIVE_MINT,
+    },
+};
+
+const DEFAULT_URL: &str = "https://api.mainnet-beta.solana.com";
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncodedAccount {
+    data: (String, String),
+    lamports: u64,
+    owner: String,
+    executable: bool,
+}
+
+impl EncodedAccount {
+    fn decode(self, address: &Pubkey) -> Result<AccountInfo, String> {
+        Ok(AccountInfo {
+            data: BASE64
+                .decode(&self.data.0)
+                .map_err(|e| format!("Invalid account data for {}: {}", address, e))?,
+            lamports: self.lamports,
+            owner: Pubkey::from_str(&self.owner)
+                .map_err(|e| format!("Invalid owner of {}: {}", address, e))?,
+            executable: self.executable,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyedAccount<T> {
+    pubkey: String,
+    account: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedTokenAccount {
+    data: ParsedTokenAccountData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedTokenAccountData {
+    parsed: ParsedTokenAccountState,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedTokenAccountState {
+    info: TokenAccountInfo,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenAccountInfo {
+    mint: String,
+    owner: String,
+    token_amount: TokenAmount,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LatestBlockhash {
+    blockhash: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrioritizationFee {
+    prioritization_fee: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Simulation {
+    #[serde(flatten)]
+    result: SimulationResult,
+    #[serde(default)]
+    accounts: Option<Vec<Option<EncodedAccount>>>,
+}
+
+/// Most accounts `getMultipleAccounts` takes per call.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// How [`SolanaRpcClient::confirm_transaction`] waits for a submitted
+/// transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfirmationConfig {
+    /// Commitment the transaction must reach to count as landed.
+    pub commitment: Commitment,
+    /// Time to wait before giving up. A blockhash expires after about a
+    /// minute, so waiting much longer rarely changes the answer.
+    pub timeout: Duration,
+    /// Delay between status checks.
+    pub poll_interval: Duration,
+}
+
+impl Default for ConfirmationConfig {
+    fn default() -> Self {
+        Self {
+            commitment: Commitment::Confirmed,
+            timeout: Duration::from_secs(60),
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// JSON-RPC client for a Solana node.
+#[derive(Debug, Clone)]
+pub struct SolanaRpcClient {
+    http: ProviderHttp,
+    url: String,
+    confirmation: ConfirmationConfig,
+}
+
+impl Default for SolanaRpcClient {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+impl SolanaRpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            http: ProviderHttp::new("solana-rpc", SOLANA_RPC_QUOTA),
+            url: url.into(),
+            confirmation: ConfirmationConfig::default(),
+        }
+    }
+
+    /// Uses the endpoint in `SOLANA_RPC_URL`, or the public mainnet endpoint,
+    /// and the commitment in `SOLANA_COMMITMENT` when it is set.
+    pub fn from_env() -> Self {
+        let client =
+            Self::new(std::env::var("SOLANA_RPC_URL").unwrap_or_else(|_| DEFAULT_URL.to_string()));
+        match std::env::var("SOLANA_COMMITMENT")
+            .ok()
+            .and_then(|commitment| commitment.parse().ok())
+        {
+            Some(commitment) => client.with_commitment(commitment),
+            None => client,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn with_http_config(mut self, config: HttpConfig) -> Self {
+        self.http = self.http.with_config(config);
+        self
+    }
+
+    /// Replaces the shared rate limiter, e.g. for a private endpoint with a
+    /// higher quota.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.http = self.http.with_limiter(limiter);
+        self
+    }
+
+    pub fn with_confirmation_config(mut self, config: ConfirmationConfig) -> Self {
+        self.confirmation = config;
+        self
+    }
+
+    /// Commitment submitted transactions are waited for.
+    pub fn with_commitment(mut self, commitment: Commitment) -> Self {
+        self.confirmation.commitment = commitment;
+        self
+    }
+
+    pub fn confirmation_config(&self) -> &ConfirmationConfig {
+        &self.confirmation
+    }
+
+    /// Calls `method` and decodes its result, turning JSON-RPC errors into
+    /// `Err`.
+    pub async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T, String> {
+        self.call_optional(method, params)
+            .await?
+            .ok_or_else(|| format!("Empty {} response", method))
+    }
+
+    /// Like [`Self::call`] for methods answering `null` when there is
+    /// nothing to return.
+    pub async fn call_optional<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<Option<T>, String> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let body = self
+            .http
+            .post_json(&self.url, &HeaderMap::new(), &request)
+            .await
+            .map_err(|e| format!("Failed to call {}: {}", method, e))?;
+        let response: RpcResponse<T> = serde_json::from_str(&body)
+            .map_err(|e| format!("Error parsing {} response: {}", method, e))?;
+        match response.error {
+            Some(error) => Err(format!(
+                "RPC error {} in {}: {}",
+                error.code, method, error.message
+            )),
+            None => Ok(response.result),
+        }
+    }
+
+    /// Decimals of an SPL token mint.
+    pub async fn get_token_decimals(&self, mint: &Pubkey) -> Result<u8, String> {
+        if mint.to_string() == NATIVE_MINT {
+            return Ok(9);
+        }
+        Ok(self.get_token_supply(mint).await?.decimals)
+    }
+
+    /// State of `address`, or `None` if the account does not exist.
+    pub async fn get_account_info(&self, address: &Pubkey) -> Result<Option<AccountInfo>, String> {
+        let account: Contextual<Option<EncodedAccount>> = self
+            .call(
+                "getAccountInfo",
+                serde_json::json!([address.to_string(), {"encoding": "base64"}]),
+            )
+            .await?;
+        account
+            .value
+            .map(|account| account.decode(address))
+            .transpose()
+    }
+
+    /// States of `addresses`, in order, with `None` for missing accounts.
+    pub async fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<AccountInfo>>, String> {
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let keys: Vec<String> = chunk.iter().map(|address| address.to_string()).collect();
+            let values: Contextual<Vec<Option<EncodedAccount>>> = self
+                .call(
+                    "getMultipleAccounts",
+                    serde_json::json!([keys, {"encoding": "base64"}]),
+                )
+                .await?;
+            for (address, account) in chunk.iter().zip(values.value) {
+                accounts.push(account.map(|account| account.decode(address)).transpose()?);
+            }
+        }
+        Ok(accounts)
+    }
+
+    /// State of `address` decoded by the node where it can, or `None` if
+    /// the account does not exist.
+    pub async fn get_parsed_account(
+        &self,
+        address: &Pubkey,
+    ) -> Result<Option<ParsedAccount>, String> {
+        let account: Contextual<Option<ParsedAccount>> = self
+            .call(
+                "getAccountInfo",
+                serde_json::json!([address.to_string(), {"encoding": "jsonParsed"}]),
+            )
+            .await?;
+        Ok(account.value)
+    }
+
+    pub async fn get_balance(&self, address: &Pubkey) -> Result<u64, String> {
+        let balance: Contextual<u64> = self
+            .call("getBalance", serde_json::json!([address.to_string()]))
+            .await?;
+        Ok(balance.value)
+    }
+
+    /// Every token account of `owner`, under both token programs.
+    pub async fn get_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+    ) -> Result<Vec<TokenAccount>, String> {
+        let mut accounts = Vec::new();
+        for program in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+            let keyed: Contextual<Vec<KeyedAccount<ParsedTokenAccount>>> = self
+                .call(
+                    "getTokenAccountsByOwner",
+                    serde_json::json!([
+                        owner.to_string(),
+                        {"programId": program.to_string()},
+                        {"encoding": "jsonParsed"}
+                    ]),
+                )
+                .await?;
+            accounts.extend(keyed.value.into_iter().map(|keyed| {
+                let info = keyed.account.data.parsed.info;
+                TokenAccount {
+                    address: keyed.pubkey,
+                    mint: info.mint,
+                    owner: info.owner,
+                    amount: info.token_amount,
+                    token_program: program,
+                }
+            }));
+        }
+        Ok(accounts)
+    }
+
+    /// Signatures of transactions touching `address`, newest first, starting
+    /// before the signature `before` when given.
+    pub async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        limit: usize,
+        before: Option<&str>,
+    ) -> Result<Vec<SignatureInfo>, String> {
+        let mut config = serde_json::json!({"limit": limit});
+        if let Some(before) = before {
+            config["before"] = serde_json::json!(before);
+        }
+        self.call(
+            "getSignaturesForAddress",
+            serde_json::json!([address.to_string(), config]),
+        )
+        .await
+    }
+
+    /// A confirmed transaction, or `None` if the node does not know it.
+    pub async fn get_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Result<Option<EncodedTransaction>, String> {
+        self.call_optional(
+            "getTransaction",
+            serde_json::json!([
+                signature.to_string(),
+                {
+                    "encoding": "jsonParsed",
+                    "maxSupportedTransactionVersion": 0,
+                    "commitment": "confirmed"
+                }
+            ]),
+        )
+        .await
+    }
+
+    /// The twenty largest token accounts of `mint`.
+    pub async fn get_token_largest_accounts(
+        &self,
+        mint: &Pubkey,
+    ) -> Result<Vec<TokenAccountBalance>, String> {
+        let accounts: Contextual<Vec<TokenAccountBalance>> = self
+            .call(
+                "getTokenLargestAccounts",
+                serde_json::json!([mint.to_string()]),
+            )
+            .await?;
+        Ok(accounts.value)
+    }
+
+    pub async fn get_token_supply(&self, mint: &Pubkey) -> Result<TokenAmount, String> {
+        let supply: Contextual<TokenAmount> = self
+            .call("getTokenSupply", serde_json::json!([mint.to_string()]))
+            .await?;
+        Ok(supply.value)
+    }
+
+    /// Blockhash to build a transaction on.
+    pub async fn get_latest_blockhash(&self) -> Result<Hash, String> {
+        let blockhash: Contextual<LatestBlockhash> = self
+            .call(
+                "getLatestBlockhash",
+                serde_json::json!([{"commitment": "confirmed"}]),
+            )
+            .await?;
+        Hash::from_str(&blockhash.value.blockhash)
+            .map_err(|e| format!("Invalid blockhash from RPC: {}", e))
+    }
+
+    /// Whether transactions built on `blockhash` can still land. Once it
+    /// is not, a transaction the node has not seen never will be.
+    pub async fn is_blockhash_valid(&self, blockhash: &Hash) -> Result<bool, String> {
+        let valid: Contextual<bool> = self
+            .call(
+                "isBlockhashValid",
+                serde_json::json!([blockhash.to_string(), {"commitment": "confirmed"}]),
+            )
+            .await?;
+        Ok(valid.value)
+    }
+
+    /// Compute-unit prices, in micro-lamports, that landed transactions
+    /// writing to `accounts` paid over the last slots the node keeps.
+    pub async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<u64>, String> {
+        let accounts: Vec<String> = accounts.iter().map(Pubkey::to_string).collect();
+        let fees: Vec<PrioritizationFee> = self
+            .call("getRecentPrioritizationFees", serde_json::json!([accounts]))
+            .await?;
+        Ok(fees.into_iter().map(|fee| fee.prioritization_fee).collect())
+    }
+
+    /// Runs `transaction` against the current state without submitting it.
+    /// Signatures are not checked and the blockhash is replaced by a recent
+    /// one, so unsigned or stale transactions can be simulated.
+    pub async fn simulate_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<SimulationResult, String> {
+        Ok(self
+            .simulate_transaction_with_accounts(transaction, &[])
+            .await?
+            .0)
+    }
+
+    /// Like [`Self::simulate_transaction`], also returning the state
+    /// `addresses` would be left in, with `None` for accounts that would not
+    /// exist.
+    pub async fn simulate_transaction_with_accounts(
+        &self,
+        transaction: &VersionedTransaction,
+        addresses: &[Pubkey],
+    ) -> Result<(SimulationResult, Vec<Option<AccountInfo>>), String> {
+        let bytes = bincode::serialize(transaction)
+            .map_err(|e| format!("Failed to serialize transaction: {}", e))?;
+        let mut config = serde_json::json!({
+            "encoding": "base64",
+            "sigVerify": false,
+            "replaceRecentBlockhash": true,
+            "commitment": "confirmed"
+        });
+        if !addresses.is_empty() {
+            let keys: Vec<String> = addresses.iter().map(Pubkey::to_string).collect();
+            config["accounts"] = serde_json::json!({"encoding": "base64", "addresses": keys});
+        }
+        let simulation: Contextual<Simulation> = self
+            .call(
+                "simulateTransaction",
+                serde_json::json!([BASE64.encode(bytes), config]),
+            )
+            .await?;
+        let accounts = match simulation.value.accounts {
+            Some(accounts) => addresses
+                .iter()
+                .zip(accounts)
+                .map(|(address, account)| {
+                    account.map(|account| account.decode(address)).transpose()
+                })
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+        Ok((simulation.value.result, accounts))
+    }
+
+    /// Submits a signed transaction after preflight simulation and returns
+    /// its signature. The transaction may still fail or expire on chain.
+    pub async fn send_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<Signature, String> {
+        let bytes = bincode::serialize(transaction)
+            .map_err(|e| format!("Failed to serialize transaction: {}", e))?;
+        let signature: String = self
+            .call(
+                "sendTransaction",
+                serde_json::json!([
+                    BASE64.encode(bytes),
+                    {"encoding": "base64", "preflightCommitment": "confirmed"}
+                ]),
+            )
+            .await?;
+        Signature::from_str(&signature).map_err(|e| format!("Invalid signature from RPC: {}", e))
+    }
+
+    /// Broadcasts `transaction` again without simulating it, for when an
+    /// earlier submission may have been dropped on the way to the leader.
+    pub async fn resend_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<(), String> {
+        let bytes = bincode::serialize(transaction)
+            .map_err(|e| format!("Failed to serialize transaction: {}", e))?;
+        let _: String = self
+            .call(
+                "sendTransaction",
+                serde_json::json!([
+                    BASE64.encode(bytes),
+                    {"encoding": "base64", "skipPreflight": true, "maxRetries": 0}
+                ]),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Statuses of `signatures`, in order, with `None` for transactions the
+    /// node has not seen.
+    pub async fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> Result<Vec<Option<SignatureStatus>>, String> {
+        let signatures: Vec<String> = signatures.iter().map(Signature::to_string).collect();
+        let statuses: Contextual<Vec<Option<SignatureStatus>>> = self
+            .call(
+                "getSignatureStatuses",
+                serde_json::json!([signatures, {"searchTransactionHistory": false}]),
+            )
+            .await?;
+        Ok(statuses.value)
+    }
+
+    /// Fee in lamports the network charges for `message`, or `None` if its
+    /// blockhash has expired.
+    pub async fn get_fee_for_message(
+        &self,
+        message: &VersionedMessage,
+    ) -> Result<Option<u64>, String> {
+        let bytes = bincode::serialize(message)
+            .map_err(|e| format!("Failed to serialize message: {}", e))?;
+        let fee: Contextual<Option<u64>> = self
+            .call(
+                "getFeeForMessage",
+                serde_json::json!([BASE64.encode(bytes), {"commitment": "confirmed"}]),
+            )
+            .await?;
+        Ok(fee.value)
+    }
+
+    /// Lamports an account of `space` bytes must hold to be rent exempt.
+    pub async fn get_minimum_balance_for_rent_exemption(
+        &self,
+        space: usize,
+    ) -> Result<u64, String> {
+        self.call(
+            "getMinimumBalanceForRentExemption",
+            serde_json::json!([space]),
+        )
+        .await
+    }
+
+    /// Polls the status of `signature` until it reaches the configured
+    /// commitment, failing if the transaction failed on chain or did not
+    /// land in time.
+    pub async fn confirm_transaction(&self, signature: &Signature) -> Result<(), String> {
+        let config = &self.confirmation;
+        let deadline = tokio::time::Instant::now() + config.timeout;
+        loop {
+            if let Some(status) = self
+                .get_signature_statuses(std::slice::from_ref(signature))
+                .await?
+                .into_iter()
+                .next()
+                .flatten()
+            {
+                if let Some(err) = status.err {
+                    return Err(format!(
+                        "Transaction {} failed: {}",
+                        explorer_url(&signature.to_string()),
+                        err
+                    ));
+                }
+                if status
+                    .confirmation_status
+                    .is_some_and(|reached| reached >= config.commitment)
+                {
+                    return Ok(());
+                }
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(format!(
+                    "Transaction {} was not {} within {}s. It may still land; check it before retrying",
+                    explorer_url(&signature.to_string()),
+                    config.commitment.as_str(),
+                    config.timeout.as_secs()
+                ));
+            }
+            tokio::time::sleep(config.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Quota;
+    use mockito::Matcher;
+
+    fn client(server: &mockito::Server) -> SolanaRpcClient {
+        SolanaRpcClient::new(server.url())
+            .with_rate_limiter(Arc::new(RateLimiter::new(Quota::per_second(100))))
+    }
+
+    #[tokio::test]
+    async fn test_get_token_decimals() {
+        let mut server = mockito::Server::new_async().await;
+        let mint = Pubkey::new_unique();
+        let mock = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "method": "getTokenSupply",
+                "params": [mint.to_string()],
+            })))
+            .with_body(
+                r#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":1},"value":{"amount":"1000","decimals":6,"uiAmount":0.001,"uiAmountString":"0.001"}}}"#,
+            )
+            .create_async()
+            .await;
+
+        let client = client(&server);
+        assert_eq!(client.get_token_decimals(&mint).await, Ok(6));
+        // Native SOL needs no request
+        let native = Pubkey::from_str(NATIVE_MINT).unwrap();
+        assert_eq!(client.get_token_decimals(&native).await, Ok(9));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_rpc_errors_are_returned() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/")
+            .with_body(r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32602,"message":"Invalid param: could not find mint"}}"#)
+            .create_async()
+            .await;
+
+        let error = client(&server)
+            .get_token_decimals(&Pubkey::new_unique())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "RPC error -32602 in getTokenSupply: Invalid param: could not find mint"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_confirm_transaction_waits_for_commitment() {
+        let mut server = mockito::Server::new_async().await;
+        let signature = Signature::new_unique();
+        let polls = std::sync::atomic::AtomicUsize::new(0);
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "method": "getSignatureStatuses",
+                "params": [[signature.to_string()]],
+            })))
+            .with_body_from_request(move |_| {
+                // Unknown, then processed, then confirmed
+                let status = match polls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+                    0 => serde_json::Value::Null,
+                    1 => serde_json::json!({"slot": 5, "err": null, "confirmationStatus": "processed"}),
+                    _ => serde_json::json!({"slot": 5, "err": null, "confirmationStatus": "confirmed"}),
+                };
+                serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": {"context": {"slot": 6}, "value": [status]}})
+                    .to_string()
+                    .into()
+            })
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client = client(&server).with_confirmation_config(ConfirmationConfig {
+            poll_interval: Duration::from_millis(1),
+            ..Default::default()
+        });
+        assert_eq!(client.confirm_transaction(&signature).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_confirm_transaction_reports_failures() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/")
+            .with_body(r#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":6},"value":[{"slot":5,"err":{"InstructionError":[0,{"Custom":1}]},"confirmationStatus":"confirmed"}]}}"#)
+            .create_async()
+            .await;
+        let error = client(&server)
+            .confirm_transaction(&Signature::new_unique())
+            .await
+            .unwrap_err();
+        assert!(error.contains("failed"), "{}", error);
+
+        // Never seen at all: give up at the timeout
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/")
+            .with_body(r#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":6},"value":[null]}}"#)
+            .create_async()
+            .await;
+        let error = client(&server)
+            .with_confirmation_config(ConfirmationConfig {
+                commitment: Commitment::Finalized,
+                timeout: Duration::from_millis(20),
+                poll_interval: Duration::from_millis(5),
+            })
+            .confirm_transaction(&Signature::new_unique())
+            .await
+            .unwrap_err();
+        assert!(error.contains("was not finalized"), "{}", error);
+    }
+}
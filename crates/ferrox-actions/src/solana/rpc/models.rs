@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::solana::to_ui_amount;
+
+#[derive(Debug, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+/// A result the node reports together with the slot it was read at.
+#[derive(Debug, Deserialize)]
+pub struct Contextual<T> {
+    pub value: T,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenAmount {
+    pub amount: String,
+    pub decimals: u8,
+    pub ui_amount_string: Option<String>,
+}
+
+impl TokenAmount {
+    /// The amount in base units.
+    pub fn units(&self) -> u64 {
+        self.amount.parse().unwrap_or_default()
+    }
+
+    pub fn ui_amount(&self) -> f64 {
+        to_ui_amount(self.units(), self.decimals)
+    }
+}
+
+/// A token account and its balance, as listed by `getTokenLargestAccounts`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenAccountBalance {
+    pub address: String,
+    #[serde(flatten)]
+    pub amount: TokenAmount,
+}
+
+/// An account's raw state.
+#[derive(Debug, Clone)]
+pub struct AccountInfo {
+    pub data: Vec<u8>,
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+/// An account as `jsonParsed` encoding presents it: `data` is the decoded
+/// state for accounts of programs the node knows (tokens, stake, vote...),
+/// otherwise `[base64, "base64"]`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedAccount {
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub data: serde_json::Value,
+    #[serde(default)]
+    pub space: Option<u64>,
+}
+
+/// A token account owned by a wallet.
+#[derive(Debug, Clone)]
+pub struct TokenAccount {
+    pub address: String,
+    pub mint: String,
+    pub owner: String,
+    pub amount: TokenAmount,
+    pub token_program: Pubkey,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureInfo {
+    pub signature: String,
+    pub slot: u64,
+    pub err: Option<serde_json::Value>,
+    pub memo: Option<String>,
+    pub block_time: Option<i64>,
+    pub confirmation_status: Option<String>,
+}
+
+/// A transaction from `getTransaction` in `jsonParsed` encoding.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodedTransaction {
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub meta: Option<TransactionMeta>,
+    pub transaction: ParsedTransaction,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionMeta {
+    pub err: Option<serde_json::Value>,
+    pub fee: u64,
+    pub pre_balances: Vec<u64>,
+    pub post_balances: Vec<u64>,
+    #[serde(default)]
+    pub pre_token_balances: Vec<TokenBalance>,
+    #[serde(default)]
+    pub post_token_balances: Vec<TokenBalance>,
+    #[serde(default)]
+    pub log_messages: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenBalance {
+    pub account_index: usize,
+    pub mint: String,
+    pub owner: Option<String>,
+    pub ui_token_amount: TokenAmount,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParsedTransaction {
+    pub signatures: Vec<String>,
+    pub message: ParsedMessage,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedMessage {
+    pub account_keys: Vec<ParsedAccountKey>,
+    /// Instructions, decoded into `{program, parsed: {type, info}}` for
+    /// programs the node knows and `{programId, accounts, data}` otherwise.
+    pub instructions: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParsedAccountKey {
+    pub pubkey: String,
+    pub signer: bool,
+    pub writable: bool,
+}
+
+/// How settled a transaction or state must be: `Processed` by the node
+/// answering, `Confirmed` by a supermajority, or `Finalized` and rooted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    Processed,
+    #[default]
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Processed => "processed",
+            Self::Confirmed => "confirmed",
+            Self::Finalized => "finalized",
+        }
+    }
+}
+
+impl std::str::FromStr for Commitment {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "processed" => Ok(Self::Processed),
+            "confirmed" => Ok(Self::Confirmed),
+            "finalized" => Ok(Self::Finalized),
+            _ => Err(format!("Unknown commitment '{}'", value)),
+        }
+    }
+}
+
+/// Where a submitted transaction stands, from `getSignatureStatuses`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureStatus {
+    pub slot: u64,
+    pub err: Option<serde_json::Value>,
+    pub confirmation_status: Option<Commitment>,
+}
+
+/// What `simulateTransaction` reports.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationResult {
+    pub err: Option<serde_json::Value>,
+    #[serde(default)]
+    pub logs: Option<Vec<String>>,
+    #[serde(default)]
+    pub units_consumed: Option<u64>,
+}
@@ -0,0 +1,313 @@
+//! Dry runs of transactions before the user is asked to confirm them: what
+//! each account the transaction writes would gain or lose, and why it would
+//! fail.
+
+use crate::{
+    output::{self, Table},
+    solana::{
+        mint_label,
+        rpc::{client::SolanaRpcClient, models::AccountInfo, BalanceChange, TokenBalanceChange},
+        to_ui_amount,
+        token::{is_token_account, is_token_program, token_account_amount},
+    },
+};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
+
+/// Program log lines kept in a preview.
+const PREVIEW_LOG_LINES: usize = 10;
+
+/// The outcome of simulating a transaction, as shown in a preview.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationPreview {
+    pub success: bool,
+    pub error: Option<String>,
+    pub units_consumed: Option<u64>,
+    /// SOL gained or lost by each account, fees aside.
+    pub sol_changes: Vec<BalanceChange>,
+    pub token_changes: Vec<TokenBalanceChange>,
+    /// The last program log lines.
+    pub logs: Vec<String>,
+}
+
+impl SimulationPreview {
+    /// Refuses a transaction that would fail, quoting its error and logs, so
+    /// the preview offers nothing to confirm. Otherwise attaches the balance
+    /// changes as a table.
+    pub fn confirmable(self) -> Result<Self, String> {
+        if let Some(error) = &self.error {
+            let mut message = format!(
+                "The transaction would fail, so there is nothing to confirm: {}",
+                error
+            );
+            if !self.logs.is_empty() {
+                message.push_str("\nProgram logs:\n");
+                message.push_str(&self.logs.join("\n"));
+            }
+            return Err(message);
+        }
+        let rows = self
+            .sol_changes
+            .iter()
+            .map(|change| (&change.account, "SOL".to_string(), change.change))
+            .chain(self.token_changes.iter().filter_map(|change| {
+                Some((
+                    change.owner.as_ref()?,
+                    mint_label(&change.mint),
+                    change.change,
+                ))
+            }));
+        let table = rows.fold(
+            Table::new(["Account", "Token", "Change"]).title("Simulated balance changes"),
+            |table, (account, token, change)| {
+                table.row([mint_label(account), token, format!("{:+}", change)])
+            },
+        );
+        if !table.rows.is_empty() {
+            output::attach_table(table);
+        }
+        Ok(self)
+    }
+}
+
+/// Simulates `transaction` and compares the accounts it writes before and
+/// after. Accounts loaded from address lookup tables are not compared.
+pub async fn simulate(
+    rpc: &SolanaRpcClient,
+    transaction: &VersionedTransaction,
+) -> Result<SimulationPreview, String> {
+    let message = &transaction.message;
+    let writable: Vec<Pubkey> = message
+        .static_account_keys()
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| message.is_maybe_writable(*index))
+        .map(|(_, key)| *key)
+        .collect();
+    let before = rpc.get_multiple_accounts(&writable).await?;
+    let (result, after) = rpc
+        .simulate_transaction_with_accounts(transaction, &writable)
+        .await?;
+    let logs = result.logs.unwrap_or_default();
+    let logs = logs[logs.len().saturating_sub(PREVIEW_LOG_LINES)..].to_vec();
+
+    if let Some(err) = result.err {
+        return Ok(SimulationPreview {
+            success: false,
+            error: Some(err.to_string()),
+            units_consumed: result.units_consumed,
+            sol_changes: Vec::new(),
+            token_changes: Vec::new(),
+            logs,
+        });
+    }
+
+    let lamports = |account: &Option<AccountInfo>| account.as_ref().map_or(0, |a| a.lamports);
+    let mut sol_changes = Vec::new();
+    let mut token_deltas = Vec::new();
+    for ((address, before), after) in writable.iter().zip(&before).zip(&after) {
+        let change = lamports(after) as i128 - lamports(before) as i128;
+        if change != 0 {
+            sol_changes.push(BalanceChange {
+                account: address.to_string(),
+                change: change as f64 / 1e9,
+            });
+        }
+        if let Some((mint, owner, change)) = token_delta(before.as_ref(), after.as_ref()) {
+            token_deltas.push((mint, owner, change));
+        }
+    }
+
+    let mut mints: Vec<Pubkey> = token_deltas.iter().map(|(mint, _, _)| *mint).collect();
+    mints.sort_unstable();
+    mints.dedup();
+    let decimals: Vec<(Pubkey, u8)> = if mints.is_empty() {
+        Vec::new()
+    } else {
+        mints
+            .iter()
+            .zip(rpc.get_multiple_accounts(&mints).await?)
+            .map(|(mint, account)| {
+                let decimals = account
+                    .and_then(|account| account.data.get(44).copied())
+                    .unwrap_or_default();
+                (*mint, decimals)
+            })
+            .collect()
+    };
+    let token_changes = token_deltas
+        .into_iter()
+        .map(|(mint, owner, change)| {
+            let decimals = decimals
+                .iter()
+                .find(|(known, _)| *known == mint)
+                .map_or(0, |(_, decimals)| *decimals);
+            let ui = to_ui_amount(change.unsigned_abs() as u64, decimals);
+            TokenBalanceChange {
+                owner: Some(owner.to_string()),
+                mint: mint.to_string(),
+                change: if change < 0 { -ui } else { ui },
+            }
+        })
+        .collect();
+
+    Ok(SimulationPreview {
+        success: true,
+        error: None,
+        units_consumed: result.units_consumed,
+        sol_changes,
+        token_changes,
+        logs,
+    })
+}
+
+/// The mint, owner and change in base units of a token account that the
+/// transaction changes, creates or closes.
+fn token_delta(
+    before: Option<&AccountInfo>,
+    after: Option<&AccountInfo>,
+) -> Option<(Pubkey, Pubkey, i128)> {
+    let token = |account: Option<&AccountInfo>| {
+        let account = account.filter(|account| {
+            is_token_program(&account.owner) && is_token_account(&account.data)
+        })?;
+        let mint = Pubkey::try_from(account.data.get(0..32)?).ok()?;
+        let owner = Pubkey::try_from(account.data.get(32..64)?).ok()?;
+        let amount = token_account_amount(&account.data).ok()?;
+        Some((mint, owner, amount))
+    };
+    let (before, after) = (token(before), token(after));
+    let (mint, owner, _) = after.or(before)?;
+    let amount = |state: Option<(Pubkey, Pubkey, u64)>| state.map_or(0, |(_, _, amount)| amount);
+    let change = amount(after) as i128 - amount(before) as i128;
+    (change != 0).then_some((mint, owner, change))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::{Quota, RateLimiter},
+        solana::token::{TOKEN_ACCOUNT_SIZE, TOKEN_PROGRAM_ID},
+    };
+    use mockito::Matcher;
+    use solana_sdk::{
+        hash::Hash,
+        message::{Message, VersionedMessage},
+        signature::{Keypair, Signature},
+        signer::Signer,
+        system_instruction, system_program,
+    };
+    use std::sync::Arc;
+
+    fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> AccountInfo {
+        let mut data = vec![0u8; TOKEN_ACCOUNT_SIZE];
+        data[..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        AccountInfo {
+            data,
+            lamports: 2_039_280,
+            owner: TOKEN_PROGRAM_ID,
+            executable: false,
+        }
+    }
+
+    #[test]
+    fn test_token_delta() {
+        let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let before = token_account(&mint, &owner, 5_000_000);
+        let after = token_account(&mint, &owner, 3_500_000);
+        assert_eq!(
+            token_delta(Some(&before), Some(&after)),
+            Some((mint, owner, -1_500_000))
+        );
+        // A token account the transaction creates
+        assert_eq!(
+            token_delta(None, Some(&after)),
+            Some((mint, owner, 3_500_000))
+        );
+        assert_eq!(token_delta(Some(&before), Some(&before)), None);
+        let mint_account = AccountInfo {
+            data: vec![0; 82],
+            ..before
+        };
+        assert_eq!(token_delta(None, Some(&mint_account)), None);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_reports_balance_changes() {
+        let mut server = mockito::Server::new_async().await;
+        let (payer, recipient) = (Keypair::new().pubkey(), Keypair::new().pubkey());
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::Legacy(Message::new_with_blockhash(
+                &[system_instruction::transfer(
+                    &payer,
+                    &recipient,
+                    250_000_000,
+                )],
+                Some(&payer),
+                &Hash::default(),
+            )),
+        };
+        let account = |lamports: u64| serde_json::json!({"data": ["", "base64"], "executable": false, "lamports": lamports, "owner": system_program::id().to_string()});
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "method": "getMultipleAccounts",
+                "params": [[payer.to_string(), recipient.to_string()]],
+            })))
+            .with_body(
+                serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": {"context": {"slot": 1}, "value": [account(1_000_000_000), null]}})
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        let simulation = server
+            .mock("POST", "/")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::PartialJson(serde_json::json!({"method": "simulateTransaction"})),
+                Matcher::Regex(format!(r#""addresses":\["{}","{}"\]"#, payer, recipient)),
+            ]))
+            .with_body(
+                serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": {"context": {"slot": 1}, "value": {
+                    "err": null,
+                    "logs": ["Program 11111111111111111111111111111111 success"],
+                    "unitsConsumed": 150,
+                    "accounts": [account(750_000_000), account(250_000_000)]
+                }}})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let rpc = SolanaRpcClient::new(server.url())
+            .with_rate_limiter(Arc::new(RateLimiter::new(Quota::per_second(100))));
+
+        let preview = simulate(&rpc, &transaction).await.unwrap();
+        simulation.assert_async().await;
+        assert!(preview.success);
+        assert_eq!(preview.units_consumed, Some(150));
+        assert_eq!(preview.sol_changes.len(), 2);
+        assert_eq!(preview.sol_changes[0].account, payer.to_string());
+        assert_eq!(preview.sol_changes[0].change, -0.25);
+        assert_eq!(preview.sol_changes[1].change, 0.25);
+        assert!(preview.token_changes.is_empty());
+        assert!(preview.confirmable().is_ok());
+    }
+
+    #[test]
+    fn test_failed_simulation_is_not_confirmable() {
+        let preview = SimulationPreview {
+            success: false,
+            error: Some(r#"{"InstructionError":[0,{"Custom":6001}]}"#.to_string()),
+            units_consumed: Some(20_000),
+            sol_changes: Vec::new(),
+            token_changes: Vec::new(),
+            logs: vec!["Program log: Error: slippage exceeded".to_string()],
+        };
+        let error = preview.confirmable().unwrap_err();
+        assert!(error.contains("Custom\":6001"), "{}", error);
+        assert!(error.ends_with("Program log: Error: slippage exceeded"));
+    }
+}
@@ -0,0 +1,88 @@
+//! Resolving Solana Name Service `.sol` domains to the wallet that owns them.
+//!
+//! A domain's record lives at an address derived from the SHA-256 of its
+//! name under its parent: the `.sol` root for `name.sol`, and `name.sol`
+//! itself for `sub.name.sol`. The record starts with the parent, owner and
+//! class keys.
+
+use crate::solana::rpc::client::SolanaRpcClient;
+use sha2::{Digest, Sha256};
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+pub const NAME_PROGRAM_ID: Pubkey = pubkey!("namesLPneVptA9Z5rqUDD9tMTWEJwofgaYwp8cawRkX");
+/// The record of the `.sol` top-level domain.
+pub const SOL_TLD: Pubkey = pubkey!("58PwtjSDuFHuUkYjH9BYnnQKHfwo9reZhC2zMJv9JPkx");
+
+const HASH_PREFIX: &str = "SPL Name Service";
+/// Parent, owner and class keys.
+const HEADER_LEN: usize = 96;
+
+/// Whether `name` is written as a `.sol` domain.
+pub fn is_domain(name: &str) -> bool {
+    name.trim().to_lowercase().ends_with(".sol")
+}
+
+fn name_account(name: &str, parent: &Pubkey) -> Pubkey {
+    let hashed = Sha256::digest(format!("{}{}", HASH_PREFIX, name).as_bytes());
+    Pubkey::find_program_address(
+        &[&hashed, Pubkey::default().as_ref(), parent.as_ref()],
+        &NAME_PROGRAM_ID,
+    )
+    .0
+}
+
+/// The record address of `domain`, such as `bonfida.sol` or
+/// `dex.bonfida.sol`.
+pub fn domain_key(domain: &str) -> Result<Pubkey, String> {
+    let domain = domain.trim().to_lowercase();
+    let labels: Vec<&str> = domain
+        .strip_suffix(".sol")
+        .unwrap_or(&domain)
+        .split('.')
+        .collect();
+    match labels[..] {
+        [name] if !name.is_empty() => Ok(name_account(name, &SOL_TLD)),
+        [sub, name] if !sub.is_empty() && !name.is_empty() => Ok(name_account(
+            &format!("\0{}", sub),
+            &name_account(name, &SOL_TLD),
+        )),
+        _ => Err(format!("Invalid .sol domain '{}'", domain)),
+    }
+}
+
+/// The wallet that owns `domain`.
+pub async fn resolve(rpc: &SolanaRpcClient, domain: &str) -> Result<Pubkey, String> {
+    let key = domain_key(domain)?;
+    let account = rpc
+        .get_account_info(&key)
+        .await?
+        .ok_or_else(|| format!("{} is not registered", domain.trim()))?;
+    if account.owner != NAME_PROGRAM_ID || account.data.len() < HEADER_LEN {
+        return Err(format!("{} has no valid name record", domain.trim()));
+    }
+    Pubkey::try_from(&account.data[32..64])
+        .map_err(|_| format!("{} has no valid name record", domain.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_key() {
+        assert_eq!(
+            domain_key("Bonfida.sol").unwrap().to_string(),
+            "Crf8hzfthWGbGbLTVCiqRqV5MVnbpHB1L9KQMd6gsinb"
+        );
+        assert_eq!(
+            domain_key("bonfida").unwrap(),
+            domain_key("bonfida.sol").unwrap()
+        );
+        assert_ne!(
+            domain_key("dex.bonfida.sol").unwrap(),
+            domain_key("bonfida.sol").unwrap()
+        );
+        assert!(domain_key(".sol").is_err());
+        assert!(domain_key("a.b.c.sol").is_err());
+    }
+}
@@ -0,0 +1,836 @@
+//! Getting transactions on chain: sizing the compute budget by simulation,
+//! bidding a priority fee from recent fees, and rebroadcasting until the
+//! transaction lands or its blockhash expires.
+
+use crate::solana::{
+    explorer_url,
+    rpc::{client::SolanaRpcClient, models::SignatureStatus},
+    simulation::{self, SimulationPreview},
+};
+use ferrox_wallet::WalletSigner;
+use solana_sdk::{
+    compute_budget::{self, ComputeBudgetInstruction},
+    hash::Hash,
+    instruction::Instruction,
+    message::{Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Most compute units a transaction may use.
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+const DEFAULT_PRIORITY_FEE_PERCENTILE: u8 = 75;
+/// Most a transaction pays in priority fees by default, 0.001 SOL.
+const DEFAULT_MAX_PRIORITY_FEE_LAMPORTS: u64 = 1_000_000;
+/// Program log lines quoted when a transaction fails.
+const FAILURE_LOG_LINES: usize = 8;
+
+/// How [`TransactionSubmitter`] prices and resubmits transactions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmitConfig {
+    /// Percentile of the compute-unit prices recently paid to write the
+    /// same accounts that the transaction bids.
+    pub priority_fee_percentile: u8,
+    /// Cap on the priority fee of one transaction, in lamports.
+    pub max_priority_fee_lamports: u64,
+    /// Compute units allowed on top of what simulation used, in percent.
+    pub compute_unit_margin_percent: u64,
+    /// How often a transaction that has not landed is sent again.
+    pub rebroadcast_interval: Duration,
+    /// Transactions built in total when earlier ones expire unseen.
+    pub max_attempts: u32,
+}
+
+impl Default for SubmitConfig {
+    fn default() -> Self {
+        Self {
+            priority_fee_percentile: DEFAULT_PRIORITY_FEE_PERCENTILE,
+            max_priority_fee_lamports: DEFAULT_MAX_PRIORITY_FEE_LAMPORTS,
+            compute_unit_margin_percent: 10,
+            rebroadcast_interval: Duration::from_secs(2),
+            max_attempts: 3,
+        }
+    }
+}
+
+impl SubmitConfig {
+    /// Takes the priority fee cap from `SOLANA_MAX_PRIORITY_FEE_LAMPORTS`
+    /// when it is set.
+    pub fn from_env() -> Self {
+        let config = Self::default();
+        match std::env::var("SOLANA_MAX_PRIORITY_FEE_LAMPORTS")
+            .ok()
+            .and_then(|max| max.parse().ok())
+        {
+            Some(max_priority_fee_lamports) => Self {
+                max_priority_fee_lamports,
+                ..config
+            },
+            None => config,
+        }
+    }
+}
+
+/// How a submission ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionOutcome {
+    /// Reached the commitment the RPC client waits for.
+    Confirmed { signature: Signature, slot: u64 },
+    /// Failed in simulation, before anything was sent (`signature` is
+    /// `None`), or on chain, where the fee was still paid.
+    Failed {
+        signature: Option<Signature>,
+        error: String,
+        logs: Vec<String>,
+    },
+    /// The blockhash expired before the transaction landed, so it never
+    /// will; nothing was spent.
+    Expired { signature: Signature },
+    /// Sent, but checking its status failed, so it may still land.
+    Unknown { signature: Signature, error: String },
+}
+
+impl TransactionOutcome {
+    pub fn signature(&self) -> Option<&Signature> {
+        match self {
+            Self::Confirmed { signature, .. }
+            | Self::Expired { signature }
+            | Self::Unknown { signature, .. } => Some(signature),
+            Self::Failed { signature, .. } => signature.as_ref(),
+        }
+    }
+
+    /// Whether the transaction moved funds or still may, so a spend
+    /// approved for it has to stay counted.
+    pub fn may_have_landed(&self) -> bool {
+        matches!(self, Self::Confirmed { .. } | Self::Unknown { .. })
+    }
+
+    /// The signature of a confirmed transaction, or a message telling the
+    /// user why it did not go through.
+    pub fn into_result(self) -> Result<Signature, String> {
+        match self {
+            Self::Confirmed { signature, .. } => Ok(signature),
+            Self::Failed {
+                signature,
+                error,
+                logs,
+            } => {
+                let mut message = match signature {
+                    Some(signature) => format!(
+                        "The transaction failed on chain ({}): {}",
+                        explorer_url(&signature.to_string()),
+                        error
+                    ),
+                    None => format!(
+                        "The transaction would fail, so it was not sent: {}",
+                        error
+                    ),
+                };
+                if !logs.is_empty() {
+                    message.push_str("\nProgram logs:\n");
+                    message.push_str(&logs[logs.len().saturating_sub(FAILURE_LOG_LINES)..].join("\n"));
+                }
+                Err(message)
+            }
+            Self::Expired { signature } => Err(format!(
+                "The transaction {} expired before it was confirmed, so nothing was spent. Try again",
+                explorer_url(&signature.to_string())
+            )),
+            Self::Unknown { signature, error } => Err(format!(
+                "The transaction {} was sent, but its outcome is unknown ({}). Check it before trying again",
+                explorer_url(&signature.to_string()),
+                error
+            )),
+        }
+    }
+}
+
+/// Submits transactions for actions, waiting for the commitment and
+/// polling at the interval of the RPC client's
+/// [`ConfirmationConfig`](crate::solana::rpc::client::ConfirmationConfig).
+#[derive(Debug, Clone)]
+pub struct TransactionSubmitter {
+    rpc: SolanaRpcClient,
+    config: SubmitConfig,
+}
+
+impl TransactionSubmitter {
+    /// Uses the settings from [`SubmitConfig::from_env`].
+    pub fn new(rpc: SolanaRpcClient) -> Self {
+        Self {
+            rpc,
+            config: SubmitConfig::from_env(),
+        }
+    }
+
+    pub fn with_config(mut self, config: SubmitConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn rpc(&self) -> &SolanaRpcClient {
+        &self.rpc
+    }
+
+    pub fn config(&self) -> &SubmitConfig {
+        &self.config
+    }
+
+    /// Builds a transaction of `instructions` paid by `payer`, sizes its
+    /// compute budget and priority fee unless the instructions already set
+    /// them, and submits it until it lands. A transaction that expires
+    /// unseen is rebuilt on a fresh blockhash, up to
+    /// [`SubmitConfig::max_attempts`] times.
+    pub async fn submit(
+        &self,
+        payer: &dyn WalletSigner,
+        instructions: &[Instruction],
+    ) -> Result<TransactionOutcome, String> {
+        let instructions = if has_compute_budget(instructions) {
+            instructions.to_vec()
+        } else {
+            let simulation = self
+                .rpc
+                .simulate_transaction(&unsigned(&payer.pubkey(), &with_max_compute(instructions)))
+                .await?;
+            if let Some(err) = simulation.err {
+                return Ok(TransactionOutcome::Failed {
+                    signature: None,
+                    error: err.to_string(),
+                    logs: simulation.logs.unwrap_or_default(),
+                });
+            }
+            self.with_compute_budget(&payer.pubkey(), instructions, simulation.units_consumed)
+                .await?
+        };
+
+        let mut attempt = 1;
+        loop {
+            let blockhash = self.rpc.get_latest_blockhash().await?;
+            let message =
+                Message::new_with_blockhash(&instructions, Some(&payer.pubkey()), &blockhash);
+            let transaction = payer
+                .sign_transaction(VersionedTransaction {
+                    signatures: Vec::new(),
+                    message: VersionedMessage::Legacy(message),
+                })
+                .await
+                .map_err(|e| format!("Failed to sign transaction: {}", e))?;
+            let outcome = self.submit_signed(&transaction).await?;
+            if matches!(outcome, TransactionOutcome::Expired { .. })
+                && attempt < self.config.max_attempts
+            {
+                attempt += 1;
+                continue;
+            }
+            return Ok(outcome);
+        }
+    }
+
+    /// Simulates `instructions` as `payer` would send them, for the preview
+    /// of an action, without signing them.
+    pub async fn preview(
+        &self,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+    ) -> Result<SimulationPreview, String> {
+        let instructions = if has_compute_budget(instructions) {
+            instructions.to_vec()
+        } else {
+            with_max_compute(instructions)
+        };
+        simulation::simulate(&self.rpc, &unsigned(payer, &instructions)).await
+    }
+
+    /// Submits a transaction built and signed elsewhere, such as a swap
+    /// from an aggregator, and rebroadcasts it until it lands or its
+    /// blockhash expires. It is not rebuilt, since its signers may not be
+    /// at hand. Once it is sent, a failure to follow it up is reported as
+    /// [`TransactionOutcome::Unknown`] rather than as an error.
+    pub async fn submit_signed(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<TransactionOutcome, String> {
+        let signature = self.rpc.send_transaction(transaction).await?;
+        Ok(self
+            .follow(transaction, signature)
+            .await
+            .unwrap_or_else(|error| TransactionOutcome::Unknown { signature, error }))
+    }
+
+    /// Polls a sent transaction, rebroadcasting it, until it lands or its
+    /// blockhash expires.
+    async fn follow(
+        &self,
+        transaction: &VersionedTransaction,
+        signature: Signature,
+    ) -> Result<TransactionOutcome, String> {
+        let blockhash = *transaction.message.recent_blockhash();
+        let confirmation = self.rpc.confirmation_config().clone();
+        let mut last_broadcast = Instant::now();
+        loop {
+            tokio::time::sleep(confirmation.poll_interval).await;
+            match self.status(&signature).await? {
+                Some(status) if status.err.is_some() => {
+                    return Ok(TransactionOutcome::Failed {
+                        signature: Some(signature),
+                        error: status.err.unwrap_or_default().to_string(),
+                        logs: self.logs(&signature).await,
+                    })
+                }
+                Some(status)
+                    if status
+                        .confirmation_status
+                        .is_some_and(|reached| reached >= confirmation.commitment) =>
+                {
+                    return Ok(TransactionOutcome::Confirmed {
+                        signature,
+                        slot: status.slot,
+                    })
+                }
+                // Landed, waiting for the commitment
+                Some(_) => {}
+                None => {
+                    if !self.rpc.is_blockhash_valid(&blockhash).await? {
+                        // It may have landed between the two requests
+                        if self.status(&signature).await?.is_none() {
+                            return Ok(TransactionOutcome::Expired { signature });
+                        }
+                    } else if last_broadcast.elapsed() >= self.config.rebroadcast_interval {
+                        // A dropped resend is retried on the next interval
+                        let _ = self.rpc.resend_transaction(transaction).await;
+                        last_broadcast = Instant::now();
+                    }
+                }
+            }
+        }
+    }
+
+    async fn status(&self, signature: &Signature) -> Result<Option<SignatureStatus>, String> {
+        Ok(self
+            .rpc
+            .get_signature_statuses(std::slice::from_ref(signature))
+            .await?
+            .into_iter()
+            .next()
+            .flatten())
+    }
+
+    /// Program logs of a landed transaction, if the node has them yet.
+    async fn logs(&self, signature: &Signature) -> Vec<String> {
+        self.rpc
+            .get_transaction(signature)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|transaction| transaction.meta)
+            .and_then(|meta| meta.log_messages)
+            .unwrap_or_default()
+    }
+
+    /// Prepends a compute-unit limit sized from `units_consumed` in
+    /// simulation and a compute-unit price bid from recent fees on the
+    /// accounts the transaction writes.
+    async fn with_compute_budget(
+        &self,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+        units_consumed: Option<u64>,
+    ) -> Result<Vec<Instruction>, String> {
+        let units = units_consumed
+            .map(|units| {
+                units.saturating_mul(self.config.compute_unit_margin_percent.saturating_add(100))
+                    / 100
+            })
+            .unwrap_or(MAX_COMPUTE_UNITS as u64)
+            .clamp(1, MAX_COMPUTE_UNITS as u64);
+        let mut writable = vec![*payer];
+        for account in instructions
+            .iter()
+            .flat_map(|instruction| &instruction.accounts)
+        {
+            if account.is_writable && !writable.contains(&account.pubkey) {
+                writable.push(account.pubkey);
+            }
+        }
+        let fees = self.rpc.get_recent_prioritization_fees(&writable).await?;
+        let price = percentile(fees, self.config.priority_fee_percentile).min(
+            self.config
+                .max_priority_fee_lamports
+                .saturating_mul(1_000_000)
+                / units,
+        );
+
+        let mut budgeted = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            units as u32,
+        )];
+        if price > 0 {
+            budgeted.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+        }
+        budgeted.extend_from_slice(instructions);
+        Ok(budgeted)
+    }
+}
+
+fn has_compute_budget(instructions: &[Instruction]) -> bool {
+    instructions
+        .iter()
+        .any(|instruction| instruction.program_id == compute_budget::id())
+}
+
+/// `instructions` allowed the most compute units, for simulation.
+fn with_max_compute(instructions: &[Instruction]) -> Vec<Instruction> {
+    [
+        &[ComputeBudgetInstruction::set_compute_unit_limit(
+            MAX_COMPUTE_UNITS,
+        )],
+        instructions,
+    ]
+    .concat()
+}
+
+/// `instructions` in a transaction with blank signatures, for simulation.
+fn unsigned(payer: &Pubkey, instructions: &[Instruction]) -> VersionedTransaction {
+    let message = Message::new_with_blockhash(instructions, Some(payer), &Hash::default());
+    VersionedTransaction {
+        signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+        message: VersionedMessage::Legacy(message),
+    }
+}
+
+/// The `percent`th percentile of `values`, or 0 when there are none.
+fn percentile(mut values: Vec<u64>, percent: u8) -> u64 {
+    if values.is_empty() {
+        return 0;
+    }
+    values.sort_unstable();
+    values[(values.len() - 1) * percent.min(100) as usize / 100]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::{
+        rpc::client::ConfirmationConfig,
+        test_support::{mock_method, rpc_client, rpc_result, rpc_url},
+    };
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use ferrox_wallet::LocalSigner;
+    use mockito::Matcher;
+    use solana_sdk::{signature::Keypair, system_instruction};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn submitter(server: &mockito::Server, config: SubmitConfig) -> TransactionSubmitter {
+        TransactionSubmitter::new(rpc_client(rpc_url(server)).with_confirmation_config(
+            ConfirmationConfig {
+                poll_interval: Duration::from_millis(1),
+                ..Default::default()
+            },
+        ))
+        .with_config(config)
+    }
+
+    /// Answers `getLatestBlockhash` with a new blockhash every time.
+    async fn mock_blockhash(server: &mut mockito::Server) {
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "getLatestBlockhash"}),
+            ))
+            .with_body_from_request(|_| {
+                rpc_result(serde_json::json!({
+                    "context": {"slot": 1},
+                    "value": {"blockhash": Hash::new_unique().to_string(), "lastValidBlockHeight": 3090}
+                }))
+                .into()
+            })
+            .create_async()
+            .await;
+    }
+
+    /// Answers `sendTransaction` with the signature of the transaction
+    /// sent, after handing it to `inspect`.
+    async fn mock_send(
+        server: &mut mockito::Server,
+        inspect: impl Fn(VersionedTransaction) + Send + Sync + 'static,
+    ) -> mockito::Mock {
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::PartialJson(serde_json::json!({"method": "sendTransaction"})),
+                Matcher::Regex("preflightCommitment".to_string()),
+            ]))
+            .with_body_from_request(move |request| {
+                let body: serde_json::Value =
+                    serde_json::from_slice(request.body().unwrap()).unwrap();
+                let bytes = BASE64.decode(body["params"][0].as_str().unwrap()).unwrap();
+                let transaction: VersionedTransaction = bincode::deserialize(&bytes).unwrap();
+                let signature = transaction.signatures[0].to_string();
+                inspect(transaction);
+                rpc_result(serde_json::json!(signature)).into()
+            })
+            .create_async()
+            .await
+    }
+
+    fn transfer(payer: &LocalSigner) -> Instruction {
+        system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1_000)
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(vec![], 75), 0);
+        assert_eq!(percentile(vec![40, 10, 30, 20, 50], 50), 30);
+        assert_eq!(percentile(vec![40, 10, 30, 20, 50], 75), 40);
+        assert_eq!(percentile(vec![40, 10, 30, 20, 50], 100), 50);
+    }
+
+    #[tokio::test]
+    async fn test_compute_budget_and_priority_fee_are_added() {
+        let mut server = mockito::Server::new_async().await;
+        let payer = LocalSigner::new(Keypair::new());
+        mock_method(
+            &mut server,
+            "simulateTransaction",
+            serde_json::json!({"context": {"slot": 1}, "value": {"err": null, "logs": [], "unitsConsumed": 10_000}}),
+        )
+        .await;
+        mock_method(
+            &mut server,
+            "getRecentPrioritizationFees",
+            serde_json::json!([
+                {"slot": 1, "prioritizationFee": 0},
+                {"slot": 2, "prioritizationFee": 1_000},
+                {"slot": 3, "prioritizationFee": 5_000},
+                {"slot": 4, "prioritizationFee": 2_000},
+                {"slot": 5, "prioritizationFee": 3_000}
+            ]),
+        )
+        .await;
+        mock_blockhash(&mut server).await;
+        let send = mock_send(&mut server, |transaction| {
+            assert!(transaction.verify_with_results().iter().all(|ok| *ok));
+            let instructions = transaction.message.instructions();
+            assert_eq!(instructions.len(), 3);
+            // 10% over what simulation used, at the 75th percentile price
+            assert_eq!(instructions[0].data[1..], 11_000u32.to_le_bytes());
+            assert_eq!(instructions[1].data[1..], 3_000u64.to_le_bytes());
+        })
+        .await;
+        mock_method(
+            &mut server,
+            "getSignatureStatuses",
+            serde_json::json!({"context": {"slot": 7}, "value": [{"slot": 6, "err": null, "confirmationStatus": "confirmed"}]}),
+        )
+        .await;
+
+        let outcome = submitter(&server, SubmitConfig::default())
+            .submit(&payer, &[transfer(&payer)])
+            .await
+            .unwrap();
+        send.assert_async().await;
+        assert!(matches!(
+            outcome,
+            TransactionOutcome::Confirmed { slot: 6, .. }
+        ));
+        assert!(outcome.into_result().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_priority_fee_is_capped() {
+        let mut server = mockito::Server::new_async().await;
+        let payer = LocalSigner::new(Keypair::new());
+        mock_method(
+            &mut server,
+            "simulateTransaction",
+            serde_json::json!({"context": {"slot": 1}, "value": {"err": null, "logs": [], "unitsConsumed": 100_000}}),
+        )
+        .await;
+        mock_method(
+            &mut server,
+            "getRecentPrioritizationFees",
+            serde_json::json!([{"slot": 1, "prioritizationFee": 50_000_000}]),
+        )
+        .await;
+
+        let submitter = submitter(
+            &server,
+            SubmitConfig {
+                compute_unit_margin_percent: 0,
+                max_priority_fee_lamports: 10_000,
+                ..Default::default()
+            },
+        );
+        let instructions = submitter
+            .with_compute_budget(&payer.pubkey(), &[transfer(&payer)], Some(100_000))
+            .await
+            .unwrap();
+        // 10,000 lamports over 100,000 units is 100,000 micro-lamports a unit
+        assert_eq!(
+            instructions[1],
+            ComputeBudgetInstruction::set_compute_unit_price(100_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_priority_fee_does_not_overflow() {
+        let mut server = mockito::Server::new_async().await;
+        let payer = LocalSigner::new(Keypair::new());
+        mock_method(
+            &mut server,
+            "getRecentPrioritizationFees",
+            serde_json::json!([{"slot": 1, "prioritizationFee": 50_000_000}]),
+        )
+        .await;
+
+        let submitter = submitter(
+            &server,
+            SubmitConfig {
+                max_priority_fee_lamports: u64::MAX,
+                ..Default::default()
+            },
+        );
+        let instructions = submitter
+            .with_compute_budget(&payer.pubkey(), &[transfer(&payer)], Some(100_000))
+            .await
+            .unwrap();
+        assert_eq!(
+            instructions[1],
+            ComputeBudgetInstruction::set_compute_unit_price(50_000_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failing_simulation_is_not_sent() {
+        let mut server = mockito::Server::new_async().await;
+        let payer = LocalSigner::new(Keypair::new());
+        mock_method(
+            &mut server,
+            "simulateTransaction",
+            serde_json::json!({"context": {"slot": 1}, "value": {
+                "err": {"InstructionError": [1, {"Custom": 1}]},
+                "logs": ["Program 11111111111111111111111111111111 invoke [1]", "Transfer: insufficient lamports 0, need 1000"],
+                "unitsConsumed": 150
+            }}),
+        )
+        .await;
+        let send = mock_send(&mut server, |_| {}).await;
+
+        let outcome = submitter(&server, SubmitConfig::default())
+            .submit(&payer, &[transfer(&payer)])
+            .await
+            .unwrap();
+        assert!(!send.matched_async().await);
+        let error = outcome.into_result().unwrap_err();
+        assert!(error.contains("was not sent"), "{}", error);
+        assert!(error.contains("insufficient lamports"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_failure_on_chain_reports_logs() {
+        let mut server = mockito::Server::new_async().await;
+        let payer = LocalSigner::new(Keypair::new());
+        mock_blockhash(&mut server).await;
+        mock_send(&mut server, |_| {}).await;
+        mock_method(
+            &mut server,
+            "getSignatureStatuses",
+            serde_json::json!({"context": {"slot": 7}, "value": [{"slot": 6, "err": {"InstructionError": [0, {"Custom": 6001}]}, "confirmationStatus": "processed"}]}),
+        )
+        .await;
+        mock_method(
+            &mut server,
+            "getTransaction",
+            serde_json::json!({
+                "slot": 6,
+                "meta": {"err": {}, "fee": 5000, "preBalances": [], "postBalances": [], "logMessages": ["Program log: Error: slippage exceeded"]},
+                "transaction": {"signatures": [], "message": {"accountKeys": [], "instructions": []}}
+            }),
+        )
+        .await;
+
+        // Instructions that set their own budget are not simulated
+        let outcome = submitter(&server, SubmitConfig::default())
+            .submit(
+                &payer,
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_limit(5_000),
+                    transfer(&payer),
+                ],
+            )
+            .await
+            .unwrap();
+        match &outcome {
+            TransactionOutcome::Failed {
+                signature, logs, ..
+            } => {
+                assert!(signature.is_some());
+                assert_eq!(logs, &["Program log: Error: slippage exceeded"]);
+            }
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+        assert!(outcome
+            .into_result()
+            .unwrap_err()
+            .contains("failed on chain"));
+    }
+
+    #[tokio::test]
+    async fn test_status_failure_after_sending_is_unknown() {
+        let mut server = mockito::Server::new_async().await;
+        let payer = LocalSigner::new(Keypair::new());
+        mock_blockhash(&mut server).await;
+        mock_send(&mut server, |_| {}).await;
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "getSignatureStatuses"}),
+            ))
+            .with_status(503)
+            .create_async()
+            .await;
+
+        let outcome = submitter(&server, SubmitConfig::default())
+            .submit(
+                &payer,
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_limit(5_000),
+                    transfer(&payer),
+                ],
+            )
+            .await
+            .unwrap();
+        assert!(matches!(outcome, TransactionOutcome::Unknown { .. }));
+        assert!(outcome.may_have_landed());
+        let error = outcome.into_result().unwrap_err();
+        assert!(error.contains("outcome is unknown"), "{}", error);
+        assert!(error.contains("solscan.io/tx/"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_unconfirmed_transaction_is_rebroadcast() {
+        let mut server = mockito::Server::new_async().await;
+        let payer = LocalSigner::new(Keypair::new());
+        mock_blockhash(&mut server).await;
+        mock_send(&mut server, |_| {}).await;
+        let resend = server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::PartialJson(serde_json::json!({"method": "sendTransaction"})),
+                Matcher::Regex("skipPreflight".to_string()),
+            ]))
+            .with_body(rpc_result(serde_json::json!(
+                Signature::default().to_string()
+            )))
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        mock_method(
+            &mut server,
+            "isBlockhashValid",
+            serde_json::json!({"context": {"slot": 7}, "value": true}),
+        )
+        .await;
+        let polls = AtomicUsize::new(0);
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "getSignatureStatuses"}),
+            ))
+            .with_body_from_request(move |_| {
+                let status = match polls.fetch_add(1, Ordering::SeqCst) {
+                    0..=2 => serde_json::Value::Null,
+                    _ => serde_json::json!({"slot": 9, "err": null, "confirmationStatus": "finalized"}),
+                };
+                rpc_result(serde_json::json!({"context": {"slot": 9}, "value": [status]})).into()
+            })
+            .create_async()
+            .await;
+
+        let outcome = submitter(
+            &server,
+            SubmitConfig {
+                rebroadcast_interval: Duration::ZERO,
+                ..Default::default()
+            },
+        )
+        .submit(
+            &payer,
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(5_000),
+                transfer(&payer),
+            ],
+        )
+        .await
+        .unwrap();
+        resend.assert_async().await;
+        assert!(matches!(
+            outcome,
+            TransactionOutcome::Confirmed { slot: 9, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expired_transaction_is_rebuilt() {
+        let mut server = mockito::Server::new_async().await;
+        let payer = LocalSigner::new(Keypair::new());
+        mock_blockhash(&mut server).await;
+        let signatures = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sent = signatures.clone();
+        let send = mock_send(&mut server, move |transaction| {
+            sent.lock().unwrap().push(transaction.signatures[0])
+        })
+        .await
+        .expect(2);
+        mock_method(
+            &mut server,
+            "getSignatureStatuses",
+            serde_json::json!({"context": {"slot": 9}, "value": [null]}),
+        )
+        .await;
+        mock_method(
+            &mut server,
+            "isBlockhashValid",
+            serde_json::json!({"context": {"slot": 9}, "value": false}),
+        )
+        .await;
+
+        let outcome = submitter(
+            &server,
+            SubmitConfig {
+                max_attempts: 2,
+                ..Default::default()
+            },
+        )
+        .submit(
+            &payer,
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(5_000),
+                transfer(&payer),
+            ],
+        )
+        .await
+        .unwrap();
+        send.assert_async().await;
+        let signatures = signatures.lock().unwrap();
+        // Each attempt is a new transaction on its own blockhash
+        assert_ne!(signatures[0], signatures[1]);
+        assert_eq!(
+            outcome,
+            TransactionOutcome::Expired {
+                signature: signatures[1]
+            }
+        );
+        assert!(outcome
+            .into_result()
+            .unwrap_err()
+            .contains("nothing was spent"));
+    }
+}
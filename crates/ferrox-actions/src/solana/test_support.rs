@@ -0,0 +1,126 @@
+//! Fixtures shared by the tests of the Solana actions. Tests mock the RPC
+//! node under `/rpc` of their mockito server, leaving the rest of it to
+//! the HTTP APIs an action calls.
+
+use crate::{
+    http::{Quota, RateLimiter},
+    solana::{rpc::client::SolanaRpcClient, wallet::solana_signer},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ferrox_wallet::simple_wallet_manager::SimpleWalletManager;
+use mockito::Matcher;
+use solana_sdk::{
+    message::{Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction,
+    transaction::VersionedTransaction,
+};
+use std::sync::Arc;
+
+/// A limiter loose enough not to slow tests down.
+pub(crate) fn limiter() -> Arc<RateLimiter> {
+    Arc::new(RateLimiter::new(Quota::per_second(1000)))
+}
+
+pub(crate) fn rpc_url(server: &mockito::Server) -> String {
+    format!("{}/rpc", server.url())
+}
+
+pub(crate) fn rpc_client(url: String) -> SolanaRpcClient {
+    SolanaRpcClient::new(url).with_rate_limiter(limiter())
+}
+
+/// The address of the wallet [`SimpleWalletManager`] gives user 42, who
+/// sends every test action.
+pub(crate) async fn wallet() -> Pubkey {
+    solana_signer(&SimpleWalletManager::new(), "42", None)
+        .await
+        .unwrap()
+        .pubkey()
+}
+
+pub(crate) fn rpc_result(result: serde_json::Value) -> String {
+    serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": result}).to_string()
+}
+
+/// Answers every call of `method` with `result`.
+pub(crate) async fn mock_method(
+    server: &mut mockito::Server,
+    method: &str,
+    result: serde_json::Value,
+) -> mockito::Mock {
+    server
+        .mock("POST", "/rpc")
+        .match_body(Matcher::PartialJson(serde_json::json!({"method": method})))
+        .with_body(rpc_result(result))
+        .create_async()
+        .await
+}
+
+/// Answers `getAccountInfo` for `address` with `data` owned by `owner`, or
+/// with no account.
+pub(crate) async fn mock_account(
+    server: &mut mockito::Server,
+    address: &Pubkey,
+    account: Option<(&[u8], &Pubkey)>,
+) -> mockito::Mock {
+    let value = account.map(|(data, owner)| {
+        serde_json::json!({
+            "data": [BASE64.encode(data), "base64"],
+            "executable": false,
+            "lamports": 2_039_280,
+            "owner": owner.to_string(),
+            "rentEpoch": 0,
+            "space": data.len()
+        })
+    });
+    server
+        .mock("POST", "/rpc")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "method": "getAccountInfo",
+            "params": [address.to_string(), {"encoding": "base64"}],
+        })))
+        .with_body(rpc_result(
+            serde_json::json!({"context": {"slot": 1}, "value": value}),
+        ))
+        .create_async()
+        .await
+}
+
+/// Simulates transactions successfully, with the accounts they touch
+/// holding `before` and, after the transaction, `after`.
+pub(crate) async fn mock_simulation(
+    server: &mut mockito::Server,
+    before: serde_json::Value,
+    after: serde_json::Value,
+) {
+    mock_method(
+        server,
+        "getMultipleAccounts",
+        serde_json::json!({"context": {"slot": 1}, "value": before}),
+    )
+    .await;
+    mock_method(
+        server,
+        "simulateTransaction",
+        serde_json::json!({"context": {"slot": 1}, "value": {"err": null, "logs": [], "unitsConsumed": 60000, "accounts": after}}),
+    )
+    .await;
+}
+
+/// A base64 transaction for `payer` to sign, as aggregators hand them out.
+pub(crate) fn unsigned_transaction(payer: &Pubkey) -> String {
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default()],
+        message: VersionedMessage::Legacy(Message::new(
+            &[system_instruction::transfer(
+                payer,
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(payer),
+        )),
+    };
+    BASE64.encode(bincode::serialize(&transaction).unwrap())
+}
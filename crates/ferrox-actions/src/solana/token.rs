@@ -0,0 +1,153 @@
+//! SPL token program ids, associated token accounts and token account state.
+
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Size of a token account. Associated accounts under Token-2022 also carry
+/// the immutable owner extension, which takes them to 170 bytes.
+pub const TOKEN_ACCOUNT_SIZE: usize = 165;
+pub const TOKEN_2022_ACCOUNT_SIZE: usize = 170;
+
+/// The token account of `owner` for `mint` that wallets look at by default.
+/// `token_program` is the program that owns the mint.
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Creates the associated token account of `owner` for `mint`, paid by
+/// `payer`, and does nothing if it already exists.
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(owner, mint, token_program), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![1],
+    }
+}
+
+/// Moves `amount` base units of `mint` from the token account `source` to
+/// `destination`, signed by `owner`. The program checks `decimals` against
+/// the mint, so a wrong mint or scale fails instead of moving the wrong
+/// amount.
+pub fn transfer_checked(
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    token_program: &Pubkey,
+) -> Instruction {
+    let mut data = vec![12];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+    Instruction {
+        program_id: *token_program,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data,
+    }
+}
+
+/// Whether `program` is one of the token programs.
+pub fn is_token_program(program: &Pubkey) -> bool {
+    *program == TOKEN_PROGRAM_ID || *program == TOKEN_2022_PROGRAM_ID
+}
+
+/// Whether `data` holds a token account rather than a mint. Token-2022
+/// accounts with extensions are longer and say which they are right after
+/// the base account.
+pub fn is_token_account(data: &[u8]) -> bool {
+    data.len() == TOKEN_ACCOUNT_SIZE || data.get(TOKEN_ACCOUNT_SIZE) == Some(&2)
+}
+
+/// Balance in base units held by a token account, read from its raw data
+/// (mint, owner, then the amount).
+pub fn token_account_amount(data: &[u8]) -> Result<u64, String> {
+    data.get(64..72)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| "Not a token account".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_associated_token_address() {
+        let owner = Pubkey::from_str("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM").unwrap();
+        let mint = Pubkey::from_str(crate::solana::USDC_MINT).unwrap();
+        let address = associated_token_address(&owner, &mint, &TOKEN_PROGRAM_ID);
+        let instruction =
+            create_associated_token_account_idempotent(&owner, &owner, &mint, &TOKEN_PROGRAM_ID);
+        assert_eq!(instruction.accounts[1].pubkey, address);
+        assert_eq!(instruction.data, vec![1]);
+        assert_ne!(
+            address,
+            associated_token_address(&owner, &mint, &TOKEN_2022_PROGRAM_ID)
+        );
+    }
+
+    #[test]
+    fn test_transfer_checked() {
+        let (source, mint, destination, owner) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let instruction = transfer_checked(
+            &source,
+            &mint,
+            &destination,
+            &owner,
+            1_500_000,
+            6,
+            &TOKEN_2022_PROGRAM_ID,
+        );
+        assert_eq!(instruction.program_id, TOKEN_2022_PROGRAM_ID);
+        assert_eq!(instruction.data[0], 12);
+        assert_eq!(instruction.data[1..9], 1_500_000u64.to_le_bytes());
+        assert_eq!(instruction.data[9], 6);
+        assert!(instruction.accounts[3].is_signer);
+    }
+
+    #[test]
+    fn test_token_account_amount() {
+        let mut data = vec![0u8; 165];
+        data[64..72].copy_from_slice(&1_234_567u64.to_le_bytes());
+        assert_eq!(token_account_amount(&data), Ok(1_234_567));
+        assert!(token_account_amount(&data[..40]).is_err());
+        assert!(is_token_account(&data));
+        assert!(!is_token_account(&data[..82]));
+    }
+}
@@ -0,0 +1,1046 @@
+//! Sending SOL and SPL tokens from the user's wallet.
+
+use crate::{
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
+    solana::{
+        address_book::{resolve_recipient, AddressBook},
+        explorer_url, mint_label, parse_mint,
+        policy::{Approval, Spend, SpendingPolicy},
+        rpc::client::SolanaRpcClient,
+        simulation::SimulationPreview,
+        submit::TransactionSubmitter,
+        to_base_units, to_ui_amount,
+        token::{
+            associated_token_address, create_associated_token_account_idempotent, is_token_account,
+            is_token_program, token_account_amount, transfer_checked, TOKEN_2022_ACCOUNT_SIZE,
+            TOKEN_2022_PROGRAM_ID, TOKEN_ACCOUNT_SIZE,
+        },
+        wallet::{check_confirmer, sender_id, solana_signer},
+        NATIVE_MINT,
+    },
+    AgentState,
+};
+use ferrox_wallet::{WalletManager, WalletSigner};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    instruction::Instruction,
+    message::{Message, VersionedMessage},
+    pubkey::Pubkey,
+    system_instruction, system_program,
+};
+use std::{str::FromStr, sync::Arc};
+
+/// Fee per signature when the node cannot price a message.
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+#[derive(Debug, Deserialize)]
+pub struct TransferParams {
+    recipient: String,
+    amount: f64,
+    mint: Option<String>,
+    account: Option<String>,
+}
+
+/// What the user confirms. `mint` is `None` for native SOL.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferPreview {
+    pub user_id: String,
+    pub wallet: String,
+    /// Name of the wallet account the user picked; `None` for their default.
+    pub account: Option<String>,
+    pub recipient: String,
+    /// The contact or `.sol` domain the user named the recipient by.
+    #[serde(default)]
+    pub recipient_name: Option<String>,
+    pub token: String,
+    pub mint: Option<String>,
+    pub amount: f64,
+    pub units: u64,
+    pub decimals: u8,
+    pub fee_sol: f64,
+    /// Most the transfer pays on top of `fee_sol` to land faster.
+    pub max_priority_fee_sol: f64,
+    /// Whether the recipient's token account is created, paid by the sender.
+    pub create_recipient_account: bool,
+    pub account_rent_sol: f64,
+    pub warnings: Vec<String>,
+    /// What the spending policy refuses about the transfer; it cannot be
+    /// confirmed while there are any.
+    #[serde(default)]
+    pub violations: Vec<String>,
+    pub simulation: SimulationPreview,
+}
+
+impl TransferPreview {
+    fn spend(&self) -> Spend {
+        Spend::new(&self.user_id)
+            .to(&self.recipient)
+            .amount(self.mint.as_deref().unwrap_or(NATIVE_MINT), self.amount)
+    }
+}
+
+/// The mint of an SPL token and the program that owns it.
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    mint: Pubkey,
+    program: Pubkey,
+    decimals: u8,
+}
+
+/// A transfer ready to sign, with what it costs the sender on top of the
+/// amount sent.
+struct PreparedTransfer {
+    instructions: Vec<Instruction>,
+    fee_lamports: u64,
+    rent_lamports: u64,
+    create_recipient_account: bool,
+    warnings: Vec<String>,
+}
+
+/// Everything the transfer action needs, cloned into each invocation.
+#[derive(Clone)]
+struct TransferContext<W> {
+    rpc: SolanaRpcClient,
+    submitter: TransactionSubmitter,
+    wallets: W,
+    book: AddressBook,
+    policy: SpendingPolicy,
+}
+
+impl<W> TransferContext<W> {
+    async fn token(&self, mint: &Pubkey) -> Result<Token, String> {
+        let account = self
+            .rpc
+            .get_account_info(mint)
+            .await?
+            .ok_or_else(|| format!("Mint {} does not exist", mint))?;
+        if !is_token_program(&account.owner) {
+            return Err(format!("{} is not a token mint", mint));
+        }
+        Ok(Token {
+            mint: *mint,
+            program: account.owner,
+            decimals: self.rpc.get_token_decimals(mint).await?,
+        })
+    }
+
+    /// Refuses recipients that cannot receive a transfer and warns about
+    /// ones that look like a mistake. Returns whether the recipient account
+    /// exists.
+    async fn check_recipient(
+        &self,
+        recipient: &Pubkey,
+        warnings: &mut Vec<String>,
+    ) -> Result<bool, String> {
+        let account = self.rpc.get_account_info(recipient).await?;
+        match &account {
+            None => warnings.push(format!(
+                "{} has never been used; check the address before confirming",
+                recipient
+            )),
+            Some(account) if account.executable => {
+                return Err(format!("{} is a program, not a wallet", recipient))
+            }
+            Some(account)
+                if is_token_program(&account.owner) && is_token_account(&account.data) =>
+            {
+                return Err(format!(
+                    "{} is a token account, not a wallet; send to the wallet that owns it",
+                    recipient
+                ))
+            }
+            Some(account) if is_token_program(&account.owner) => warnings.push(format!(
+                "{} is a token mint, not a wallet; anything sent to it is likely lost",
+                recipient
+            )),
+            Some(account) if account.owner != system_program::id() => warnings.push(format!(
+                "{} is an account of program {}, not a plain wallet",
+                recipient, account.owner
+            )),
+            Some(_) => {}
+        }
+        if !recipient.is_on_curve() {
+            warnings.push(format!(
+                "{} is a program-derived address that no private key controls",
+                recipient
+            ));
+        }
+        Ok(account.is_some())
+    }
+
+    /// Builds the transfer of `units` of `token`, or of lamports when
+    /// `token` is `None`, and checks the sender can pay for it.
+    async fn prepare(
+        &self,
+        sender: &Pubkey,
+        recipient: &Pubkey,
+        token: Option<Token>,
+        units: u64,
+    ) -> Result<PreparedTransfer, String> {
+        if sender == recipient {
+            return Err("The recipient is the sending wallet itself".to_string());
+        }
+        let mut warnings = Vec::new();
+        let recipient_exists = self.check_recipient(recipient, &mut warnings).await?;
+
+        let mut instructions = Vec::new();
+        let mut rent_lamports = 0;
+        let mut create_recipient_account = false;
+        match token {
+            None => {
+                if !recipient_exists {
+                    let minimum = self.rpc.get_minimum_balance_for_rent_exemption(0).await?;
+                    if units < minimum {
+                        return Err(format!(
+                            "{} has no balance yet, so it must be sent at least {} SOL",
+                            recipient,
+                            to_ui_amount(minimum, 9)
+                        ));
+                    }
+                }
+                instructions.push(system_instruction::transfer(sender, recipient, units));
+            }
+            Some(token) => {
+                let source = associated_token_address(sender, &token.mint, &token.program);
+                let balance = match self.rpc.get_account_info(&source).await? {
+                    Some(account) => token_account_amount(&account.data)?,
+                    None => 0,
+                };
+                if balance < units {
+                    return Err(format!(
+                        "The wallet holds only {} of this token",
+                        to_ui_amount(balance, token.decimals)
+                    ));
+                }
+                let destination = associated_token_address(recipient, &token.mint, &token.program);
+                if self.rpc.get_account_info(&destination).await?.is_none() {
+                    let space = if token.program == TOKEN_2022_PROGRAM_ID {
+                        TOKEN_2022_ACCOUNT_SIZE
+                    } else {
+                        TOKEN_ACCOUNT_SIZE
+                    };
+                    rent_lamports = self
+                        .rpc
+                        .get_minimum_balance_for_rent_exemption(space)
+                        .await?;
+                    create_recipient_account = true;
+                    instructions.push(create_associated_token_account_idempotent(
+                        sender,
+                        recipient,
+                        &token.mint,
+                        &token.program,
+                    ));
+                }
+                instructions.push(transfer_checked(
+                    &source,
+                    &token.mint,
+                    &destination,
+                    sender,
+                    units,
+                    token.decimals,
+                    &token.program,
+                ));
+            }
+        }
+
+        let blockhash = self.rpc.get_latest_blockhash().await?;
+        let message = Message::new_with_blockhash(&instructions, Some(sender), &blockhash);
+        let signatures = message.header.num_required_signatures as u64;
+        let fee_lamports = self
+            .rpc
+            .get_fee_for_message(&VersionedMessage::Legacy(message))
+            .await?
+            .unwrap_or(signatures * LAMPORTS_PER_SIGNATURE);
+        let lamports_needed =
+            fee_lamports + rent_lamports + if token.is_none() { units } else { 0 };
+        let lamports = self.rpc.get_balance(sender).await?;
+        if lamports < lamports_needed {
+            return Err(format!(
+                "The wallet holds {} SOL but this transfer needs {} SOL including fees",
+                to_ui_amount(lamports, 9),
+                to_ui_amount(lamports_needed, 9)
+            ));
+        }
+
+        Ok(PreparedTransfer {
+            instructions,
+            fee_lamports,
+            rent_lamports,
+            create_recipient_account,
+            warnings,
+        })
+    }
+
+    /// Submits `instructions` and waits for the commitment the RPC client is
+    /// configured with. The spend stays counted once the transfer was sent,
+    /// unless it is known not to have moved anything.
+    async fn submit(
+        &self,
+        signer: &dyn WalletSigner,
+        instructions: &[Instruction],
+        approval: Approval,
+    ) -> Result<String, String> {
+        let outcome = self.submitter.submit(signer, instructions).await?;
+        if outcome.may_have_landed() {
+            approval.commit();
+        }
+        let signature = outcome.into_result()?;
+        Ok(explorer_url(&signature.to_string()))
+    }
+}
+
+/// Parses the optional mint of a transfer; `None` and SOL mean native SOL.
+fn transfer_mint(mint: Option<&str>) -> Result<Option<Pubkey>, String> {
+    match mint.map(str::trim).filter(|mint| !mint.is_empty()) {
+        None => Ok(None),
+        Some(mint) => {
+            let mint = parse_mint(mint)?;
+            Ok((mint.to_string() != NATIVE_MINT).then_some(mint))
+        }
+    }
+}
+
+pub struct TransferActionGroup<S: Send + Sync + Clone + 'static> {
+    actions: Vec<Arc<FunctionAction<S>>>,
+}
+
+impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for TransferActionGroup<S> {
+    fn actions(&self) -> &[Arc<FunctionAction<S>>] {
+        &self.actions
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> TransferActionGroup<S> {
+    /// Builds the group on the endpoint and commitment configured in the
+    /// environment (see [`SolanaRpcClient::from_env`]), sending from the
+    /// wallets in `wallets` to addresses, `.sol` domains or contacts saved in
+    /// `book`, within the limits of `policy`.
+    pub fn new<W: WalletManager + 'static>(
+        wallets: W,
+        book: AddressBook,
+        policy: SpendingPolicy,
+    ) -> Self {
+        Self::with_client(SolanaRpcClient::from_env(), wallets, book, policy)
+    }
+
+    /// Uses `rpc` to submit transfers and to wait for them with its
+    /// [`ConfirmationConfig`](crate::solana::rpc::client::ConfirmationConfig).
+    pub fn with_client<W: WalletManager + 'static>(
+        rpc: SolanaRpcClient,
+        wallets: W,
+        book: AddressBook,
+        policy: SpendingPolicy,
+    ) -> Self {
+        let context = TransferContext {
+            submitter: TransactionSubmitter::new(rpc.clone()),
+            rpc,
+            wallets,
+            book,
+            policy,
+        };
+        let mut actions = Vec::new();
+
+        // Add transfer action
+        {
+            async fn preview_transfer<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: TransferContext<W>,
+                params: TransferParams,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<TransferPreview, String> {
+                let user_id = sender_id(&send_state)?;
+                let signer =
+                    solana_signer(&context.wallets, &user_id, params.account.as_deref()).await?;
+                let named =
+                    resolve_recipient(&context.rpc, &context.book, &user_id, &params.recipient)
+                        .await?;
+                let recipient = named.address;
+                let token = match transfer_mint(params.mint.as_deref())? {
+                    Some(mint) => Some(context.token(&mint).await?),
+                    None => None,
+                };
+                let decimals = token.map(|token| token.decimals).unwrap_or(9);
+                let units = to_base_units(params.amount, decimals)?;
+                let mut transfer = context
+                    .prepare(&signer.pubkey(), &recipient, token, units)
+                    .await?;
+                if !context.book.knows(&user_id, &recipient.to_string()) {
+                    transfer.warnings.insert(
+                        0,
+                        format!(
+                            "The user has not sent to {} before; check the address",
+                            recipient
+                        ),
+                    );
+                }
+                let simulation = context
+                    .submitter
+                    .preview(&signer.pubkey(), &transfer.instructions)
+                    .await?
+                    .confirmable()?;
+
+                let mut preview = TransferPreview {
+                    user_id,
+                    wallet: signer.pubkey().to_string(),
+                    account: params.account,
+                    recipient: recipient.to_string(),
+                    recipient_name: named.name,
+                    token: token
+                        .map(|token| mint_label(&token.mint.to_string()))
+                        .unwrap_or_else(|| "SOL".to_string()),
+                    mint: token.map(|token| token.mint.to_string()),
+                    amount: to_ui_amount(units, decimals),
+                    units,
+                    decimals,
+                    fee_sol: to_ui_amount(transfer.fee_lamports, 9),
+                    max_priority_fee_sol: to_ui_amount(
+                        context.submitter.config().max_priority_fee_lamports,
+                        9,
+                    ),
+                    create_recipient_account: transfer.create_recipient_account,
+                    account_rent_sol: to_ui_amount(transfer.rent_lamports, 9),
+                    warnings: transfer.warnings,
+                    violations: Vec::new(),
+                    simulation,
+                };
+                preview.violations = context.policy.check(&preview.spend());
+                Ok(preview)
+            }
+
+            async fn confirm_transfer<S: Send + Sync + Clone + 'static, W: WalletManager>(
+                context: TransferContext<W>,
+                preview: TransferPreview,
+                send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                check_confirmer(&send_state, &preview.user_id)?;
+                let signer = solana_signer(
+                    &context.wallets,
+                    &preview.user_id,
+                    preview.account.as_deref(),
+                )
+                .await?;
+                if signer.pubkey().to_string() != preview.wallet {
+                    return Err("The preview does not match this wallet; ask again".to_string());
+                }
+                let recipient = Pubkey::from_str(&preview.recipient)
+                    .map_err(|e| format!("Invalid recipient address: {}", e))?;
+                let token = match transfer_mint(preview.mint.as_deref())? {
+                    Some(mint) => {
+                        let token = context.token(&mint).await?;
+                        if token.decimals != preview.decimals {
+                            return Err(
+                                "The token changed since the preview; ask again".to_string()
+                            );
+                        }
+                        Some(token)
+                    }
+                    None => None,
+                };
+                let transfer = context
+                    .prepare(&signer.pubkey(), &recipient, token, preview.units)
+                    .await?;
+                let approval = context.policy.approve(&preview.spend())?;
+                let url = context
+                    .submit(signer.as_ref(), &transfer.instructions, approval)
+                    .await?;
+                // The transfer went through even if this fails to save
+                let _ = context
+                    .book
+                    .record_sent(&preview.user_id, &preview.recipient);
+
+                Ok(format!(
+                    "Sent {} {} to {}. Transaction: {}",
+                    preview.amount,
+                    preview.token,
+                    match &preview.recipient_name {
+                        Some(name) => format!("{} ({})", name, preview.recipient),
+                        None => preview.recipient.clone(),
+                    },
+                    url
+                ))
+            }
+
+            let action =
+                ActionBuilder::<_, TransferParams, serde_json::Value, S, TransferPreview, _>::new(
+                    "send_tokens",
+                    with_client(&context, preview_transfer),
+                    Some(with_client(&context, confirm_transfer)),
+                )
+                .description("Prepare sending SOL or an SPL token from the user's wallet to another address. This does not send anything by itself: the transfer is simulated and the user is shown the amount, network fee, balance changes, any token account the transfer creates and warnings about the recipient, such as a first-time recipient, with a Confirm button")
+                .parameter(
+                    "recipient",
+                    "Solana address, .sol domain or name of a contact in the user's address book to send to, exactly as the user gave it",
+                    "string",
+                    true,
+                )
+                .parameter("amount", "Amount to send, in whole tokens", "number", true)
+                .parameter(
+                    "mint",
+                    "Mint address of the SPL token to send, or USDC/USDT; leave out for SOL",
+                    "string",
+                    false,
+                )
+                .parameter(
+                    "account",
+                    "Name of the user's wallet account to use, such as \"trading\"; leave out for their default account",
+                    "string",
+                    false,
+                )
+                .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        Self { actions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::{
+        policy::PolicyConfig,
+        test_support::{
+            mock_account, mock_method, mock_simulation, rpc_client, rpc_result, rpc_url, wallet,
+        },
+        token::TOKEN_PROGRAM_ID,
+        USDC_MINT,
+    };
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use ferrox_wallet::simple_wallet_manager::SimpleWalletManager;
+    use mockito::Matcher;
+    use solana_sdk::{signature::Keypair, signer::Signer, transaction::VersionedTransaction};
+    use tokio::sync::Mutex;
+
+    fn group(server: &mockito::Server) -> TransferActionGroup<()> {
+        group_with(server, AddressBook::new(), SpendingPolicy::default())
+    }
+
+    fn group_with(
+        server: &mockito::Server,
+        book: AddressBook,
+        policy: SpendingPolicy,
+    ) -> TransferActionGroup<()> {
+        TransferActionGroup::with_client(
+            rpc_client(rpc_url(server)),
+            SimpleWalletManager::new(),
+            book,
+            policy,
+        )
+    }
+
+    fn send_action(group: &TransferActionGroup<()>) -> Arc<FunctionAction<()>> {
+        group.actions()[0].clone()
+    }
+
+    /// Simulation that succeeds, moving 1.25 SOL from the sender to the
+    /// recipient when the `lamports` they hold are given.
+    async fn mock_transfer_simulation(server: &mut mockito::Server, lamports: Option<(u64, u64)>) {
+        let account = |lamports: u64| serde_json::json!({"data": ["", "base64"], "executable": false, "lamports": lamports, "owner": system_program::id().to_string()});
+        let (before, after) = match lamports {
+            Some((sender, recipient)) => (
+                serde_json::json!([account(sender), account(recipient)]),
+                serde_json::json!([
+                    account(sender - 1_250_000_000),
+                    account(recipient + 1_250_000_000)
+                ]),
+            ),
+            None => (serde_json::json!([]), serde_json::Value::Null),
+        };
+        mock_simulation(server, before, after).await;
+    }
+
+    async fn mock_network(server: &mut mockito::Server, lamports: u64) {
+        mock_method(
+            server,
+            "getLatestBlockhash",
+            serde_json::json!({"context": {"slot": 1}, "value": {"blockhash": "EkSnNWid2cvwEVnVx9aBqawnmiCNiDgp3gUdkDPTKN1N", "lastValidBlockHeight": 3090}}),
+        )
+        .await;
+        mock_method(
+            server,
+            "getFeeForMessage",
+            serde_json::json!({"context": {"slot": 1}, "value": 5000}),
+        )
+        .await;
+        mock_method(
+            server,
+            "getBalance",
+            serde_json::json!({"context": {"slot": 1}, "value": lamports}),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_sol_transfer_is_sent_and_confirmed() {
+        let mut server = mockito::Server::new_async().await;
+        let recipient = Keypair::new().pubkey();
+        mock_account(&mut server, &recipient, Some((&[], &system_program::id()))).await;
+        mock_network(&mut server, 2_000_000_000).await;
+        mock_transfer_simulation(&mut server, Some((2_000_000_000, 1_000_000))).await;
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "getRecentPrioritizationFees"}),
+            ))
+            .with_body(r#"{"jsonrpc":"2.0","id":1,"result":[{"slot":1,"prioritizationFee":1000}]}"#)
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "getSignatureStatuses"}),
+            ))
+            .with_body(r#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":2},"value":[{"slot":2,"err":null,"confirmationStatus":"confirmed"}]}}"#)
+            .create_async()
+            .await;
+        let sender = wallet().await;
+        let send = server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "sendTransaction"}),
+            ))
+            .with_body_from_request(move |request| {
+                let body: serde_json::Value =
+                    serde_json::from_slice(request.body().unwrap()).unwrap();
+                let bytes = BASE64.decode(body["params"][0].as_str().unwrap()).unwrap();
+                let transaction: VersionedTransaction = bincode::deserialize(&bytes).unwrap();
+                assert!(transaction.verify_with_results().iter().all(|ok| *ok));
+                let keys = transaction.message.static_account_keys();
+                assert_eq!(keys[0], sender);
+                // After the compute unit limit and price
+                let transfer = &transaction.message.instructions()[2];
+                assert_eq!(
+                    keys[transfer.program_id_index as usize],
+                    system_program::id()
+                );
+                assert_eq!(keys[transfer.accounts[1] as usize], recipient);
+                assert_eq!(transfer.data[4..], 1_250_000_000u64.to_le_bytes());
+                rpc_result(serde_json::json!(transaction.signatures[0].to_string())).into()
+            })
+            .create_async()
+            .await;
+
+        let action = send_action(&group(&server));
+        let state = Arc::new(Mutex::new(()));
+        let preview = action
+            .execute(
+                serde_json::json!({"recipient": recipient.to_string(), "amount": 1.25}),
+                serde_json::json!({"from": {"id": 42}}),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        let preview: serde_json::Value = serde_json::from_str(&preview).unwrap();
+        assert_eq!(preview["token"], "SOL");
+        assert_eq!(preview["fee_sol"], 0.000005);
+        assert_eq!(preview["create_recipient_account"], false);
+        assert_eq!(
+            preview["warnings"],
+            serde_json::json!([format!(
+                "The user has not sent to {} before; check the address",
+                recipient
+            )])
+        );
+        assert_eq!(preview["simulation"]["success"], true);
+        assert_eq!(
+            preview["simulation"]["sol_changes"][1],
+            serde_json::json!({"account": recipient.to_string(), "change": 1.25})
+        );
+
+        let result = action
+            .confirm(preview, serde_json::json!({"from": {"id": 42}}), state)
+            .unwrap()
+            .await
+            .unwrap();
+        send.assert_async().await;
+        assert!(result.starts_with("Sent 1.25 SOL to"), "{}", result);
+    }
+
+    #[tokio::test]
+    async fn test_only_the_previewing_user_can_confirm() {
+        let mut server = mockito::Server::new_async().await;
+        let recipient = Keypair::new().pubkey();
+        mock_account(&mut server, &recipient, Some((&[], &system_program::id()))).await;
+        mock_network(&mut server, 2_000_000_000).await;
+        mock_transfer_simulation(&mut server, Some((2_000_000_000, 1_000_000))).await;
+        let send = server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "sendTransaction"}),
+            ))
+            .expect(0)
+            .create_async()
+            .await;
+
+        let action = send_action(&group(&server));
+        let state = Arc::new(Mutex::new(()));
+        let preview = action
+            .execute(
+                serde_json::json!({"recipient": recipient.to_string(), "amount": 1.25}),
+                serde_json::json!({"from": {"id": 42}}),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        let preview: serde_json::Value = serde_json::from_str(&preview).unwrap();
+
+        // Someone else in the chat pressing the button
+        let err = action
+            .confirm(preview, serde_json::json!({"from": {"id": 7}}), state)
+            .unwrap()
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Only the user who asked for this can confirm it");
+        send.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_token_transfer_creates_the_recipient_account() {
+        let mut server = mockito::Server::new_async().await;
+        let mint = Pubkey::from_str(USDC_MINT).unwrap();
+        let recipient = Keypair::new().pubkey();
+        let sender = wallet().await;
+        let mut token_account = vec![0u8; TOKEN_ACCOUNT_SIZE];
+        token_account[64..72].copy_from_slice(&50_000_000u64.to_le_bytes());
+        mock_account(&mut server, &mint, Some((&[0; 82], &TOKEN_PROGRAM_ID))).await;
+        mock_account(&mut server, &recipient, None).await;
+        mock_account(
+            &mut server,
+            &associated_token_address(&sender, &mint, &TOKEN_PROGRAM_ID),
+            Some((&token_account, &TOKEN_PROGRAM_ID)),
+        )
+        .await;
+        mock_account(
+            &mut server,
+            &associated_token_address(&recipient, &mint, &TOKEN_PROGRAM_ID),
+            None,
+        )
+        .await;
+        mock_method(
+            &mut server,
+            "getTokenSupply",
+            serde_json::json!({"context": {"slot": 1}, "value": {"amount": "1000000000", "decimals": 6, "uiAmountString": "1000"}}),
+        )
+        .await;
+        let rent = server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "method": "getMinimumBalanceForRentExemption",
+                "params": [165],
+            })))
+            .with_body(rpc_result(serde_json::json!(2_039_280)))
+            .create_async()
+            .await;
+        mock_network(&mut server, 100_000_000).await;
+        mock_transfer_simulation(&mut server, None).await;
+
+        let preview = send_action(&group(&server))
+            .execute(
+                serde_json::json!({"recipient": recipient.to_string(), "amount": 12.5, "mint": "USDC"}),
+                serde_json::json!({"from": {"id": 42}}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+            .unwrap();
+        rent.assert_async().await;
+        let preview: TransferPreview = serde_json::from_str(&preview).unwrap();
+        assert_eq!(preview.token, "USDC");
+        assert_eq!(preview.units, 12_500_000);
+        assert!(preview.create_recipient_account);
+        assert_eq!(preview.account_rent_sol, 0.00203928);
+        // A never-used recipient the user has not sent to before is worth a
+        // second look
+        assert_eq!(preview.warnings.len(), 2);
+
+        let error = send_action(&group(&server))
+            .execute(
+                serde_json::json!({"recipient": recipient.to_string(), "amount": 51, "mint": "USDC"}),
+                serde_json::json!({"from": {"id": 42}}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error, "The wallet holds only 50 of this token");
+    }
+
+    #[tokio::test]
+    async fn test_recipients_are_resolved_and_checked() {
+        let mut server = mockito::Server::new_async().await;
+        let friend = Keypair::new().pubkey();
+        let mint = Keypair::new().pubkey();
+        mock_account(&mut server, &friend, Some((&[], &system_program::id()))).await;
+        mock_account(&mut server, &mint, Some((&[0; 82], &TOKEN_PROGRAM_ID))).await;
+        mock_network(&mut server, 2_000_000_000).await;
+        mock_transfer_simulation(&mut server, None).await;
+        let book = AddressBook::new();
+        book.save("42", "Mom", &friend, None).unwrap();
+        let action = send_action(&group_with(&server, book, SpendingPolicy::default()));
+        let send = |recipient: String| {
+            action.execute(
+                serde_json::json!({"recipient": recipient, "amount": 0.5}),
+                serde_json::json!({"from": {"id": 42}}),
+                Arc::new(Mutex::new(())),
+            )
+        };
+
+        // A saved contact is known, so nothing to warn about
+        let preview: TransferPreview =
+            serde_json::from_str(&send("mom".to_string()).await.unwrap()).unwrap();
+        assert_eq!(preview.recipient, friend.to_string());
+        assert_eq!(preview.recipient_name.as_deref(), Some("mom"));
+        assert!(preview.warnings.is_empty(), "{:?}", preview.warnings);
+
+        let preview: TransferPreview =
+            serde_json::from_str(&send(mint.to_string()).await.unwrap()).unwrap();
+        assert_eq!(
+            preview.warnings[1],
+            format!(
+                "{} is a token mint, not a wallet; anything sent to it is likely lost",
+                mint
+            )
+        );
+
+        let error = send("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWW0".to_string())
+            .await
+            .unwrap_err();
+        assert!(error.contains("is not a Solana address"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_policy_violations_block_the_transfer() {
+        let mut server = mockito::Server::new_async().await;
+        let recipient = Keypair::new().pubkey();
+        mock_account(&mut server, &recipient, Some((&[], &system_program::id()))).await;
+        mock_network(&mut server, 2_000_000_000).await;
+        mock_transfer_simulation(&mut server, Some((2_000_000_000, 1_000_000))).await;
+        let send = server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "sendTransaction"}),
+            ))
+            .expect(0)
+            .create_async()
+            .await;
+        let policy = SpendingPolicy::new(PolicyConfig {
+            denied_recipients: vec![recipient.to_string()],
+            ..PolicyConfig::default()
+        })
+        .unwrap();
+
+        let action = send_action(&group_with(&server, AddressBook::new(), policy));
+        let state = Arc::new(Mutex::new(()));
+        let preview = action
+            .execute(
+                serde_json::json!({"recipient": recipient.to_string(), "amount": 1.25}),
+                serde_json::json!({"from": {"id": 42}}),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        let preview: serde_json::Value = serde_json::from_str(&preview).unwrap();
+        assert_eq!(
+            preview["violations"],
+            serde_json::json!([format!("{} is on the list of denied recipients", recipient)])
+        );
+
+        let error = action
+            .confirm(preview, serde_json::json!({"from": {"id": 42}}), state)
+            .unwrap()
+            .await
+            .unwrap_err();
+        assert!(
+            error.starts_with("Refused by the spending policy"),
+            "{}",
+            error
+        );
+        send.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_sent_transfer_counts_when_its_outcome_is_unknown() {
+        let mut server = mockito::Server::new_async().await;
+        let recipient = Keypair::new().pubkey();
+        mock_account(&mut server, &recipient, Some((&[], &system_program::id()))).await;
+        mock_network(&mut server, 2_000_000_000).await;
+        mock_transfer_simulation(&mut server, Some((2_000_000_000, 1_000_000))).await;
+        mock_method(
+            &mut server,
+            "getRecentPrioritizationFees",
+            serde_json::json!([]),
+        )
+        .await;
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "sendTransaction"}),
+            ))
+            .with_body_from_request(|request| {
+                let body: serde_json::Value =
+                    serde_json::from_slice(request.body().unwrap()).unwrap();
+                let bytes = BASE64.decode(body["params"][0].as_str().unwrap()).unwrap();
+                let transaction: VersionedTransaction = bincode::deserialize(&bytes).unwrap();
+                rpc_result(serde_json::json!(transaction.signatures[0].to_string())).into()
+            })
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/rpc")
+            .match_body(Matcher::PartialJson(
+                serde_json::json!({"method": "getSignatureStatuses"}),
+            ))
+            .with_status(503)
+            .create_async()
+            .await;
+        let policy = SpendingPolicy::new(PolicyConfig {
+            daily_limit_sol: Some(2.0),
+            ..PolicyConfig::default()
+        })
+        .unwrap();
+
+        let action = send_action(&group_with(&server, AddressBook::new(), policy));
+        let state = Arc::new(Mutex::new(()));
+        let params = serde_json::json!({"recipient": recipient.to_string(), "amount": 1.25});
+        let preview = action
+            .execute(
+                params.clone(),
+                serde_json::json!({"from": {"id": 42}}),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        let preview: serde_json::Value = serde_json::from_str(&preview).unwrap();
+        let error = action
+            .confirm(
+                preview,
+                serde_json::json!({"from": {"id": 42}}),
+                state.clone(),
+            )
+            .unwrap()
+            .await
+            .unwrap_err();
+        assert!(error.contains("outcome is unknown"), "{}", error);
+
+        // The first 1.25 SOL may have gone out, so another would pass the limit
+        let preview = action
+            .execute(params, serde_json::json!({"from": {"id": 42}}), state)
+            .await
+            .unwrap();
+        let preview: serde_json::Value = serde_json::from_str(&preview).unwrap();
+        assert_eq!(preview["violations"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_that_would_fail_is_refused() {
+        let mut server = mockito::Server::new_async().await;
+        let recipient = Keypair::new().pubkey();
+        mock_account(&mut server, &recipient, Some((&[], &system_program::id()))).await;
+        mock_network(&mut server, 2_000_000_000).await;
+        mock_method(
+            &mut server,
+            "getMultipleAccounts",
+            serde_json::json!({"context": {"slot": 1}, "value": []}),
+        )
+        .await;
+        mock_method(
+            &mut server,
+            "simulateTransaction",
+            serde_json::json!({"context": {"slot": 1}, "value": {"err": {"InstructionError": [1, {"Custom": 1}]}, "logs": ["Program 11111111111111111111111111111111 failed: custom program error: 0x1"], "accounts": null}}),
+        )
+        .await;
+
+        let error = send_action(&group(&server))
+            .execute(
+                serde_json::json!({"recipient": recipient.to_string(), "amount": 1.25}),
+                serde_json::json!({"from": {"id": 42}}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.starts_with("The transaction would fail"), "{}", error);
+        assert!(error.contains("custom program error: 0x1"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_unsuitable_recipients_are_refused() {
+        let mut server = mockito::Server::new_async().await;
+        let token_account = Pubkey::new_unique();
+        mock_account(
+            &mut server,
+            &token_account,
+            Some((&[0; TOKEN_ACCOUNT_SIZE], &TOKEN_PROGRAM_ID)),
+        )
+        .await;
+        let action = send_action(&group(&server));
+
+        let error = action
+            .execute(
+                serde_json::json!({"recipient": token_account.to_string(), "amount": 1}),
+                serde_json::json!({"from": {"id": 42}}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.contains("is a token account"), "{}", error);
+
+        let error = action
+            .execute(
+                serde_json::json!({"recipient": wallet().await.to_string(), "amount": 1}),
+                serde_json::json!({"from": {"id": 42}}),
+                Arc::new(Mutex::new(())),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.contains("sending wallet itself"), "{}", error);
+    }
+
+    /// Sends SOL on a local `solana-test-validator` after funding the
+    /// wallet with an airdrop.
+    #[tokio::test]
+    #[ignore = "needs a running solana-test-validator"]
+    async fn test_transfer_on_local_validator() {
+        let url =
+            std::env::var("SOLANA_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8899".to_string());
+        let rpc = SolanaRpcClient::new(url);
+        let sender = wallet().await;
+        let airdrop: String = rpc
+            .call(
+                "requestAirdrop",
+                serde_json::json!([sender.to_string(), 2_000_000_000u64]),
+            )
+            .await
+            .unwrap();
+        rpc.confirm_transaction(&solana_sdk::signature::Signature::from_str(&airdrop).unwrap())
+            .await
+            .unwrap();
+
+        let group = TransferActionGroup::<()>::with_client(
+            rpc.clone(),
+            SimpleWalletManager::new(),
+            AddressBook::new(),
+            SpendingPolicy::default(),
+        );
+        let action = send_action(&group);
+        let recipient = Keypair::new().pubkey();
+        let state = Arc::new(Mutex::new(()));
+        let preview = action
+            .execute(
+                serde_json::json!({"recipient": recipient.to_string(), "amount": 0.5}),
+                serde_json::json!({"from": {"id": 42}}),
+                state.clone(),
+            )
+            .await
+            .unwrap();
+        action
+            .confirm(
+                serde_json::from_str(&preview).unwrap(),
+                serde_json::json!({"from": {"id": 42}}),
+                state,
+            )
+            .unwrap()
+            .await
+            .unwrap();
+        assert_eq!(rpc.get_balance(&recipient).await, Ok(500_000_000));
+    }
+}
@@ -0,0 +1,140 @@
+//! Finding the wallet of the user an action runs for.
+
+use ferrox_wallet::{Wallet, WalletManager, WalletSigner};
+use solana_sdk::pubkey::Pubkey;
+use std::{str::FromStr, sync::Arc};
+
+/// What users call their own wallet instead of giving an address.
+const OWN_WALLET: &[&str] = &["", "me", "mine", "my", "my wallet", "self"];
+
+/// Id of the user who sent the message in `send_state`, in the form the
+/// example wallet managers key wallets by. Ferrox passes the Telegram
+/// message, whose sender is under `from.id`.
+pub fn sender_id(send_state: &serde_json::Value) -> Result<String, String> {
+    match &send_state["from"]["id"] {
+        serde_json::Value::Number(id) => Ok(id.to_string()),
+        serde_json::Value::String(id) => Ok(id.clone()),
+        _ => Err("Cannot tell which user sent this request".to_string()),
+    }
+}
+
+/// Checks that a confirmation comes from `user_id`, the user the preview was
+/// made for. Ferrox passes whoever pressed the button as the sender, so in a
+/// group chat this keeps other members from confirming someone's funds away.
+pub fn check_confirmer(send_state: &serde_json::Value, user_id: &str) -> Result<(), String> {
+    if sender_id(send_state)? != user_id {
+        return Err("Only the user who asked for this can confirm it".to_string());
+    }
+    Ok(())
+}
+
+/// Id of the chat `send_state` came from, if it is a private chat with the
+/// bot. Keys are only accepted and handed out there.
+pub fn private_chat(send_state: &serde_json::Value) -> Result<i64, String> {
+    let chat = &send_state["chat"];
+    match (chat["type"].as_str(), chat["id"].as_i64()) {
+        (Some("private"), Some(id)) => Ok(id),
+        _ => Err("Wallet keys are only handled in a private chat with the bot".to_string()),
+    }
+}
+
+/// The signer of the Solana wallet of `user_id`: the account called
+/// `account`, or the user's default account when `account` is `None`.
+pub async fn solana_signer<W: WalletManager>(
+    wallets: &W,
+    user_id: &str,
+    account: Option<&str>,
+) -> Result<Arc<dyn WalletSigner>, String> {
+    let wallet = match account.map(str::trim).filter(|account| !account.is_empty()) {
+        Some(account) => wallets.get_account(user_id, account).await?,
+        None => wallets.get_wallet(user_id).await?,
+    };
+    match wallet {
+        Wallet::Solana(signer) => Ok(signer),
+        Wallet::Evm(signer) => Err(format!(
+            "{} is an EVM wallet, not a Solana one",
+            signer.address()
+        )),
+    }
+}
+
+/// `address`, or the wallet of the user who sent `send_state` when no
+/// address is given or it refers to their own wallet. The names of the
+/// user's accounts, such as "savings", resolve to those accounts.
+pub async fn resolve_address<W: WalletManager>(
+    wallets: &W,
+    address: Option<&str>,
+    send_state: &serde_json::Value,
+) -> Result<Pubkey, String> {
+    match address.map(str::trim) {
+        Some(address) if !OWN_WALLET.contains(&address.to_lowercase().as_str()) => {
+            match Pubkey::from_str(address) {
+                Ok(pubkey) => Ok(pubkey),
+                Err(e) => {
+                    let user_id = sender_id(send_state)?;
+                    match solana_signer(wallets, &user_id, Some(address)).await {
+                        Ok(signer) => Ok(signer.pubkey()),
+                        Err(_) => Err(format!("Invalid address '{}': {}", address, e)),
+                    }
+                }
+            }
+        }
+        _ => Ok(solana_signer(wallets, &sender_id(send_state)?, None)
+            .await?
+            .pubkey()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sender_id() {
+        let message = serde_json::json!({"chat": {"id": -100}, "from": {"id": 42}});
+        assert_eq!(sender_id(&message), Ok("42".to_string()));
+        assert!(sender_id(&serde_json::json!({"chat": {"id": -100}})).is_err());
+    }
+
+    #[test]
+    fn test_private_chat() {
+        let private = serde_json::json!({"chat": {"id": 42, "type": "private"}});
+        assert_eq!(private_chat(&private), Ok(42));
+        let group = serde_json::json!({"chat": {"id": -100, "type": "supergroup"}});
+        assert!(private_chat(&group).is_err());
+        assert!(private_chat(&serde_json::json!({})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_address() {
+        let wallets = ferrox_wallet::simple_wallet_manager::SimpleWalletManager::new();
+        let message = serde_json::json!({"from": {"id": 42}});
+        let own = solana_signer(&wallets, "42", None).await.unwrap().pubkey();
+        assert_eq!(resolve_address(&wallets, None, &message).await, Ok(own));
+        assert_eq!(
+            resolve_address(&wallets, Some("My wallet"), &message).await,
+            Ok(own)
+        );
+        let other = Pubkey::new_unique();
+        assert_eq!(
+            resolve_address(&wallets, Some(&other.to_string()), &message).await,
+            Ok(other)
+        );
+        assert!(resolve_address(&wallets, Some("nope"), &message)
+            .await
+            .is_err());
+
+        wallets.create_wallet("7").await.unwrap();
+        let savings = wallets.create_account("7", "savings").await.unwrap();
+        assert_eq!(
+            resolve_address(
+                &wallets,
+                Some("Savings"),
+                &serde_json::json!({"from": {"id": 7}})
+            )
+            .await
+            .map(|pubkey| pubkey.to_string()),
+            Ok(savings.address())
+        );
+    }
+}
@@ -0,0 +1,330 @@
+pub mod indicators;
+pub mod levels;
+
+use crate::{
+    action::{with_client, ActionBuilder, ActionGroup, FunctionAction},
+    candles::{fetcher::CandleFetcher, CandleInterval, CandleSeries, CandleSource, CandlesParams},
+    output::{self, Table},
+    AgentState,
+};
+use indicators::{Bands, Macd};
+use levels::{SupportResistance, VolumeProfile};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct IndicatorsParams {
+    #[serde(flatten)]
+    candles: CandlesParams,
+    ma_period: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SupportResistanceParams {
+    #[serde(flatten)]
+    candles: CandlesParams,
+    window: Option<usize>,
+    tolerance: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VolumeProfileParams {
+    #[serde(flatten)]
+    candles: CandlesParams,
+    bins: Option<usize>,
+}
+
+/// Latest value of each indicator, computed over the whole fetched series.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorSummary {
+    pub source: CandleSource,
+    pub interval: CandleInterval,
+    pub candles: usize,
+    pub time: i64,
+    pub close: f64,
+    pub ma_period: usize,
+    pub sma: Option<f64>,
+    pub ema: Option<f64>,
+    pub rsi_14: Option<f64>,
+    pub macd_12_26_9: Option<Macd>,
+    pub bollinger_20_2: Option<Bands>,
+    pub atr_14: Option<f64>,
+    pub vwap: Option<f64>,
+}
+
+impl IndicatorSummary {
+    pub fn from_series(series: &CandleSeries, ma_period: usize) -> Option<Self> {
+        let last = series.candles.last()?;
+        let closes: Vec<f64> = series.candles.iter().map(|c| c.close).collect();
+        Some(Self {
+            source: series.source,
+            interval: series.interval,
+            candles: series.candles.len(),
+            time: last.time,
+            close: last.close,
+            ma_period,
+            sma: indicators::last(&indicators::sma(&closes, ma_period)),
+            ema: indicators::last(&indicators::ema(&closes, ma_period)),
+            rsi_14: indicators::last(&indicators::rsi(&closes, 14)),
+            macd_12_26_9: indicators::last(&indicators::macd(&closes, 12, 26, 9)),
+            bollinger_20_2: indicators::last(&indicators::bollinger(&closes, 20, 2.0)),
+            atr_14: indicators::last(&indicators::atr(&series.candles, 14)),
+            vwap: indicators::last(&indicators::vwap(&series.candles)),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct LevelsResult {
+    source: CandleSource,
+    interval: CandleInterval,
+    close: f64,
+    #[serde(flatten)]
+    levels: SupportResistance,
+}
+
+#[derive(Debug, Serialize)]
+struct VolumeProfileResult {
+    source: CandleSource,
+    interval: CandleInterval,
+    #[serde(flatten)]
+    profile: VolumeProfile,
+}
+
+/// Levels nearest to `close` first, as a table for the user.
+fn levels_table(levels: &SupportResistance, close: f64) -> Table {
+    let rows = levels
+        .resistance
+        .iter()
+        .map(|level| ("Resistance", level))
+        .chain(levels.support.iter().map(|level| ("Support", level)));
+    rows.fold(
+        Table::new(["Level", "Price", "Distance", "Touches"]).title("Support and resistance"),
+        |table, (kind, level)| {
+            table.row([
+                kind.to_string(),
+                format!("{}", level.price),
+                format!("{:+.2}%", (level.price - close) / close * 100.0),
+                level.touches.to_string(),
+            ])
+        },
+    )
+}
+
+pub struct TechnicalAnalysisActionGroup<S: Send + Sync + Clone + 'static> {
+    actions: Vec<Arc<FunctionAction<S>>>,
+}
+
+impl<S: Send + Sync + Clone + 'static> ActionGroup<S> for TechnicalAnalysisActionGroup<S> {
+    fn actions(&self) -> &[Arc<FunctionAction<S>>] {
+        &self.actions
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> Default for TechnicalAnalysisActionGroup<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Send + Sync + Clone + 'static> TechnicalAnalysisActionGroup<S> {
+    pub fn new() -> Self {
+        Self::with_fetcher(CandleFetcher::new())
+    }
+
+    /// Builds the group around the fetcher that supplies its candles.
+    pub fn with_fetcher(fetcher: CandleFetcher) -> Self {
+        let mut actions = Vec::new();
+        // Add indicators action
+        {
+            async fn get_technical_indicators<S: Send + Sync + Clone + 'static>(
+                fetcher: CandleFetcher,
+                params: IndicatorsParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let series = fetcher.fetch(&params.candles.into_request()?).await?;
+                let summary =
+                    IndicatorSummary::from_series(&series, params.ma_period.unwrap_or(20))
+                        .ok_or_else(|| "No candles to analyze".to_string())?;
+
+                serde_json::to_string(&summary)
+                    .map_err(|e| format!("Failed to serialize indicators: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_technical_indicators",
+                with_client(&fetcher, get_technical_indicators),
+                None,
+            )
+            .description(
+                "Compute SMA, EMA, RSI(14), MACD(12,26,9), Bollinger Bands(20,2), ATR(14) and VWAP for a token from its candles. Use these values instead of calculating indicators yourself; fields are null when there are too few candles",
+            )
+            .parameter("address", "Token address", "string", true)
+            .parameter("time_from", "Start timestamp (Unix seconds)", "integer", true)
+            .parameter("time_to", "End timestamp (Unix seconds)", "integer", true)
+            .parameter(
+                "interval",
+                "Candle width: 1m, 5m, 15m, 30m, 1h, 4h or 1d (default 1h)",
+                "string",
+                false,
+            )
+            .parameter(
+                "chain",
+                "Chain: sol, eth, base, bsc or tron (default sol)",
+                "string",
+                false,
+            )
+            .parameter(
+                "coingecko_id",
+                "CoinGecko coin id (e.g. solana), enables CoinGecko as a last resort",
+                "string",
+                false,
+            )
+            .parameter(
+                "ma_period",
+                "Period of the SMA and EMA (default 20)",
+                "integer",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add support/resistance action
+        {
+            async fn get_support_resistance<S: Send + Sync + Clone + 'static>(
+                fetcher: CandleFetcher,
+                params: SupportResistanceParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let series = fetcher.fetch(&params.candles.into_request()?).await?;
+                let close = series
+                    .candles
+                    .last()
+                    .map(|candle| candle.close)
+                    .ok_or_else(|| "No candles to analyze".to_string())?;
+                let levels = levels::support_resistance(
+                    &series.candles,
+                    params.window.unwrap_or(3),
+                    params.tolerance.unwrap_or(0.01),
+                );
+                output::attach_table(levels_table(&levels, close));
+
+                serde_json::to_string(&LevelsResult {
+                    source: series.source,
+                    interval: series.interval,
+                    close,
+                    levels,
+                })
+                .map_err(|e| format!("Failed to serialize levels: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_support_resistance",
+                with_client(&fetcher, get_support_resistance),
+                None,
+            )
+            .description(
+                "Find support and resistance levels for a token from swing highs and lows in its candles, nearest first, with how often price turned at each",
+            )
+            .parameter("address", "Token address", "string", true)
+            .parameter("time_from", "Start timestamp (Unix seconds)", "integer", true)
+            .parameter("time_to", "End timestamp (Unix seconds)", "integer", true)
+            .parameter(
+                "interval",
+                "Candle width: 1m, 5m, 15m, 30m, 1h, 4h or 1d (default 1h)",
+                "string",
+                false,
+            )
+            .parameter(
+                "chain",
+                "Chain: sol, eth, base, bsc or tron (default sol)",
+                "string",
+                false,
+            )
+            .parameter(
+                "coingecko_id",
+                "CoinGecko coin id (e.g. solana), enables CoinGecko as a last resort",
+                "string",
+                false,
+            )
+            .parameter(
+                "window",
+                "Candles on each side a swing must exceed (default 3)",
+                "integer",
+                false,
+            )
+            .parameter(
+                "tolerance",
+                "Fraction of price within which swings count as one level (default 0.01)",
+                "number",
+                false,
+            )
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        // Add volume profile action
+        {
+            async fn get_volume_profile<S: Send + Sync + Clone + 'static>(
+                fetcher: CandleFetcher,
+                params: VolumeProfileParams,
+                _send_state: serde_json::Value,
+                _state: AgentState<S>,
+            ) -> Result<String, String> {
+                let series = fetcher.fetch(&params.candles.into_request()?).await?;
+                let profile = levels::volume_profile(&series.candles, params.bins.unwrap_or(20))
+                    .ok_or_else(|| {
+                        format!("{} reported no volume for these candles", series.source)
+                    })?;
+
+                serde_json::to_string(&VolumeProfileResult {
+                    source: series.source,
+                    interval: series.interval,
+                    profile,
+                })
+                .map_err(|e| format!("Failed to serialize volume profile: {}", e))
+            }
+
+            let action = ActionBuilder::<_, _, _, _>::new(
+                "get_volume_profile",
+                with_client(&fetcher, get_volume_profile),
+                None,
+            )
+            .description(
+                "Compute a token's volume profile: traded volume per price bin, the point of control and the 70% value area",
+            )
+            .parameter("address", "Token address", "string", true)
+            .parameter("time_from", "Start timestamp (Unix seconds)", "integer", true)
+            .parameter("time_to", "End timestamp (Unix seconds)", "integer", true)
+            .parameter(
+                "interval",
+                "Candle width: 1m, 5m, 15m, 30m, 1h, 4h or 1d (default 1h)",
+                "string",
+                false,
+            )
+            .parameter(
+                "chain",
+                "Chain: sol, eth, base, bsc or tron (default sol)",
+                "string",
+                false,
+            )
+            .parameter(
+                "coingecko_id",
+                "CoinGecko coin id (e.g. solana), enables CoinGecko as a last resort",
+                "string",
+                false,
+            )
+            .parameter("bins", "Number of price bins (default 20)", "integer", false)
+            .build();
+
+            actions.push(Arc::new(action));
+        }
+
+        Self { actions }
+    }
+}
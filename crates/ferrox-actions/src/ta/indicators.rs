@@ -0,0 +1,308 @@
+//! Indicators over price series. Every function returns one entry per input
+//! bar, `None` until the indicator has enough history.
+
+use serde::{Deserialize, Serialize};
+
+use crate::candles::Candle;
+
+/// Simple moving average over `period` values.
+pub fn sma(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    if period == 0 || values.len() < period {
+        return out;
+    }
+    let mut sum: f64 = values[..period].iter().sum();
+    out[period - 1] = Some(sum / period as f64);
+    for i in period..values.len() {
+        sum += values[i] - values[i - period];
+        out[i] = Some(sum / period as f64);
+    }
+    out
+}
+
+/// Exponential moving average with smoothing `2 / (period + 1)`, seeded with
+/// the SMA of the first `period` values.
+pub fn ema(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; values.len()];
+    if period == 0 || values.len() < period {
+        return out;
+    }
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut current = values[..period].iter().sum::<f64>() / period as f64;
+    out[period - 1] = Some(current);
+    for i in period..values.len() {
+        current = values[i] * alpha + current * (1.0 - alpha);
+        out[i] = Some(current);
+    }
+    out
+}
+
+/// Wilder's relative strength index.
+pub fn rsi(closes: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; closes.len()];
+    if period == 0 || closes.len() <= period {
+        return out;
+    }
+    let changes: Vec<f64> = closes.windows(2).map(|w| w[1] - w[0]).collect();
+    let mut gain = changes[..period].iter().map(|c| c.max(0.0)).sum::<f64>() / period as f64;
+    let mut loss = changes[..period].iter().map(|c| (-c).max(0.0)).sum::<f64>() / period as f64;
+    let index = |gain: f64, loss: f64| {
+        if loss == 0.0 {
+            100.0
+        } else {
+            100.0 - 100.0 / (1.0 + gain / loss)
+        }
+    };
+    out[period] = Some(index(gain, loss));
+    for (i, change) in changes.iter().enumerate().skip(period) {
+        gain = (gain * (period as f64 - 1.0) + change.max(0.0)) / period as f64;
+        loss = (loss * (period as f64 - 1.0) + (-change).max(0.0)) / period as f64;
+        out[i + 1] = Some(index(gain, loss));
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Macd {
+    pub macd: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+/// MACD line (`fast` EMA minus `slow` EMA), its `signal` EMA and the
+/// histogram between them. The usual settings are 12, 26, 9.
+pub fn macd(closes: &[f64], fast: usize, slow: usize, signal: usize) -> Vec<Option<Macd>> {
+    let fast = ema(closes, fast);
+    let slow = ema(closes, slow);
+    let line: Vec<Option<f64>> = fast
+        .iter()
+        .zip(&slow)
+        .map(|(fast, slow)| Some((*fast)? - (*slow)?))
+        .collect();
+
+    // The signal EMA runs over the defined part of the MACD line only
+    let start = line.iter().position(Option::is_some).unwrap_or(line.len());
+    let defined: Vec<f64> = line[start..].iter().flatten().copied().collect();
+    let signal = ema(&defined, signal);
+
+    let mut out = vec![None; closes.len()];
+    for (offset, signal) in signal.into_iter().enumerate() {
+        if let Some(signal) = signal {
+            let macd = defined[offset];
+            out[start + offset] = Some(Macd {
+                macd,
+                signal,
+                histogram: macd - signal,
+            });
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bands {
+    pub upper: f64,
+    pub middle: f64,
+    pub lower: f64,
+}
+
+/// Bollinger Bands: the `period` SMA plus and minus `width` population
+/// standard deviations.
+pub fn bollinger(closes: &[f64], period: usize, width: f64) -> Vec<Option<Bands>> {
+    sma(closes, period)
+        .into_iter()
+        .enumerate()
+        .map(|(i, middle)| {
+            let middle = middle?;
+            let window = &closes[i + 1 - period..=i];
+            let variance = window.iter().map(|c| (c - middle).powi(2)).sum::<f64>() / period as f64;
+            let deviation = variance.sqrt() * width;
+            Some(Bands {
+                upper: middle + deviation,
+                middle,
+                lower: middle - deviation,
+            })
+        })
+        .collect()
+}
+
+/// Wilder's average true range.
+pub fn atr(candles: &[Candle], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; candles.len()];
+    if period == 0 || candles.len() < period {
+        return out;
+    }
+    let true_range: Vec<f64> = candles
+        .iter()
+        .enumerate()
+        .map(|(i, candle)| match i {
+            0 => candle.high - candle.low,
+            _ => {
+                let previous = candles[i - 1].close;
+                (candle.high - candle.low)
+                    .max((candle.high - previous).abs())
+                    .max((candle.low - previous).abs())
+            }
+        })
+        .collect();
+    let mut current = true_range[..period].iter().sum::<f64>() / period as f64;
+    out[period - 1] = Some(current);
+    for i in period..candles.len() {
+        current = (current * (period as f64 - 1.0) + true_range[i]) / period as f64;
+        out[i] = Some(current);
+    }
+    out
+}
+
+/// Volume-weighted average of the typical price `(high + low + close) / 3`,
+/// cumulative from the first candle. `None` where there is no volume yet, or
+/// throughout when the provider reports no volume.
+pub fn vwap(candles: &[Candle]) -> Vec<Option<f64>> {
+    let mut weighted = 0.0;
+    let mut volume = 0.0;
+    candles
+        .iter()
+        .map(|candle| {
+            let v = candle.volume?;
+            weighted += typical_price(candle) * v;
+            volume += v;
+            (volume > 0.0).then(|| weighted / volume)
+        })
+        .collect()
+}
+
+pub(crate) fn typical_price(candle: &Candle) -> f64 {
+    (candle.high + candle.low + candle.close) / 3.0
+}
+
+/// Last defined value of an indicator.
+pub fn last<T: Copy>(series: &[Option<T>]) -> Option<T> {
+    series.iter().rev().find_map(|value| *value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("value is defined");
+        assert!(
+            (actual - expected).abs() < 0.005,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    fn candle(high: f64, low: f64, close: f64, volume: f64) -> Candle {
+        Candle {
+            time: 0,
+            open: close,
+            high,
+            low,
+            close,
+            volume: Some(volume),
+            filled: false,
+        }
+    }
+
+    // StockCharts' 10-day EMA worked example
+    const EMA_CLOSES: [f64; 30] = [
+        22.27, 22.19, 22.08, 22.17, 22.18, 22.13, 22.23, 22.43, 22.24, 22.29, 22.15, 22.39, 22.38,
+        22.61, 23.36, 24.05, 23.75, 23.83, 23.95, 23.63, 23.82, 23.87, 23.65, 23.19, 23.10, 23.33,
+        22.68, 23.10, 22.40, 22.17,
+    ];
+
+    // Wilder's 14-day RSI worked example, as published by StockCharts
+    const RSI_CLOSES: [f64; 20] = [
+        44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03, 45.61,
+        46.28, 46.28, 46.00, 46.03, 46.41, 46.22, 45.64,
+    ];
+
+    #[test]
+    fn test_sma() {
+        let sma = sma(&EMA_CLOSES, 10);
+        assert_eq!(sma[8], None);
+        assert_close(sma[9], 22.22);
+        assert_close(sma[10], 22.21);
+        assert_close(sma[29], 23.13);
+    }
+
+    #[test]
+    fn test_ema() {
+        let ema = ema(&EMA_CLOSES, 10);
+        assert_eq!(ema[8], None);
+        let expected = [
+            22.22, 22.21, 22.24, 22.27, 22.33, 22.52, 22.80, 22.97, 23.13, 23.28,
+        ];
+        for (i, expected) in expected.iter().enumerate() {
+            assert_close(ema[9 + i], *expected);
+        }
+        assert_close(ema[29], 22.92);
+    }
+
+    #[test]
+    fn test_rsi() {
+        let rsi = rsi(&RSI_CLOSES, 14);
+        assert_eq!(rsi[13], None);
+        let expected = [70.46, 66.25, 66.48, 69.35, 66.29, 57.92];
+        for (i, expected) in expected.iter().enumerate() {
+            assert_close(rsi[14 + i], *expected);
+        }
+        // A series that only rises is maximally overbought
+        let rising: Vec<f64> = (0..20).map(f64::from).collect();
+        assert_eq!(self::rsi(&rising, 14)[19], Some(100.0));
+    }
+
+    #[test]
+    fn test_macd_of_linear_series() {
+        // On a straight line each EMA lags by (period - 1) / 2 once seeded,
+        // so MACD(12, 26, 9) is exactly 12.5 - 5.5 = 7
+        let closes: Vec<f64> = (0..60).map(f64::from).collect();
+        let macd = macd(&closes, 12, 26, 9);
+        assert_eq!(macd[32], None);
+        let value = macd[33].unwrap();
+        assert!((value.macd - 7.0).abs() < 1e-9);
+        assert!((value.signal - 7.0).abs() < 1e-9);
+        assert!(value.histogram.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_bollinger() {
+        // 1..=20 has mean 10.5 and population deviation sqrt(399 / 12)
+        let closes: Vec<f64> = (1..=20).map(f64::from).collect();
+        let bands = bollinger(&closes, 20, 2.0)[19].unwrap();
+        let deviation = (399.0f64 / 12.0).sqrt();
+        assert!((bands.middle - 10.5).abs() < 1e-9);
+        assert!((bands.upper - (10.5 + 2.0 * deviation)).abs() < 1e-9);
+        assert!((bands.lower - (10.5 - 2.0 * deviation)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_atr() {
+        // Constant 2-wide bars, then a gap up that makes the true range 6
+        let mut candles: Vec<Candle> = (0..14).map(|_| candle(11.0, 9.0, 10.0, 1.0)).collect();
+        candles.push(candle(16.0, 14.0, 15.0, 1.0));
+        let atr = atr(&candles, 14);
+        assert_eq!(atr[12], None);
+        assert_close(atr[13], 2.0);
+        assert_close(atr[14], (2.0 * 13.0 + 6.0) / 14.0);
+    }
+
+    #[test]
+    fn test_vwap() {
+        let candles = [
+            candle(12.0, 9.0, 9.0, 100.0),
+            candle(22.0, 19.0, 19.0, 300.0),
+        ];
+        let vwap = vwap(&candles);
+        assert_close(vwap[0], 10.0);
+        assert_close(vwap[1], (10.0 * 100.0 + 20.0 * 300.0) / 400.0);
+
+        let without_volume = Candle {
+            volume: None,
+            ..candles[0]
+        };
+        assert_eq!(self::vwap(&[without_volume]), vec![None]);
+    }
+}
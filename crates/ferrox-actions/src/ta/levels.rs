@@ -0,0 +1,221 @@
+//! Price levels derived from a candle series: support and resistance from
+//! swing points, and the volume profile.
+
+use serde::{Deserialize, Serialize};
+
+use super::indicators::typical_price;
+use crate::candles::Candle;
+
+/// A price level the series has turned at `touches` times.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub price: f64,
+    pub touches: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SupportResistance {
+    /// Levels below the last close, nearest first.
+    pub support: Vec<Level>,
+    /// Levels above the last close, nearest first.
+    pub resistance: Vec<Level>,
+}
+
+/// Finds swing highs and lows (bars whose high or low is the extreme of the
+/// `window` bars on either side), merges swings within `tolerance` (a
+/// fraction of price, e.g. 0.01) into one level, and splits the levels around
+/// the last close.
+pub fn support_resistance(candles: &[Candle], window: usize, tolerance: f64) -> SupportResistance {
+    let Some(last) = candles.last() else {
+        return SupportResistance::default();
+    };
+    let mut swings = Vec::new();
+    if window > 0 && candles.len() > 2 * window {
+        for i in window..candles.len() - window {
+            let neighbours = candles[i - window..=i + window]
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != window);
+            if neighbours
+                .clone()
+                .all(|(_, other)| candles[i].high >= other.high)
+            {
+                swings.push(candles[i].high);
+            }
+            if neighbours
+                .clone()
+                .all(|(_, other)| candles[i].low <= other.low)
+            {
+                swings.push(candles[i].low);
+            }
+        }
+    }
+    swings.sort_by(f64::total_cmp);
+
+    // Merge neighbouring swings into levels at their average price
+    let mut clusters: Vec<Vec<f64>> = Vec::new();
+    for price in swings {
+        match clusters.last_mut() {
+            Some(cluster) if price - cluster[0] <= cluster[0].abs() * tolerance => {
+                cluster.push(price)
+            }
+            _ => clusters.push(vec![price]),
+        }
+    }
+    let levels = clusters.into_iter().map(|cluster| Level {
+        price: cluster.iter().sum::<f64>() / cluster.len() as f64,
+        touches: cluster.len(),
+    });
+
+    let (mut support, resistance): (Vec<Level>, Vec<Level>) =
+        levels.partition(|level| level.price <= last.close);
+    support.reverse();
+    SupportResistance {
+        support,
+        resistance,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VolumeBin {
+    pub price_low: f64,
+    pub price_high: f64,
+    pub volume: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VolumeProfile {
+    pub bins: Vec<VolumeBin>,
+    /// Middle of the bin with the most volume.
+    pub point_of_control: f64,
+    /// Price range around the point of control holding 70% of the volume.
+    pub value_area_low: f64,
+    pub value_area_high: f64,
+}
+
+/// Splits the traded range into `bins` equal price bins and adds each
+/// candle's volume to the bin holding its typical price. `None` without
+/// volume data.
+pub fn volume_profile(candles: &[Candle], bins: usize) -> Option<VolumeProfile> {
+    if bins == 0 {
+        return None;
+    }
+    let low = candles.iter().map(|c| c.low).reduce(f64::min)?;
+    let high = candles.iter().map(|c| c.high).reduce(f64::max)?;
+    let width = (high - low) / bins as f64;
+
+    let mut volumes = vec![0.0; bins];
+    for candle in candles {
+        let index = if width > 0.0 {
+            (((typical_price(candle) - low) / width) as usize).min(bins - 1)
+        } else {
+            0
+        };
+        volumes[index] += candle.volume?;
+    }
+    let total: f64 = volumes.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let poc = volumes
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(index, _)| index)?;
+    // Grow the value area from the POC towards the heavier neighbour
+    let (mut from, mut to, mut area) = (poc, poc, volumes[poc]);
+    while area < total * 0.7 {
+        let below = from.checked_sub(1).map(|i| volumes[i]);
+        let above = volumes.get(to + 1).copied();
+        match (below, above) {
+            (Some(b), Some(a)) if b >= a => {
+                from -= 1;
+                area += b;
+            }
+            (_, Some(a)) => {
+                to += 1;
+                area += a;
+            }
+            (Some(b), None) => {
+                from -= 1;
+                area += b;
+            }
+            (None, None) => break,
+        }
+    }
+
+    let bin = |index: usize| VolumeBin {
+        price_low: low + width * index as f64,
+        price_high: low + width * (index + 1) as f64,
+        volume: volumes[index],
+    };
+    Some(VolumeProfile {
+        bins: (0..bins).map(bin).collect(),
+        point_of_control: low + width * (poc as f64 + 0.5),
+        value_area_low: bin(from).price_low,
+        value_area_high: bin(to).price_high,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(high: f64, low: f64, close: f64, volume: f64) -> Candle {
+        Candle {
+            time: 0,
+            open: close,
+            high,
+            low,
+            close,
+            volume: Some(volume),
+            filled: false,
+        }
+    }
+
+    #[test]
+    fn test_support_resistance() {
+        // Oscillates between ~10 and ~20 and ends near 17
+        let highs = [12.0, 20.0, 12.0, 20.2, 12.0, 19.9, 12.0];
+        let lows = [10.0, 18.0, 10.1, 18.0, 9.9, 18.0, 14.0];
+        let candles: Vec<Candle> = highs
+            .iter()
+            .zip(&lows)
+            .map(|(h, l)| candle(*h, *l, (h + l) / 2.0, 1.0))
+            .collect();
+        let levels = support_resistance(&candles, 1, 0.03);
+
+        assert_eq!(levels.resistance.len(), 1);
+        assert_eq!(levels.resistance[0].touches, 3);
+        assert!((levels.resistance[0].price - (19.9 + 20.0 + 20.2) / 3.0).abs() < 1e-9);
+        assert_eq!(levels.support.len(), 1);
+        assert_eq!(levels.support[0].touches, 2);
+        assert!((levels.support[0].price - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_volume_profile() {
+        // Range 0..10 in 10 bins; most volume trades around 5
+        let candles = [
+            candle(10.0, 0.0, 5.0, 100.0),
+            candle(6.0, 4.0, 5.0, 500.0),
+            candle(2.0, 0.0, 1.0, 50.0),
+            candle(9.0, 9.0, 9.0, 50.0),
+        ];
+        let profile = volume_profile(&candles, 10).unwrap();
+
+        assert_eq!(profile.bins.len(), 10);
+        assert_eq!(profile.bins[5].volume, 600.0);
+        assert!((profile.point_of_control - 5.5).abs() < 1e-9);
+        // 600 of 700 is already over 70%
+        assert!((profile.value_area_low - 5.0).abs() < 1e-9);
+        assert!((profile.value_area_high - 6.0).abs() < 1e-9);
+
+        let without_volume = Candle {
+            volume: None,
+            ..candles[0]
+        };
+        assert_eq!(volume_profile(&[without_volume], 10), None);
+    }
+}
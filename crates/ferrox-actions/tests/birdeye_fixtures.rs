@@ -0,0 +1,197 @@
+mod common;
+
+use common::{actions, run_cases, unlimited, Case, Provider};
+use ferrox_actions::{birdeye::client::BirdeyeClient, BirdeyeActionGroup};
+use serde_json::json;
+
+const SOL: &str = "So11111111111111111111111111111111111111112";
+const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+const SOL_USDC_PAIR: &str = "8HoQnePLqPj4M7PUDzfw8e3Ymdwgc7NLGnaTUapubyvu";
+const WALLET: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+fn provider() -> Provider {
+    Provider {
+        name: "birdeye",
+        headers: vec![("x-api-key", "test-key"), ("accept", "application/json")],
+        group: |base_url| match base_url {
+            Some(base_url) => actions(BirdeyeActionGroup::with_client(
+                BirdeyeClient::new("test-key".to_string())
+                    .with_base_url(base_url)
+                    .with_rate_limiter(unlimited()),
+            )),
+            None => actions(BirdeyeActionGroup::new()),
+        },
+    }
+}
+
+fn cases() -> Vec<Case> {
+    vec![
+        Case::new("get_token_price", json!({ "address": SOL }), "/defi/price")
+            .query("address", SOL),
+        Case::new(
+            "get_token_price_history",
+            json!({ "address": SOL, "resolution": "15", "limit": 10 }),
+            "/defi/history_price",
+        )
+        .query("address", SOL)
+        .query("address_type", "token")
+        .query("type", "15m")
+        .query_any("time_from")
+        .query_any("time_to")
+        .query("limit", 10),
+        Case::new(
+            "get_multi_token_price",
+            json!({ "addresses": format!("{}, {}", SOL, USDC) }),
+            "/defi/multi_price",
+        )
+        .query("list_address", format!("{},{}", SOL, USDC)),
+        Case::new(
+            "get_token_trending",
+            json!({ "limit": 5 }),
+            "/defi/token_trending",
+        )
+        .query("limit", 5),
+        Case::new(
+            "get_token_ohlcv",
+            json!({ "address": SOL, "resolution": "1D" }),
+            "/defi/ohlcv",
+        )
+        .query("address", SOL)
+        .query("type", "1D")
+        .query_any("time_from")
+        .query_any("time_to"),
+        Case::new(
+            "get_pair_ohlcv",
+            json!({ "pair_address": SOL_USDC_PAIR, "resolution": "60" }),
+            "/defi/ohlcv/pair",
+        )
+        .query("address", SOL_USDC_PAIR)
+        .query("type", "60m")
+        .query_any("time_from")
+        .query_any("time_to"),
+        Case::new(
+            "get_token_trades",
+            json!({ "address": SOL, "limit": 10, "offset": 0 }),
+            "/defi/txs/token",
+        )
+        .query("address", SOL)
+        .query("sort_type", "desc")
+        .query("limit", 10)
+        .query("offset", 0),
+        // Birdeye caps pair trades at 50 per page
+        Case::new(
+            "get_pair_trades",
+            json!({ "pair_address": SOL_USDC_PAIR, "limit": 100, "offset": 5 }),
+            "/defi/txs/pair",
+        )
+        .query("address", SOL_USDC_PAIR)
+        .query("tx_type", "swap")
+        .query("sort_type", "desc")
+        .query("limit", 50)
+        .query("offset", 5),
+        Case::new(
+            "get_token_overview",
+            json!({ "address": SOL }),
+            "/defi/token_overview",
+        )
+        .query("address", SOL),
+        Case::new(
+            "get_token_list",
+            json!({ "limit": 10, "offset": 20 }),
+            "/defi/tokenList",
+        )
+        .query("limit", 10)
+        .query("offset", 20),
+        Case::new(
+            "get_token_security",
+            json!({ "address": SOL }),
+            "/defi/token_security",
+        )
+        .query("address", SOL),
+        Case::new(
+            "get_token_market_list",
+            json!({ "address": SOL }),
+            "/defi/v2/markets",
+        )
+        .query("address", SOL),
+        Case::new(
+            "get_token_new_listing",
+            json!({ "limit": 10 }),
+            "/defi/v2/tokens/new_listing",
+        )
+        .query("limit", 10),
+        Case::new(
+            "get_token_top_traders",
+            json!({ "address": SOL, "limit": 5 }),
+            "/defi/v2/tokens/top_traders",
+        )
+        .query("address", SOL)
+        .query("limit", 5),
+        Case::new("get_gainers_losers", json!({}), "/trader/gainers-losers"),
+        Case::new(
+            "get_trader_txs_by_time",
+            json!({
+                "address": WALLET,
+                "time_from": 1700000000,
+                "time_to": 1700086400,
+                "limit": 10
+            }),
+            "/trader/txs/seek_by_time",
+        )
+        .query("address", WALLET)
+        .query("from", 1700000000)
+        .query("to", 1700086400)
+        .query("limit", 10),
+        Case::new(
+            "list_supported_chains",
+            json!({}),
+            "/v1/wallet/list_supported_chain",
+        ),
+        Case::new(
+            "get_wallet_portfolio",
+            json!({ "wallet_address": WALLET, "chain_id": "solana" }),
+            "/v1/wallet/token_list",
+        )
+        .query("wallet", WALLET)
+        .query("chain_id", "solana"),
+        Case::new(
+            "get_wallet_portfolio_multichain",
+            json!({ "wallet_address": WALLET }),
+            "/v1/wallet/multichain_token_list",
+        )
+        .query("wallet", WALLET),
+        Case::new(
+            "get_wallet_transaction_history",
+            json!({
+                "wallet_address": WALLET,
+                "chain_id": "solana",
+                "limit": 10,
+                "offset": 0
+            }),
+            "/v1/wallet/tx_list",
+        )
+        .query("wallet", WALLET)
+        .query("chain_id", "solana")
+        .query("limit", 10)
+        .query("offset", 0),
+        Case::new(
+            "get_wallet_transaction_history_multichain",
+            json!({ "wallet_address": WALLET, "limit": 10 }),
+            "/v1/wallet/multichain_tx_list",
+        )
+        .query("wallet", WALLET)
+        .query("limit", 10),
+        Case::new(
+            "simulate_transaction",
+            json!({ "chain_id": "solana", "tx_data": "AQABAgMEBQ" }),
+            "/v1/wallet/simulate",
+        )
+        .query("chain_id", "solana")
+        .query("tx_data", "AQABAgMEBQ"),
+    ]
+}
+
+#[tokio::test]
+async fn test_birdeye_actions_against_fixtures() {
+    run_cases(provider(), cases()).await;
+}
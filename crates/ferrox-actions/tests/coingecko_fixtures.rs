@@ -0,0 +1,201 @@
+mod common;
+
+use common::{actions, run_cases, unlimited, Case, Provider};
+use ferrox_actions::{coingecko::client::CoinGeckoClient, CoinGeckoActionGroup};
+use serde_json::json;
+
+const USDC_ERC20: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+
+/// Replays against a Pro client so the Pro-only actions are covered too.
+fn provider() -> Provider {
+    Provider {
+        name: "coingecko",
+        headers: vec![("x-cg-pro-api-key", "test-key")],
+        group: |base_url| match base_url {
+            Some(base_url) => actions(CoinGeckoActionGroup::with_client(
+                CoinGeckoClient::new("test-key".to_string())
+                    .with_base_url(base_url)
+                    .with_rate_limiter(unlimited()),
+            )),
+            None => actions(CoinGeckoActionGroup::new()),
+        },
+    }
+}
+
+fn cases() -> Vec<Case> {
+    vec![
+        Case::new(
+            "get_coin_contract_market_chart_range",
+            json!({
+                "id": "ethereum",
+                "contract_address": USDC_ERC20,
+                "vs_currency": "usd",
+                "days": "7"
+            }),
+            format!("/coins/ethereum/contract/{}/market_chart/range", USDC_ERC20),
+        )
+        .query("vs_currency", "usd")
+        .query_any("from")
+        .query_any("to"),
+        Case::new(
+            "get_coin_market_chart",
+            json!({ "id": "bitcoin", "vs_currency": "usd", "days": "1", "interval": "daily" }),
+            "/coins/bitcoin/market_chart",
+        )
+        .query("vs_currency", "usd")
+        .query("days", "1")
+        .query("interval", "daily"),
+        Case::new("get_network_status", json!({}), "/ping"),
+        Case::new("get_global_data", json!({}), "/global"),
+        Case::new(
+            "get_global_defi_data",
+            json!({}),
+            "/global/decentralized_finance_defi",
+        ),
+        Case::new(
+            "get_exchanges",
+            json!({ "per_page": 10, "page": 1 }),
+            "/exchanges",
+        )
+        .query("per_page", 10)
+        .query("page", 1)
+        .empty_list(),
+        Case::new(
+            "get_exchange",
+            json!({ "id": "binance" }),
+            "/exchanges/binance",
+        ),
+        Case::new(
+            "get_exchange_tickers",
+            json!({
+                "id": "binance",
+                "coin_ids": ["bitcoin", "ethereum"],
+                "page": 1,
+                "depth": true,
+                "order": "volume_desc"
+            }),
+            "/exchanges/binance/tickers",
+        )
+        .query("coin_ids", "bitcoin,ethereum")
+        .query("page", 1)
+        .query("depth", true)
+        .query("order", "volume_desc"),
+        Case::new(
+            "get_exchange_volume_chart",
+            json!({ "id": "binance", "days": 7 }),
+            "/exchanges/binance/volume_chart",
+        )
+        .query("days", 7)
+        .empty_list(),
+        Case::new(
+            "get_coins_list",
+            json!({ "include_platform": true }),
+            "/coins/list",
+        )
+        .query("include_platform", true)
+        .empty_list(),
+        Case::new(
+            "get_coin_tickers",
+            json!({
+                "id": "bitcoin",
+                "exchange_ids": ["binance"],
+                "include_exchange_logo": false,
+                "page": 1,
+                "order": "trust_score_desc",
+                "depth": false
+            }),
+            "/coins/bitcoin/tickers",
+        )
+        .query("exchange_ids", "binance")
+        .query("include_exchange_logo", false)
+        .query("page", 1)
+        .query("order", "trust_score_desc")
+        .query("depth", false),
+        Case::new(
+            "get_coin_history",
+            json!({ "id": "bitcoin", "date": "30-12-2023", "localization": false }),
+            "/coins/bitcoin/history",
+        )
+        .query("date", "30-12-2023")
+        .query("localization", false),
+        Case::new(
+            "get_coin_ohlc",
+            json!({ "id": "bitcoin", "vs_currency": "usd", "days": "1" }),
+            "/coins/bitcoin/ohlc",
+        )
+        .query("vs_currency", "usd")
+        .query("days", "1")
+        .empty_list(),
+        Case::new(
+            "get_coin_contract",
+            json!({ "id": "ethereum", "contract_address": USDC_ERC20 }),
+            format!("/coins/ethereum/contract/{}", USDC_ERC20),
+        ),
+        Case::new(
+            "get_coin_contract_market_chart",
+            json!({
+                "id": "ethereum",
+                "contract_address": USDC_ERC20,
+                "vs_currency": "usd",
+                "days": "30"
+            }),
+            format!("/coins/ethereum/contract/{}/market_chart", USDC_ERC20),
+        )
+        .query("vs_currency", "usd")
+        .query("days", "30"),
+        Case::new("get_asset_platforms", json!({}), "/asset_platforms").empty_list(),
+        Case::new(
+            "get_coins_categories_list",
+            json!({}),
+            "/coins/categories/list",
+        )
+        .empty_list(),
+        Case::new(
+            "get_coins_categories",
+            json!({ "order": "market_cap_desc" }),
+            "/coins/categories",
+        )
+        .query("order", "market_cap_desc")
+        .empty_list(),
+        Case::new("get_indexes", json!({}), "/indexes").empty_list(),
+        Case::new("get_indexes_list", json!({}), "/indexes/list").empty_list(),
+        Case::new("get_derivatives", json!({}), "/derivatives").empty_list(),
+        Case::new(
+            "get_derivatives_exchanges",
+            json!({ "order": "name_asc", "per_page": 10, "page": 1 }),
+            "/derivatives/exchanges",
+        )
+        .query("order", "name_asc")
+        .query("per_page", 10)
+        .query("page", 1)
+        .empty_list(),
+        Case::new(
+            "get_derivatives_exchange",
+            json!({ "id": "binance_futures", "include_tickers": "unexpired" }),
+            "/derivatives/exchanges/binance_futures",
+        )
+        .query("include_tickers", "unexpired"),
+        Case::new("get_exchange_rates", json!({}), "/exchange_rates"),
+        Case::new("search", json!({ "query": "solana" }), "/search").query("query", "solana"),
+        Case::new("get_trending", json!({}), "/search/trending"),
+        Case::new(
+            "get_companies_public_treasury",
+            json!({ "coin_id": "bitcoin" }),
+            "/companies/public_treasury/bitcoin",
+        ),
+        Case::new(
+            "get_top_gainers_losers",
+            json!({ "vs_currency": "usd", "duration": "24h", "top_coins": "1000" }),
+            "/coins/top_gainers_losers",
+        )
+        .query("vs_currency", "usd")
+        .query("duration", "24h")
+        .query("top_coins", "1000"),
+        Case::new("get_new_coins", json!({}), "/coins/list/new").empty_list(),
+    ]
+}
+
+#[tokio::test]
+async fn test_coingecko_actions_against_fixtures() {
+    run_cases(provider(), cases()).await;
+}
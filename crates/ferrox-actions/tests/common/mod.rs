@@ -0,0 +1,276 @@
+//! Record/replay harness for the data-provider action groups.
+//!
+//! Each [`Case`] names an action, the parameters the model would send, and the
+//! request the action must produce. [`run_cases`] replays every case against a
+//! mockito server three times: with the recorded fixture (success), with an
+//! empty payload and with a 400 error, checking path, query and headers each
+//! time.
+//!
+//! Fixtures live in `tests/fixtures/<provider>/<action>.json`. Set
+//! `FERROX_RECORD_FIXTURES=1` (plus the provider API keys) to refresh them from
+//! the live APIs instead of replaying.
+
+#![allow(dead_code)]
+
+use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
+
+use ferrox_actions::{
+    http::{Quota, RateLimiter},
+    ActionGroup, FunctionAction,
+};
+use mockito::{Matcher, Server};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+/// Expected value of a query parameter.
+pub enum Param {
+    Eq(String),
+    /// Present with any value, for timestamps derived from the current time.
+    Any,
+}
+
+pub struct Case {
+    pub action: &'static str,
+    pub params: Value,
+    pub path: String,
+    pub query: Vec<(&'static str, Param)>,
+    /// Body served for the empty-payload run, `{}` or `[]`.
+    pub empty: &'static str,
+}
+
+impl Case {
+    pub fn new(action: &'static str, params: Value, path: impl Into<String>) -> Self {
+        Self {
+            action,
+            params,
+            path: path.into(),
+            query: Vec::new(),
+            empty: "{}",
+        }
+    }
+
+    pub fn query(mut self, key: &'static str, value: impl ToString) -> Self {
+        self.query.push((key, Param::Eq(value.to_string())));
+        self
+    }
+
+    pub fn query_any(mut self, key: &'static str) -> Self {
+        self.query.push((key, Param::Any));
+        self
+    }
+
+    pub fn empty_list(mut self) -> Self {
+        self.empty = "[]";
+        self
+    }
+
+    fn query_matcher(&self) -> Matcher {
+        if self.query.is_empty() {
+            return Matcher::Missing;
+        }
+        Matcher::AllOf(
+            self.query
+                .iter()
+                .map(|(key, value)| match value {
+                    Param::Eq(value) => Matcher::UrlEncoded(key.to_string(), value.clone()),
+                    Param::Any => Matcher::Regex(format!("(^|&){}=[^&]+", key)),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A provider under test: where its fixtures live, which headers every request
+/// must carry, and how to build its action group.
+pub struct Provider {
+    pub name: &'static str,
+    pub headers: Vec<(&'static str, &'static str)>,
+    /// Builds the group against `base_url`, or against the live API when `None`.
+    pub group: fn(Option<String>) -> Vec<Arc<FunctionAction<()>>>,
+}
+
+/// A limiter that never throttles, so replay runs aren't slowed to live quotas.
+pub fn unlimited() -> Arc<RateLimiter> {
+    Arc::new(RateLimiter::new(Quota::per_second(10_000)))
+}
+
+pub fn actions<G: ActionGroup<()>>(group: G) -> Vec<Arc<FunctionAction<()>>> {
+    group.actions().to_vec()
+}
+
+fn fixture_path(provider: &str, action: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(provider)
+        .join(format!("{}.json", action))
+}
+
+fn recording() -> bool {
+    std::env::var("FERROX_RECORD_FIXTURES").is_ok_and(|value| value == "1")
+}
+
+async fn execute(action: &FunctionAction<()>, params: &Value) -> Result<String, String> {
+    action
+        .execute(
+            params.clone(),
+            serde_json::json!({}),
+            Arc::new(Mutex::new(())),
+        )
+        .await
+}
+
+/// Action results are JSON-encoded strings whose content is the provider JSON.
+fn decode_result(result: &str) -> Value {
+    let inner: String = serde_json::from_str(result).expect("action result is a JSON string");
+    serde_json::from_str(&inner).expect("action result wraps JSON")
+}
+
+/// Checks that everything in `actual` came from `expected`. Typed endpoints
+/// drop unknown fields and fill defaults, so `actual` may be a subset with
+/// extra nulls or empty collections.
+fn assert_subset(actual: &Value, expected: &Value, at: &str) {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => {
+            for (key, value) in actual {
+                let path = format!("{}.{}", at, key);
+                match expected.get(key) {
+                    Some(expected) => assert_subset(value, expected, &path),
+                    None => assert!(
+                        is_default(value),
+                        "{} is not in the fixture: {}",
+                        path,
+                        value
+                    ),
+                }
+            }
+        }
+        (Value::Array(actual), Value::Array(expected)) => {
+            assert_eq!(actual.len(), expected.len(), "{} length differs", at);
+            for (index, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+                assert_subset(actual, expected, &format!("{}[{}]", at, index));
+            }
+        }
+        (Value::Number(actual), Value::Number(expected)) => assert_eq!(
+            actual.as_f64(),
+            expected.as_f64(),
+            "{} differs from the fixture",
+            at
+        ),
+        (actual, expected) => assert_eq!(actual, expected, "{} differs from the fixture", at),
+    }
+}
+
+fn is_default(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Bool(value) => !value,
+        Value::Array(values) => values.is_empty(),
+        Value::Object(values) => values.is_empty(),
+        _ => false,
+    }
+}
+
+/// Fails unless `cases` cover exactly the actions the provider's group exposes.
+fn assert_covers_group(provider: &Provider, cases: &[Case]) {
+    let group: BTreeSet<String> = (provider.group)(Some("http://127.0.0.1:9".to_string()))
+        .iter()
+        .map(|action| action.definition().name)
+        .collect();
+    let covered: BTreeSet<String> = cases.iter().map(|case| case.action.to_string()).collect();
+    assert_eq!(
+        group, covered,
+        "{} fixture cases must cover every action",
+        provider.name
+    );
+}
+
+fn find<'a>(actions: &'a [Arc<FunctionAction<()>>], name: &str) -> &'a FunctionAction<()> {
+    actions
+        .iter()
+        .find(|action| action.definition().name == name)
+        .unwrap_or_else(|| panic!("no action named {}", name))
+}
+
+/// Re-records every fixture from the live API.
+async fn record(provider: &Provider, cases: &[Case]) {
+    let actions = (provider.group)(None);
+    for case in cases {
+        let result = execute(find(&actions, case.action), &case.params)
+            .await
+            .unwrap_or_else(|e| panic!("recording {} failed: {}", case.action, e));
+        let body = serde_json::to_string_pretty(&decode_result(&result)).unwrap();
+        let path = fixture_path(provider.name, case.action);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, body + "\n").unwrap();
+    }
+}
+
+/// Replays every case against a mock server, or re-records the fixtures when
+/// `FERROX_RECORD_FIXTURES=1`.
+pub async fn run_cases(provider: Provider, cases: Vec<Case>) {
+    assert_covers_group(&provider, &cases);
+    if recording() {
+        return record(&provider, &cases).await;
+    }
+
+    let mut server = Server::new_async().await;
+    let actions = (provider.group)(Some(server.url()));
+
+    for case in &cases {
+        let action = find(&actions, case.action);
+        let fixture = std::fs::read_to_string(fixture_path(provider.name, case.action))
+            .unwrap_or_else(|e| panic!("missing fixture for {}: {}", case.action, e));
+
+        let mut mock_with = |status: usize, body: &str| {
+            let mut mock = server
+                .mock("GET", case.path.as_str())
+                .match_query(case.query_matcher());
+            for (name, value) in &provider.headers {
+                mock = mock.match_header(*name, *value);
+            }
+            mock.with_status(status).with_body(body).expect(1)
+        };
+
+        // Success: the fixture is passed through to the model.
+        let mock = mock_with(200, &fixture).create_async().await;
+        let result = execute(action, &case.params).await;
+        mock.assert_async().await;
+        mock.remove_async().await;
+        let result = result.unwrap_or_else(|e| panic!("{} failed: {}", case.action, e));
+        assert_subset(
+            &decode_result(&result),
+            &serde_json::from_str(&fixture).unwrap(),
+            case.action,
+        );
+
+        // Empty payload: passed through, or reported as a decode error for
+        // typed endpoints. Never a panic.
+        let mock = mock_with(200, case.empty).create_async().await;
+        let result = execute(action, &case.params).await;
+        mock.assert_async().await;
+        mock.remove_async().await;
+        if let Err(e) = result {
+            assert!(
+                e.to_lowercase().contains("decode") || e.to_lowercase().contains("pars"),
+                "{} with an empty payload: {}",
+                case.action,
+                e
+            );
+        }
+
+        // Error status: surfaced as an error naming the status.
+        let mock = mock_with(400, r#"{"error":"bad request"}"#)
+            .create_async()
+            .await;
+        let result = execute(action, &case.params).await;
+        mock.assert_async().await;
+        mock.remove_async().await;
+        let error = result.expect_err(case.action);
+        assert!(
+            error.contains("400"),
+            "{} error does not name the status: {}",
+            case.action,
+            error
+        );
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{actions, run_cases, unlimited, Case, Provider};
+use ferrox_actions::{dexscreener::client::DexScreenerClient, DexScreenerActionGroup};
+use serde_json::json;
+
+const SOL: &str = "So11111111111111111111111111111111111111112";
+const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+const SOL_USDC_PAIR: &str = "8HoQnePLqPj4M7PUDzfw8e3Ymdwgc7NLGnaTUapubyvu";
+
+fn provider() -> Provider {
+    Provider {
+        name: "dexscreener",
+        headers: vec![],
+        group: |base_url| match base_url {
+            Some(base_url) => actions(DexScreenerActionGroup::with_client(
+                DexScreenerClient::new()
+                    .with_base_url(base_url)
+                    .with_rate_limiter(unlimited()),
+            )),
+            None => actions(DexScreenerActionGroup::new()),
+        },
+    }
+}
+
+fn cases() -> Vec<Case> {
+    vec![
+        Case::new("get_token_profiles", json!({}), "/token-profiles/latest/v1").empty_list(),
+        Case::new(
+            "get_token_orders",
+            json!({ "chain_id": "solana", "token_address": SOL }),
+            format!("/orders/v1/solana/{}", SOL),
+        )
+        .empty_list(),
+        Case::new("get_token_boosts", json!({}), "/token-boosts/latest/v1").empty_list(),
+        Case::new("get_token_boosts_top", json!({}), "/token-boosts/top/v1").empty_list(),
+        Case::new(
+            "get_token_pairs",
+            json!({ "chain_id": "solana", "token_address": SOL }),
+            format!("/token-pairs/v1/solana/{}", SOL),
+        )
+        .empty_list(),
+        Case::new(
+            "get_tokens",
+            json!({ "chain_id": "solana", "token_addresses": format!("{},{}", SOL, USDC) }),
+            format!("/tokens/v1/solana/{},{}", SOL, USDC),
+        )
+        .empty_list(),
+        Case::new(
+            "search_pairs",
+            json!({ "query": "SOL/USDC" }),
+            "/latest/dex/search",
+        )
+        .query("q", "SOL/USDC"),
+        Case::new(
+            "get_pairs",
+            json!({ "chain_id": "solana", "pair_id": SOL_USDC_PAIR }),
+            format!("/latest/dex/pairs/solana/{}", SOL_USDC_PAIR),
+        ),
+    ]
+}
+
+#[tokio::test]
+async fn test_dexscreener_actions_against_fixtures() {
+    run_cases(provider(), cases()).await;
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::{actions, run_cases, unlimited, Case, Provider};
+use ferrox_actions::{gmgn::client::GmgnClient, GmgnActionGroup};
+use serde_json::json;
+
+const SOL: &str = "So11111111111111111111111111111111111111112";
+const BONK: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+const WALLET: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+fn provider() -> Provider {
+    Provider {
+        name: "gmgn",
+        headers: vec![],
+        group: |base_url| match base_url {
+            Some(base_url) => actions(GmgnActionGroup::with_client(
+                GmgnClient::new()
+                    .with_base_url(base_url)
+                    .with_rate_limiter(unlimited()),
+            )),
+            None => actions(GmgnActionGroup::new()),
+        },
+    }
+}
+
+fn cases() -> Vec<Case> {
+    vec![
+        Case::new(
+            "get_gmgn_kline_data",
+            json!({
+                "token_address": SOL,
+                "time_from": 1700000000,
+                "time_to": 1700086400,
+                "resolution": "15m"
+            }),
+            format!("/defi/quotation/v1/tokens/kline/sol/{}", SOL),
+        )
+        .query("resolution", "15m")
+        .query("from", 1700000000)
+        .query("to", 1700086400),
+        Case::new(
+            "get_gmgn_token_info",
+            json!({ "token_address": BONK, "chain": "solana" }),
+            format!("/defi/quotation/v1/tokens/sol/{}", BONK),
+        ),
+        Case::new(
+            "get_gmgn_top_holders",
+            json!({ "token_address": BONK, "limit": 2 }),
+            format!("/defi/quotation/v1/tokens/top_holders/sol/{}", BONK),
+        )
+        .query("limit", 2)
+        .query("orderby", "amount_percentage")
+        .query("direction", "desc"),
+        Case::new(
+            "get_gmgn_wallet_activity",
+            json!({ "wallet_address": WALLET, "limit": 10, "cursor": "abc" }),
+            "/defi/quotation/v1/wallet_activity/sol",
+        )
+        .query("wallet", WALLET)
+        .query("limit", 10)
+        .query("cursor", "abc"),
+        Case::new(
+            "get_gmgn_new_pairs",
+            json!({ "chain": "sol", "limit": 5 }),
+            "/defi/quotation/v1/pairs/sol/new_pairs",
+        )
+        .query("limit", 5)
+        .query("orderby", "open_timestamp")
+        .query("direction", "desc"),
+    ]
+}
+
+#[tokio::test]
+async fn test_gmgn_actions_against_fixtures() {
+    run_cases(provider(), cases()).await;
+}
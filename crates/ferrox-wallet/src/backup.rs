@@ -0,0 +1,396 @@
+//! Moving wallets in and out of a [`WalletManager`](crate::WalletManager).
+//!
+//! Users can bring a recovery phrase or a keypair from another wallet, and
+//! take theirs out either as a keypair file, the JSON array of 64 bytes
+//! `solana-keygen` writes and Phantom and Solflare import, or as a backup. A
+//! backup holds the recovery phrase and every account, sealed with
+//! XChaCha20-Poly1305 under a key derived from its password with Argon2id,
+//! and restores into any wallet manager.
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    bs58,
+    signature::{keypair_from_seed, Keypair},
+    signer::Signer,
+};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{
+    hd,
+    keystore_wallet_manager::{
+        derive_key, open, random, seal, KdfConfig, KdfSettings, Sealed, SALT_LEN,
+    },
+};
+
+const BACKUP_FORMAT: &str = "ferrox-wallet-backup";
+const BACKUP_VERSION: u32 = 1;
+const BACKUP_AAD: &[u8] = b"ferrox-backup:v1";
+
+/// Shortest password a backup can be sealed with.
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// The most Argon2 work a pasted backup can ask for: 1 GiB, ten passes over
+/// it and 16 lanes. Backups made here use far less; larger ones are refused
+/// rather than left to exhaust the bot's memory.
+const MAX_BACKUP_KDF: KdfConfig = KdfConfig {
+    memory_kib: 1024 * 1024,
+    iterations: 10,
+    parallelism: 16,
+};
+
+/// A wallet brought in from elsewhere.
+pub enum WalletImport {
+    /// A BIP39 recovery phrase. It becomes the user's seed, so only users
+    /// without a wallet can import one.
+    Phrase(Zeroizing<String>),
+    /// The 64 bytes of a keypair, added as an account called `label`.
+    Keypair {
+        label: String,
+        keypair: Zeroizing<Vec<u8>>,
+    },
+    /// A backup made with [`ExportFormat::Backup`]. Like a phrase, it can
+    /// only be restored for a user without a wallet.
+    Backup {
+        backup: String,
+        password: Zeroizing<String>,
+    },
+}
+
+impl WalletImport {
+    /// Recognizes what a user pasted: a keypair file, a base58 secret key as
+    /// Phantom exports it, a backup, which needs `password`, or otherwise a
+    /// recovery phrase. Keypairs are imported as `label`.
+    pub fn parse(secret: &str, label: &str, password: Option<&str>) -> Result<Self, String> {
+        let secret = secret.trim();
+        if secret.starts_with('{') {
+            let password = password.ok_or("A password is needed to restore a backup")?;
+            return Ok(Self::Backup {
+                backup: secret.to_string(),
+                password: Zeroizing::new(password.to_string()),
+            });
+        }
+        let keypair = if secret.starts_with('[') {
+            serde_json::from_str::<Vec<u8>>(secret)
+                .map(Zeroizing::new)
+                .map_err(|_| "Invalid keypair file: expected an array of 64 bytes".to_string())?
+        } else if !secret.contains(char::is_whitespace) {
+            bs58::decode(secret)
+                .into_vec()
+                .map(Zeroizing::new)
+                .map_err(|_| "Invalid secret key: expected base58".to_string())?
+        } else {
+            return Ok(Self::Phrase(hd::normalize_phrase(secret)?));
+        };
+        Ok(Self::Keypair {
+            label: hd::normalize_label(label)?,
+            keypair,
+        })
+    }
+
+    /// What the import adds, with the backup opened and keys checked.
+    /// Backups are opened on a blocking thread, as deriving their key takes
+    /// a while.
+    pub(crate) async fn open(self) -> Result<Import, String> {
+        match self {
+            Self::Phrase(phrase) => Ok(Import::Restore(BackupContents::from_phrase(&phrase)?)),
+            Self::Keypair { label, keypair } => Ok(Import::Keypair {
+                label: hd::normalize_label(&label)?,
+                keypair: keypair_from_bytes(&keypair)?,
+            }),
+            Self::Backup { backup, password } => {
+                let contents = tokio::task::spawn_blocking(move || open_backup(&backup, &password))
+                    .await
+                    .map_err(|e| format!("Failed to open the backup: {}", e))??;
+                Ok(Import::Restore(contents))
+            }
+        }
+    }
+}
+
+pub(crate) enum Import {
+    /// Replaces the user's (empty) wallet.
+    Restore(BackupContents),
+    Keypair {
+        label: String,
+        keypair: Keypair,
+    },
+}
+
+/// How to export a wallet.
+pub enum ExportFormat {
+    /// One account as a Solana keypair file. Anyone holding the file
+    /// controls the account.
+    KeypairFile,
+    /// The recovery phrase and every account in use, encrypted with
+    /// `password`.
+    Backup { password: Zeroizing<String> },
+}
+
+/// What a backup holds, before it is sealed.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct BackupContents {
+    pub phrase: Option<String>,
+    pub default_account: Option<String>,
+    pub accounts: Vec<BackupAccount>,
+    /// Index of the user's EVM wallet in `phrase`, if they have one.
+    #[serde(default)]
+    pub evm_index: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct BackupAccount {
+    pub label: String,
+    /// Index in `phrase` of accounts derived from it.
+    pub index: Option<u32>,
+    /// The base58 keypair of accounts that were not derived from the phrase.
+    pub keypair: Option<String>,
+}
+
+impl BackupContents {
+    /// A phrase restored with its first account, as if it was created here.
+    fn from_phrase(phrase: &str) -> Result<Self, String> {
+        Ok(Self {
+            phrase: Some(hd::normalize_phrase(phrase)?.to_string()),
+            default_account: Some(hd::DEFAULT_ACCOUNT.to_string()),
+            accounts: vec![BackupAccount {
+                label: hd::DEFAULT_ACCOUNT.to_string(),
+                index: Some(0),
+                keypair: None,
+            }],
+            evm_index: None,
+        })
+    }
+
+    /// The keypair of `account`, as stored or derived from the phrase.
+    pub fn keypair(&self, account: &BackupAccount) -> Result<Keypair, String> {
+        match (&account.keypair, account.index, &self.phrase) {
+            (Some(keypair), _, _) => {
+                let bytes = Zeroizing::new(
+                    bs58::decode(keypair)
+                        .into_vec()
+                        .map_err(|_| format!("The keypair of '{}' is corrupted", account.label))?,
+                );
+                keypair_from_bytes(&bytes)
+            }
+            (None, Some(index), Some(phrase)) => hd::derive_keypair(phrase, "", index),
+            _ => Err(format!("The backup has no key for '{}'", account.label)),
+        }
+    }
+
+    /// The label of the account to use by default: the stored one if the
+    /// backup has it, else the first account.
+    pub fn default_label(&self) -> Option<&str> {
+        self.default_account
+            .as_deref()
+            .filter(|label| self.accounts.iter().any(|account| account.label == *label))
+            .or_else(|| self.accounts.first().map(|account| account.label.as_str()))
+    }
+}
+
+impl Drop for BackupContents {
+    fn drop(&mut self) {
+        self.phrase.zeroize();
+        for account in &mut self.accounts {
+            account.keypair.zeroize();
+        }
+    }
+}
+
+/// The sealed form of a backup.
+#[derive(Serialize, Deserialize)]
+struct BackupFile {
+    format: String,
+    version: u32,
+    kdf: KdfSettings,
+    contents: Sealed,
+}
+
+/// `keypair` as a Solana keypair file.
+pub(crate) fn keypair_file(keypair: &Keypair) -> Zeroizing<String> {
+    let bytes = Zeroizing::new(keypair.to_bytes());
+    let bytes: Vec<String> = bytes.iter().map(u8::to_string).collect();
+    Zeroizing::new(format!("[{}]", bytes.join(",")))
+}
+
+/// The keypair whose secret and public halves are `bytes`. Fails if the
+/// halves do not belong together, which a truncated or edited key gives.
+pub(crate) fn keypair_from_bytes(bytes: &[u8]) -> Result<Keypair, String> {
+    if bytes.len() != 64 {
+        return Err(format!(
+            "Invalid keypair: expected 64 bytes, got {}",
+            bytes.len()
+        ));
+    }
+    let keypair = keypair_from_seed(&bytes[..32]).map_err(|e| format!("Invalid keypair: {}", e))?;
+    if keypair.pubkey().as_ref() != &bytes[32..] {
+        return Err("Invalid keypair: its public key does not match the secret key".to_string());
+    }
+    Ok(keypair)
+}
+
+pub(crate) fn seal_backup(
+    contents: &BackupContents,
+    password: &str,
+    kdf: KdfConfig,
+) -> Result<Zeroizing<String>, String> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(format!(
+            "Backup passwords must be at least {} characters long",
+            MIN_PASSWORD_LEN
+        ));
+    }
+    let kdf = KdfSettings {
+        salt: bs58::encode(random::<SALT_LEN>()).into_string(),
+        config: kdf,
+    };
+    let plaintext = Zeroizing::new(
+        serde_json::to_vec(contents).map_err(|e| format!("Failed to serialize backup: {}", e))?,
+    );
+    let file = BackupFile {
+        format: BACKUP_FORMAT.to_string(),
+        version: BACKUP_VERSION,
+        contents: seal(&derive_key(password, &kdf)?, &plaintext, BACKUP_AAD)?,
+        kdf,
+    };
+    serde_json::to_string_pretty(&file)
+        .map(Zeroizing::new)
+        .map_err(|e| format!("Failed to serialize backup: {}", e))
+}
+
+pub(crate) fn open_backup(backup: &str, password: &str) -> Result<BackupContents, String> {
+    let file: BackupFile =
+        serde_json::from_str(backup).map_err(|e| format!("Invalid backup: {}", e))?;
+    if file.format != BACKUP_FORMAT || file.version != BACKUP_VERSION {
+        return Err(format!(
+            "Unsupported backup {} version {}",
+            file.format, file.version
+        ));
+    }
+    let kdf = &file.kdf.config;
+    if kdf.memory_kib > MAX_BACKUP_KDF.memory_kib
+        || kdf.iterations > MAX_BACKUP_KDF.iterations
+        || kdf.parallelism > MAX_BACKUP_KDF.parallelism
+    {
+        return Err(format!(
+            "The backup asks for more key derivation work than allowed (at most {} MiB, {} passes and {} lanes)",
+            MAX_BACKUP_KDF.memory_kib / 1024,
+            MAX_BACKUP_KDF.iterations,
+            MAX_BACKUP_KDF.parallelism
+        ));
+    }
+    let plaintext = open(
+        &derive_key(password, &file.kdf)?,
+        &file.contents,
+        BACKUP_AAD,
+    )
+    .map_err(|_| "Wrong backup password".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Invalid backup: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    const TEST_KDF: KdfConfig = KdfConfig {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[tokio::test]
+    async fn test_parse_recognizes_each_format() {
+        let keypair = Keypair::new();
+        let file = keypair_file(&keypair);
+        let base58 = keypair.to_base58_string();
+        for secret in [file.as_str(), base58.as_str()] {
+            match WalletImport::parse(secret, "Old", None)
+                .unwrap()
+                .open()
+                .await
+            {
+                Ok(Import::Keypair {
+                    label,
+                    keypair: imported,
+                }) => {
+                    assert_eq!(label, "old");
+                    assert_eq!(imported.pubkey(), keypair.pubkey());
+                }
+                _ => panic!("{} was not read as a keypair", secret),
+            }
+        }
+        assert!(matches!(
+            WalletImport::parse(&format!(" {} ", PHRASE.to_uppercase()), "main", None),
+            Ok(WalletImport::Phrase(phrase)) if *phrase == PHRASE
+        ));
+        assert!(WalletImport::parse("{}", "main", None).is_err());
+
+        // A keypair whose halves were swapped
+        let mut bytes = keypair.to_bytes();
+        bytes.rotate_left(32);
+        assert!(keypair_from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_backup_round_trip() {
+        let imported = Keypair::new();
+        let contents = BackupContents {
+            phrase: Some(PHRASE.to_string()),
+            default_account: Some("old".to_string()),
+            accounts: vec![
+                BackupAccount {
+                    label: "main".to_string(),
+                    index: Some(0),
+                    keypair: None,
+                },
+                BackupAccount {
+                    label: "old".to_string(),
+                    index: None,
+                    keypair: Some(imported.to_base58_string()),
+                },
+            ],
+            evm_index: Some(0),
+        };
+        assert!(seal_backup(&contents, "short", TEST_KDF).is_err());
+        let backup = seal_backup(&contents, "correct horse", TEST_KDF).unwrap();
+        assert!(!backup.contains("abandon"));
+        assert_eq!(
+            open_backup(&backup, "wrong password").err().unwrap(),
+            "Wrong backup password"
+        );
+
+        let restored = open_backup(&backup, "correct horse").unwrap();
+        assert_eq!(restored.default_label(), Some("old"));
+        assert_eq!(
+            restored
+                .keypair(&restored.accounts[0])
+                .unwrap()
+                .pubkey()
+                .to_string(),
+            "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+        );
+        assert_eq!(
+            restored.keypair(&restored.accounts[1]).unwrap().pubkey(),
+            imported.pubkey()
+        );
+    }
+
+    #[test]
+    fn test_backups_asking_for_too_much_work_are_refused() {
+        let contents = BackupContents::from_phrase(PHRASE).unwrap();
+        let backup = seal_backup(&contents, "correct horse", TEST_KDF).unwrap();
+        for (field, value) in [
+            ("memory_kib", 4_000_000_000u32),
+            ("iterations", 1_000_000),
+            ("parallelism", 1_000),
+        ] {
+            let mut file: serde_json::Value = serde_json::from_str(&backup).unwrap();
+            file["kdf"][field] = value.into();
+            let err = open_backup(&file.to_string(), "correct horse")
+                .err()
+                .unwrap();
+            assert!(err.contains("more key derivation work"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,397 @@
+//! Wallets for Ethereum, its layer 2s such as Base and Arbitrum, and
+//! Hyperliquid, which signs the same way.
+//!
+//! Keys are secp256k1. An [`EvmSigner`] only has to sign 32-byte digests:
+//! transactions (EIP-155), typed data (EIP-712) and personal messages
+//! (EIP-191) are hashed here, so a signer backed by another process or a
+//! key management service implements [`EvmSigner::sign_hash`] and gets the
+//! rest.
+
+pub mod eip712;
+
+use std::{fmt, str::FromStr};
+
+use libsecp256k1::{Message, PublicKey, RecoveryId, SecretKey, Signature};
+use rand::{rngs::OsRng, RngCore};
+use sha3::{Digest, Keccak256};
+use zeroize::Zeroizing;
+
+use crate::signer::SignFuture;
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// A 20-byte account address. It displays with its EIP-55 checksum and
+/// parses from any case, rejecting mixed case with a wrong checksum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address(pub [u8; 20]);
+
+impl Address {
+    pub fn from_public_key(key: &PublicKey) -> Self {
+        // The uncompressed key without its 0x04 prefix
+        let hash = keccak256(&key.serialize()[1..]);
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&hash[12..]);
+        Self(address)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = encode_hex(&self.0);
+        let hash = keccak256(hex.as_bytes());
+        let checksummed: String = hex
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = if i % 2 == 0 {
+                    hash[i / 2] >> 4
+                } else {
+                    hash[i / 2] & 0x0f
+                };
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+        write!(f, "0x{}", checksummed)
+    }
+}
+
+impl FromStr for Address {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_hex(s).map_err(|e| format!("Invalid address '{}': {}", s, e))?;
+        let address = Self(
+            bytes
+                .try_into()
+                .map_err(|_| format!("Invalid address '{}': expected 20 bytes", s))?,
+        );
+        let digits = s.trim_start_matches("0x");
+        let mixed_case = digits.chars().any(|c| c.is_ascii_uppercase())
+            && digits.chars().any(|c| c.is_ascii_lowercase());
+        if mixed_case && address.to_string()[2..] != *digits {
+            return Err(format!("Invalid address '{}': wrong checksum", s));
+        }
+        Ok(address)
+    }
+}
+
+/// A recoverable ECDSA signature. `recovery_id` is 0 or 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvmSignature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    pub recovery_id: u8,
+}
+
+impl EvmSignature {
+    /// `r`, `s` and `v` = 27 + recovery id, the 65 bytes personal_sign and
+    /// eth_signTypedData_v4 return.
+    pub fn to_bytes(&self) -> [u8; 65] {
+        let mut bytes = [0u8; 65];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..64].copy_from_slice(&self.s);
+        bytes[64] = 27 + self.recovery_id;
+        bytes
+    }
+
+    pub fn to_hex(&self) -> String {
+        format!("0x{}", encode_hex(&self.to_bytes()))
+    }
+
+    /// The address whose key produced this signature over `hash`.
+    pub fn recover(&self, hash: &[u8; 32]) -> Result<Address, String> {
+        let mut compact = [0u8; 64];
+        compact[..32].copy_from_slice(&self.r);
+        compact[32..].copy_from_slice(&self.s);
+        let signature = Signature::parse_standard(&compact)
+            .map_err(|e| format!("Invalid signature: {:?}", e))?;
+        let recovery_id = RecoveryId::parse(self.recovery_id)
+            .map_err(|e| format!("Invalid recovery id: {:?}", e))?;
+        let key = libsecp256k1::recover(&Message::parse(hash), &signature, &recovery_id)
+            .map_err(|e| format!("Cannot recover the signer: {:?}", e))?;
+        Ok(Address::from_public_key(&key))
+    }
+}
+
+/// Signs for one EVM address.
+pub trait EvmSigner: Send + Sync {
+    fn address(&self) -> Address;
+
+    /// Signs a 32-byte digest as is.
+    fn sign_hash(&self, hash: [u8; 32]) -> SignFuture<'_, EvmSignature>;
+
+    /// Signs `transaction` and returns it RLP-encoded, ready for
+    /// eth_sendRawTransaction.
+    fn sign_transaction(&self, transaction: &EvmTransaction) -> SignFuture<'_, Vec<u8>> {
+        let transaction = transaction.clone();
+        Box::pin(async move {
+            let signature = self.sign_hash(transaction.signing_hash()).await?;
+            Ok(transaction.encode_signed(&signature))
+        })
+    }
+
+    /// Signs EIP-712 typed data, given as the JSON eth_signTypedData_v4
+    /// takes.
+    fn sign_typed_data<'a>(
+        &'a self,
+        typed_data: &'a serde_json::Value,
+    ) -> SignFuture<'a, EvmSignature> {
+        Box::pin(async move {
+            let hash = eip712::hash_typed_data(typed_data)?;
+            self.sign_hash(hash).await
+        })
+    }
+
+    /// Signs `message` as personal_sign does, behind the EIP-191 prefix.
+    fn personal_sign<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a, EvmSignature> {
+        Box::pin(async move { self.sign_hash(personal_message_hash(message)).await })
+    }
+}
+
+/// Signs with a secp256k1 key held in this process.
+pub struct LocalEvmSigner {
+    key: SecretKey,
+    address: Address,
+}
+
+impl LocalEvmSigner {
+    pub fn new(secret: &[u8; 32]) -> Result<Self, String> {
+        let key = SecretKey::parse(secret).map_err(|_| "Invalid secp256k1 key".to_string())?;
+        Ok(Self {
+            address: Address::from_public_key(&PublicKey::from_secret_key(&key)),
+            key,
+        })
+    }
+
+    pub fn random() -> Self {
+        loop {
+            let mut secret = Zeroizing::new([0u8; 32]);
+            OsRng.fill_bytes(secret.as_mut());
+            // All but a 2^-128 fraction of values are valid keys
+            if let Ok(signer) = Self::new(&secret) {
+                return signer;
+            }
+        }
+    }
+}
+
+impl fmt::Debug for LocalEvmSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalEvmSigner")
+            .field("address", &self.address)
+            .finish()
+    }
+}
+
+impl EvmSigner for LocalEvmSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_hash(&self, hash: [u8; 32]) -> SignFuture<'_, EvmSignature> {
+        let (signature, recovery_id) = libsecp256k1::sign(&Message::parse(&hash), &self.key);
+        let compact = signature.serialize();
+        let mut signature = EvmSignature {
+            r: [0; 32],
+            s: [0; 32],
+            recovery_id: recovery_id.serialize(),
+        };
+        signature.r.copy_from_slice(&compact[..32]);
+        signature.s.copy_from_slice(&compact[32..]);
+        Box::pin(async move { Ok(signature) })
+    }
+}
+
+/// A legacy transaction, replay-protected with its chain id as in EIP-155.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvmTransaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub gas_price: u128,
+    pub gas_limit: u64,
+    /// `None` deploys `data` as a contract.
+    pub to: Option<Address>,
+    pub value: u128,
+    pub data: Vec<u8>,
+}
+
+impl EvmTransaction {
+    /// The hash that is signed: the fields followed by the chain id and two
+    /// zeros.
+    pub fn signing_hash(&self) -> [u8; 32] {
+        let mut fields = self.fields();
+        fields.extend([rlp_uint(self.chain_id.into()), rlp_uint(0), rlp_uint(0)]);
+        keccak256(&rlp_list(&fields))
+    }
+
+    pub fn encode_signed(&self, signature: &EvmSignature) -> Vec<u8> {
+        let v = u128::from(self.chain_id) * 2 + 35 + u128::from(signature.recovery_id);
+        let mut fields = self.fields();
+        fields.extend([
+            rlp_uint(v),
+            rlp_bytes(trim_zeros(&signature.r)),
+            rlp_bytes(trim_zeros(&signature.s)),
+        ]);
+        rlp_list(&fields)
+    }
+
+    fn fields(&self) -> Vec<Vec<u8>> {
+        vec![
+            rlp_uint(self.nonce.into()),
+            rlp_uint(self.gas_price),
+            rlp_uint(self.gas_limit.into()),
+            rlp_bytes(self.to.as_ref().map_or(&[][..], |to| &to.0[..])),
+            rlp_uint(self.value),
+            rlp_bytes(&self.data),
+        ]
+    }
+}
+
+/// The digest personal_sign signs: `message` behind
+/// `"\x19Ethereum Signed Message:\n"` and its length.
+pub fn personal_message_hash(message: &[u8]) -> [u8; 32] {
+    let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    prefixed.extend_from_slice(message);
+    keccak256(&prefixed)
+}
+
+fn trim_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn rlp_uint(value: u128) -> Vec<u8> {
+    rlp_bytes(trim_zeros(&value.to_be_bytes()))
+}
+
+fn rlp_bytes(bytes: &[u8]) -> Vec<u8> {
+    if let [byte] = bytes {
+        if *byte < 0x80 {
+            return vec![*byte];
+        }
+    }
+    let mut encoded = rlp_length(bytes.len(), 0x80);
+    encoded.extend_from_slice(bytes);
+    encoded
+}
+
+/// An RLP list of already encoded items.
+fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+    let mut encoded = rlp_length(payload.len(), 0xc0);
+    encoded.extend(payload);
+    encoded
+}
+
+fn rlp_length(length: usize, offset: u8) -> Vec<u8> {
+    if length <= 55 {
+        vec![offset + length as u8]
+    } else {
+        let length = trim_zeros(&length.to_be_bytes()).to_vec();
+        let mut encoded = vec![offset + 55 + length.len() as u8];
+        encoded.extend(length);
+        encoded
+    }
+}
+
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decodes hex with or without a `0x` prefix.
+pub(crate) fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.trim();
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    if !hex.len().is_multiple_of(2) {
+        return Err("odd number of hex digits".to_string());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("invalid hex digits at {}", i))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(hex: &str) -> [u8; 32] {
+        decode_hex(hex).unwrap().try_into().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_eip155_example() {
+        // The example transaction of EIP-155
+        let signer = LocalEvmSigner::new(&[0x46; 32]).unwrap();
+        assert_eq!(
+            signer.address().to_string(),
+            "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F"
+        );
+        let transaction = EvmTransaction {
+            chain_id: 1,
+            nonce: 9,
+            gas_price: 20_000_000_000,
+            gas_limit: 21_000,
+            to: Some(
+                "0x3535353535353535353535353535353535353535"
+                    .parse()
+                    .unwrap(),
+            ),
+            value: 1_000_000_000_000_000_000,
+            data: Vec::new(),
+        };
+        assert_eq!(
+            encode_hex(&transaction.signing_hash()),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+        let signed = signer.sign_transaction(&transaction).await.unwrap();
+        assert_eq!(
+            encode_hex(&signed),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_personal_sign() {
+        assert_eq!(
+            encode_hex(&personal_message_hash(b"Hello World")),
+            "a1de988600a42c4b4ab089b619297c17d53cffae5d5120d82d8a92d0bb3b78f2"
+        );
+        let signer = LocalEvmSigner::new(&key(
+            "0x0123456789012345678901234567890123456789012345678901234567890123",
+        ))
+        .unwrap();
+        let signature = signer.personal_sign(b"Hello World").await.unwrap();
+        assert_eq!(signature.to_bytes()[64], 27 + signature.recovery_id);
+        assert_eq!(
+            signature
+                .recover(&personal_message_hash(b"Hello World"))
+                .unwrap(),
+            signer.address()
+        );
+    }
+
+    #[test]
+    fn test_address_checksum() {
+        let address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        assert_eq!(address.parse::<Address>().unwrap().to_string(), address);
+        assert!(address.to_lowercase().parse::<Address>().is_ok());
+        assert!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD"
+            .parse::<Address>()
+            .is_err());
+        assert!("0x5aaeb6".parse::<Address>().is_err());
+    }
+}
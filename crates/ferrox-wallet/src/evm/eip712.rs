@@ -0,0 +1,261 @@
+//! Hashing EIP-712 typed data, in the JSON form eth_signTypedData_v4 takes:
+//! `{"types": ..., "primaryType": ..., "domain": ..., "message": ...}`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{decode_hex, keccak256, Address};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TypedData {
+    types: HashMap<String, Vec<Field>>,
+    primary_type: String,
+    domain: Value,
+    message: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Field {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+type Types = HashMap<String, Vec<Field>>;
+
+/// The digest that is signed: `keccak256(0x19 0x01 ‖ domainSeparator ‖
+/// hashStruct(message))`.
+pub fn hash_typed_data(typed_data: &Value) -> Result<[u8; 32], String> {
+    let data: TypedData = serde_json::from_value(typed_data.clone())
+        .map_err(|e| format!("Invalid typed data: {}", e))?;
+    let mut encoded = vec![0x19, 0x01];
+    encoded.extend(hash_struct(&data.types, "EIP712Domain", &data.domain)?);
+    if data.primary_type != "EIP712Domain" {
+        encoded.extend(hash_struct(&data.types, &data.primary_type, &data.message)?);
+    }
+    Ok(keccak256(&encoded))
+}
+
+fn hash_struct(types: &Types, name: &str, value: &Value) -> Result<[u8; 32], String> {
+    let fields = types
+        .get(name)
+        .ok_or_else(|| format!("Typed data has no type {}", name))?;
+    let mut encoded = keccak256(encode_type(types, name)?.as_bytes()).to_vec();
+    for field in fields {
+        let value = value
+            .get(&field.name)
+            .ok_or_else(|| format!("{} is missing its field {}", name, field.name))?;
+        encoded.extend(encode_value(types, &field.kind, value)?);
+    }
+    Ok(keccak256(&encoded))
+}
+
+/// `Name(type field,...)` followed by the struct types it references,
+/// sorted by name.
+fn encode_type(types: &Types, name: &str) -> Result<String, String> {
+    let mut dependencies = BTreeSet::new();
+    collect_dependencies(types, name, &mut dependencies);
+    dependencies.remove(name);
+    std::iter::once(name)
+        .chain(dependencies.iter().map(String::as_str))
+        .map(|name| {
+            let fields = types
+                .get(name)
+                .ok_or_else(|| format!("Typed data has no type {}", name))?;
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| format!("{} {}", field.kind, field.name))
+                .collect();
+            Ok(format!("{}({})", name, fields.join(",")))
+        })
+        .collect()
+}
+
+fn collect_dependencies(types: &Types, name: &str, found: &mut BTreeSet<String>) {
+    if !types.contains_key(name) || !found.insert(name.to_string()) {
+        return;
+    }
+    for field in &types[name] {
+        let kind = field.kind.split('[').next().unwrap_or_default();
+        collect_dependencies(types, kind, found);
+    }
+}
+
+fn encode_value(types: &Types, kind: &str, value: &Value) -> Result<[u8; 32], String> {
+    if let Some(element) = kind
+        .strip_suffix(']')
+        .and_then(|kind| kind.rsplit_once('['))
+    {
+        let items = value
+            .as_array()
+            .ok_or_else(|| format!("Expected an array for {}", kind))?;
+        let mut encoded = Vec::with_capacity(items.len() * 32);
+        for item in items {
+            encoded.extend(encode_value(types, element.0, item)?);
+        }
+        return Ok(keccak256(&encoded));
+    }
+    if types.contains_key(kind) {
+        return hash_struct(types, kind, value);
+    }
+
+    let text = || {
+        value
+            .as_str()
+            .ok_or_else(|| format!("Expected a string for {}", kind))
+    };
+    let mut word = [0u8; 32];
+    match kind {
+        "string" => return Ok(keccak256(text()?.as_bytes())),
+        "bytes" => return Ok(keccak256(&decode_hex(text()?)?)),
+        "bool" => {
+            word[31] = value
+                .as_bool()
+                .ok_or_else(|| "Expected true or false for bool".to_string())?
+                as u8
+        }
+        "address" => word[12..].copy_from_slice(&text()?.parse::<Address>()?.0),
+        _ if kind.starts_with("bytes") => {
+            let bytes = decode_hex(text()?)?;
+            if bytes.len() > 32 {
+                return Err(format!("Too many bytes for {}", kind));
+            }
+            word[..bytes.len()].copy_from_slice(&bytes);
+        }
+        _ if kind.starts_with("uint") => word = parse_integer(value, false)?,
+        _ if kind.starts_with("int") => word = parse_integer(value, true)?,
+        _ => return Err(format!("Unsupported typed data type {}", kind)),
+    }
+    Ok(word)
+}
+
+/// A JSON number, decimal string or 0x-prefixed hex string as a 256-bit
+/// big-endian word, negative values in two's complement.
+fn parse_integer(value: &Value, signed: bool) -> Result<[u8; 32], String> {
+    let text = match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.trim().to_string(),
+        _ => return Err(format!("Expected an integer, got {}", value)),
+    };
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) if signed => (true, digits),
+        Some(_) => return Err(format!("{} is negative", text)),
+        None => (false, text.as_str()),
+    };
+
+    let mut word = [0u8; 32];
+    if let Some(hex) = digits.strip_prefix("0x") {
+        let bytes = decode_hex(&format!("{}{}", "0".repeat(hex.len() % 2), hex))?;
+        let bytes = bytes.strip_prefix(&[0u8][..]).unwrap_or(&bytes);
+        if bytes.len() > 32 {
+            return Err(format!("{} does not fit in 256 bits", text));
+        }
+        word[32 - bytes.len()..].copy_from_slice(bytes);
+    } else {
+        if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+            return Err(format!("Invalid integer {}", text));
+        }
+        for digit in digits.bytes() {
+            let mut carry = u16::from(digit - b'0');
+            for byte in word.iter_mut().rev() {
+                let product = u16::from(*byte) * 10 + carry;
+                *byte = product as u8;
+                carry = product >> 8;
+            }
+            if carry != 0 {
+                return Err(format!("{} does not fit in 256 bits", text));
+            }
+        }
+    }
+
+    if negative {
+        let mut carry = true;
+        for byte in word.iter_mut().rev() {
+            let (sum, overflow) = (!*byte).overflowing_add(carry as u8);
+            *byte = sum;
+            carry = overflow;
+        }
+    }
+    Ok(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::{encode_hex, EvmSigner, LocalEvmSigner};
+
+    #[tokio::test]
+    async fn test_mail_example() {
+        // The example of EIP-712, signed with keccak256("cow")
+        let typed_data = serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    {"name": "name", "type": "string"},
+                    {"name": "version", "type": "string"},
+                    {"name": "chainId", "type": "uint256"},
+                    {"name": "verifyingContract", "type": "address"}
+                ],
+                "Person": [
+                    {"name": "name", "type": "string"},
+                    {"name": "wallet", "type": "address"}
+                ],
+                "Mail": [
+                    {"name": "from", "type": "Person"},
+                    {"name": "to", "type": "Person"},
+                    {"name": "contents", "type": "string"}
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+            },
+            "message": {
+                "from": {"name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"},
+                "to": {"name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"},
+                "contents": "Hello, Bob!"
+            }
+        });
+        let types: Types = serde_json::from_value(typed_data["types"].clone()).unwrap();
+        assert_eq!(
+            encode_type(&types, "Mail").unwrap(),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            encode_hex(&hash_typed_data(&typed_data).unwrap()),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+
+        let signer = LocalEvmSigner::new(&keccak256(b"cow")).unwrap();
+        assert_eq!(
+            signer.address().to_string(),
+            "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"
+        );
+        let signature = signer.sign_typed_data(&typed_data).await.unwrap();
+        assert_eq!(
+            signature.to_hex(),
+            "0x4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c"
+        );
+    }
+
+    #[test]
+    fn test_parse_integer() {
+        let one = parse_integer(&serde_json::json!(1), false).unwrap();
+        assert_eq!(one[31], 1);
+        assert_eq!(
+            parse_integer(&serde_json::json!("0x01"), false).unwrap(),
+            one
+        );
+        assert_eq!(
+            parse_integer(&serde_json::json!("-1"), true).unwrap(),
+            [0xff; 32]
+        );
+        assert!(parse_integer(&serde_json::json!("-1"), false).is_err());
+        assert!(parse_integer(&serde_json::json!("1".repeat(80)), false).is_err());
+    }
+}
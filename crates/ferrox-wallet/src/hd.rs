@@ -0,0 +1,208 @@
+//! Recovery phrases and the accounts derived from them.
+//!
+//! A user's seed is a BIP39 mnemonic. Accounts are derived from it with
+//! SLIP-0010 along `m/44'/501'/{index}'/0'`, the path Phantom and Solflare
+//! use, so importing the phrase into those wallets shows the same addresses.
+//! EVM accounts are derived with BIP32 along MetaMask's
+//! `m/44'/60'/0'/0/{index}`.
+
+use bip39::{Language, Mnemonic, Seed};
+use hmac::{Hmac, Mac};
+use libsecp256k1::{PublicKey, SecretKey};
+use rand::{rngs::OsRng, RngCore};
+use sha2::Sha512;
+use solana_sdk::{
+    derivation_path::DerivationPath, signature::Keypair,
+    signer::keypair::keypair_from_seed_and_derivation_path,
+};
+use zeroize::Zeroizing;
+
+/// What a user's first account is called.
+pub const DEFAULT_ACCOUNT: &str = "main";
+
+const MAX_LABEL_LEN: usize = 32;
+
+const HARDENED: u32 = 1 << 31;
+
+/// A new 24-word recovery phrase.
+pub fn generate_phrase() -> Zeroizing<String> {
+    let mut entropy = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(entropy.as_mut());
+    let mnemonic = Mnemonic::from_entropy(entropy.as_ref(), Language::English)
+        .expect("32 bytes is a valid BIP39 entropy length");
+    Zeroizing::new(mnemonic.into_phrase())
+}
+
+/// `phrase` in canonical form: lowercase words separated by single spaces.
+/// Fails if a word is not in the English wordlist or the checksum is wrong.
+pub fn normalize_phrase(phrase: &str) -> Result<Zeroizing<String>, String> {
+    let phrase = Zeroizing::new(
+        phrase
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    Mnemonic::validate(&phrase, Language::English)
+        .map_err(|e| format!("Invalid recovery phrase: {}", e))?;
+    Ok(phrase)
+}
+
+/// Path of the account at `index`.
+pub fn derivation_path(index: u32) -> String {
+    format!("m/44'/501'/{}'/0'", index)
+}
+
+/// The index of an account from its [`derivation_path`].
+pub fn account_index(path: &str) -> Option<u32> {
+    path.strip_prefix("m/44'/501'/")?
+        .strip_suffix("'/0'")?
+        .parse()
+        .ok()
+}
+
+/// The keypair of the account at `index` of `phrase`. `passphrase` is the
+/// optional BIP39 passphrase, empty for none.
+pub fn derive_keypair(phrase: &str, passphrase: &str, index: u32) -> Result<Keypair, String> {
+    let seed = seed(phrase, passphrase)?;
+    keypair_from_seed_and_derivation_path(
+        seed.as_bytes(),
+        Some(DerivationPath::new_bip44(Some(index), Some(0))),
+    )
+    .map_err(|e| format!("Failed to derive account {}: {}", index, e))
+}
+
+/// Path of the EVM account at `index`.
+pub fn evm_derivation_path(index: u32) -> String {
+    format!("m/44'/60'/0'/0/{}", index)
+}
+
+/// The secp256k1 secret key of the EVM account at `index` of `phrase`.
+pub fn derive_evm_key(
+    phrase: &str,
+    passphrase: &str,
+    index: u32,
+) -> Result<Zeroizing<[u8; 32]>, String> {
+    if index >= HARDENED {
+        return Err(format!("Account index {} is out of range", index));
+    }
+    let seed = seed(phrase, passphrase)?;
+    // Key in the first half, chain code in the second
+    let mut node = hmac_sha512(b"Bitcoin seed", seed.as_bytes());
+    for child in [44 | HARDENED, 60 | HARDENED, HARDENED, 0, index] {
+        let parent = secret_key(&node[..32])?;
+        let mut data = Zeroizing::new(Vec::with_capacity(37));
+        if child & HARDENED != 0 {
+            data.push(0);
+            data.extend_from_slice(&node[..32]);
+        } else {
+            data.extend_from_slice(&PublicKey::from_secret_key(&parent).serialize_compressed());
+        }
+        data.extend_from_slice(&child.to_be_bytes());
+        let derived = hmac_sha512(&node[32..], &data);
+        let mut key = secret_key(&derived[..32])?;
+        key.tweak_add_assign(&parent)
+            .map_err(|_| format!("Cannot derive EVM account {}", index))?;
+        node[..32].copy_from_slice(&key.serialize());
+        node[32..].copy_from_slice(&derived[32..]);
+    }
+    let mut key = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&node[..32]);
+    Ok(key)
+}
+
+fn seed(phrase: &str, passphrase: &str) -> Result<Seed, String> {
+    let mnemonic = Mnemonic::from_phrase(&normalize_phrase(phrase)?, Language::English)
+        .map_err(|e| format!("Invalid recovery phrase: {}", e))?;
+    Ok(Seed::new(&mnemonic, passphrase))
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> Zeroizing<[u8; 64]> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    Zeroizing::new(mac.finalize().into_bytes().into())
+}
+
+fn secret_key(bytes: &[u8]) -> Result<SecretKey, String> {
+    // Out of range only with negligible probability; BIP32 skips such keys
+    SecretKey::parse_slice(bytes).map_err(|_| "Derived an invalid secp256k1 key".to_string())
+}
+
+/// `label` as accounts are stored: trimmed and lowercase. Labels are short
+/// names such as "trading" made of letters, digits, `-` and `_`.
+pub fn normalize_label(label: &str) -> Result<String, String> {
+    let label = label.trim().to_lowercase();
+    if label.is_empty() || label.len() > MAX_LABEL_LEN {
+        return Err(format!(
+            "Account names must be 1 to {} characters long",
+            MAX_LABEL_LEN
+        ));
+    }
+    if !label
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid account name '{}': use letters, digits, '-' and '_'",
+            label
+        ));
+    }
+    Ok(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::{EvmSigner, LocalEvmSigner};
+    use solana_sdk::signer::Signer;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_derivation_matches_other_wallets() {
+        let first = derive_keypair(PHRASE, "", 0).unwrap();
+        assert_eq!(
+            first.pubkey().to_string(),
+            "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+        );
+        let second = derive_keypair(PHRASE, "", 1).unwrap();
+        assert_ne!(first.pubkey(), second.pubkey());
+        let with_passphrase = derive_keypair(PHRASE, "TREZOR", 0).unwrap();
+        assert_ne!(first.pubkey(), with_passphrase.pubkey());
+        assert_eq!(derivation_path(1), "m/44'/501'/1'/0'");
+        assert_eq!(account_index(&derivation_path(7)), Some(7));
+        assert_eq!(account_index("m/44'/60'/0'/0/7"), None);
+    }
+
+    #[test]
+    fn test_evm_derivation_matches_metamask() {
+        let key = derive_evm_key(PHRASE, "", 0).unwrap();
+        let signer = LocalEvmSigner::new(&key).unwrap();
+        assert_eq!(
+            signer.address().to_string(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+        assert_eq!(evm_derivation_path(2), "m/44'/60'/0'/0/2");
+    }
+
+    #[test]
+    fn test_phrases() {
+        let phrase = generate_phrase();
+        assert_eq!(phrase.split(' ').count(), 24);
+        assert_eq!(normalize_phrase(&phrase).unwrap(), phrase);
+        assert_eq!(
+            *normalize_phrase(&format!("  {}\n", PHRASE.to_uppercase())).unwrap(),
+            PHRASE
+        );
+        // Last word changed, so the checksum no longer matches
+        assert!(normalize_phrase(&PHRASE.replace("about", "abandon")).is_err());
+    }
+
+    #[test]
+    fn test_normalize_label() {
+        assert_eq!(normalize_label(" Trading ").unwrap(), "trading");
+        assert!(normalize_label("").is_err());
+        assert!(normalize_label("my savings").is_err());
+        assert!(normalize_label(&"a".repeat(33)).is_err());
+    }
+}
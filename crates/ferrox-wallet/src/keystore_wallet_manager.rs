@@ -0,0 +1,1444 @@
+//! Wallets kept on disk, encrypted at rest.
+//!
+//! The keystore is a directory holding `keystore.json` and one file per user
+//! under `wallets/`. Secret keys are sealed with XChaCha20-Poly1305 under a
+//! random data key, and data keys are sealed under a key derived from the
+//! keystore password with Argon2id. Changing the password therefore only
+//! rewrites `keystore.json`, and rotating the data key re-encrypts wallets
+//! one file at a time, keeping the old key until no wallet uses it.
+//!
+//! Each user has a recovery phrase, sealed the same way, and named accounts
+//! derived from it (see [`crate::hd`]), as well as an EVM wallet derived
+//! from the same phrase once they ask for one.
+
+use std::{
+    collections::HashMap,
+    fs,
+    future::Future,
+    io::Write,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{bs58, signature::Keypair, signer::Signer};
+use tokio::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::{
+    backup::{self, BackupAccount, BackupContents, Import},
+    hd, ChainFamily, EvmSigner, ExportFormat, LocalEvmSigner, LocalSigner, Wallet, WalletAccount,
+    WalletImport, WalletManager,
+};
+
+const KEYSTORE_FILE: &str = "keystore.json";
+const WALLETS_DIR: &str = "wallets";
+const FORMAT_VERSION: u32 = 1;
+const KEY_LEN: usize = 32;
+pub(crate) const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+pub(crate) type SecretKey = Zeroizing<[u8; KEY_LEN]>;
+
+/// Cost of deriving the key that seals the data keys from the password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfConfig {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfConfig {
+    /// The Argon2id parameters OWASP recommends: 19 MiB, two passes.
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// A nonce and the ciphertext it sealed, both base58.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Sealed {
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct KdfSettings {
+    pub(crate) salt: String,
+    #[serde(flatten)]
+    pub(crate) config: KdfConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DataKey {
+    id: u32,
+    sealed: Sealed,
+}
+
+/// Contents of `keystore.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeystoreFile {
+    version: u32,
+    kdf: KdfSettings,
+    current_key: u32,
+    keys: Vec<DataKey>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredWallet {
+    #[serde(default = "default_label")]
+    label: String,
+    /// Account index in the user's recovery phrase. Wallets created before
+    /// phrases were stored have none.
+    #[serde(default)]
+    index: Option<u32>,
+    /// The address: base58 for Solana wallets, 0x and the EIP-55 checksum
+    /// for EVM ones.
+    pubkey: String,
+    created_at: u64,
+    /// When the wallet was replaced by a newer one. Retired wallets are kept
+    /// so their funds can still be moved.
+    retired_at: Option<u64>,
+    key_id: u32,
+    secret: Sealed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredSeed {
+    key_id: u32,
+    phrase: Sealed,
+}
+
+/// The wallets of one user, newest last.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UserFile {
+    user_id: String,
+    #[serde(default)]
+    seed: Option<StoredSeed>,
+    /// Label of the account used when none is named.
+    #[serde(default)]
+    default_account: Option<String>,
+    wallets: Vec<StoredWallet>,
+    #[serde(default)]
+    evm_wallets: Vec<StoredWallet>,
+}
+
+struct Keystore {
+    dir: PathBuf,
+    file: KeystoreFile,
+    keys: HashMap<u32, SecretKey>,
+}
+
+/// A [`WalletManager`] that persists the recovery phrase and accounts of
+/// each user, encrypted with a password, in a directory. Unlike
+/// [`SimpleWalletManager`](crate::simple_wallet_manager::SimpleWalletManager)
+/// it never hands out a wallet the user has not created.
+///
+/// File access and key derivation run on tokio's blocking pool, so its
+/// async methods need a tokio runtime.
+#[derive(Clone)]
+pub struct KeystoreWalletManager {
+    keystore: Arc<Mutex<Keystore>>,
+}
+
+impl KeystoreWalletManager {
+    /// Opens the keystore in `dir`, creating it if it does not exist yet.
+    /// Fails if `password` does not unlock an existing keystore.
+    pub fn open(dir: impl AsRef<Path>, password: &str) -> Result<Self, String> {
+        Self::open_with(dir, password, KdfConfig::default())
+    }
+
+    /// Like [`Self::open`], deriving the password key of a new keystore with
+    /// `kdf`. Existing keystores keep the parameters they were created with.
+    pub fn open_with(
+        dir: impl AsRef<Path>,
+        password: &str,
+        kdf: KdfConfig,
+    ) -> Result<Self, String> {
+        let dir = dir.as_ref().to_path_buf();
+        let path = dir.join(KEYSTORE_FILE);
+        let keystore = if path.exists() {
+            let file: KeystoreFile = read_json(&path)?;
+            if file.version != FORMAT_VERSION {
+                return Err(format!(
+                    "Unsupported keystore version {} in {}",
+                    file.version,
+                    path.display()
+                ));
+            }
+            file.current()?;
+            let password_key = derive_key(password, &file.kdf)?;
+            let keys = file
+                .keys
+                .iter()
+                .map(|key| Ok((key.id, open_data_key(&password_key, key)?)))
+                .collect::<Result<_, String>>()
+                .map_err(|_| "Wrong keystore password".to_string())?;
+            Keystore { dir, file, keys }
+        } else {
+            create_private_dir(&dir.join(WALLETS_DIR))?;
+            let kdf = KdfSettings {
+                salt: bs58::encode(random::<SALT_LEN>()).into_string(),
+                config: kdf,
+            };
+            let password_key = derive_key(password, &kdf)?;
+            let data_key = Zeroizing::new(random::<KEY_LEN>());
+            let file = KeystoreFile {
+                version: FORMAT_VERSION,
+                kdf,
+                current_key: 1,
+                keys: vec![seal_data_key(&password_key, 1, &data_key)?],
+            };
+            write_json(&path, &file)?;
+            Keystore {
+                dir,
+                file,
+                keys: HashMap::from([(1, data_key)]),
+            }
+        };
+        Ok(Self {
+            keystore: Arc::new(Mutex::new(keystore)),
+        })
+    }
+
+    /// Opens the keystore in `FERROX_KEYSTORE_DIR` (default `keystore`) with
+    /// the password in `FERROX_KEYSTORE_PASSWORD`.
+    pub fn from_env() -> Result<Self, String> {
+        let dir = std::env::var("FERROX_KEYSTORE_DIR").unwrap_or_else(|_| "keystore".to_string());
+        let password = Zeroizing::new(
+            std::env::var("FERROX_KEYSTORE_PASSWORD")
+                .map_err(|_| "FERROX_KEYSTORE_PASSWORD must be set".to_string())?,
+        );
+        Self::open(dir, &password)
+    }
+
+    /// Seals the data keys under `new_password`. Wallet files are not
+    /// touched, so this is a single atomic write.
+    pub async fn change_password(&self, new_password: &str) -> Result<(), String> {
+        let new_password = Zeroizing::new(new_password.to_string());
+        self.blocking(move |manager| manager.reseal_data_keys(&new_password))
+            .await
+    }
+
+    /// Generates a new data key, re-encrypts every wallet with it and then
+    /// forgets the old ones. Interrupted rotations leave a readable keystore
+    /// and can simply be run again.
+    pub async fn rotate_encryption_key(&self, password: &str) -> Result<(), String> {
+        let password = Zeroizing::new(password.to_string());
+        self.blocking(move |manager| manager.rotate_data_key(&password))
+            .await
+    }
+
+    /// Moves the default account of `user_id` to the next unused index of
+    /// its recovery phrase. The previous keypair is retired rather than
+    /// deleted: it stays in [`WalletManager::get_wallets`] so anything left
+    /// in it can be moved. This replaces a leaked account key, not a leaked
+    /// phrase.
+    pub async fn rotate_wallet(&self, user_id: &str) -> Result<Wallet, String> {
+        let user_id = user_id.to_string();
+        self.blocking(move |manager| manager.replace_default_wallet(&user_id))
+            .await
+    }
+
+    /// Runs `task` on the blocking pool, where it may lock the keystore,
+    /// touch its files and derive keys.
+    async fn blocking<T, F>(&self, task: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Self) -> Result<T, String> + Send + Sync + 'static,
+    {
+        let manager = self.clone();
+        tokio::task::spawn_blocking(move || task(&manager))
+            .await
+            .map_err(|e| format!("The keystore task failed: {}", e))?
+    }
+
+    fn reseal_data_keys(&self, new_password: &str) -> Result<(), String> {
+        let mut keystore = self.keystore.blocking_lock();
+        let kdf = KdfSettings {
+            salt: bs58::encode(random::<SALT_LEN>()).into_string(),
+            config: keystore.file.kdf.config,
+        };
+        let password_key = derive_key(new_password, &kdf)?;
+        let mut file = keystore.file.clone();
+        file.kdf = kdf;
+        file.keys = keystore
+            .keys
+            .iter()
+            .map(|(id, key)| seal_data_key(&password_key, *id, key))
+            .collect::<Result<_, _>>()?;
+        file.keys.sort_by_key(|key| key.id);
+        keystore.save(file)
+    }
+
+    fn rotate_data_key(&self, password: &str) -> Result<(), String> {
+        let mut keystore = self.keystore.blocking_lock();
+        let password_key = derive_key(password, &keystore.file.kdf)?;
+        open_data_key(&password_key, keystore.file.current()?)
+            .map_err(|_| "Wrong keystore password".to_string())?;
+
+        let id = keystore.keys.keys().max().copied().unwrap_or_default() + 1;
+        let data_key = Zeroizing::new(random::<KEY_LEN>());
+        let mut file = keystore.file.clone();
+        file.keys.push(seal_data_key(&password_key, id, &data_key)?);
+        file.current_key = id;
+        keystore.save(file)?;
+        keystore.keys.insert(id, data_key);
+
+        for user_id in keystore.user_ids()? {
+            let mut user = keystore.load(&user_id)?.ok_or_else(|| {
+                format!(
+                    "The wallets of user {} disappeared during rotation",
+                    user_id
+                )
+            })?;
+            for wallet in user.wallets.iter_mut().chain(&mut user.evm_wallets) {
+                if wallet.key_id != id {
+                    let secret = keystore.open_wallet(&user.user_id, wallet)?;
+                    *wallet = keystore.seal_wallet(&user.user_id, &secret, wallet)?;
+                }
+            }
+            if let Some(seed) = user.seed.as_ref().filter(|seed| seed.key_id != id) {
+                let phrase = keystore.open_seed(&user.user_id, seed)?;
+                user.seed = Some(keystore.seal_seed(&user.user_id, &phrase)?);
+            }
+            keystore.store(&user)?;
+        }
+
+        let mut file = keystore.file.clone();
+        file.keys.retain(|key| key.id == id);
+        keystore.save(file)?;
+        keystore.keys.retain(|key_id, _| *key_id == id);
+        Ok(())
+    }
+
+    fn replace_default_wallet(&self, user_id: &str) -> Result<Wallet, String> {
+        let keystore = self.keystore.blocking_lock();
+        let mut user = keystore.load(user_id)?.ok_or_else(|| no_wallet(user_id))?;
+        let label = user.default_label().to_string();
+        let now = unix_time();
+        for wallet in &mut user.wallets {
+            if wallet.label == label {
+                wallet.retired_at.get_or_insert(now);
+            }
+        }
+        let wallet = keystore.derive_wallet(&mut user, &label)?;
+        keystore.store(&user)?;
+        Ok(wallet)
+    }
+
+    fn account(&self, user_id: &str, label: Option<&str>) -> Result<Wallet, String> {
+        let keystore = self.keystore.blocking_lock();
+        let user = keystore.load(user_id)?.ok_or_else(|| no_wallet(user_id))?;
+        keystore.wallet(user_id, user.select(label)?)
+    }
+
+    fn accounts(&self, user_id: &str) -> Result<Vec<WalletAccount>, String> {
+        let keystore = self.keystore.blocking_lock();
+        let Some(user) = keystore.load(user_id)? else {
+            return Ok(Vec::new());
+        };
+        let mut accounts = user
+            .active_wallets()
+            .map(|wallet| {
+                Ok(WalletAccount {
+                    label: wallet.label.clone(),
+                    derivation_path: wallet.index.map(hd::derivation_path),
+                    wallet: keystore.wallet(user_id, wallet)?,
+                    is_default: wallet.label == user.default_label(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        accounts.sort_by_key(|account| !account.is_default);
+        Ok(accounts)
+    }
+
+    fn all_wallets(&self, user_id: &str) -> Result<Vec<Wallet>, String> {
+        let keystore = self.keystore.blocking_lock();
+        let Some(user) = keystore.load(user_id)? else {
+            return Ok(Vec::new());
+        };
+        // The default account first, then other accounts and finally retired
+        // ones, each from newest to oldest
+        let mut wallets: Vec<&StoredWallet> = user.wallets.iter().rev().collect();
+        wallets.sort_by_key(|wallet| {
+            (
+                wallet.retired_at.is_some(),
+                wallet.label != user.default_label(),
+            )
+        });
+        let evm = user
+            .evm_wallets
+            .iter()
+            .rev()
+            .map(|wallet| keystore.evm_wallet(user_id, wallet));
+        wallets
+            .into_iter()
+            .map(|wallet| keystore.wallet(user_id, wallet))
+            .chain(evm)
+            .collect()
+    }
+
+    fn add_wallet(&self, user_id: &str) -> Result<Wallet, String> {
+        let keystore = self.keystore.blocking_lock();
+        let mut user = keystore
+            .load(user_id)?
+            .unwrap_or_else(|| UserFile::new(user_id));
+        if user.active_wallets().next().is_some() {
+            return Err(format!(
+                "User {} already has a wallet; rotate it to get a new one",
+                user_id
+            ));
+        }
+        let wallet = keystore.derive_wallet(&mut user, hd::DEFAULT_ACCOUNT)?;
+        user.default_account = Some(hd::DEFAULT_ACCOUNT.to_string());
+        keystore.store(&user)?;
+        Ok(wallet)
+    }
+
+    fn add_account(&self, user_id: &str, label: &str) -> Result<Wallet, String> {
+        let label = hd::normalize_label(label)?;
+        let keystore = self.keystore.blocking_lock();
+        let mut user = keystore.load(user_id)?.ok_or_else(|| no_wallet(user_id))?;
+        if user.active(&label).is_some() {
+            return Err(format!(
+                "User {} already has an account called '{}'",
+                user_id, label
+            ));
+        }
+        let wallet = keystore.derive_wallet(&mut user, &label)?;
+        keystore.store(&user)?;
+        Ok(wallet)
+    }
+
+    fn make_default(&self, user_id: &str, label: &str) -> Result<(), String> {
+        let label = hd::normalize_label(label)?;
+        let keystore = self.keystore.blocking_lock();
+        let mut user = keystore.load(user_id)?.ok_or_else(|| no_wallet(user_id))?;
+        if user.active(&label).is_none() {
+            return Err(format!(
+                "User {} has no account called '{}'",
+                user_id, label
+            ));
+        }
+        user.default_account = Some(label);
+        keystore.store(&user)
+    }
+
+    fn active_evm_wallet(&self, user_id: &str) -> Result<Wallet, String> {
+        let keystore = self.keystore.blocking_lock();
+        let wallet = keystore.load(user_id)?.and_then(|user| {
+            user.evm_wallets
+                .into_iter()
+                .rfind(|wallet| wallet.retired_at.is_none())
+        });
+        match wallet {
+            Some(wallet) => keystore.evm_wallet(user_id, &wallet),
+            None => Err(format!("User {} has no EVM wallet yet", user_id)),
+        }
+    }
+
+    fn add_evm_wallet(&self, user_id: &str) -> Result<Wallet, String> {
+        let keystore = self.keystore.blocking_lock();
+        let mut user = keystore
+            .load(user_id)?
+            .unwrap_or_else(|| UserFile::new(user_id));
+        if user
+            .evm_wallets
+            .iter()
+            .any(|wallet| wallet.retired_at.is_none())
+        {
+            return Err(format!("User {} already has an EVM wallet", user_id));
+        }
+        let phrase = keystore.phrase(&mut user)?;
+        let index = next_index(&user.evm_wallets);
+        let key = hd::derive_evm_key(&phrase, "", index)?;
+        let signer = LocalEvmSigner::new(&key)?;
+        user.evm_wallets.push(keystore.new_wallet(
+            user_id,
+            hd::DEFAULT_ACCOUNT,
+            Some(index),
+            signer.address().to_string(),
+            &Zeroizing::new(key.to_vec()),
+        )?);
+        keystore.store(&user)?;
+        Ok(Wallet::Evm(Arc::new(signer)))
+    }
+
+    fn import(&self, user_id: &str, import: Import) -> Result<Wallet, String> {
+        let keystore = self.keystore.blocking_lock();
+        let mut user = keystore
+            .load(user_id)?
+            .unwrap_or_else(|| UserFile::new(user_id));
+        let wallet = match import {
+            Import::Keypair { label, keypair } => {
+                if user.active(&label).is_some() {
+                    return Err(format!(
+                        "User {} already has an account called '{}'",
+                        user_id, label
+                    ));
+                }
+                let address = keypair.pubkey().to_string();
+                if user.active_wallets().any(|wallet| wallet.pubkey == address) {
+                    return Err(format!("User {} already has {}", user_id, address));
+                }
+                if user.active_wallets().next().is_none() {
+                    user.default_account = Some(label.clone());
+                }
+                user.wallets.push(keystore.new_wallet(
+                    user_id,
+                    &label,
+                    None,
+                    address,
+                    &Zeroizing::new(keypair.to_bytes().to_vec()),
+                )?);
+                Wallet::Solana(Arc::new(LocalSigner::new(keypair)))
+            }
+            Import::Restore(contents) => keystore.restore(&mut user, &contents)?,
+        };
+        keystore.store(&user)?;
+        Ok(wallet)
+    }
+
+    fn export(
+        &self,
+        user_id: &str,
+        account: Option<&str>,
+        format: ExportFormat,
+    ) -> Result<Zeroizing<String>, String> {
+        let keystore = self.keystore.blocking_lock();
+        let user = keystore.load(user_id)?.ok_or_else(|| no_wallet(user_id))?;
+        match format {
+            ExportFormat::KeypairFile => {
+                let secret = keystore.open_wallet(user_id, user.select(account)?)?;
+                Ok(backup::keypair_file(&backup::keypair_from_bytes(&secret)?))
+            }
+            ExportFormat::Backup { password } => {
+                let phrase = user
+                    .seed
+                    .as_ref()
+                    .map(|seed| keystore.open_seed(user_id, seed))
+                    .transpose()?;
+                // Accounts derived from the phrase are derived again on restore
+                let accounts = user
+                    .active_wallets()
+                    .map(|wallet| {
+                        let keypair = match (wallet.index, &phrase) {
+                            (Some(_), Some(_)) => None,
+                            _ => Some(
+                                bs58::encode(keystore.open_wallet(user_id, wallet)?.as_slice())
+                                    .into_string(),
+                            ),
+                        };
+                        Ok(BackupAccount {
+                            label: wallet.label.clone(),
+                            index: wallet.index,
+                            keypair,
+                        })
+                    })
+                    .collect::<Result<_, String>>()?;
+                let contents = BackupContents {
+                    phrase: phrase.map(|phrase| phrase.to_string()),
+                    default_account: Some(user.default_label().to_string()),
+                    accounts,
+                    evm_index: user
+                        .evm_wallets
+                        .iter()
+                        .rfind(|wallet| wallet.retired_at.is_none())
+                        .and_then(|wallet| wallet.index),
+                };
+                backup::seal_backup(&contents, &password, keystore.file.kdf.config)
+            }
+        }
+    }
+}
+
+impl WalletManager for KeystoreWalletManager {
+    fn get_wallet(
+        &self,
+        user_id: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        let user_id = user_id.to_string();
+        let manager = self.clone();
+        Box::pin(async move {
+            manager
+                .blocking(move |manager| manager.account(&user_id, None))
+                .await
+        })
+    }
+
+    fn get_wallets(
+        &self,
+        user_id: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Wallet>, String>> + Send + Sync>> {
+        let user_id = user_id.to_string();
+        let manager = self.clone();
+        Box::pin(async move {
+            manager
+                .blocking(move |manager| manager.all_wallets(&user_id))
+                .await
+        })
+    }
+
+    fn create_wallet(
+        &self,
+        user_id: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        let user_id = user_id.to_string();
+        let manager = self.clone();
+        Box::pin(async move {
+            manager
+                .blocking(move |manager| manager.add_wallet(&user_id))
+                .await
+        })
+    }
+
+    fn get_account(
+        &self,
+        user_id: &str,
+        label: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        let (user_id, label) = (user_id.to_string(), label.to_string());
+        let manager = self.clone();
+        Box::pin(async move {
+            manager
+                .blocking(move |manager| manager.account(&user_id, Some(&label)))
+                .await
+        })
+    }
+
+    fn list_accounts(
+        &self,
+        user_id: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<WalletAccount>, String>> + Send + Sync>> {
+        let user_id = user_id.to_string();
+        let manager = self.clone();
+        Box::pin(async move {
+            manager
+                .blocking(move |manager| manager.accounts(&user_id))
+                .await
+        })
+    }
+
+    fn create_account(
+        &self,
+        user_id: &str,
+        label: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        let (user_id, label) = (user_id.to_string(), label.to_string());
+        let manager = self.clone();
+        Box::pin(async move {
+            manager
+                .blocking(move |manager| manager.add_account(&user_id, &label))
+                .await
+        })
+    }
+
+    fn set_default_account(
+        &self,
+        user_id: &str,
+        label: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + Sync>> {
+        let (user_id, label) = (user_id.to_string(), label.to_string());
+        let manager = self.clone();
+        Box::pin(async move {
+            manager
+                .blocking(move |manager| manager.make_default(&user_id, &label))
+                .await
+        })
+    }
+
+    fn import_wallet(
+        &self,
+        user_id: &str,
+        import: WalletImport,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        let manager = self.clone();
+        let user_id = user_id.to_string();
+        Box::pin(async move {
+            let import = import.open().await?;
+            manager
+                .blocking(move |manager| manager.import(&user_id, import))
+                .await
+        })
+    }
+
+    fn export_wallet(
+        &self,
+        user_id: &str,
+        account: Option<&str>,
+        format: ExportFormat,
+    ) -> Pin<Box<dyn Future<Output = Result<Zeroizing<String>, String>> + Send + Sync>> {
+        let (user_id, account) = (user_id.to_string(), account.map(str::to_string));
+        let manager = self.clone();
+        // Backups are sealed with Argon2 on the blocking pool as well
+        Box::pin(async move {
+            manager
+                .blocking(move |manager| manager.export(&user_id, account.as_deref(), format))
+                .await
+        })
+    }
+
+    fn get_chain_wallet(
+        &self,
+        user_id: &str,
+        family: ChainFamily,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        let user_id = user_id.to_string();
+        let manager = self.clone();
+        Box::pin(async move {
+            manager
+                .blocking(move |manager| match family {
+                    ChainFamily::Solana => manager.account(&user_id, None),
+                    ChainFamily::Evm => manager.active_evm_wallet(&user_id),
+                })
+                .await
+        })
+    }
+
+    fn create_chain_wallet(
+        &self,
+        user_id: &str,
+        family: ChainFamily,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        let user_id = user_id.to_string();
+        let manager = self.clone();
+        Box::pin(async move {
+            manager
+                .blocking(move |manager| match family {
+                    ChainFamily::Solana => manager.add_wallet(&user_id),
+                    ChainFamily::Evm => manager.add_evm_wallet(&user_id),
+                })
+                .await
+        })
+    }
+}
+
+impl UserFile {
+    /// The account called `label`, or the default one when `label` is
+    /// `None`.
+    fn select(&self, label: Option<&str>) -> Result<&StoredWallet, String> {
+        match label {
+            Some(label) => {
+                let label = hd::normalize_label(label)?;
+                self.active(&label).ok_or_else(|| {
+                    format!("User {} has no account called '{}'", self.user_id, label)
+                })
+            }
+            // Fall back to the newest account if the default was renamed
+            // away by hand
+            None => self
+                .active(self.default_label())
+                .or_else(|| self.active_wallets().last())
+                .ok_or_else(|| no_wallet(&self.user_id)),
+        }
+    }
+
+    fn new(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            seed: None,
+            default_account: None,
+            wallets: Vec::new(),
+            evm_wallets: Vec::new(),
+        }
+    }
+
+    fn default_label(&self) -> &str {
+        self.default_account
+            .as_deref()
+            .unwrap_or(hd::DEFAULT_ACCOUNT)
+    }
+
+    /// Accounts in use, oldest first.
+    fn active_wallets(&self) -> impl Iterator<Item = &StoredWallet> {
+        self.wallets
+            .iter()
+            .filter(|wallet| wallet.retired_at.is_none())
+    }
+
+    fn active(&self, label: &str) -> Option<&StoredWallet> {
+        self.active_wallets().find(|wallet| wallet.label == label)
+    }
+}
+
+impl KeystoreFile {
+    fn current(&self) -> Result<&DataKey, String> {
+        self.keys
+            .iter()
+            .find(|key| key.id == self.current_key)
+            .ok_or_else(|| missing_data_key(self.current_key))
+    }
+}
+
+impl Keystore {
+    fn save(&mut self, file: KeystoreFile) -> Result<(), String> {
+        write_json(&self.dir.join(KEYSTORE_FILE), &file)?;
+        self.file = file;
+        Ok(())
+    }
+
+    fn user_path(&self, user_id: &str) -> PathBuf {
+        // Hex keeps any user id a safe file name
+        let name: String = user_id
+            .bytes()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        self.dir.join(WALLETS_DIR).join(format!("{}.json", name))
+    }
+
+    fn user_ids(&self) -> Result<Vec<String>, String> {
+        let dir = self.dir.join(WALLETS_DIR);
+        let entries =
+            fs::read_dir(&dir).map_err(|e| format!("Failed to list {}: {}", dir.display(), e))?;
+        let mut ids = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("Failed to list {}: {}", dir.display(), e))?
+                .path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                ids.push(read_json::<UserFile>(&path)?.user_id);
+            }
+        }
+        Ok(ids)
+    }
+
+    fn load(&self, user_id: &str) -> Result<Option<UserFile>, String> {
+        let path = self.user_path(user_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let user: UserFile = read_json(&path)?;
+        if user.user_id != user_id {
+            return Err(format!(
+                "{} does not belong to user {}",
+                path.display(),
+                user_id
+            ));
+        }
+        Ok(Some(user))
+    }
+
+    fn store(&self, user: &UserFile) -> Result<(), String> {
+        write_json(&self.user_path(&user.user_id), user)
+    }
+
+    /// The recovery phrase of `user`, giving them one first if they have
+    /// none yet.
+    fn phrase(&self, user: &mut UserFile) -> Result<Zeroizing<String>, String> {
+        match &user.seed {
+            Some(seed) => self.open_seed(&user.user_id, seed),
+            None => {
+                let phrase = hd::generate_phrase();
+                user.seed = Some(self.seal_seed(&user.user_id, &phrase)?);
+                Ok(phrase)
+            }
+        }
+    }
+
+    /// Gives `user`, who has no wallet yet, the phrase and accounts of
+    /// `contents`. Returns the default account.
+    fn restore(&self, user: &mut UserFile, contents: &BackupContents) -> Result<Wallet, String> {
+        if user.seed.is_some() || user.active_wallets().next().is_some() {
+            return Err(format!(
+                "User {} already has a wallet; a recovery phrase or backup can only be restored for a new user",
+                user.user_id
+            ));
+        }
+        let phrase = match &contents.phrase {
+            Some(phrase) => hd::normalize_phrase(phrase)?,
+            None => hd::generate_phrase(),
+        };
+        user.seed = Some(self.seal_seed(&user.user_id, &phrase)?);
+        let mut default = None;
+        for account in &contents.accounts {
+            let label = hd::normalize_label(&account.label)?;
+            let keypair = contents.keypair(account)?;
+            let index = account.index.filter(|_| account.keypair.is_none());
+            user.wallets.push(self.new_wallet(
+                &user.user_id,
+                &label,
+                index,
+                keypair.pubkey().to_string(),
+                &Zeroizing::new(keypair.to_bytes().to_vec()),
+            )?);
+            if Some(account.label.as_str()) == contents.default_label() {
+                user.default_account = Some(label);
+                default = Some(Wallet::Solana(Arc::new(LocalSigner::new(keypair))));
+            }
+        }
+        if let Some(index) = contents.evm_index {
+            let key = hd::derive_evm_key(&phrase, "", index)?;
+            let signer = LocalEvmSigner::new(&key)?;
+            user.evm_wallets.push(self.new_wallet(
+                &user.user_id,
+                hd::DEFAULT_ACCOUNT,
+                Some(index),
+                signer.address().to_string(),
+                &Zeroizing::new(key.to_vec()),
+            )?);
+            default = default.or_else(|| Some(Wallet::Evm(Arc::new(signer))));
+        }
+        default.ok_or_else(|| "The backup holds no wallets".to_string())
+    }
+
+    /// Derives the account after the highest index `user` has used.
+    fn derive_wallet(&self, user: &mut UserFile, label: &str) -> Result<Wallet, String> {
+        let phrase = self.phrase(user)?;
+        let index = next_index(&user.wallets);
+        let keypair = hd::derive_keypair(&phrase, "", index)?;
+        user.wallets.push(self.new_wallet(
+            &user.user_id,
+            label,
+            Some(index),
+            keypair.pubkey().to_string(),
+            &Zeroizing::new(keypair.to_bytes().to_vec()),
+        )?);
+        Ok(Wallet::Solana(Arc::new(LocalSigner::new(keypair))))
+    }
+
+    /// The id and key new secrets are sealed with.
+    fn current_key(&self) -> Result<(u32, &SecretKey), String> {
+        let key_id = self.file.current_key;
+        let key = self
+            .keys
+            .get(&key_id)
+            .ok_or_else(|| missing_data_key(key_id))?;
+        Ok((key_id, key))
+    }
+
+    fn seal_seed(&self, user_id: &str, phrase: &str) -> Result<StoredSeed, String> {
+        let (key_id, key) = self.current_key()?;
+        Ok(StoredSeed {
+            key_id,
+            phrase: seal(key, phrase.as_bytes(), seed_aad(user_id).as_bytes())?,
+        })
+    }
+
+    fn open_seed(&self, user_id: &str, seed: &StoredSeed) -> Result<Zeroizing<String>, String> {
+        let key = self.keys.get(&seed.key_id).ok_or_else(|| {
+            format!(
+                "The recovery phrase of user {} is sealed with data key {}, which this keystore does not have",
+                user_id, seed.key_id
+            )
+        })?;
+        let phrase = open(key, &seed.phrase, seed_aad(user_id).as_bytes())
+            .map_err(|_| format!("The recovery phrase of user {} is corrupted", user_id))?;
+        String::from_utf8(phrase.to_vec())
+            .map(Zeroizing::new)
+            .map_err(|_| format!("The recovery phrase of user {} is corrupted", user_id))
+    }
+
+    fn new_wallet(
+        &self,
+        user_id: &str,
+        label: &str,
+        index: Option<u32>,
+        pubkey: String,
+        secret: &Zeroizing<Vec<u8>>,
+    ) -> Result<StoredWallet, String> {
+        let wallet = StoredWallet {
+            label: label.to_string(),
+            index,
+            pubkey,
+            created_at: unix_time(),
+            retired_at: None,
+            key_id: self.file.current_key,
+            secret: Sealed {
+                nonce: String::new(),
+                ciphertext: String::new(),
+            },
+        };
+        self.seal_wallet(user_id, secret, &wallet)
+    }
+
+    /// `wallet` with `secret` sealed under the current data key.
+    fn seal_wallet(
+        &self,
+        user_id: &str,
+        secret: &Zeroizing<Vec<u8>>,
+        wallet: &StoredWallet,
+    ) -> Result<StoredWallet, String> {
+        let (key_id, key) = self.current_key()?;
+        Ok(StoredWallet {
+            key_id,
+            secret: seal(key, secret, wallet_aad(user_id, &wallet.pubkey).as_bytes())?,
+            ..wallet.clone()
+        })
+    }
+
+    fn open_wallet(
+        &self,
+        user_id: &str,
+        wallet: &StoredWallet,
+    ) -> Result<Zeroizing<Vec<u8>>, String> {
+        let key = self.keys.get(&wallet.key_id).ok_or_else(|| {
+            format!(
+                "Wallet {} is sealed with data key {}, which this keystore does not have",
+                wallet.pubkey, wallet.key_id
+            )
+        })?;
+        open(
+            key,
+            &wallet.secret,
+            wallet_aad(user_id, &wallet.pubkey).as_bytes(),
+        )
+        .map_err(|_| format!("Wallet {} of user {} is corrupted", wallet.pubkey, user_id))
+    }
+
+    fn wallet(&self, user_id: &str, wallet: &StoredWallet) -> Result<Wallet, String> {
+        let secret = self.open_wallet(user_id, wallet)?;
+        let keypair = Keypair::from_bytes(&secret)
+            .map_err(|e| format!("Wallet {} holds an invalid keypair: {}", wallet.pubkey, e))?;
+        if keypair.pubkey().to_string() != wallet.pubkey {
+            return Err(format!(
+                "Wallet {} holds a different keypair",
+                wallet.pubkey
+            ));
+        }
+        Ok(Wallet::Solana(Arc::new(LocalSigner::new(keypair))))
+    }
+
+    fn evm_wallet(&self, user_id: &str, wallet: &StoredWallet) -> Result<Wallet, String> {
+        let secret = self.open_wallet(user_id, wallet)?;
+        let signer = <&[u8; 32]>::try_from(secret.as_slice())
+            .map_err(|_| format!("Wallet {} holds an invalid key", wallet.pubkey))
+            .and_then(LocalEvmSigner::new)?;
+        if signer.address().to_string() != wallet.pubkey {
+            return Err(format!("Wallet {} holds a different key", wallet.pubkey));
+        }
+        Ok(Wallet::Evm(Arc::new(signer)))
+    }
+}
+
+/// The index after the highest one of `wallets`.
+fn next_index(wallets: &[StoredWallet]) -> u32 {
+    wallets
+        .iter()
+        .filter_map(|wallet| wallet.index)
+        .max()
+        .map_or(0, |index| index + 1)
+}
+
+fn default_label() -> String {
+    hd::DEFAULT_ACCOUNT.to_string()
+}
+
+fn no_wallet(user_id: &str) -> String {
+    format!("User {} has no wallet yet; create one first", user_id)
+}
+
+fn missing_data_key(id: u32) -> String {
+    format!(
+        "The keystore is corrupted: its current data key {} is missing",
+        id
+    )
+}
+
+/// Binds a sealed secret to its owner and address, so wallets cannot be
+/// swapped between users or entries by editing the files.
+fn wallet_aad(user_id: &str, pubkey: &str) -> String {
+    format!("ferrox-keystore:wallet:{}:{}", user_id, pubkey)
+}
+
+fn seed_aad(user_id: &str) -> String {
+    format!("ferrox-keystore:seed:{}", user_id)
+}
+
+fn data_key_aad(id: u32) -> String {
+    format!("ferrox-keystore:data-key:{}", id)
+}
+
+pub(crate) fn derive_key(password: &str, kdf: &KdfSettings) -> Result<SecretKey, String> {
+    let salt = bs58::decode(&kdf.salt)
+        .into_vec()
+        .map_err(|e| format!("Invalid keystore salt: {}", e))?;
+    let params = Params::new(
+        kdf.config.memory_kib,
+        kdf.config.iterations,
+        kdf.config.parallelism,
+        Some(KEY_LEN),
+    )
+    .map_err(|e| format!("Invalid keystore KDF parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), &salt, key.as_mut())
+        .map_err(|e| format!("Failed to derive the keystore key: {}", e))?;
+    Ok(key)
+}
+
+fn seal_data_key(password_key: &SecretKey, id: u32, key: &SecretKey) -> Result<DataKey, String> {
+    Ok(DataKey {
+        id,
+        sealed: seal(password_key, key.as_ref(), data_key_aad(id).as_bytes())?,
+    })
+}
+
+fn open_data_key(password_key: &SecretKey, key: &DataKey) -> Result<SecretKey, String> {
+    let bytes = open(password_key, &key.sealed, data_key_aad(key.id).as_bytes())?;
+    let mut data_key = Zeroizing::new([0u8; KEY_LEN]);
+    if bytes.len() != KEY_LEN {
+        return Err(format!("Data key {} has the wrong length", key.id));
+    }
+    data_key.copy_from_slice(&bytes);
+    Ok(data_key)
+}
+
+pub(crate) fn seal(key: &SecretKey, plaintext: &[u8], aad: &[u8]) -> Result<Sealed, String> {
+    let nonce = random::<NONCE_LEN>();
+    let ciphertext = XChaCha20Poly1305::new(<&Key>::from(&**key))
+        .encrypt(
+            &XNonce::from(nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| "Encryption failed".to_string())?;
+    Ok(Sealed {
+        nonce: bs58::encode(nonce).into_string(),
+        ciphertext: bs58::encode(ciphertext).into_string(),
+    })
+}
+
+pub(crate) fn open(
+    key: &SecretKey,
+    sealed: &Sealed,
+    aad: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, String> {
+    let decode = |value: &str| {
+        bs58::decode(value)
+            .into_vec()
+            .map_err(|e| format!("Invalid sealed data: {}", e))
+    };
+    let nonce: [u8; NONCE_LEN] = decode(&sealed.nonce)?
+        .try_into()
+        .map_err(|_| "Invalid nonce".to_string())?;
+    XChaCha20Poly1305::new(<&Key>::from(&**key))
+        .decrypt(
+            &XNonce::from(nonce),
+            Payload {
+                msg: &decode(&sealed.ciphertext)?,
+                aad,
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| "Decryption failed".to_string())
+}
+
+pub(crate) fn random<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Writes `value` to a temporary file readable only by this user and
+/// renames it over `path`, so readers never see a partial file.
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let bytes = serde_json::to_vec_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    let temporary = path.with_extension("json.tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(&temporary)
+        .map_err(|e| format!("Failed to write {}: {}", temporary.display(), e))?;
+    file.write_all(&bytes)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", temporary.display(), e))?;
+    fs::rename(&temporary, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+fn create_private_dir(dir: &Path) -> Result<(), String> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder
+        .create(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters so tests do not spend seconds in Argon2.
+    const TEST_KDF: KdfConfig = KdfConfig {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    fn open_store(dir: &Path, password: &str) -> Result<KeystoreWalletManager, String> {
+        KeystoreWalletManager::open_with(dir, password, TEST_KDF)
+    }
+
+    fn pubkey(wallet: &Wallet) -> String {
+        wallet.address()
+    }
+
+    #[tokio::test]
+    async fn test_wallets_persist_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = open_store(dir.path(), "correct horse").unwrap();
+        let error = store.get_wallet("42").await.err().unwrap();
+        assert!(error.contains("has no wallet"), "{}", error);
+
+        let created = store.create_wallet("42").await.unwrap();
+        assert!(store.create_wallet("42").await.is_err());
+
+        let reopened = open_store(dir.path(), "correct horse").unwrap();
+        let loaded = reopened.get_wallet("42").await.unwrap();
+        assert_eq!(pubkey(&loaded), pubkey(&created));
+
+        // Only the address is stored in the clear
+        let keystore = reopened.keystore.lock().await;
+        let file = fs::read_to_string(keystore.user_path("42")).unwrap();
+        let user = keystore.load("42").unwrap().unwrap();
+        let secret = keystore.open_wallet("42", &user.wallets[0]).unwrap();
+        assert!(!file.contains(&bs58::encode(secret.as_slice()).into_string()));
+        assert!(file.contains(&pubkey(&created)));
+        drop(keystore);
+
+        assert_eq!(
+            open_store(dir.path(), "wrong").err().unwrap(),
+            "Wrong keystore password"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_password_change_and_key_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = open_store(dir.path(), "old").unwrap();
+        let alice = pubkey(&store.create_wallet("alice").await.unwrap());
+        let bob = pubkey(&store.create_wallet("bob").await.unwrap());
+
+        store.change_password("new").await.unwrap();
+        assert!(open_store(dir.path(), "old").is_err());
+        let store = open_store(dir.path(), "new").unwrap();
+
+        assert!(store.rotate_encryption_key("old").await.is_err());
+        store.rotate_encryption_key("new").await.unwrap();
+        let file: KeystoreFile = read_json(&dir.path().join(KEYSTORE_FILE)).unwrap();
+        assert_eq!(file.keys.len(), 1);
+        assert_eq!(file.current_key, 2);
+
+        let store = open_store(dir.path(), "new").unwrap();
+        assert_eq!(pubkey(&store.get_wallet("alice").await.unwrap()), alice);
+        assert_eq!(pubkey(&store.get_wallet("bob").await.unwrap()), bob);
+    }
+
+    #[tokio::test]
+    async fn test_rotated_wallet_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = open_store(dir.path(), "secret").unwrap();
+        let old = pubkey(&store.create_wallet("42").await.unwrap());
+        let new = pubkey(&store.rotate_wallet("42").await.unwrap());
+        assert_ne!(old, new);
+        assert_eq!(pubkey(&store.get_wallet("42").await.unwrap()), new);
+        let all: Vec<String> = store
+            .get_wallets("42")
+            .await
+            .unwrap()
+            .iter()
+            .map(pubkey)
+            .collect();
+        assert_eq!(all, vec![new, old]);
+    }
+
+    #[tokio::test]
+    async fn test_accounts_derive_from_one_phrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = open_store(dir.path(), "secret").unwrap();
+        let main = pubkey(&store.create_wallet("42").await.unwrap());
+        let savings = pubkey(&store.create_account("42", "Savings").await.unwrap());
+        assert!(store.create_account("42", "savings").await.is_err());
+        store.set_default_account("42", "savings").await.unwrap();
+
+        let store = open_store(dir.path(), "secret").unwrap();
+        assert_eq!(pubkey(&store.get_wallet("42").await.unwrap()), savings);
+        assert_eq!(
+            pubkey(&store.get_account("42", "main").await.unwrap()),
+            main
+        );
+        let accounts = store.list_accounts("42").await.unwrap();
+        let labels: Vec<&str> = accounts.iter().map(|a| a.label.as_str()).collect();
+        assert_eq!(labels, vec!["savings", "main"]);
+
+        // Both accounts come from the stored phrase, and survive rotation
+        store.rotate_encryption_key("secret").await.unwrap();
+        let keystore = store.keystore.lock().await;
+        let user = keystore.load("42").unwrap().unwrap();
+        let phrase = keystore
+            .open_seed("42", user.seed.as_ref().unwrap())
+            .unwrap();
+        let derived = hd::derive_keypair(&phrase, "", 1).unwrap();
+        assert_eq!(derived.pubkey().to_string(), savings);
+    }
+
+    #[tokio::test]
+    async fn test_evm_wallet_derives_from_the_phrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = open_store(dir.path(), "secret").unwrap();
+        assert!(store
+            .get_chain_wallet("42", ChainFamily::Evm)
+            .await
+            .is_err());
+        let evm = pubkey(
+            &store
+                .create_chain_wallet("42", ChainFamily::Evm)
+                .await
+                .unwrap(),
+        );
+        assert!(store
+            .create_chain_wallet("42", ChainFamily::Evm)
+            .await
+            .is_err());
+        // The user has a phrase but no Solana account yet
+        assert!(store.get_wallet("42").await.is_err());
+        let solana = pubkey(&store.create_wallet("42").await.unwrap());
+
+        store.rotate_encryption_key("secret").await.unwrap();
+        let store = open_store(dir.path(), "secret").unwrap();
+        let loaded = store
+            .get_chain_wallet("42", ChainFamily::Evm)
+            .await
+            .unwrap();
+        assert_eq!(loaded.family(), ChainFamily::Evm);
+        assert_eq!(pubkey(&loaded), evm);
+        let all: Vec<String> = store
+            .get_wallets("42")
+            .await
+            .unwrap()
+            .iter()
+            .map(pubkey)
+            .collect();
+        assert_eq!(all, vec![solana, evm.clone()]);
+
+        let keystore = store.keystore.lock().await;
+        let user = keystore.load("42").unwrap().unwrap();
+        let phrase = keystore
+            .open_seed("42", user.seed.as_ref().unwrap())
+            .unwrap();
+        let key = hd::derive_evm_key(&phrase, "", 0).unwrap();
+        let derived = LocalEvmSigner::new(&key).unwrap();
+        assert_eq!(derived.address().to_string(), evm);
+    }
+
+    #[tokio::test]
+    async fn test_import_and_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = open_store(dir.path(), "secret").unwrap();
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let import = || WalletImport::Phrase(Zeroizing::new(phrase.to_string()));
+        let main = pubkey(&store.import_wallet("alice", import()).await.unwrap());
+        assert_eq!(main, "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk");
+        assert!(store.import_wallet("alice", import()).await.is_err());
+
+        let old = Keypair::new();
+        let file = backup::keypair_file(&old);
+        let import = WalletImport::parse(&file, "Old", None).unwrap();
+        store.import_wallet("alice", import).await.unwrap();
+        store.create_account("alice", "trading").await.unwrap();
+        store.set_default_account("alice", "old").await.unwrap();
+        let exported = store
+            .export_wallet("alice", None, ExportFormat::KeypairFile)
+            .await
+            .unwrap();
+        assert_eq!(*exported, *file);
+
+        let password = Zeroizing::new("correct horse".to_string());
+        let backup = store
+            .export_wallet(
+                "alice",
+                None,
+                ExportFormat::Backup {
+                    password: password.clone(),
+                },
+            )
+            .await
+            .unwrap();
+        assert!(!backup.contains(&old.to_base58_string()));
+        let restored = store
+            .import_wallet(
+                "bob",
+                WalletImport::parse(&backup, "", Some(&password)).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(pubkey(&restored), old.pubkey().to_string());
+        let accounts = |user| {
+            let store = store.clone();
+            async move {
+                store
+                    .list_accounts(user)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(|account| (account.label.clone(), pubkey(&account.wallet)))
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(accounts("bob").await, accounts("alice").await);
+    }
+
+    #[tokio::test]
+    async fn test_sealed_secrets_are_bound_to_their_user() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = open_store(dir.path(), "secret").unwrap();
+        store.create_wallet("alice").await.unwrap();
+        let (alice, mallory) = {
+            let keystore = store.keystore.lock().await;
+            (keystore.user_path("alice"), keystore.user_path("mallory"))
+        };
+        let mut file: UserFile = read_json(&alice).unwrap();
+        file.user_id = "mallory".to_string();
+        write_json(&mallory, &file).unwrap();
+
+        let error = store.get_wallet("mallory").await.err().unwrap();
+        assert!(error.contains("is corrupted"), "{}", error);
+    }
+
+    #[test]
+    fn test_missing_current_key_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        open_store(dir.path(), "secret").unwrap();
+        let path = dir.path().join(KEYSTORE_FILE);
+        let mut file: KeystoreFile = read_json(&path).unwrap();
+        file.current_key = 7;
+        write_json(&path, &file).unwrap();
+
+        let error = open_store(dir.path(), "secret").err().unwrap();
+        assert!(error.contains("data key 7 is missing"), "{}", error);
+    }
+}
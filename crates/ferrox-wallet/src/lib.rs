@@ -1,28 +1,237 @@
+pub mod backup;
+pub mod evm;
+pub mod hd;
+pub mod keystore_wallet_manager;
+pub mod signer;
 pub mod simple_wallet_manager;
 use std::{future::Future, pin::Pin, sync::Arc};
 
-use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
 
+pub use backup::{ExportFormat, WalletImport};
+pub use evm::{EvmSigner, LocalEvmSigner};
+pub use hd::DEFAULT_ACCOUNT;
+pub use signer::{LocalSigner, WalletSigner};
+
+/// The kinds of chain a wallet signs for. All EVM chains share one address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChainFamily {
+    Solana,
+    Evm,
+}
+
+/// A user's wallet. It can sign for its address but does not give out the
+/// secret key.
 #[derive(Clone)]
 pub enum Wallet {
-    Solana(Arc<Keypair>),
+    Solana(Arc<dyn WalletSigner>),
+    Evm(Arc<dyn EvmSigner>),
+}
+
+impl Wallet {
+    pub fn family(&self) -> ChainFamily {
+        match self {
+            Wallet::Solana(_) => ChainFamily::Solana,
+            Wallet::Evm(_) => ChainFamily::Evm,
+        }
+    }
+
+    /// The address in the usual form of its chain: base58 for Solana, 0x and
+    /// the EIP-55 checksum for EVM.
+    pub fn address(&self) -> String {
+        match self {
+            Wallet::Solana(signer) => signer.pubkey().to_string(),
+            Wallet::Evm(signer) => signer.address().to_string(),
+        }
+    }
 }
 
-//Implement this trait to manage the wallets of multiple users
+/// One of the named accounts derived from a user's recovery phrase.
+#[derive(Clone)]
+pub struct WalletAccount {
+    pub label: String,
+    /// Where the account sits in the user's seed, such as `m/44'/501'/0'/0'`.
+    /// `None` for keypairs that were not derived from it.
+    pub derivation_path: Option<String>,
+    pub wallet: Wallet,
+    /// Whether actions use this account when none is named.
+    pub is_default: bool,
+}
+
+/// The future a [`WalletManager`] returns for what it does not support.
+fn unsupported<T: Send + Sync + 'static>(
+    operation: &str,
+) -> Pin<Box<dyn Future<Output = Result<T, String>> + Send + Sync>> {
+    let error = format!("This wallet manager does not support {}", operation);
+    Box::pin(async move { Err(error) })
+}
+
+//Implement this trait to manage the wallets of multiple users. Managers
+//without named accounts, other chains or import and export can leave
+//those methods out; they then fail with an error
 pub trait WalletManager: Send + Sync + Clone {
-    // Returns a wallet for a user
+    // Returns the default account of a user
     fn get_wallet(
         &self,
         user_id: &str,
     ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>>;
 
+    // Returns every wallet of a user, including ones no longer in use
     fn get_wallets(
         &self,
         user_id: &str,
     ) -> Pin<Box<dyn Future<Output = Result<Vec<Wallet>, String>> + Send + Sync>>;
 
+    // Gives a user a recovery phrase and its first account. Fails if the
+    // user already has a wallet
     fn create_wallet(
         &self,
         user_id: &str,
     ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>>;
+
+    // Returns the account of a user called `label`
+    fn get_account(
+        &self,
+        _user_id: &str,
+        _label: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        unsupported("named accounts")
+    }
+
+    // Lists the accounts of a user, the default one first
+    fn list_accounts(
+        &self,
+        _user_id: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<WalletAccount>, String>> + Send + Sync>> {
+        unsupported("named accounts")
+    }
+
+    // Derives the next account of a user's recovery phrase and calls it `label`
+    fn create_account(
+        &self,
+        _user_id: &str,
+        _label: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        unsupported("named accounts")
+    }
+
+    // Makes the account called `label` the one used when none is named
+    fn set_default_account(
+        &self,
+        _user_id: &str,
+        _label: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + Sync>> {
+        unsupported("named accounts")
+    }
+
+    // Returns the wallet of a user for chains of `family`; for Solana, the
+    // default account
+    fn get_chain_wallet(
+        &self,
+        user_id: &str,
+        family: ChainFamily,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        match family {
+            ChainFamily::Solana => self.get_wallet(user_id),
+            ChainFamily::Evm => unsupported("EVM wallets"),
+        }
+    }
+
+    // Gives a user a wallet for chains of `family`, derived from their
+    // recovery phrase. Fails if they already have one
+    fn create_chain_wallet(
+        &self,
+        user_id: &str,
+        family: ChainFamily,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        match family {
+            ChainFamily::Solana => self.create_wallet(user_id),
+            ChainFamily::Evm => unsupported("EVM wallets"),
+        }
+    }
+
+    // Adds a wallet from elsewhere to a user and returns it; a restored
+    // phrase or backup returns the default account
+    fn import_wallet(
+        &self,
+        _user_id: &str,
+        _import: WalletImport,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        unsupported("importing wallets")
+    }
+
+    // Exports the account called `account` (the default one when `None`)
+    // as a keypair file, or every wallet of a user as an encrypted backup
+    fn export_wallet(
+        &self,
+        _user_id: &str,
+        _account: Option<&str>,
+        _format: ExportFormat,
+    ) -> Pin<Box<dyn Future<Output = Result<Zeroizing<String>, String>> + Send + Sync>> {
+        unsupported("exporting wallets")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{signature::Keypair, signer::Signer};
+
+    /// A manager with only the methods every manager has to implement.
+    #[derive(Clone)]
+    struct OneWallet(Arc<Keypair>);
+
+    impl WalletManager for OneWallet {
+        fn get_wallet(
+            &self,
+            _user_id: &str,
+        ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+            let keypair = Keypair::from_bytes(&self.0.to_bytes()).unwrap();
+            Box::pin(async move { Ok(Wallet::Solana(Arc::new(LocalSigner::new(keypair)))) })
+        }
+
+        fn get_wallets(
+            &self,
+            user_id: &str,
+        ) -> Pin<Box<dyn Future<Output = Result<Vec<Wallet>, String>> + Send + Sync>> {
+            let wallet = self.get_wallet(user_id);
+            Box::pin(async move { Ok(vec![wallet.await?]) })
+        }
+
+        fn create_wallet(
+            &self,
+            user_id: &str,
+        ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+            self.get_wallet(user_id)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_optional_methods_are_unsupported() {
+        let manager = OneWallet(Arc::new(Keypair::new()));
+        assert_eq!(
+            manager.get_account("42", "main").await.err().unwrap(),
+            "This wallet manager does not support named accounts"
+        );
+        assert!(manager.list_accounts("42").await.is_err());
+        assert!(manager.create_account("42", "main").await.is_err());
+        assert!(manager.set_default_account("42", "main").await.is_err());
+        assert!(manager
+            .get_chain_wallet("42", ChainFamily::Evm)
+            .await
+            .is_err());
+        assert!(manager
+            .export_wallet("42", None, ExportFormat::KeypairFile)
+            .await
+            .is_err());
+
+        // Solana falls back to the one wallet
+        let wallet = manager
+            .get_chain_wallet("42", ChainFamily::Solana)
+            .await
+            .unwrap();
+        assert_eq!(wallet.address(), manager.0.pubkey().to_string());
+    }
 }
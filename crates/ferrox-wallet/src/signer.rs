@@ -0,0 +1,141 @@
+//! Signing without handing out key material.
+//!
+//! Actions get a [`WalletSigner`], which can tell its address and sign but
+//! never reveal the secret key. The key may live in this process
+//! ([`LocalSigner`]), in a separate signing process reached over a Unix
+//! socket ([`socket::SocketSigner`]) or behind a key management service
+//! ([`kms::KmsSigner`]).
+
+#[cfg(unix)]
+pub mod socket;
+
+pub mod kms;
+
+use std::{fmt, future::Future, pin::Pin};
+
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::VersionedTransaction,
+};
+
+/// A signing operation in progress.
+pub type SignFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send + Sync + 'a>>;
+
+/// Signs for one Solana address.
+pub trait WalletSigner: Send + Sync {
+    fn pubkey(&self) -> Pubkey;
+
+    /// Signs `message` with the key of [`Self::pubkey`].
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a, Signature>;
+
+    /// Adds this signer's signature to `transaction`, leaving the
+    /// signatures of other signers as they are.
+    fn sign_transaction(
+        &self,
+        mut transaction: VersionedTransaction,
+    ) -> SignFuture<'_, VersionedTransaction> {
+        Box::pin(async move {
+            let message = transaction.message.serialize();
+            let signature = self.sign_message(&message).await?;
+            place_signature(&mut transaction, &self.pubkey(), signature)?;
+            Ok(transaction)
+        })
+    }
+}
+
+/// Puts `signature` in the slot of `signer` among the transaction's
+/// required signers.
+fn place_signature(
+    transaction: &mut VersionedTransaction,
+    signer: &Pubkey,
+    signature: Signature,
+) -> Result<(), String> {
+    let required = transaction.message.header().num_required_signatures as usize;
+    let index = transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .take(required)
+        .position(|key| key == signer)
+        .ok_or_else(|| format!("{} is not a signer of this transaction", signer))?;
+    transaction
+        .signatures
+        .resize(required, Signature::default());
+    transaction.signatures[index] = signature;
+    Ok(())
+}
+
+/// Signs with a keypair held in this process.
+pub struct LocalSigner {
+    keypair: Keypair,
+}
+
+impl LocalSigner {
+    pub fn new(keypair: Keypair) -> Self {
+        Self { keypair }
+    }
+}
+
+impl fmt::Debug for LocalSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalSigner")
+            .field("pubkey", &self.keypair.pubkey())
+            .finish()
+    }
+}
+
+impl WalletSigner for LocalSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a, Signature> {
+        let signature = self.keypair.sign_message(message);
+        Box::pin(async move { Ok(signature) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        message::{Message, VersionedMessage},
+        system_instruction,
+    };
+
+    #[tokio::test]
+    async fn test_sign_transaction_fills_the_signer_slot() {
+        let (payer, sender) = (Keypair::new(), Keypair::new());
+        let transaction = VersionedTransaction {
+            signatures: Vec::new(),
+            message: VersionedMessage::Legacy(Message::new_with_blockhash(
+                &[system_instruction::transfer(
+                    &sender.pubkey(),
+                    &Pubkey::new_unique(),
+                    1,
+                )],
+                Some(&payer.pubkey()),
+                &Hash::new_unique(),
+            )),
+        };
+
+        let transaction = LocalSigner::new(sender)
+            .sign_transaction(transaction)
+            .await
+            .unwrap();
+        let transaction = LocalSigner::new(payer)
+            .sign_transaction(transaction)
+            .await
+            .unwrap();
+        assert!(transaction.verify_with_results().iter().all(|ok| *ok));
+
+        let error = LocalSigner::new(Keypair::new())
+            .sign_transaction(transaction)
+            .await
+            .unwrap_err();
+        assert!(error.contains("is not a signer"), "{}", error);
+    }
+}
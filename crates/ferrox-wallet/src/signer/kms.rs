@@ -0,0 +1,108 @@
+//! A local stand-in for a key management service. Keys are generated inside
+//! the service and used by id; there is no call that returns them. Swapping
+//! it for a real KMS client only means implementing [`WalletSigner`] over
+//! that client's sign call.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+
+use super::{SignFuture, WalletSigner};
+
+#[derive(Clone, Default)]
+pub struct LocalKms {
+    keys: Arc<Mutex<HashMap<String, Keypair>>>,
+}
+
+impl LocalKms {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates a key and returns its id.
+    pub fn create_key(&self) -> String {
+        let keypair = Keypair::new();
+        let key_id = format!("solana/{}", keypair.pubkey());
+        self.keys.lock().unwrap().insert(key_id.clone(), keypair);
+        key_id
+    }
+
+    /// Forgets the key `key_id`. Signers for it fail from then on.
+    pub fn delete_key(&self, key_id: &str) -> bool {
+        self.keys.lock().unwrap().remove(key_id).is_some()
+    }
+
+    /// A signer for the key `key_id`.
+    pub fn signer(&self, key_id: &str) -> Result<KmsSigner, String> {
+        let pubkey = self
+            .keys
+            .lock()
+            .unwrap()
+            .get(key_id)
+            .map(|keypair| keypair.pubkey())
+            .ok_or_else(|| format!("No key {} in the KMS", key_id))?;
+        Ok(KmsSigner {
+            kms: self.clone(),
+            key_id: key_id.to_string(),
+            pubkey,
+        })
+    }
+
+    fn sign(&self, key_id: &str, message: &[u8]) -> Result<Signature, String> {
+        self.keys
+            .lock()
+            .unwrap()
+            .get(key_id)
+            .map(|keypair| keypair.sign_message(message))
+            .ok_or_else(|| format!("No key {} in the KMS", key_id))
+    }
+}
+
+/// Signs with a key held by a [`LocalKms`].
+#[derive(Clone)]
+pub struct KmsSigner {
+    kms: LocalKms,
+    key_id: String,
+    pubkey: Pubkey,
+}
+
+impl KmsSigner {
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+}
+
+impl WalletSigner for KmsSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a, Signature> {
+        let signature = self.kms.sign(&self.key_id, message);
+        Box::pin(async move { signature })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_kms_signs_by_key_id() {
+        let kms = LocalKms::new();
+        let signer = kms.signer(&kms.create_key()).unwrap();
+        let signature = signer.sign_message(b"hello").await.unwrap();
+        assert!(signature.verify(signer.pubkey().as_ref(), b"hello"));
+
+        assert!(kms.delete_key(signer.key_id()));
+        assert!(signer.sign_message(b"hello").await.is_err());
+        assert!(kms.signer("solana/unknown").is_err());
+    }
+}
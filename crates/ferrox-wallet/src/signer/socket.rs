@@ -0,0 +1,189 @@
+//! Signing in a separate process over a Unix socket, so the agent process
+//! never holds the key.
+//!
+//! The signing process runs [`serve`] with its own signer; the agent uses a
+//! [`SocketSigner`]. Each request is one line of JSON answered by one line:
+//! `{"method":"pubkey"}` is answered with `{"result":"<address>"}` and
+//! `{"method":"sign_message","message":"<base58>"}` with
+//! `{"result":"<signature>"}`. Failures are answered with `{"error":"..."}`.
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::{bs58, pubkey::Pubkey, signature::Signature};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+};
+
+use super::{SignFuture, WalletSigner};
+
+/// Largest request the signing process reads, well above any transaction.
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request {
+    Pubkey,
+    SignMessage { message: String },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Response {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Signs through the signing process listening on a Unix socket.
+#[derive(Debug, Clone)]
+pub struct SocketSigner {
+    path: PathBuf,
+    pubkey: Pubkey,
+}
+
+impl SocketSigner {
+    /// Connects to the signing process at `path` and asks which address it
+    /// signs for.
+    pub async fn connect(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let pubkey = call(&path, &Request::Pubkey).await?;
+        let pubkey = Pubkey::from_str(&pubkey)
+            .map_err(|e| format!("The signer sent an invalid address: {}", e))?;
+        Ok(Self { path, pubkey })
+    }
+}
+
+impl WalletSigner for SocketSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a, Signature> {
+        Box::pin(async move {
+            let request = Request::SignMessage {
+                message: bs58::encode(message).into_string(),
+            };
+            let signature = call(&self.path, &request).await?;
+            let signature = Signature::from_str(&signature)
+                .map_err(|e| format!("The signer sent an invalid signature: {}", e))?;
+            if !signature.verify(self.pubkey.as_ref(), message) {
+                return Err("The signer's signature does not match its address".to_string());
+            }
+            Ok(signature)
+        })
+    }
+}
+
+async fn call(path: &Path, request: &Request) -> Result<String, String> {
+    let stream = UnixStream::connect(path)
+        .await
+        .map_err(|e| format!("Failed to reach the signer at {}: {}", path.display(), e))?;
+    let (reader, mut writer) = stream.into_split();
+    let mut line = serde_json::to_string(request)
+        .map_err(|e| format!("Failed to encode the signing request: {}", e))?;
+    line.push('\n');
+    writer
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to send the signing request: {}", e))?;
+
+    let mut line = String::new();
+    BufReader::new(reader)
+        .read_line(&mut line)
+        .await
+        .map_err(|e| format!("Failed to read the signer's response: {}", e))?;
+    let response: Response = serde_json::from_str(&line)
+        .map_err(|e| format!("Invalid response from the signer: {}", e))?;
+    match (response.result, response.error) {
+        (_, Some(error)) => Err(format!("The signer refused: {}", error)),
+        (Some(result), None) => Ok(result),
+        (None, None) => Err("The signer sent an empty response".to_string()),
+    }
+}
+
+/// Answers signing requests on `listener` with `signer` until accepting
+/// fails. Run this in the process that holds the key.
+pub async fn serve(listener: UnixListener, signer: Arc<dyn WalletSigner>) -> Result<(), String> {
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .map_err(|e| format!("Failed to accept a signing request: {}", e))?;
+        let signer = signer.clone();
+        tokio::spawn(async move {
+            // A client that hangs up early only loses its own answer
+            let _ = handle(stream, signer.as_ref()).await;
+        });
+    }
+}
+
+async fn handle(stream: UnixStream, signer: &dyn WalletSigner) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    tokio::io::AsyncReadExt::take(BufReader::new(reader), MAX_REQUEST_BYTES)
+        .read_line(&mut line)
+        .await?;
+    let response = match answer(&line, signer).await {
+        Ok(result) => Response {
+            result: Some(result),
+            ..Default::default()
+        },
+        Err(error) => Response {
+            error: Some(error),
+            ..Default::default()
+        },
+    };
+    let mut line = serde_json::to_string(&response)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await
+}
+
+async fn answer(line: &str, signer: &dyn WalletSigner) -> Result<String, String> {
+    match serde_json::from_str(line).map_err(|e| format!("Invalid request: {}", e))? {
+        Request::Pubkey => Ok(signer.pubkey().to_string()),
+        Request::SignMessage { message } => {
+            let message = bs58::decode(message)
+                .into_vec()
+                .map_err(|e| format!("Invalid message: {}", e))?;
+            Ok(signer.sign_message(&message).await?.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::LocalSigner;
+    use solana_sdk::{signature::Keypair, signer::Signer};
+
+    #[tokio::test]
+    async fn test_socket_signer_signs_in_the_serving_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+        let keypair = Keypair::new();
+        let expected = keypair.pubkey();
+        let listener = UnixListener::bind(&path).unwrap();
+        tokio::spawn(serve(listener, Arc::new(LocalSigner::new(keypair))));
+
+        let signer = SocketSigner::connect(&path).await.unwrap();
+        assert_eq!(signer.pubkey(), expected);
+        let signature = signer.sign_message(b"transfer").await.unwrap();
+        assert!(signature.verify(expected.as_ref(), b"transfer"));
+
+        let error = call(
+            &path,
+            &Request::SignMessage {
+                message: "0OIl".to_string(),
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(error.starts_with("The signer refused"), "{}", error);
+    }
+}
@@ -5,21 +5,337 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use solana_sdk::signature::Keypair;
+use solana_sdk::{signature::Keypair, signer::Signer};
+use zeroize::Zeroizing;
 
-use crate::{Wallet, WalletManager};
+use crate::{
+    backup::{self, BackupAccount, BackupContents, Import},
+    hd,
+    keystore_wallet_manager::KdfConfig,
+    ChainFamily, ExportFormat, LocalEvmSigner, LocalSigner, Wallet, WalletAccount, WalletImport,
+    WalletManager,
+};
 
-#[derive(Clone)]
+/// Wallets held in memory, for tests and demos. Users without a wallet all
+/// get the same hard-coded keypair, so never use it with real funds; see
+/// [`KeystoreWalletManager`](crate::keystore_wallet_manager::KeystoreWalletManager).
+#[derive(Clone, Default)]
 pub struct SimpleWalletManager {
-    wallets: Arc<Mutex<HashMap<String, Wallet>>>,
+    users: Arc<Mutex<HashMap<String, UserWallets>>>,
+}
+
+/// A user's recovery phrase and the accounts derived from it, oldest first.
+struct UserWallets {
+    phrase: Zeroizing<String>,
+    accounts: Vec<WalletAccount>,
+    /// Keypairs of the accounts that were imported rather than derived, by
+    /// label.
+    imported: HashMap<String, Zeroizing<Vec<u8>>>,
+    /// The EVM wallet and its index in `phrase`.
+    evm: Option<(u32, Wallet)>,
+}
+
+impl UserWallets {
+    fn new() -> Self {
+        Self::with_phrase(hd::generate_phrase())
+    }
+
+    fn with_phrase(phrase: Zeroizing<String>) -> Self {
+        Self {
+            phrase,
+            accounts: Vec::new(),
+            imported: HashMap::new(),
+            evm: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.evm.is_none()
+    }
+
+    fn next_index(&self) -> u32 {
+        self.accounts
+            .iter()
+            .filter_map(|account| account.derivation_path.as_deref())
+            .filter_map(hd::account_index)
+            .max()
+            .map_or(0, |index| index + 1)
+    }
+
+    fn keypair(&self, account: &WalletAccount) -> Result<Keypair, String> {
+        if let Some(bytes) = self.imported.get(&account.label) {
+            return backup::keypair_from_bytes(bytes);
+        }
+        let index = account
+            .derivation_path
+            .as_deref()
+            .and_then(hd::account_index)
+            .ok_or_else(|| format!("Account '{}' has no key to export", account.label))?;
+        hd::derive_keypair(&self.phrase, "", index)
+    }
+
+    fn backup(&self) -> Result<BackupContents, String> {
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|account| {
+                let keypair = match self.imported.get(&account.label) {
+                    Some(_) => Some(self.keypair(account)?.to_base58_string()),
+                    None => None,
+                };
+                Ok(BackupAccount {
+                    label: account.label.clone(),
+                    index: account
+                        .derivation_path
+                        .as_deref()
+                        .and_then(hd::account_index),
+                    keypair,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(BackupContents {
+            phrase: Some(self.phrase.to_string()),
+            default_account: self
+                .accounts
+                .iter()
+                .find(|account| account.is_default)
+                .map(|account| account.label.clone()),
+            accounts,
+            evm_index: self.evm.as_ref().map(|(index, _)| *index),
+        })
+    }
+
+    fn restore(contents: &BackupContents) -> Result<Self, String> {
+        let phrase = match &contents.phrase {
+            Some(phrase) => hd::normalize_phrase(phrase)?,
+            None => hd::generate_phrase(),
+        };
+        let mut user = Self::with_phrase(phrase);
+        for account in &contents.accounts {
+            let label = hd::normalize_label(&account.label)?;
+            let keypair = contents.keypair(account)?;
+            let derivation_path = match &account.keypair {
+                Some(_) => {
+                    user.imported
+                        .insert(label.clone(), Zeroizing::new(keypair.to_bytes().to_vec()));
+                    None
+                }
+                None => account.index.map(hd::derivation_path),
+            };
+            user.accounts.push(WalletAccount {
+                is_default: Some(account.label.as_str()) == contents.default_label(),
+                label,
+                derivation_path,
+                wallet: Wallet::Solana(Arc::new(LocalSigner::new(keypair))),
+            });
+        }
+        if let Some(index) = contents.evm_index {
+            let key = hd::derive_evm_key(&user.phrase, "", index)?;
+            user.evm = Some((index, Wallet::Evm(Arc::new(LocalEvmSigner::new(&key)?))));
+        }
+        Ok(user)
+    }
 }
 
 impl SimpleWalletManager {
     pub fn new() -> Self {
         Self {
-            wallets: Arc::new(Mutex::new(HashMap::new())),
+            users: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn account(&self, user_id: &str, label: &str) -> Result<Wallet, String> {
+        let label = hd::normalize_label(label)?;
+        let users = self.users.lock().unwrap();
+        users
+            .get(user_id)
+            .and_then(|user| user.accounts.iter().find(|account| account.label == label))
+            .map(|account| account.wallet.clone())
+            .ok_or_else(|| format!("User {} has no account called '{}'", user_id, label))
+    }
+
+    fn accounts(&self, user_id: &str) -> Vec<WalletAccount> {
+        let users = self.users.lock().unwrap();
+        let mut accounts = users
+            .get(user_id)
+            .map(|user| user.accounts.clone())
+            .unwrap_or_default();
+        accounts.sort_by_key(|account| !account.is_default);
+        accounts
+    }
+
+    fn add_wallet(&self, user_id: &str) -> Result<Wallet, String> {
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .entry(user_id.to_string())
+            .or_insert_with(UserWallets::new);
+        if !user.accounts.is_empty() {
+            return Err(format!("User {} already has a wallet", user_id));
+        }
+        let account = derive_account(&user.phrase, 0, hd::DEFAULT_ACCOUNT)?;
+        let wallet = account.wallet.clone();
+        user.accounts.push(WalletAccount {
+            is_default: true,
+            ..account
+        });
+        Ok(wallet)
+    }
+
+    fn evm_wallet(&self, user_id: &str) -> Result<Wallet, String> {
+        let users = self.users.lock().unwrap();
+        users
+            .get(user_id)
+            .and_then(|user| user.evm.as_ref().map(|(_, wallet)| wallet.clone()))
+            .ok_or_else(|| format!("User {} has no EVM wallet", user_id))
+    }
+
+    fn add_evm_wallet(&self, user_id: &str) -> Result<Wallet, String> {
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .entry(user_id.to_string())
+            .or_insert_with(UserWallets::new);
+        if user.evm.is_some() {
+            return Err(format!("User {} already has an EVM wallet", user_id));
+        }
+        let key = hd::derive_evm_key(&user.phrase, "", 0)?;
+        let wallet = Wallet::Evm(Arc::new(LocalEvmSigner::new(&key)?));
+        user.evm = Some((0, wallet.clone()));
+        Ok(wallet)
+    }
+
+    fn add_account(&self, user_id: &str, label: &str) -> Result<Wallet, String> {
+        let label = hd::normalize_label(label)?;
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .get_mut(user_id)
+            .ok_or_else(|| format!("User {} has no wallet yet; create one first", user_id))?;
+        if user.accounts.iter().any(|account| account.label == label) {
+            return Err(format!(
+                "User {} already has an account called '{}'",
+                user_id, label
+            ));
+        }
+        let account = derive_account(&user.phrase, user.next_index(), &label)?;
+        let wallet = account.wallet.clone();
+        user.accounts.push(account);
+        Ok(wallet)
+    }
+
+    fn make_default(&self, user_id: &str, label: &str) -> Result<(), String> {
+        let label = hd::normalize_label(label)?;
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .get_mut(user_id)
+            .filter(|user| user.accounts.iter().any(|account| account.label == label))
+            .ok_or_else(|| format!("User {} has no account called '{}'", user_id, label))?;
+        for account in &mut user.accounts {
+            account.is_default = account.label == label;
         }
+        Ok(())
     }
+
+    fn import(&self, user_id: &str, import: Import) -> Result<Wallet, String> {
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .entry(user_id.to_string())
+            .or_insert_with(UserWallets::new);
+        match import {
+            Import::Keypair { label, keypair } => {
+                if user.accounts.iter().any(|account| account.label == label) {
+                    return Err(format!(
+                        "User {} already has an account called '{}'",
+                        user_id, label
+                    ));
+                }
+                let address = keypair.pubkey().to_string();
+                if user
+                    .accounts
+                    .iter()
+                    .any(|account| account.wallet.address() == address)
+                {
+                    return Err(format!("User {} already has {}", user_id, address));
+                }
+                user.imported
+                    .insert(label.clone(), Zeroizing::new(keypair.to_bytes().to_vec()));
+                let wallet = Wallet::Solana(Arc::new(LocalSigner::new(keypair)));
+                user.accounts.push(WalletAccount {
+                    label,
+                    derivation_path: None,
+                    wallet: wallet.clone(),
+                    is_default: user.accounts.is_empty(),
+                });
+                Ok(wallet)
+            }
+            Import::Restore(contents) => {
+                if !user.is_empty() {
+                    return Err(already_has_wallet(user_id));
+                }
+                let restored = UserWallets::restore(&contents)?;
+                let wallet = restored
+                    .accounts
+                    .iter()
+                    .find(|account| account.is_default)
+                    .map(|account| account.wallet.clone())
+                    .or_else(|| restored.evm.as_ref().map(|(_, wallet)| wallet.clone()))
+                    .ok_or_else(|| "The backup holds no wallets".to_string())?;
+                *user = restored;
+                Ok(wallet)
+            }
+        }
+    }
+
+    fn export(
+        &self,
+        user_id: &str,
+        account: Option<&str>,
+        format: ExportFormat,
+    ) -> Result<Zeroizing<String>, String> {
+        let users = self.users.lock().unwrap();
+        let user = users
+            .get(user_id)
+            .filter(|user| !user.is_empty())
+            .ok_or_else(|| format!("User {} has no wallet yet; create one first", user_id))?;
+        match format {
+            ExportFormat::KeypairFile => {
+                let label = account.map(hd::normalize_label).transpose()?;
+                let account = user
+                    .accounts
+                    .iter()
+                    .find(|account| match &label {
+                        Some(label) => account.label == *label,
+                        None => account.is_default,
+                    })
+                    .ok_or_else(|| {
+                        format!(
+                            "User {} has no account called '{}'",
+                            user_id,
+                            label.as_deref().unwrap_or(hd::DEFAULT_ACCOUNT)
+                        )
+                    })?;
+                Ok(backup::keypair_file(&user.keypair(account)?))
+            }
+            ExportFormat::Backup { password } => {
+                backup::seal_backup(&user.backup()?, &password, KdfConfig::default())
+            }
+        }
+    }
+}
+
+fn already_has_wallet(user_id: &str) -> String {
+    format!(
+        "User {} already has a wallet; a recovery phrase or backup can only be restored for a new user",
+        user_id
+    )
+}
+
+fn derive_account(phrase: &str, index: u32, label: &str) -> Result<WalletAccount, String> {
+    let keypair = hd::derive_keypair(phrase, "", index)?;
+    Ok(WalletAccount {
+        label: label.to_string(),
+        derivation_path: Some(hd::derivation_path(index)),
+        wallet: Wallet::Solana(Arc::new(LocalSigner::new(keypair))),
+        is_default: false,
+    })
 }
 
 impl WalletManager for SimpleWalletManager {
@@ -27,9 +343,13 @@ impl WalletManager for SimpleWalletManager {
         &self,
         user_id: &str,
     ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
-        let wallet = self.wallets.lock().unwrap().get(user_id).cloned();
+        let wallet = self
+            .accounts(user_id)
+            .into_iter()
+            .next()
+            .map(|account| account.wallet);
         if let Some(wallet) = wallet {
-            return Box::pin(async move { Ok(wallet.clone()) });
+            Box::pin(async move { Ok(wallet) })
         } else {
             // For test purposes, we return 1 hardcoded wallet
             let private_key = [
@@ -38,32 +358,252 @@ impl WalletManager for SimpleWalletManager {
                 143, 75, 110, 195, 235, 251, 190, 182, 47, 42, 83, 2, 95, 187, 132, 253, 38, 244,
                 162, 168, 81, 252, 6, 133, 28, 79, 228,
             ];
-            return Box::pin(async move {
-                Ok(Wallet::Solana(Arc::new(
+            Box::pin(async move {
+                Ok(Wallet::Solana(Arc::new(LocalSigner::new(
                     Keypair::from_bytes(&private_key).unwrap(),
-                )))
-            });
+                ))))
+            })
         }
     }
 
     fn get_wallets(
         &self,
-        _user_id: &str,
+        user_id: &str,
     ) -> Pin<Box<dyn Future<Output = Result<Vec<Wallet>, String>> + Send + Sync>> {
-        let wallets = self.wallets.lock().unwrap().values().cloned().collect();
-        return Box::pin(async move { Ok(wallets) });
+        let wallets = self
+            .accounts(user_id)
+            .into_iter()
+            .map(|account| account.wallet)
+            .chain(self.evm_wallet(user_id).ok())
+            .collect();
+        Box::pin(async move { Ok(wallets) })
     }
 
     fn create_wallet(
         &self,
         user_id: &str,
     ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
-        let keypair = Keypair::new();
-        let wallet = Wallet::Solana(Arc::new(keypair));
-        self.wallets
-            .lock()
+        let wallet = self.add_wallet(user_id);
+        Box::pin(async move { wallet })
+    }
+
+    fn get_account(
+        &self,
+        user_id: &str,
+        label: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        let wallet = self.account(user_id, label);
+        Box::pin(async move { wallet })
+    }
+
+    fn list_accounts(
+        &self,
+        user_id: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<WalletAccount>, String>> + Send + Sync>> {
+        let accounts = self.accounts(user_id);
+        Box::pin(async move { Ok(accounts) })
+    }
+
+    fn create_account(
+        &self,
+        user_id: &str,
+        label: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        let wallet = self.add_account(user_id, label);
+        Box::pin(async move { wallet })
+    }
+
+    fn set_default_account(
+        &self,
+        user_id: &str,
+        label: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + Sync>> {
+        let result = self.make_default(user_id, label);
+        Box::pin(async move { result })
+    }
+
+    fn import_wallet(
+        &self,
+        user_id: &str,
+        import: WalletImport,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        let manager = self.clone();
+        let user_id = user_id.to_string();
+        Box::pin(async move { manager.import(&user_id, import.open().await?) })
+    }
+
+    fn export_wallet(
+        &self,
+        user_id: &str,
+        account: Option<&str>,
+        format: ExportFormat,
+    ) -> Pin<Box<dyn Future<Output = Result<Zeroizing<String>, String>> + Send + Sync>> {
+        let export = self.export(user_id, account, format);
+        Box::pin(async move { export })
+    }
+
+    fn get_chain_wallet(
+        &self,
+        user_id: &str,
+        family: ChainFamily,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        match family {
+            ChainFamily::Solana => self.get_wallet(user_id),
+            ChainFamily::Evm => {
+                let wallet = self.evm_wallet(user_id);
+                Box::pin(async move { wallet })
+            }
+        }
+    }
+
+    fn create_chain_wallet(
+        &self,
+        user_id: &str,
+        family: ChainFamily,
+    ) -> Pin<Box<dyn Future<Output = Result<Wallet, String>> + Send + Sync>> {
+        match family {
+            ChainFamily::Solana => self.create_wallet(user_id),
+            ChainFamily::Evm => {
+                let wallet = self.add_evm_wallet(user_id);
+                Box::pin(async move { wallet })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_accounts_are_kept_per_user() {
+        let wallets = SimpleWalletManager::new();
+        let main = wallets.create_wallet("alice").await.unwrap().address();
+        assert!(wallets.create_wallet("alice").await.is_err());
+        wallets.create_wallet("bob").await.unwrap();
+
+        let trading = wallets
+            .create_account("alice", "Trading")
+            .await
             .unwrap()
-            .insert(user_id.to_string(), wallet.clone());
-        return Box::pin(async move { Ok(wallet) });
+            .address();
+        assert!(wallets.create_account("alice", "trading").await.is_err());
+        assert_eq!(wallets.get_wallets("alice").await.unwrap().len(), 2);
+        assert_eq!(wallets.get_wallets("bob").await.unwrap().len(), 1);
+
+        assert_eq!(wallets.get_wallet("alice").await.unwrap().address(), main);
+        wallets
+            .set_default_account("alice", "trading")
+            .await
+            .unwrap();
+        assert_eq!(
+            wallets.get_wallet("alice").await.unwrap().address(),
+            trading
+        );
+        let accounts = wallets.list_accounts("alice").await.unwrap();
+        assert_eq!(accounts[0].label, "trading");
+        assert_eq!(
+            accounts[0].derivation_path.as_deref(),
+            Some("m/44'/501'/1'/0'")
+        );
+        assert!(wallets.set_default_account("bob", "trading").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_evm_wallets() {
+        let wallets = SimpleWalletManager::new();
+        assert!(wallets
+            .get_chain_wallet("alice", ChainFamily::Evm)
+            .await
+            .is_err());
+        let evm = wallets
+            .create_chain_wallet("alice", ChainFamily::Evm)
+            .await
+            .unwrap();
+        assert_eq!(evm.family(), ChainFamily::Evm);
+        assert!(evm.address().starts_with("0x"));
+        assert!(wallets
+            .create_chain_wallet("alice", ChainFamily::Evm)
+            .await
+            .is_err());
+        assert_eq!(
+            wallets
+                .get_chain_wallet("alice", ChainFamily::Evm)
+                .await
+                .unwrap()
+                .address(),
+            evm.address()
+        );
+
+        // Both come from the same phrase, which the EVM wallet created
+        let solana = wallets
+            .create_chain_wallet("alice", ChainFamily::Solana)
+            .await
+            .unwrap();
+        assert_eq!(solana.family(), ChainFamily::Solana);
+        assert_eq!(wallets.get_wallets("alice").await.unwrap().len(), 2);
+    }
+
+    async fn addresses(wallets: &SimpleWalletManager, user_id: &str) -> Vec<String> {
+        let mut addresses: Vec<String> = wallets
+            .get_wallets(user_id)
+            .await
+            .unwrap()
+            .iter()
+            .map(Wallet::address)
+            .collect();
+        addresses.sort();
+        addresses
+    }
+
+    #[tokio::test]
+    async fn test_import_export_and_restore() {
+        let wallets = SimpleWalletManager::new();
+        let main = wallets.create_wallet("alice").await.unwrap().address();
+        let old = Keypair::new();
+        let import = WalletImport::parse(&old.to_base58_string(), "old", None).unwrap();
+        let imported = wallets.import_wallet("alice", import).await.unwrap();
+        assert_eq!(imported.address(), old.pubkey().to_string());
+        // Imported keys take no index, so the next account is still the second
+        let trading = wallets.create_account("alice", "trading").await.unwrap();
+        let trading = trading.address();
+        wallets
+            .create_chain_wallet("alice", ChainFamily::Evm)
+            .await
+            .unwrap();
+
+        let file = wallets
+            .export_wallet("alice", Some("old"), ExportFormat::KeypairFile)
+            .await
+            .unwrap();
+        assert_eq!(*file, *backup::keypair_file(&old));
+        assert!(wallets
+            .export_wallet("bob", None, ExportFormat::KeypairFile)
+            .await
+            .is_err());
+
+        let password = || Zeroizing::new("correct horse".to_string());
+        let backup = wallets
+            .export_wallet(
+                "alice",
+                None,
+                ExportFormat::Backup {
+                    password: password(),
+                },
+            )
+            .await
+            .unwrap();
+        let restore = || WalletImport::Backup {
+            backup: backup.to_string(),
+            password: password(),
+        };
+        assert!(wallets.import_wallet("alice", restore()).await.is_err());
+        let restored = wallets.import_wallet("bob", restore()).await.unwrap();
+        assert_eq!(restored.address(), main);
+        assert_eq!(
+            addresses(&wallets, "bob").await,
+            addresses(&wallets, "alice").await
+        );
+        assert!(addresses(&wallets, "bob").await.contains(&trading));
     }
 }
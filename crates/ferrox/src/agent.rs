@@ -3,7 +3,7 @@ pub mod text_agent;
 
 use std::{future::Future, pin::Pin, sync::Arc};
 
-use ferrox_actions::{ActionGroup, AgentState, ConfirmHandler, FunctionAction};
+use ferrox_actions::{ActionGroup, ActionOutput, AgentState, ConfirmHandler, FunctionAction};
 pub use null_agent::NullAgent;
 
 /// Agent trait represents an LLM with state management capabilities
@@ -43,4 +43,11 @@ pub trait Agent<S: Send + Sync + Clone + 'static = ()>: Clone {
                 + Sync,
         >,
     >;
+
+    /// Takes the outputs of the actions the agent ran while answering the last
+    /// prompt in `history_id`, so their artifacts and UI hints can be shown to
+    /// the user.
+    fn take_action_outputs(&self, _history_id: &str) -> Vec<ActionOutput> {
+        Vec::new()
+    }
 }
@@ -1,5 +1,5 @@
 use super::{Agent, ConfirmHandler};
-use ferrox_actions::{AgentState, FunctionAction};
+use ferrox_actions::{ActionOutput, AgentState, FunctionAction};
 use openai_api::{
     completions::Client as OpenAIClient,
     models::{FunctionDefinition, Message, Model, Tool},
@@ -21,6 +21,7 @@ where
     pub system_prompt: String,
     pub open_ai_client: OpenAIClient,
     conversation_history: Arc<Mutex<HashMap<String, Vec<Message>>>>,
+    action_outputs: Arc<Mutex<HashMap<String, Vec<ActionOutput>>>>,
     actions: Arc<Mutex<Vec<Arc<FunctionAction<S>>>>>,
     state: AgentState<S>,
 }
@@ -42,6 +43,7 @@ where
             system_prompt,
             open_ai_client: OpenAIClient::new(api_key, model),
             conversation_history: Arc::new(Mutex::new(HashMap::new())),
+            action_outputs: Arc::new(Mutex::new(HashMap::new())),
             actions: Arc::new(Mutex::new(Vec::new())),
             state: Arc::new(tokio::sync::Mutex::new(state)),
         }
@@ -66,6 +68,7 @@ where
         println!("Sending prompt: {:?}", prompt);
         // Clone what we need for the async block
         let conversation_history = self.conversation_history.clone();
+        let action_outputs = self.action_outputs.clone();
         let system_prompt = self.system_prompt.clone();
         let state = self.state.clone();
         let open_ai_client = self.open_ai_client.clone();
@@ -134,6 +137,7 @@ where
             let mut final_result = String::new();
             let mut prev_result: String = String::new();
             let mut confirm_handler: Option<ConfirmHandler<S>> = None;
+            let mut outputs = Vec::new();
             let mut count = 0;
             while count <= 5 {
                 let response = open_ai_client
@@ -178,7 +182,7 @@ where
                         .find(|a| a.definition().name == tool_call.function.name)
                     {
                         let result = action
-                            .execute(
+                            .execute_rich(
                                 serde_json::from_str(&tool_call.function.arguments)
                                     .map_err(|e| e.to_string())?,
                                 send_state.clone(),
@@ -189,18 +193,23 @@ where
                                 format!("Failed to execute {}: {}", tool_call.function.name, e)
                             });
                         println!("Executed function {}", tool_call.function.name);
-                        let result = match result {
-                            Ok(result) => result,
+                        // A preview that failed has nothing to confirm
+                        let (result, handler) = match result {
+                            Ok(output) => {
+                                let content = output.content.clone();
+                                outputs.push(output);
+                                (content, action.confirm_handler.clone())
+                            }
                             Err(e) => {
                                 println!(
                                     "LLM called the function but failed to execute {}: {}",
                                     tool_call.function.name, e
                                 );
-                                e.to_string()
+                                (e.to_string(), None)
                             }
                         };
                         prev_result = result.clone();
-                        confirm_handler = action.confirm_handler.clone();
+                        confirm_handler = handler;
                         conversation.push(Message {
                             role: "tool".to_string(),
                             content: Some(result),
@@ -231,10 +240,17 @@ where
                     "Failed to get a final response from the AI agent within 5 rounds".to_string(),
                 );
             }
+            action_outputs
+                .lock()
+                .map_err(|e| e.to_string())?
+                .insert(history_id, outputs);
             Ok((
                 final_result,
-                confirm_handler
-                    .map(|handler| (serde_json::from_str(&prev_result).unwrap(), handler)),
+                confirm_handler.and_then(|handler| {
+                    serde_json::from_str(&prev_result)
+                        .ok()
+                        .map(|preview| (preview, handler))
+                }),
             ))
         })
     }
@@ -285,6 +301,17 @@ where
             Ok((text_result, confirm_option))
         })
     }
+
+    fn take_action_outputs(&self, history_id: &str) -> Vec<ActionOutput> {
+        let mut outputs = self
+            .action_outputs
+            .lock()
+            .unwrap()
+            .remove(history_id)
+            .unwrap_or_default();
+        outputs.extend(self.inner_agent.take_action_outputs(history_id));
+        outputs
+    }
 }
 
 //Tests remain the same but need to be updated to use ActionBuilder instead of MockAction
@@ -652,4 +679,90 @@ mod tests {
         // Response should contain the time
         assert!(response.contains("12:00 PM"));
     }
+
+    #[tokio::test]
+    async fn test_failed_preview_offers_no_confirm() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/v1/chat/completions")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body_from_request(|request| {
+                let body = String::from_utf8_lossy(request.body().unwrap()).to_string();
+                // Call the action first, then answer once its result is in
+                let message = if body.contains(r#""role":"tool""#) {
+                    serde_json::json!({
+                        "role": "assistant",
+                        "content": "The transfer can't be made.",
+                        "tool_calls": null
+                    })
+                } else {
+                    serde_json::json!({
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": { "name": "send_funds", "arguments": "{}" }
+                        }]
+                    })
+                };
+                serde_json::json!({
+                    "id": "chatcmpl-123",
+                    "object": "chat.completion",
+                    "created": 1677652288,
+                    "choices": [{ "index": 0, "message": message, "finish_reason": "stop" }]
+                })
+                .to_string()
+                .into()
+            })
+            .create_async()
+            .await;
+
+        let mut agent = TextAgent::<TestState, NullAgent>::new(
+            NullAgent::default(),
+            "You are a helpful assistant.".to_string(),
+            "test-key".to_string(),
+            Model::OpenAI(OpenAIModel::GPT35Turbo),
+            TestState::default(),
+        );
+        agent.open_ai_client = agent.open_ai_client.clone().with_base_url(server.url());
+
+        async fn preview(
+            _params: EmptyParams,
+            _send_state: serde_json::Value,
+            _state: AgentState<TestState>,
+        ) -> Result<serde_json::Value, String> {
+            Err("Simulation failed: insufficient funds".to_string())
+        }
+        async fn confirm(
+            _preview: serde_json::Value,
+            _send_state: serde_json::Value,
+            state: AgentState<TestState>,
+        ) -> Result<String, String> {
+            state.lock().await.counter += 1;
+            Ok("Sent".to_string())
+        }
+        let action = ActionBuilder::<_, _, _, _, serde_json::Value, _>::new(
+            "send_funds",
+            preview,
+            Some(confirm),
+        )
+        .description("Send funds")
+        .build();
+        agent.add_action(Arc::new(action));
+
+        let (response, confirm) = agent
+            .process_prompt(
+                "Send my funds",
+                "test_failed_preview",
+                serde_json::Value::Null,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response, "The transfer can't be made.");
+        assert!(confirm.is_none());
+        assert_eq!(agent.state().lock().await.counter, 0);
+    }
 }
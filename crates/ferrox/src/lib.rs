@@ -2,15 +2,33 @@ pub mod agent;
 use std::{collections::HashMap, sync::Arc};
 
 use agent::Agent;
-use ferrox_actions::ConfirmHandler;
+use ferrox_actions::{output, Artifact, Attachment, AttachmentKind, ConfirmHandler, Table, UiHint};
 pub use teloxide::types::Message;
 use teloxide::{
     prelude::*,
-    types::{CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup},
+    types::{
+        CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup, InputFile, ParseMode, User,
+    },
+    utils::html,
     RequestError,
 };
 use tokio::sync::Mutex;
 
+/// Telegram rejects photo captions longer than this many characters.
+const CAPTION_LIMIT: usize = 1024;
+/// Telegram rejects messages longer than this many characters.
+const MESSAGE_LIMIT: usize = 4096;
+
+/// What pressing an inline button does.
+enum Callback<S: Send + Sync + Clone + 'static> {
+    /// Runs the confirm handler of a previewed action.
+    Confirm(serde_json::Value, ConfirmHandler<S>),
+    /// Sends a prompt to the agent, from a [`UiHint::Button`].
+    Prompt(String),
+}
+
+type Callbacks<S> = Arc<Mutex<HashMap<String, Callback<S>>>>;
+
 pub struct Ferrox<A, S>
 where
     A: Agent<S> + Send + Sync + Clone + 'static,
@@ -18,7 +36,7 @@ where
 {
     bot: Bot,
     agent: A,
-    callback_data: Arc<Mutex<HashMap<String, (serde_json::Value, ConfirmHandler<S>)>>>,
+    callback_data: Callbacks<S>,
     _state: std::marker::PhantomData<S>,
 }
 
@@ -46,52 +64,15 @@ where
             let callback_data = callback_data.clone();
             async move {
                 if let Some(text) = msg.text() {
-                    let history_id = msg.chat.id.to_string();
-                    let sent_message = bot.send_message(msg.chat.id, "Thinking...").await?;
-                    println!("event=PROCESSING_PROMPT");
-                    match agent
-                        .process_prompt(text, &history_id, serde_json::to_value(&msg).unwrap())
-                        .await
-                    {
-                        Ok((response, confirm_handler)) => {
-                            println!("event=RECEIVE_RESPONSE_FROM_AGENT: {:?}", response);
-                            // Check if this is a preview response that needs confirmation
-                            if let Some((value, confirm_handler)) = confirm_handler {
-                                // Create confirm/cancel buttons
-                                let uuid = uuid::Uuid::new_v4().to_string();
-                                let keyboard = InlineKeyboardMarkup::new(vec![vec![
-                                    InlineKeyboardButton::callback("Confirm", uuid.clone()),
-                                ]]);
-
-                                // Store the preview data for later confirmation
-                                // You might want to store this in a HashMap in the agent
-                                bot.edit_message_text(
-                                    sent_message.chat.id,
-                                    sent_message.id,
-                                    response,
-                                )
-                                .reply_markup(keyboard)
-                                .await?;
-                                callback_data
-                                    .lock()
-                                    .await
-                                    .insert(uuid, (value, confirm_handler));
-                            } else {
-                                bot.edit_message_text(
-                                    sent_message.chat.id,
-                                    sent_message.id,
-                                    response,
-                                )
-                                .await?;
-                            }
-                        }
-                        Err(e) => {
-                            println!("Error processing prompt");
-                            println!("Error: {:?}", e);
-                            bot.send_message(msg.chat.id, "Error processing prompt")
-                                .await?;
-                        }
-                    }
+                    respond(
+                        &bot,
+                        agent.as_ref(),
+                        &callback_data,
+                        msg.chat.id,
+                        text,
+                        serde_json::to_value(&msg).unwrap(),
+                    )
+                    .await?;
                 }
                 Ok::<(), RequestError>(())
             }
@@ -104,21 +85,55 @@ where
             let agent = agent.clone();
 
             async move {
-                if let Some(data) = q.data {
-                    // Get the stored data and handler
-                    if let Some((value, handler)) = callback_data.lock().await.remove(&data) {
-                        // Execute the confirmation handler
-                        let result = handler(
-                            value,
-                            serde_json::to_value(&q.message).unwrap(),
-                            agent.state(),
-                        )
-                        .await;
+                // Get the stored data and handler
+                let callback = match &q.data {
+                    Some(data) => {
+                        let mut callbacks = callback_data.lock().await;
+                        match callbacks.remove(data) {
+                            // Leave someone else's preview for them to confirm
+                            Some(Callback::Confirm(value, handler))
+                                if !previewed_for(&value, &q.from) =>
+                            {
+                                callbacks.insert(data.clone(), Callback::Confirm(value, handler));
+                                drop(callbacks);
+                                bot.answer_callback_query(q.id)
+                                    .text("Only the user who asked for this can confirm it")
+                                    .await?;
+                                return Ok(());
+                            }
+                            callback => callback,
+                        }
+                    }
+                    None => None,
+                };
+                match callback {
+                    Some(Callback::Confirm(value, handler)) => {
+                        // The message is the bot's own preview, so the sender
+                        // is whoever pressed the button
+                        let mut send_state = serde_json::to_value(&q.message).unwrap();
+                        send_state["from"] = serde_json::to_value(&q.from).unwrap();
+                        // Execute the confirmation handler, keeping any files it sends
+                        let result =
+                            output::collect(handler(value, send_state, agent.state())).await;
                         match result {
-                            Ok(response) => {
+                            Ok(output) => {
                                 // Update the message with the confirmation result
                                 if let Some(message) = q.message {
-                                    bot.edit_message_text(message.chat.id, message.id, response)
+                                    bot.edit_message_text(
+                                        message.chat.id,
+                                        message.id,
+                                        output.content,
+                                    )
+                                    .await?;
+                                    let attachments = output
+                                        .artifacts
+                                        .into_iter()
+                                        .filter_map(|artifact| match artifact {
+                                            Artifact::Attachment(attachment) => Some(attachment),
+                                            Artifact::Table(_) => None,
+                                        })
+                                        .collect();
+                                    send_attachments(&bot, message.chat.id, attachments, None)
                                         .await?;
                                 }
                             }
@@ -135,6 +150,27 @@ where
                             }
                         }
                     }
+                    Some(Callback::Prompt(prompt)) => {
+                        // Answer first, the agent may take longer than Telegram waits
+                        bot.answer_callback_query(q.id).await?;
+                        if let Some(message) = q.message {
+                            // Act for whoever pressed the button, not the bot
+                            // that sent the message
+                            let mut send_state = serde_json::to_value(&message).unwrap();
+                            send_state["from"] = serde_json::to_value(&q.from).unwrap();
+                            respond(
+                                &bot,
+                                agent.as_ref(),
+                                &callback_data,
+                                message.chat.id,
+                                &prompt,
+                                send_state,
+                            )
+                            .await?;
+                        }
+                        return Ok(());
+                    }
+                    None => {}
                 }
 
                 // Answer the callback query to remove the loading state
@@ -158,3 +194,187 @@ where
             .await;
     }
 }
+
+/// Whether `user` is the one a preview was made for. Previews of wallet
+/// actions name their user; others can be confirmed by anyone.
+fn previewed_for(preview: &serde_json::Value, user: &User) -> bool {
+    match &preview["user_id"] {
+        serde_json::Value::String(id) => *id == user.id.0.to_string(),
+        _ => true,
+    }
+}
+
+/// Runs `prompt` through the agent and replies in `chat_id`.
+async fn respond<A, S>(
+    bot: &Bot,
+    agent: &A,
+    callback_data: &Callbacks<S>,
+    chat_id: ChatId,
+    prompt: &str,
+    send_state: serde_json::Value,
+) -> Result<(), RequestError>
+where
+    A: Agent<S>,
+    S: Send + Sync + Clone + 'static,
+{
+    let history_id = chat_id.to_string();
+    let sent_message = bot.send_message(chat_id, "Thinking...").await?;
+    println!("event=PROCESSING_PROMPT");
+    match agent.process_prompt(prompt, &history_id, send_state).await {
+        Ok((response, confirm_handler)) => {
+            println!("event=RECEIVE_RESPONSE_FROM_AGENT: {:?}", response);
+            let outputs = agent.take_action_outputs(&history_id);
+            let mut rows = Vec::new();
+            // Check if this is a preview response that needs confirmation
+            let is_preview = confirm_handler.is_some();
+            if let Some((value, confirm_handler)) = confirm_handler {
+                // Store the preview data for later confirmation
+                let uuid = uuid::Uuid::new_v4().to_string();
+                rows.push(vec![InlineKeyboardButton::callback(
+                    "Confirm",
+                    uuid.clone(),
+                )]);
+                callback_data
+                    .lock()
+                    .await
+                    .insert(uuid, Callback::Confirm(value, confirm_handler));
+            }
+            for hint in outputs.iter().flat_map(|output| &output.hints) {
+                if let Some(button) = hint_button(hint, callback_data).await {
+                    rows.push(vec![button]);
+                }
+            }
+            let keyboard = (!rows.is_empty()).then(|| InlineKeyboardMarkup::new(rows));
+
+            let (mut attachments, mut tables) = (Vec::new(), Vec::new());
+            for artifact in outputs.into_iter().flat_map(|output| output.artifacts) {
+                match artifact {
+                    Artifact::Attachment(attachment) => attachments.push(attachment),
+                    Artifact::Table(table) => tables.push(table),
+                }
+            }
+
+            // Confirmations edit the message text, so previews stay text messages
+            if !is_preview
+                && !response.is_empty()
+                && response.chars().count() <= CAPTION_LIMIT
+                && attachments.iter().any(|a| a.kind == AttachmentKind::Image)
+            {
+                // The response becomes the caption of the first image
+                bot.delete_message(sent_message.chat.id, sent_message.id)
+                    .await?;
+                send_attachments(bot, chat_id, attachments, Some((response, keyboard))).await?;
+            } else {
+                let request =
+                    bot.edit_message_text(sent_message.chat.id, sent_message.id, response);
+                match keyboard {
+                    Some(keyboard) => request.reply_markup(keyboard).await?,
+                    None => request.await?,
+                };
+                send_attachments(bot, chat_id, attachments, None).await?;
+            }
+            for table in tables {
+                send_table(bot, chat_id, &table).await?;
+            }
+        }
+        Err(e) => {
+            println!("Error processing prompt");
+            println!("Error: {:?}", e);
+            bot.send_message(chat_id, "Error processing prompt").await?;
+        }
+    }
+    Ok(())
+}
+
+/// The inline button for `hint`, or `None` if its link is not a valid URL.
+async fn hint_button<S: Send + Sync + Clone + 'static>(
+    hint: &UiHint,
+    callback_data: &Callbacks<S>,
+) -> Option<InlineKeyboardButton> {
+    match hint {
+        UiHint::Button { label, prompt } => {
+            let uuid = uuid::Uuid::new_v4().to_string();
+            callback_data
+                .lock()
+                .await
+                .insert(uuid.clone(), Callback::Prompt(prompt.clone()));
+            Some(InlineKeyboardButton::callback(label, uuid))
+        }
+        UiHint::Link { label, url } => match url.parse() {
+            Ok(url) => Some(InlineKeyboardButton::url(label, url)),
+            Err(e) => {
+                println!("Skipping link {:?} with invalid url {}: {}", label, url, e);
+                None
+            }
+        },
+    }
+}
+
+/// Sends each attachment as a photo or document, putting `caption` and its
+/// keyboard under the first photo.
+async fn send_attachments(
+    bot: &Bot,
+    chat_id: ChatId,
+    attachments: Vec<Attachment>,
+    mut caption: Option<(String, Option<InlineKeyboardMarkup>)>,
+) -> Result<(), RequestError> {
+    for attachment in attachments {
+        let file = InputFile::memory(attachment.data).file_name(attachment.file_name);
+        match attachment.kind {
+            AttachmentKind::Image => {
+                let mut request = bot.send_photo(chat_id, file);
+                if let Some((caption, keyboard)) = caption.take() {
+                    request = request.caption(caption);
+                    if let Some(keyboard) = keyboard {
+                        request = request.reply_markup(keyboard);
+                    }
+                }
+                request.await?;
+            }
+            AttachmentKind::File => {
+                bot.send_document(chat_id, file).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sends `table` as monospaced text, or as a CSV file when it is too long
+/// for one message.
+async fn send_table(bot: &Bot, chat_id: ChatId, table: &Table) -> Result<(), RequestError> {
+    let title = table
+        .title
+        .as_deref()
+        .map(|title| html::bold(&html::escape(title)));
+    let text = match &title {
+        Some(title) => format!("{}\n{}", title, html::code_block(&table.to_text())),
+        None => html::code_block(&table.to_text()),
+    };
+    if text.chars().count() <= MESSAGE_LIMIT {
+        bot.send_message(chat_id, text)
+            .parse_mode(ParseMode::Html)
+            .await?;
+        return Ok(());
+    }
+
+    let name = table
+        .title
+        .as_deref()
+        .unwrap_or("table")
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let file = InputFile::memory(table.to_csv().into_bytes()).file_name(format!("{}.csv", name));
+    let mut request = bot.send_document(chat_id, file);
+    if let Some(title) = title {
+        request = request.caption(title).parse_mode(ParseMode::Html);
+    }
+    request.await?;
+    Ok(())
+}
@@ -32,7 +32,9 @@ impl Client {
         self
     }
 
-    #[cfg(test)]
+    /// Sends requests to `base_url` instead of the provider's API, e.g. a
+    /// proxy or a server compatible with it. The endpoint path, such as
+    /// `/v1/chat/completions`, is appended, so leave it out.
     pub fn with_base_url(mut self, base_url: String) -> Self {
         self.base_url = Some(base_url);
         self
@@ -347,5 +349,9 @@ mod tests {
             Model::Anthropic(AnthropicModel::Claude3Sonnet),
         );
         assert_eq!(anthropic_client.get_base_url(), "https://api.anthropic.com");
+
+        let proxied_client =
+            anthropic_client.with_base_url("https://llm-proxy.internal".to_string());
+        assert_eq!(proxied_client.get_base_url(), "https://llm-proxy.internal");
     }
 }
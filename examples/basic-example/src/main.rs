@@ -1,26 +1,25 @@
-use std::{env, str::FromStr, sync::Arc};
+use std::{env, sync::Arc};
 
 use ferrox::{
     agent::{text_agent::TextAgent, Agent, NullAgent},
-    Ferrox, Message,
+    Ferrox,
 };
 use ferrox_actions::{
-    ActionBuilder, AgentState, BirdeyeActionGroup, CoinGeckoActionGroup, DexScreenerActionGroup,
-    EmptyParams, GmgnActionGroup,
+    cache::{self, ResponseCache},
+    solana::policy::SpendingPolicy,
+    ActionBuilder, AddressBook, AddressBookActionGroup, AgentState, BirdeyeActionGroup,
+    CandleActionGroup, ChartActionGroup, CoinGeckoActionGroup, DexScreenerActionGroup, EmptyParams,
+    GmgnActionGroup, JupiterActionGroup, JupiterDcaActionGroup, PumpActionGroup,
+    SolanaRpcActionGroup, TechnicalAnalysisActionGroup, TransferActionGroup,
+    WalletAccountsActionGroup, WalletBackupActionGroup,
 };
-use ferrox_wallet::{simple_wallet_manager::SimpleWalletManager, Wallet, WalletManager};
+use ferrox_wallet::keystore_wallet_manager::KeystoreWalletManager;
 use openai_api::models::{Model, OpenAIModel};
-use serde::{Deserialize, Serialize};
-use solana_sdk::{
-    pubkey::Pubkey,
-    signature::{Keypair, Signature},
-    signer::Signer,
-};
+use serde::Deserialize;
 
 #[derive(Clone)]
 struct TestState {
     counter: u32,
-    wallet_manager: SimpleWalletManager,
 }
 
 #[derive(Deserialize, Debug)]
@@ -31,23 +30,28 @@ struct HelloParams {
 const SYSTEM_PROMPT: &str = "
 You are an onchain trading assitant with native capability to pull data from coingecko dexscreener or birdseye.
 If asked to do a multi step action, and one of the steps produces invalid data our empty data, try to call an alternative api from the lsit of dexscreener, birdseye or coingecko api set.
-For example when asked for technical analaysis, you can first get the tick data via the birdseye OHLCV API and then use that data to create the actual technical analyssis
+When asked for technical analysis, use the technical indicator, support/resistance and volume profile actions rather than calculating indicators from raw OHLCV data yourself, and cite the values they return
+When asked for a chart, call render_price_chart; the image is sent to the user with your reply as its caption, so keep that reply short
+Swaps only happen once the user presses Confirm under the quote that swap_tokens shows them, so never tell them a swap went through before that; the same goes for send_tokens, create_dca_order, close_dca_order, pump_buy and pump_sell
+If one of those previews lists violations, the spending policy will refuse it: tell the user why instead of asking them to confirm
 ";
 
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
     let api_key = env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set");
-    let wallet_manager = SimpleWalletManager::new();
+    //Wallets are encrypted on disk under FERROX_KEYSTORE_PASSWORD
+    let wallet_manager = KeystoreWalletManager::from_env().expect("Failed to open the keystore");
+    let address_book = match env::var("FERROX_ADDRESS_BOOK") {
+        Ok(path) => AddressBook::open(path).expect("Failed to open the address book"),
+        Err(_) => AddressBook::new(),
+    };
     let mut decision_agent = TextAgent::<TestState, NullAgent>::new(
         NullAgent::default(),
         SYSTEM_PROMPT.to_string(),
         api_key,
         Model::OpenAI(OpenAIModel::GPT40),
-        TestState {
-            counter: 0,
-            wallet_manager,
-        },
+        TestState { counter: 0 },
     );
 
     //Now let's add some actions to the decision agent.
@@ -106,86 +110,29 @@ async fn main() {
         decision_agent.add_action(Arc::new(get_counter_action));
     }
 
-    {
-        //The data the agent will call the preview_send_solana with
-        #[derive(Deserialize, Debug)]
-        struct SendSolanaParams {
-            target_wallet: String,
-            amount_to_send: String,
-        }
-        //The data the user will see in the preview
-        #[derive(Serialize, Deserialize, Debug)]
-        struct SendSolanaPreview {
-            sender: Pubkey,
-            target_wallet: Pubkey,
-            amount_to_send: u64,
-        }
-
-        //This function will cause a preview to be shown to the user
-        //The second arugment is the message from telegram.
-        //It acceps one parameter which is the target wallet to send to. So the user can prompt the agent to send to a specific wallet.
-        async fn preview_send_solana(
-            params: SendSolanaParams,
-            message: Message,
-            state: AgentState<TestState>,
-        ) -> Result<SendSolanaPreview, String> {
-            println!("LLM called preview send solana");
-            let user_id = message.from().unwrap().id.0;
-            println!("User ID: {:?}", user_id);
-            let amount_to_send =
-                (params.amount_to_send.parse::<f64>().unwrap() * 10.0f64.powi(9)).round() as u64;
-            let wallet = state
-                .lock()
-                .await
-                .wallet_manager
-                .get_wallet(&format!("{:?}", user_id))
-                .await
-                .unwrap();
-            let wallet = match wallet {
-                Wallet::Solana(wallet) => wallet.pubkey(),
-            };
-            println!("Wallet: {:?}", wallet);
-            let target_wallet = Pubkey::from_str(&params.target_wallet).unwrap();
-            Ok(SendSolanaPreview {
-                sender: wallet,
-                target_wallet,
-                amount_to_send,
-            })
-        }
-
-        //NOTE: The params value in the confirm MUST match the output type of the preview
-        async fn confirm_send_solana(
-            _params: SendSolanaPreview,
-            _message: Message,
-            _state: AgentState<TestState>,
-        ) -> Result<String, String> {
-            println!("User clicked confirm send solana");
-            // For now we just return a dummy signature
-            // In reality, we can use the input parameters to hit some backend service to send the transaction or do some processing
-            Ok(Signature::new_unique().to_string())
+    //Cache market data responses so repeated questions don't spend API quota
+    let _ = cache::install(ResponseCache::memory());
+
+    //Spending limits on everything that signs with a user's wallet, re-read
+    //every minute so they can be changed without a restart
+    let spending_policy = match env::var("FERROX_POLICY_FILE") {
+        Ok(path) => {
+            let spending_policy =
+                SpendingPolicy::from_file(&path).expect("Invalid spending policy");
+            let reloaded = spending_policy.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = reloaded.reload_from_file(&path) {
+                        eprintln!("Keeping the previous spending policy: {}", e);
+                    }
+                }
+            });
+            spending_policy
         }
-
-        //Create the action
-        let get_send_solana_action =
-            ActionBuilder::<_, SendSolanaParams, Message, TestState, SendSolanaPreview, _>::new(
-                "send_solana",
-                preview_send_solana,
-                Some(confirm_send_solana),
-            )
-            .description("
-                Generates the payload to send SOL to a target wallet. 
-                This action itself will not send the SOL, but merely a preview for the user to confirm. 
-                Never mention that the sol has been sent, nor is this a preview. Prompt the user to confirm.")
-            .parameter(
-                "target_wallet",
-                "Target wallet to send SOL to",
-                "string",
-                true,
-            )
-            .parameter("amount_to_send", "Amount of SOL to send", "string", true)
-            .build();
-        decision_agent.add_action(Arc::new(get_send_solana_action));
-    }
+        Err(_) => SpendingPolicy::default(),
+    };
 
     //Coingecko actions
     let coingecko_group = CoinGeckoActionGroup::new();
@@ -203,6 +150,54 @@ async fn main() {
     let gmgn_group = GmgnActionGroup::new();
     decision_agent.add_action_group(&gmgn_group);
 
+    //Candles from whichever provider has them
+    let candle_group = CandleActionGroup::new();
+    decision_agent.add_action_group(&candle_group);
+
+    //Technical analysis over those candles
+    let ta_group = TechnicalAnalysisActionGroup::new();
+    decision_agent.add_action_group(&ta_group);
+
+    //Price charts sent back as images
+    let chart_group = ChartActionGroup::new();
+    decision_agent.add_action_group(&chart_group);
+
+    //Named accounts of the user's wallet and which one is the default
+    let accounts_group = WalletAccountsActionGroup::new(wallet_manager.clone());
+    decision_agent.add_action_group(&accounts_group);
+
+    //Importing keys and exporting or backing up the wallet, in private chats
+    let backup_group = WalletBackupActionGroup::new(wallet_manager.clone());
+    decision_agent.add_action_group(&backup_group);
+
+    //Saved addresses the user can send to by name
+    let address_book_group = AddressBookActionGroup::new(address_book.clone());
+    decision_agent.add_action_group(&address_book_group);
+
+    //SOL and SPL token transfers from the user's wallet
+    let transfer_group = TransferActionGroup::new(
+        wallet_manager.clone(),
+        address_book,
+        spending_policy.clone(),
+    );
+    decision_agent.add_action_group(&transfer_group);
+
+    //Jupiter quotes and swaps from the user's wallet
+    let jupiter_group = JupiterActionGroup::new(wallet_manager.clone(), spending_policy.clone());
+    decision_agent.add_action_group(&jupiter_group);
+
+    //Jupiter DCA orders
+    let dca_group = JupiterDcaActionGroup::new(wallet_manager.clone(), spending_policy.clone());
+    decision_agent.add_action_group(&dca_group);
+
+    //Pump.fun launches and bonding-curve trades
+    let pump_group = PumpActionGroup::new(wallet_manager.clone(), spending_policy);
+    decision_agent.add_action_group(&pump_group);
+
+    //Balances, token accounts and transaction history
+    let rpc_group = SolanaRpcActionGroup::new(wallet_manager);
+    decision_agent.add_action_group(&rpc_group);
+
     let ferrox = Ferrox::<_, TestState>::new(decision_agent);
     ferrox.start().await;
 }